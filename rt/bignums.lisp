@@ -0,0 +1,209 @@
+;; integers that do not fit into 32 bits are bignums, with a sign of 1 or -1 as
+;; their car and a list of digits in base 32768 as their cdr, the least
+;; significant digit first and without leading zeros.
+;;
+;; the functions here take any integers, and only make a bignum if the result
+;; does not fit into 32 bits, so that every integer has a single
+;; representation. sums and products of digits and carries stay small enough
+;; that the intrinsics never overflow on them.
+
+(defparameter *bignum-radix* 32768)
+
+(defun bignum-add (left right)
+    (add-signed-digits
+        (integer-sign left) (integer-digits left)
+        (integer-sign right) (integer-digits right)))
+
+(defun bignum-subtract (left right)
+    (add-signed-digits
+        (integer-sign left) (integer-digits left)
+        (intrinsic:sub-2 0 (integer-sign right)) (integer-digits right)))
+
+(defun bignum-multiply (left right)
+    (make-integer
+        (intrinsic:mul-2 (integer-sign left) (integer-sign right))
+        (multiply-digits (integer-digits left) (integer-digits right))))
+
+;; division that truncates towards zero, the divisor must not be zero
+(defun bignum-truncate (dividend divisor)
+    (make-integer
+        (intrinsic:mul-2 (integer-sign dividend) (integer-sign divisor))
+        (divide-digits (integer-digits dividend) (integer-digits divisor))))
+
+;; -1, 0 or 1 if left is less than, equal to or greater than right
+(defun bignum-compare (left right)
+    (let ((left-sign (integer-sign left))
+          (right-sign (integer-sign right)))
+        (cond
+            ((intrinsic:<-2 left-sign right-sign) -1)
+            ((intrinsic:<-2 right-sign left-sign) 1)
+            (t (intrinsic:mul-2 left-sign
+                (compare-digits (integer-digits left) (integer-digits right)))))))
+
+(defun bignum-to-float (integer)
+    (intrinsic:float-mul-2
+        (intrinsic:int-to-float (integer-sign integer))
+        (digits-to-float (integer-digits integer))))
+
+(defun integer-sign (integer)
+    (cond
+        ((bignump integer) (intrinsic:car integer))
+        ((intrinsic:<-2 integer 0) -1)
+        (t 1)))
+
+;; digits of numbers are taken from their negation if they are positive, since
+;; the lowest 32-bit number cannot be negated
+(defun integer-digits (integer)
+    (cond
+        ((bignump integer) (intrinsic:cdr integer))
+        ((intrinsic:<-2 integer 0) (negative-digits integer))
+        (t (negative-digits (intrinsic:sub-2 0 integer)))))
+
+(defun negative-digits (number)
+    (let ((reversed nil))
+        (do ((number number (intrinsic:div-2 number *bignum-radix*)))
+            ((intrinsic:=-2 number 0) (finish-digits reversed))
+            (setq reversed (cons
+                (intrinsic:sub-2 (intrinsic:mul-2 (high-digit number) *bignum-radix*) number)
+                reversed)))))
+
+;; the integer with a sign and digits
+(defun make-integer (sign digits)
+    (cond
+        ((not (digits-fit-p sign digits)) (intrinsic:make-bignum sign digits))
+        ((intrinsic:<-2 sign 0) (digits-to-negative digits))
+        (t (intrinsic:sub-2 0 (digits-to-negative digits)))))
+
+;; the lowest 32-bit number is -2147483648, which has the digits (0 0 2)
+(defun digits-fit-p (sign digits)
+    (let ((order (compare-digits digits '(0 0 2))))
+        (or (intrinsic:<-2 order 0)
+            (and (intrinsic:=-2 order 0) (intrinsic:<-2 sign 0)))))
+
+(defun digits-to-negative (digits)
+    (let ((negative 0))
+        (dolist (digit (reverse-digits digits) negative)
+            (setq negative (intrinsic:sub-2
+                (intrinsic:mul-2 negative *bignum-radix*)
+                digit)))))
+
+(defun digits-to-float (digits)
+    (let ((result 0.0))
+        (dolist (digit (reverse-digits digits) result)
+            (setq result (intrinsic:float-add-2
+                (intrinsic:float-mul-2 result 32768.0)
+                (intrinsic:int-to-float digit))))))
+
+;; the functions below work on the digits with loops rather than recursion,
+;; since bignums can have more digits than the stack has room for calls. they
+;; collect digits most significant first, and this puts them in order without
+;; leading zeros
+(defun finish-digits (reversed)
+    (do ((reversed reversed (cdr reversed)))
+        ((or (null reversed) (not (intrinsic:=-2 (car reversed) 0)))
+            (reverse-digits reversed))))
+
+(defun reverse-digits (digits)
+    (let ((reversed nil))
+        (dolist (digit digits reversed)
+            (setq reversed (cons digit reversed)))))
+
+;; missing digits are zero, so that the digits of different lengths line up
+(defun first-digit (digits)
+    (if digits (car digits) 0))
+
+;; -1, 0 or 1 like bignum-compare, going up from the least significant digit
+;; so that the highest digit that differs decides
+(defun compare-digits (left right)
+    (do ((left left (cdr left))
+         (right right (cdr right))
+         (order 0 (compare-digit (first-digit left) (first-digit right) order)))
+        ((and (null left) (null right)) order)))
+
+(defun compare-digit (left right order)
+    (cond
+        ((intrinsic:<-2 left right) -1)
+        ((intrinsic:<-2 right left) 1)
+        (t order)))
+
+(defun add-digits (left right)
+    (let ((reversed nil) (carry 0))
+        (do ((left left (cdr left))
+             (right right (cdr right)))
+            ((and (null left) (null right))
+                (finish-digits (cons carry reversed)))
+            (let ((sum (intrinsic:add-2
+                    (intrinsic:add-2 (first-digit left) (first-digit right))
+                    carry)))
+                (setq reversed (cons (low-digit sum) reversed))
+                (setq carry (high-digit sum))))))
+
+;; left must not be less than right
+(defun subtract-digits (left right)
+    (let ((reversed nil) (borrow 0))
+        (do ((left left (cdr left))
+             (right right (cdr right)))
+            ((null left) (finish-digits reversed))
+            (let ((difference (intrinsic:sub-2
+                    (intrinsic:sub-2 (car left) (first-digit right))
+                    borrow)))
+                (setq borrow (if (intrinsic:<-2 difference 0) 1 0))
+                (setq reversed (cons
+                    (intrinsic:add-2 difference (intrinsic:mul-2 borrow *bignum-radix*))
+                    reversed))))))
+
+(defun add-signed-digits (left-sign left right-sign right)
+    (cond
+        ((intrinsic:=-2 left-sign right-sign)
+            (make-integer left-sign (add-digits left right)))
+        ((intrinsic:<-2 (compare-digits left right) 0)
+            (make-integer right-sign (subtract-digits right left)))
+        (t (make-integer left-sign (subtract-digits left right)))))
+
+(defun multiply-digit (digits factor)
+    (let ((reversed nil) (carry 0))
+        (dolist (digit digits (finish-digits (cons carry reversed)))
+            (let ((product (intrinsic:add-2 (intrinsic:mul-2 digit factor) carry)))
+                (setq reversed (cons (low-digit product) reversed))
+                (setq carry (high-digit product))))))
+
+;; adds up the products with each digit of right, shifted by one more digit
+;; each time
+(defun multiply-digits (left right)
+    (let ((product nil) (shifted left))
+        (dolist (digit right product)
+            (setq product (add-digits product (multiply-digit shifted digit)))
+            (setq shifted (cons 0 shifted)))))
+
+;; long division, going through the digits of the dividend from the most
+;; significant one, returns the digits of the quotient and of the remainder
+(defun divide-digits (dividend divisor)
+    (let ((quotient nil) (remainder nil))
+        (dolist (digit (reverse-digits dividend))
+            (setq remainder (cons digit remainder))
+            (let ((next (quotient-digit remainder divisor)))
+                (setq quotient (cons next quotient))
+                (setq remainder
+                    (subtract-digits remainder (multiply-digit divisor next)))))
+        (values (finish-digits (reverse-digits quotient)) remainder)))
+
+;; bisects for the highest digit that the divisor can be multiplied with
+;; without exceeding the remainder
+(defun quotient-digit (remainder divisor)
+    (do ((low 0) (high *bignum-radix*))
+        ((intrinsic:=-2 (intrinsic:add-2 low 1) high) low)
+        (let ((middle (intrinsic:div-2 (intrinsic:add-2 low high) 2)))
+            (if (intrinsic:<-2
+                    0
+                    (compare-digits (multiply-digit divisor middle) remainder))
+                (setq high middle)
+                (setq low middle)))))
+
+;; the digit and the carry of a sum or product of digits
+(defun low-digit (number)
+    (intrinsic:sub-2
+        number
+        (intrinsic:mul-2 (high-digit number) *bignum-radix*)))
+
+(defun high-digit (number)
+    (intrinsic:div-2 number *bignum-radix*))
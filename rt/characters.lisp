@@ -0,0 +1,25 @@
+;; characters hold the code of a unicode scalar value after their type tag
+
+(defun char-code (character)
+    (intrinsic:char-code (assert-character character)))
+
+;; surrogates and codes after the last one of unicode are not characters
+(defun code-char (code)
+    (if (and (<= 0 (assert-integer code) 1114111)
+             (not (<= 55296 code 57343)))
+        (intrinsic:code-char code)
+        nil))
+
+(defun char= (first &rest rest)
+    (if (null rest)
+        t
+        (and
+            (= (char-code first) (char-code (car rest)))
+            (apply #'char= rest))))
+
+(defun char< (first &rest rest)
+    (if (null rest)
+        t
+        (and
+            (< (char-code first) (char-code (car rest)))
+            (apply #'char< rest))))
@@ -0,0 +1,110 @@
+;; conditions are lists of their type, their message and, for type errors, the
+;; datum and the expected type
+
+;; each type of condition and its parent type
+(defparameter *condition-types*
+    '((condition nil)
+      (warning condition)
+      (simple-condition condition)
+      (error condition)
+      (simple-error error)
+      (type-error error)
+      (program-error error)
+      (control-error error)
+      (arithmetic-error error)
+      (division-by-zero arithmetic-error)))
+
+;; the clusters of handlers from handler-bind and handler-case, innermost
+;; first, where each handler is the type and either a function to call or
+;; the tag to throw the condition to
+(defparameter *handlers* '())
+
+(defun make-condition (type &key format-control datum expected-type)
+    (list type
+          (or format-control (default-condition-message type expected-type))
+          datum
+          expected-type))
+
+(defun default-condition-message (type expected-type)
+    (if (and (eq type 'type-error) expected-type)
+        (concatenate 'string "type error: expected " expected-type)
+        (concatenate 'string "condition of type " type)))
+
+(defun condition-type (condition)
+    (car condition))
+
+(defun condition-message (condition)
+    (cadr condition))
+
+(defun type-error-datum (condition)
+    (car (cdr (cdr condition))))
+
+(defun type-error-expected-type (condition)
+    (car (cdr (cdr (cdr condition)))))
+
+(defun condition-subtype-p (type ancestor)
+    (cond
+        ((null type) nil)
+        ((eq type ancestor) t)
+        (t (condition-subtype-p (condition-parent-type type *condition-types*) ancestor))))
+
+(defun condition-parent-type (type types)
+    (cond
+        ((null types) nil)
+        ((eq (car (car types)) type) (cadr (car types)))
+        (t (condition-parent-type type (cdr types)))))
+
+;; a string is the message of a condition of the default type, a symbol
+;; the type of a condition made with the arguments
+(defun coerce-to-condition (datum arguments default-type)
+    (cond
+        ((stringp datum) (make-condition default-type :format-control datum))
+        ((symbolp datum) (apply #'make-condition (cons datum arguments)))
+        (t datum)))
+
+;; returns nil if no handler takes over
+(defun signal (datum &rest arguments)
+    (signal-clusters
+        (coerce-to-condition datum arguments 'simple-condition)
+        *handlers*)
+    nil)
+
+;; panics with the message if no handler takes over
+(defun error (datum &rest arguments)
+    (let ((condition (coerce-to-condition datum arguments 'simple-error)))
+        (signal-clusters condition *handlers*)
+        (panic (condition-message condition))))
+
+(defun signal-clusters (condition clusters)
+    (when clusters
+        (signal-cluster condition (car clusters) (cdr clusters))
+        (signal-clusters condition (cdr clusters))))
+
+;; handlers run with only the clusters outside of their own one established,
+;; so that they can signal conditions themselves
+(defun signal-cluster (condition handlers outer)
+    (when handlers
+        (let ((type (car (car handlers)))
+              (handler (cdr (car handlers))))
+            (when (condition-subtype-p (condition-type condition) type)
+                (if (functionp handler)
+                    (call-handler handler condition outer)
+                    (throw handler condition))))
+        (signal-cluster condition (cdr handlers) outer)))
+
+(defun call-handler (handler condition outer)
+    (let ((active *handlers*))
+        (setq *handlers* outer)
+        (unwind-protect
+            (funcall handler condition)
+            (setq *handlers* active))))
+
+;; called around the body of handler-bind and handler-case, which restore
+;; the handlers returned here when leaving it
+(defun push-handlers (cluster)
+    (let ((outer *handlers*))
+        (setq *handlers* (cons cluster outer))
+        outer))
+
+(defun restore-handlers (outer)
+    (setq *handlers* outer))
@@ -1,12 +1,49 @@
 ;; non-standard utilities to help with debugging programs (and the compiler itself)
 
-(defun assert (that-not-nil or-else-panic-msg)
-    (if that-not-nil nil (panic or-else-panic-msg)))
+(defun assert (that-not-nil or-else-error-msg)
+    (if that-not-nil nil (error or-else-error-msg)))
 
 (defun panic (message)
     (intrinsic:princ message)
     (intrinsic:panic))
 
+;; called by the prologue of a function that got the wrong number of arguments,
+;; expected is a description like "1 to 2 arguments"
+(defun arity-error (name expected arguments)
+    (error 'program-error :format-control (concatenate 'string
+        "function " name " expects " expected
+        ", got " (to-string-number (length arguments)))))
+
+;; called by the prologue of a function with key parameters if an argument
+;; after the positional ones is not one of the known keywords or has no value,
+;; which is the first such argument
+(defun key-error (name known arguments)
+    (loop
+        (let ((keyword (car arguments)))
+            (if (null (cdr arguments))
+                (error 'program-error :format-control (concatenate 'string
+                    "function " name " got keyword " (to-string-keyword keyword)
+                    " without a value")))
+            (if (not (keyword-known-p keyword known))
+                (error 'program-error :format-control (concatenate 'string
+                    "function " name " got unknown keyword "
+                    (to-string-keyword keyword))))
+            (setq arguments (cdr (cdr arguments))))))
+
+(defun keyword-known-p (keyword known)
+    (and known
+         (or (eq keyword (car known)) (keyword-known-p keyword (cdr known)))))
+
+;; symbols as they are written, since they are usually keywords
+(defun to-string-keyword (thingy)
+    (if (symbolp thingy) thingy (to-string-any thingy)))
+
+;; called by destructuring-bind when the list does not match the lambda list,
+;; which is passed as its source text
+(defun destructuring-error (lambda-list list)
+    (error 'program-error :format-control (concatenate 'string
+        "lambda list " lambda-list " does not match " (to-string-any list))))
+
 (defun to-string-list-items (thingy)
     (if (cdr thingy)
         (concatenate 'string (to-string-any (car thingy)) " " (to-string-list-items (cdr thingy)))
@@ -18,24 +55,110 @@
 (defun to-string-number (thingy)
     (if (< thingy 0)
         (concatenate 'string "-" (to-string-number (- thingy)))
-        (if (< thingy 10)
-            (to-string-digit thingy)
-            (let (
-                (last-digit (- thingy (* 10 (floor thingy 10))))
-                (rest (floor thingy 10)))
-                (concatenate 'string (to-string-number rest) (to-string-digit last-digit))))))
+        ;; goes from the last digit to the first in a loop, since bignums can
+        ;; have more digits than the stack has room for calls
+        (let ((digits ""))
+            (loop
+                (multiple-value-bind (rest last-digit) (floor thingy 10)
+                    (setq digits (concatenate 'string (to-string-digit last-digit) digits))
+                    (if (= rest 0) (return digits))
+                    (setq thingy rest))))))
+
+(defun to-string-ratio (thingy)
+    (concatenate 'string
+        (to-string-number (numerator thingy)) "/" (to-string-number (denominator thingy))))
 
 (defun to-string-digit (thingy)
-    (if (= thingy 0) "0"
-    (if (= thingy 1) "1"
-    (if (= thingy 2) "2"
-    (if (= thingy 3) "3"
-    (if (= thingy 4) "4"
-    (if (= thingy 5) "5"
-    (if (= thingy 6) "6"
-    (if (= thingy 7) "7"
-    (if (= thingy 8) "8"
-    (if (= thingy 9) "9")))))))))))
+    (cond
+        ((= thingy 0) "0")
+        ((= thingy 1) "1")
+        ((= thingy 2) "2")
+        ((= thingy 3) "3")
+        ((= thingy 4) "4")
+        ((= thingy 5) "5")
+        ((= thingy 6) "6")
+        ((= thingy 7) "7")
+        ((= thingy 8) "8")
+        ((= thingy 9) "9")))
+
+;; prints up to seven significant digits, in scientific notation for floats
+;; that are very large or small, like the interpreter does. the digits are
+;; calculated from the exact value of the float, since rounding them with float
+;; arithmetic would be off in the last digit
+(defun to-string-float (thingy)
+    (cond
+        ((/= thingy thingy) "NaN")
+        ((< thingy 0) (concatenate 'string "-" (to-string-float (- thingy))))
+        ((= thingy 0) "0.0")
+        ((= thingy (* thingy 2)) "Infinity")
+        ((>= thingy 10000000.0) (to-string-float-scientific (rational thingy)))
+        ((< thingy 0.001) (to-string-float-scientific (rational thingy)))
+        (t (to-string-float-fixed (rational thingy)))))
+
+;; one digit before the point and up to six after it
+(defun to-string-float-scientific (number)
+    (let ((exponent (decimal-exponent number)))
+        (let ((digits (round-half-up (times-power-of-ten number (- 6 exponent)))))
+            ;; rounding up can carry over to the next power of ten
+            (if (= digits 10000000)
+                (progn
+                    (setq digits 1000000)
+                    (setq exponent (+ exponent 1))))
+            (concatenate 'string
+                (to-string-float-parts (floor digits 1000000) (mod digits 1000000) 6)
+                "e" (to-string-number exponent)))))
+
+(defun to-string-float-fixed (number)
+    (let ((digits (fraction-digits number)))
+        (let ((scale (power-of-ten digits)))
+            ;; rounding up can carry over to the whole part
+            (let ((scaled (round-half-up (* number scale))))
+                (to-string-float-parts (floor scaled scale) (mod scaled scale) digits)))))
+
+;; unlike round, which goes to the even integer when halfway
+(defun round-half-up (number)
+    (floor (+ number (/ 1 2))))
+
+;; the exponent of the largest power of ten that is not larger than the
+;; positive number
+(defun decimal-exponent (number)
+    (cond
+        ((>= number 10) (+ (decimal-exponent (/ number 10)) 1))
+        ((< number 1) (- (decimal-exponent (* number 10)) 1))
+        (t 0)))
+
+(defun times-power-of-ten (number exponent)
+    (if (< exponent 0)
+        (/ number (power-of-ten (- exponent)))
+        (* number (power-of-ten exponent))))
+
+(defun to-string-float-parts (whole fraction digits)
+    (concatenate 'string (to-string-number whole) "." (to-string-fraction fraction digits)))
+
+;; digits after the point so that there are seven significant digits, which
+;; start at the first digit that is not zero, but at least one
+(defun fraction-digits (number)
+    (let ((exponent (decimal-exponent number)))
+        (if (>= exponent 6) 1 (- 6 exponent))))
+
+(defun power-of-ten (exponent)
+    (if (= exponent 0)
+        1
+        (* 10 (power-of-ten (- exponent 1)))))
+
+;; leaves out trailing zeros, but keeps at least one digit
+(defun to-string-fraction (fraction digits)
+    (if (and (> digits 1) (= (- fraction (* 10 (floor fraction 10))) 0))
+        (to-string-fraction (floor fraction 10) (- digits 1))
+        (to-string-padded fraction digits)))
+
+;; with leading zeros up to the given number of digits
+(defun to-string-padded (number digits)
+    (if (<= digits 1)
+        (to-string-digit number)
+        (concatenate 'string
+            (to-string-padded (floor number 10) (- digits 1))
+            (to-string-digit (- number (* 10 (floor number 10)))))))
 
 (defun to-string-string (thingy)
     ;; TODO should escape interior quotes
@@ -44,20 +167,32 @@
 (defun to-string-symbol (thingy)
     (concatenate 'string "SYMBOL:" thingy))
 
+(defun to-string-character (thingy)
+    (cond
+        ((char= thingy #\Space) "#\\Space")
+        ((char= thingy #\Newline) "#\\Newline")
+        (t (concatenate 'string "#\\" (intrinsic:char-string thingy)))))
+
+(defun to-string-vector (thingy)
+    (let ((items ""))
+        (dotimes (index (length thingy))
+            (setq items (concatenate 'string
+                items (if (= index 0) "" " ") (to-string-any (aref thingy index)))))
+        (concatenate 'string "#(" items ")")))
+
 (defun to-string-any (thingy)
-    (if (null thingy)
-        "NIL"
-        (if (consp thingy)
-            (to-string-list thingy)
-            (if (numberp thingy)
-                (to-string-number thingy)
-                (if (stringp thingy)
-                    (to-string-string thingy)
-                    (if (symbolp thingy)
-                        (to-string-symbol thingy)
-                        (if (functionp thingy)
-                            "FUNCTION"
-                            "CANNOTDUMPTHIS")))))))
+    (cond
+        ((null thingy) "NIL")
+        ((consp thingy) (to-string-list thingy))
+        ((floatp thingy) (to-string-float thingy))
+        ((ratiop thingy) (to-string-ratio thingy))
+        ((numberp thingy) (to-string-number thingy))
+        ((stringp thingy) (to-string-string thingy))
+        ((symbolp thingy) (to-string-symbol thingy))
+        ((characterp thingy) (to-string-character thingy))
+        ((simple-vector-p thingy) (to-string-vector thingy))
+        ((functionp thingy) "FUNCTION")
+        (t "CANNOTDUMPTHIS")))
 
 (defun dump (first &rest rest)
     (format t (to-string-any first))
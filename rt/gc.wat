@@ -0,0 +1,543 @@
+;; gc.wat start
+;; ============
+
+;; mark-sweep garbage collector for the heap, which starts at $heap_base and
+;; ends at $heap_start, where fresh memory is taken from when no free block
+;; fits.
+;;
+;; every block on the heap is either an object starting with its type tag, or
+;; a free block starting with its size in bytes plus the free bit 0x40000000.
+;; free blocks of at least two words are linked in the list at $free_list
+;; through their second word, single free words are only reclaimed when they
+;; merge with free neighbors in the next sweep.
+;;
+;; roots are the places of global variables, marked by the generated function
+;; $gc_mark_static_places, the values register $values, the list being thrown
+;; in $thrown, the tags of catchers in $catchers, and all words on the stack,
+;; which are either local places or pointers to the blocks of persistent places
+;; of running functions. places may also hold immediate integers, which have
+;; the lowest bit set and are not followed.
+
+;; tries to allocate a word-aligned number of bytes from the free list or the
+;; rest of memory, returns zero if it doesn't fit
+(func $gc_try_alloc (param $bytes i32) (result i32) (local $prev i32) (local $block i32) (local $rest i32) (local $next i32)
+    ;; first fit from the free list
+    global.get $free_list
+    local.set $block
+    (block $not_found (loop $next_block
+        local.get $block
+        i32.eqz
+        br_if $not_found
+        ;; remaining bytes after allocating from this block
+        local.get $block
+        i32.load
+        i32.const 0x3FFFFFFF ;; without free bit
+        i32.and
+        local.get $bytes
+        i32.sub
+        local.tee $rest
+        i32.const 0
+        i32.ge_s
+        if
+            local.get $block
+            i32.const 4
+            i32.add
+            i32.load
+            local.set $next
+            local.get $rest
+            i32.const 8
+            i32.ge_s
+            if
+                ;; split and let the remaining block take the place in the list
+                local.get $block
+                local.get $bytes
+                i32.add
+                local.get $rest
+                i32.const 0x40000000
+                i32.or
+                i32.store
+                local.get $block
+                local.get $bytes
+                i32.add
+                i32.const 4
+                i32.add
+                local.get $next
+                i32.store
+                local.get $block
+                local.get $bytes
+                i32.add
+                local.set $next
+            else
+                local.get $rest
+                if
+                    ;; a single free word that is not in the list
+                    local.get $block
+                    local.get $bytes
+                    i32.add
+                    i32.const 0x40000004
+                    i32.store
+                end
+            end
+            ;; unlink the allocated block
+            local.get $prev
+            if
+                local.get $prev
+                i32.const 4
+                i32.add
+                local.get $next
+                i32.store
+            else
+                local.get $next
+                global.set $free_list
+            end
+            local.get $block
+            return
+        end
+        local.get $block
+        local.set $prev
+        local.get $block
+        i32.const 4
+        i32.add
+        i32.load
+        local.set $block
+        br $next_block
+    ))
+    ;; otherwise take fresh memory after the last block if there is enough,
+    ;; compared with 64 bits since the end can be at 4GiB
+    global.get $heap_start
+    i64.extend_i32_u
+    local.get $bytes
+    i64.extend_i32_u
+    i64.add
+    memory.size
+    i64.extend_i32_u
+    i64.const 65536
+    i64.mul
+    i64.gt_u
+    if
+        i32.const 0
+        return
+    end
+    global.get $heap_start ;; return value
+    global.get $heap_start
+    local.get $bytes
+    i32.add
+    global.set $heap_start
+)
+
+;; grows memory so that the given number of bytes fit after the last block,
+;; returns zero if that would exceed $max_pages or the host refuses. memory
+;; grows at least by its current size, so that collections do not get more
+;; frequent the more memory is in use
+(func $gc_grow (param $bytes i32) (result i32) (local $needed i32) (local $pages i32) (local $grow_by i32) (local $room i32) (local $missing i64)
+    ;; pages needed for the end of the allocation, rounded up, nothing may be
+    ;; missing if growing early
+    global.get $heap_start
+    i64.extend_i32_u
+    local.get $bytes
+    i64.extend_i32_u
+    i64.add
+    memory.size
+    i64.extend_i32_u
+    i64.const 65536
+    i64.mul
+    i64.sub
+    local.tee $missing
+    i64.const 0
+    i64.gt_s
+    if
+        local.get $missing
+        i64.const 65535
+        i64.add
+        i64.const 16
+        i64.shr_u
+        i32.wrap_i64
+        local.set $needed
+    end
+    memory.size
+    local.tee $pages
+    local.get $needed
+    i32.add
+    global.get $max_pages
+    i32.gt_u
+    if
+        i32.const 0
+        return
+    end
+    ;; grow by the larger of needed and current pages, up to the maximum
+    local.get $needed
+    local.get $pages
+    local.get $needed
+    local.get $pages
+    i32.gt_u
+    select
+    local.tee $grow_by
+    global.get $max_pages
+    local.get $pages
+    i32.sub
+    local.tee $room
+    local.get $grow_by
+    local.get $room
+    i32.lt_u
+    select
+    memory.grow
+    i32.const -1
+    i32.ne
+)
+
+;; whether more than half of the memory available to the heap was still in
+;; use after the last collection, so that memory should grow before
+;; collections get more frequent
+(func $gc_mostly_live (result i32)
+    global.get $heap_live
+    i64.extend_i32_u
+    i64.const 2
+    i64.mul
+    memory.size
+    i64.extend_i32_u
+    i64.const 65536
+    i64.mul
+    global.get $heap_base
+    i64.extend_i32_u
+    i64.sub
+    i64.gt_u
+)
+
+;; marks everything reachable from the roots and frees everything else
+(func $gc_collect (local $addr i32)
+    call $gc_mark_static_places
+    global.get $values
+    call $gc_mark
+    global.get $thrown
+    call $gc_mark
+    global.get $catchers
+    call $gc_mark
+    global.get $stack_start
+    local.set $addr
+    (block $done (loop $next_word
+        local.get $addr
+        global.get $stack_bottom
+        i32.ge_u
+        br_if $done
+        local.get $addr
+        i32.load
+        call $gc_mark
+        local.get $addr
+        i32.const 4
+        i32.add
+        local.set $addr
+        br $next_word
+    ))
+    call $gc_sweep
+)
+
+;; marks an object and everything reachable from it, ignoring immediates and
+;; addresses outside of the heap like static data
+(func $gc_mark (param $addr i32) (local $tag i32) (local $place i32) (local $end i32)
+    (loop $next_object
+        local.get $addr
+        i32.const 1
+        i32.and
+        if
+            return
+        end
+        local.get $addr
+        global.get $heap_base
+        i32.lt_u
+        if
+            return
+        end
+        local.get $addr
+        global.get $heap_start
+        i32.ge_u
+        if
+            return
+        end
+        local.get $addr
+        i32.load
+        local.tee $tag
+        i32.const 0x80000000 ;; already marked
+        i32.and
+        if
+            return
+        end
+        local.get $addr
+        local.get $tag
+        i32.const 0x80000000
+        i32.or
+        i32.store
+
+        ;; list node, bignum or ratio, mark the car and continue with the
+        ;; cdr, so that long lists do not need deep recursion
+        local.get $tag
+        i32.const 2
+        i32.eq
+        local.get $tag
+        i32.const 128
+        i32.eq
+        i32.or
+        local.get $tag
+        i32.const 256
+        i32.eq
+        i32.or
+        if
+            local.get $addr
+            i32.const 4
+            i32.add
+            i32.load
+            call $gc_mark
+            local.get $addr
+            i32.const 8
+            i32.add
+            i32.load
+            local.set $addr
+            br $next_object
+        end
+
+        ;; function, continue with the block of persistent places if any,
+        ;; which starts with type tag and length before the first place
+        local.get $tag
+        i32.const 32
+        i32.eq
+        if
+            local.get $addr
+            i32.const 8
+            i32.add
+            i32.load
+            i32.const 8
+            i32.sub
+            local.set $addr
+            br $next_object
+        end
+
+        ;; block of places or vector, mark what every place points to
+        local.get $tag
+        i32.const 0x20000000
+        i32.eq
+        local.get $tag
+        i32.const 1024
+        i32.eq
+        i32.or
+        if
+            local.get $addr
+            i32.const 8
+            i32.add
+            local.tee $place
+            local.get $addr
+            i32.const 4
+            i32.add
+            i32.load
+            i32.add
+            local.set $end
+            (block $done (loop $next_place
+                local.get $place
+                local.get $end
+                i32.ge_u
+                br_if $done
+                local.get $place
+                i32.load
+                call $gc_mark
+                local.get $place
+                i32.const 4
+                i32.add
+                local.set $place
+                br $next_place
+            ))
+        end
+        ;; other types do not point to anything
+    )
+)
+
+;; size of the object or free block at the given address in bytes
+(func $gc_block_size (param $addr i32) (result i32) (local $tag i32)
+    local.get $addr
+    i32.load
+    i32.const 0x7FFFFFFF ;; without mark bit
+    i32.and
+    local.tee $tag
+    i32.const 0x40000000
+    i32.and
+    if
+        local.get $tag
+        i32.const 0x3FFFFFFF
+        i32.and
+        return
+    end
+    ;; list nodes, functions, bignums and ratios have three words
+    local.get $tag
+    i32.const 2
+    i32.eq
+    local.get $tag
+    i32.const 32
+    i32.eq
+    i32.or
+    local.get $tag
+    i32.const 128
+    i32.eq
+    i32.or
+    local.get $tag
+    i32.const 256
+    i32.eq
+    i32.or
+    if
+        i32.const 12
+        return
+    end
+    ;; numbers, floats and characters have two
+    local.get $tag
+    i32.const 4
+    i32.eq
+    local.get $tag
+    i32.const 64
+    i32.eq
+    i32.or
+    local.get $tag
+    i32.const 512
+    i32.eq
+    i32.or
+    if
+        i32.const 8
+        return
+    end
+    ;; strings, identifiers, vectors and blocks of places have a length in
+    ;; bytes after the type tag, rounded up to whole words
+    local.get $tag
+    i32.const 8
+    i32.eq
+    local.get $tag
+    i32.const 16
+    i32.eq
+    i32.or
+    local.get $tag
+    i32.const 1024
+    i32.eq
+    i32.or
+    local.get $tag
+    i32.const 0x20000000
+    i32.eq
+    i32.or
+    if
+        local.get $addr
+        i32.const 4
+        i32.add
+        i32.load
+        i32.const 11 ;; two words for type and length, plus three for rounding up
+        i32.add
+        i32.const -4
+        i32.and
+        return
+    end
+    unreachable ;; heap is corrupted
+)
+
+;; frees all unmarked objects and unmarks the rest, adjacent free blocks are
+;; merged and the last ones are given back to the rest of memory
+(func $gc_sweep (local $addr i32) (local $size i32) (local $tag i32) (local $run i32) (local $tail i32)
+    i32.const 0
+    global.set $free_list
+    i32.const 0
+    global.set $heap_live
+    global.get $heap_base
+    local.set $addr
+    i32.const -1 ;; no run of free blocks so far
+    local.set $run
+    (block $done (loop $next_block
+        local.get $addr
+        global.get $heap_start
+        i32.ge_u
+        br_if $done
+        local.get $addr
+        call $gc_block_size
+        local.set $size
+        local.get $addr
+        i32.load
+        local.tee $tag
+        i32.const 0x80000000
+        i32.and
+        if
+            ;; live, so unmark it and end any run of free blocks before it
+            local.get $addr
+            local.get $tag
+            i32.const 0x7FFFFFFF
+            i32.and
+            i32.store
+            global.get $heap_live
+            local.get $size
+            i32.add
+            global.set $heap_live
+            local.get $run
+            i32.const -1
+            i32.ne
+            if
+                local.get $run
+                local.get $addr
+                local.get $run
+                i32.sub
+                local.get $tail
+                call $gc_free_run
+                local.set $tail
+                i32.const -1
+                local.set $run
+            end
+        else
+            local.get $run
+            i32.const -1
+            i32.eq
+            if
+                local.get $addr
+                local.set $run
+            end
+        end
+        local.get $addr
+        local.get $size
+        i32.add
+        local.set $addr
+        br $next_block
+    ))
+    ;; free blocks at the end become part of the rest of memory
+    local.get $run
+    i32.const -1
+    i32.ne
+    if
+        local.get $run
+        global.set $heap_start
+    end
+)
+
+;; turns a run of garbage into a single free block and appends it to the free
+;; list after the given tail, returning the new tail
+(func $gc_free_run (param $start i32) (param $size i32) (param $tail i32) (result i32)
+    local.get $start
+    local.get $size
+    i32.const 0x40000000
+    i32.or
+    i32.store
+    ;; single words are not linked
+    local.get $size
+    i32.const 8
+    i32.lt_u
+    if
+        local.get $tail
+        return
+    end
+    local.get $start
+    i32.const 4
+    i32.add
+    i32.const 0
+    i32.store
+    local.get $tail
+    if
+        local.get $tail
+        i32.const 4
+        i32.add
+        local.get $start
+        i32.store
+    else
+        local.get $start
+        global.set $free_list
+    end
+    local.get $start
+)
+
+;; gc.wat end
+;; ==========
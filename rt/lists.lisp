@@ -10,6 +10,12 @@
     ;; TODO param should be a list
     (intrinsic:cdr list))
 
+(defun rplaca (cons object)
+    (intrinsic:rplaca (assert-cons cons) object))
+
+(defun rplacd (cons object)
+    (intrinsic:rplacd (assert-cons cons) object))
+
 (defun list (&rest items)
     items)
 
@@ -20,24 +26,22 @@
     (if thingy nil t))
 
 (defun append (&rest lists)
-    (let (
-        (first (car lists))
-        (rest (cdr lists)))
-        (if (null rest)
-            first
-            (apply #'append (cons (append-2 first (car rest)) (cdr rest))))))
-
-;; this could maybe be private or letf
-(defun append-2 (before after)
-    (if (null before)
-        after
-        (if (null after)
-            before
-            (if (null (cdr before))
-                ;; last item
-                (cons (car before) after)
-                ;; item before
-                (cons (car before) (append-2 (cdr before) after))))))
+    (labels ((append-2 (before after)
+                (if (null before)
+                    after
+                    (if (null after)
+                        before
+                        (if (null (cdr before))
+                            ;; last item
+                            (cons (car before) after)
+                            ;; item before
+                            (cons (car before) (append-2 (cdr before) after)))))))
+        (let (
+            (first (car lists))
+            (rest (cdr lists)))
+            (if (null rest)
+                first
+                (apply #'append (cons (append-2 first (car rest)) (cdr rest)))))))
 
 
 (defun remove-if-not (func list)
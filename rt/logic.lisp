@@ -1,2 +1,9 @@
 (defun not (thingy)
     (if thingy nil t))
+
+;; compares the words themselves. symbols are only stored once, and fixnums
+;; are immediates that compare by value. boxed numbers (floats, bignums and
+;; ratios) and characters compare by identity, so equal ones are not
+;; necessarily eq
+(defun eq (left right)
+    (intrinsic:eq-2 left right))
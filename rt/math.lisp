@@ -1,66 +1,297 @@
 (defun + (&rest addends)
     (if (null addends)
         0
-        (intrinsic:add-2 (car addends) (apply #'+ (cdr addends)))))
+        (add-2 (car addends) (apply #'+ (cdr addends)))))
 
 (defun - (arg &rest rest)
     (if (null rest)
-        (intrinsic:sub-2 0 arg)
+        (sub-2 0 arg)
         (subtract-list arg rest)))
 
 ;; not a standard function, just lack of module privacy to have this public
 (defun subtract-list (subtrahend list)
     (if (null list)
         subtrahend
-        (subtract-list (intrinsic:sub-2 subtrahend (car list)) (cdr list))))
-
-;; division would need fractions and is unsupported, but floor can be used
+        (subtract-list (sub-2 subtrahend (car list)) (cdr list))))
 
 (defun * (&rest factors)
     (if (null factors)
         1
-        (intrinsic:mul-2 (assert-number (car factors)) (apply #'* (cdr factors)))))
+        (mul-2 (assert-number (car factors)) (apply #'* (cdr factors)))))
+
+(defun / (arg &rest rest)
+    (if (null rest)
+        (divide-2 1 arg)
+        (divide-list arg rest)))
+
+(defun divide-list (dividend list)
+    (if (null list)
+        dividend
+        (divide-list (divide-2 dividend (car list)) (cdr list))))
+
+;; the functions that divide to an integer return the remainder as a second
+;; value. the quotient is rounded towards zero by truncate, and the others
+;; adjust that by one where it rounds the wrong way
+(defun truncate (number &optional (divisor 1))
+    (let ((quotient (truncate-quotient number divisor)))
+        (values quotient (sub-2 number (mul-2 quotient divisor)))))
+
+(defun floor (number &optional (divisor 1))
+    (multiple-value-bind (quotient remainder) (truncate number divisor)
+        (if (or (and (<-2 remainder 0) (<-2 0 divisor))
+                (and (<-2 0 remainder) (<-2 divisor 0)))
+            (values (sub-2 quotient 1) (add-2 remainder divisor))
+            (values quotient remainder))))
+
+(defun ceiling (number &optional (divisor 1))
+    (multiple-value-bind (quotient remainder) (truncate number divisor)
+        (if (or (and (<-2 0 remainder) (<-2 0 divisor))
+                (and (<-2 remainder 0) (<-2 divisor 0)))
+            (values (add-2 quotient 1) (sub-2 remainder divisor))
+            (values quotient remainder))))
 
-;; one-argument version not supported
-(defun floor (top bottom)
-    (intrinsic:div-2 (assert-number top) (assert-number bottom)))
+;; halfway between two integers, round goes to the even one
+(defun round (number &optional (divisor 1))
+    (multiple-value-bind (quotient remainder) (floor number divisor)
+        ;; the remainder has the sign of the divisor, and is past the halfway
+        ;; point if twice of it is further from zero than the divisor
+        (let ((twice (mul-2 remainder 2)))
+            (if (or (if (<-2 0 divisor) (<-2 divisor twice) (<-2 twice divisor))
+                    (and (=-2 twice divisor) (oddp quotient)))
+                (values (add-2 quotient 1) (sub-2 remainder divisor))
+                (values quotient remainder)))))
+
+(defun mod (number divisor)
+    (multiple-value-bind (quotient remainder) (floor number divisor)
+        remainder))
+
+(defun rem (number divisor)
+    (multiple-value-bind (quotient remainder) (truncate number divisor)
+        remainder))
+
+(defun evenp (integer)
+    (=-2 (rem (assert-integer integer) 2) 0))
+
+(defun oddp (integer)
+    (not (evenp integer)))
+
+;; only the one-argument version is supported
+(defun float (number)
+    (cond
+        ((floatp number) number)
+        ((bignump number) (bignum-to-float number))
+        ((ratiop number) (ratio-to-float number))
+        (t (intrinsic:int-to-float (assert-number number)))))
+
+(defun signal-division-by-zero ()
+    (error 'division-by-zero :format-control "arithmetic error: division by zero"))
 
 (defun = (first &rest rest)
     (if (null rest)
         t
         (and
-            (intrinsic:=-2 first (car rest))
+            (=-2 first (car rest))
             (apply #'= rest))))
 
 (defun < (first &rest rest)
     (if (null rest)
         t
         (and
-            (intrinsic:<-2 first (car rest))
+            (<-2 first (car rest))
             (apply #'< rest))))
 
 (defun > (first &rest rest)
     (if (null rest)
         t
         (and
-            (intrinsic:>-2 first (car rest))
+            (<-2 (car rest) first)
             (apply #'> rest))))
 
 (defun <= (first &rest rest)
     (if (null rest)
         t
         (and
-            (intrinsic:<=-2 first (car rest))
+            (<=-2 first (car rest))
             (apply #'<= rest))))
 
 (defun >= (first &rest rest)
     (if (null rest)
         t
         (and
-            (intrinsic:>=-2 first (car rest))
+            (<=-2 (car rest) first)
             (apply #'>= rest))))
 
 ;; the one and three or more argument versions of common lisp are not
 ;; supported (would need to check all permutations)
 (defun /= (first second)
-    (intrinsic:/=-2 first second))
+    (not (=-2 first second)))
+
+;; the two-argument versions of the functions above are not standard either.
+;; integers of 32 bits are added etc. with the intrinsics, and with bignums if
+;; the intrinsic overflows and returns nil or if one of them is a bignum. floats
+;; are contagious: if one of the arguments is a float, the other one is
+;; converted to a float too. otherwise a ratio makes the other argument be
+;; treated as a ratio too
+
+(defun small-integers-p (left right)
+    (and
+        (intrinsic:=-2 (intrinsic:type-tag-of left) *tag-sint32*)
+        (intrinsic:=-2 (intrinsic:type-tag-of right) *tag-sint32*)))
+
+(defun float-contagion-p (left right)
+    (or (floatp left) (floatp right)))
+
+(defun ratio-contagion-p (left right)
+    (or (ratiop left) (ratiop right)))
+
+(defun add-2 (left right)
+    (cond
+        ((small-integers-p left right)
+            (or (intrinsic:add-2 left right) (bignum-add left right)))
+        ((float-contagion-p left right)
+            (intrinsic:float-add-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (ratio-add (assert-rational left) (assert-rational right)))
+        (t (bignum-add (assert-integer left) (assert-integer right)))))
+
+(defun sub-2 (left right)
+    (cond
+        ((small-integers-p left right)
+            (or (intrinsic:sub-2 left right) (bignum-subtract left right)))
+        ((float-contagion-p left right)
+            (intrinsic:float-sub-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (ratio-subtract (assert-rational left) (assert-rational right)))
+        (t (bignum-subtract (assert-integer left) (assert-integer right)))))
+
+(defun mul-2 (left right)
+    (cond
+        ((small-integers-p left right)
+            (or (intrinsic:mul-2 left right) (bignum-multiply left right)))
+        ((float-contagion-p left right)
+            (intrinsic:float-mul-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (ratio-multiply (assert-rational left) (assert-rational right)))
+        (t (bignum-multiply (assert-integer left) (assert-integer right)))))
+
+;; division of integers that truncates towards zero
+(defun truncate-2 (dividend divisor)
+    (if (small-integers-p dividend divisor)
+        (or (intrinsic:div-2 dividend divisor)
+            (bignum-truncate dividend divisor))
+        (bignum-truncate (assert-integer dividend) (assert-integer divisor))))
+
+;; integers are divided with the intrinsic first, which is exact if multiplying
+;; back gives the dividend
+(defun divide-2 (dividend divisor)
+    (cond
+        ((float-contagion-p dividend divisor)
+            (intrinsic:float-div-2 (float dividend) (float divisor)))
+        ((=-2 (assert-number divisor) 0) (signal-division-by-zero))
+        ((small-integers-p dividend divisor)
+            (let ((quotient (intrinsic:div-2 dividend divisor)))
+                (if (and quotient
+                         (intrinsic:=-2 (intrinsic:mul-2 quotient divisor) dividend))
+                    quotient
+                    (ratio-divide dividend divisor))))
+        (t (ratio-divide dividend divisor))))
+
+;; the quotient rounded towards zero, as an integer
+(defun truncate-quotient (number divisor)
+    (cond
+        ((=-2 (assert-number divisor) 0) (signal-division-by-zero))
+        ((and (integerp number) (integerp divisor)) (truncate-2 number divisor))
+        ((float-contagion-p number divisor)
+            (float-to-integer
+                (intrinsic:float-div-2 (float number) (float divisor))))
+        (t (let ((quotient (divide-2 number divisor)))
+            (truncate-2 (numerator quotient) (denominator quotient))))))
+
+;; floats in the 32-bit range are truncated with the intrinsic, which cannot
+;; convert the others. these are whole numbers already, since floats only have
+;; 24 bits of precision, and become bignums
+(defun float-to-integer (float)
+    (cond
+        ((and (intrinsic:float-<=-2 -2147483648.0 float)
+              (intrinsic:float-<-2 float 2147483648.0))
+            (intrinsic:float-truncate float))
+        ;; nan is not in the range above, and infinity is the only other float
+        ;; that does not change when doubled
+        ((or (not (intrinsic:float-=-2 float float))
+             (intrinsic:float-=-2 float (intrinsic:float-add-2 float float)))
+            (error 'arithmetic-error
+                :format-control "arithmetic error: float is not a finite number"))
+        ((intrinsic:float-<-2 float 0.0)
+            (make-integer -1 (float-digits (intrinsic:float-sub-2 0.0 float))))
+        (t (make-integer 1 (float-digits float)))))
+
+;; the digits of a positive whole float, going down from the highest power of
+;; the radix that fits into it. dividing and multiplying by powers of two is
+;; exact, so no precision is lost on the way
+(defun float-digits (float)
+    (let ((scale 1.0) (digits nil))
+        (do () ((intrinsic:float-<-2 float (intrinsic:float-mul-2 scale 32768.0)))
+            (setq scale (intrinsic:float-mul-2 scale 32768.0)))
+        (do () ((intrinsic:float-<-2 scale 1.0) digits)
+            (let ((digit (intrinsic:float-truncate (intrinsic:float-div-2 float scale))))
+                (setq float (intrinsic:float-sub-2
+                    float
+                    (intrinsic:float-mul-2 (intrinsic:int-to-float digit) scale)))
+                (setq digits (cons digit digits))
+                (setq scale (intrinsic:float-div-2 scale 32768.0))))))
+
+;; the exact value of a finite float as an integer or ratio. the float is
+;; doubled until it is a whole number, which is exact, and then divided by the
+;; power of two it was multiplied with
+(defun rational (number)
+    (cond
+        ((not (floatp number)) (assert-rational number))
+        ((intrinsic:float-<-2 number 0.0)
+            (sub-2 0 (rational (intrinsic:float-sub-2 0.0 number))))
+        (t (let ((denominator 1))
+            ;; floats of 2^23 and more have no fraction anymore
+            (do () ((or (intrinsic:float-<=-2 8388608.0 number)
+                        (intrinsic:float-=-2 number
+                            (intrinsic:int-to-float (intrinsic:float-truncate number)))))
+                (setq number (intrinsic:float-mul-2 number 2.0))
+                (setq denominator (mul-2 denominator 2)))
+            (divide-2 (float-to-integer number) denominator)))))
+
+(defun =-2 (left right)
+    (cond
+        ((small-integers-p left right) (intrinsic:=-2 left right))
+        ((float-contagion-p left right)
+            (intrinsic:float-=-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (intrinsic:=-2
+                (ratio-compare (assert-rational left) (assert-rational right))
+                0))
+        (t (intrinsic:=-2
+            (bignum-compare (assert-integer left) (assert-integer right))
+            0))))
+
+(defun <-2 (left right)
+    (cond
+        ((small-integers-p left right) (intrinsic:<-2 left right))
+        ((float-contagion-p left right)
+            (intrinsic:float-<-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (intrinsic:<-2
+                (ratio-compare (assert-rational left) (assert-rational right))
+                0))
+        (t (intrinsic:<-2
+            (bignum-compare (assert-integer left) (assert-integer right))
+            0))))
+
+(defun <=-2 (left right)
+    (cond
+        ((small-integers-p left right) (intrinsic:<=-2 left right))
+        ((float-contagion-p left right)
+            (intrinsic:float-<=-2 (float left) (float right)))
+        ((ratio-contagion-p left right)
+            (intrinsic:<=-2
+                (ratio-compare (assert-rational left) (assert-rational right))
+                0))
+        (t (intrinsic:<=-2
+            (bignum-compare (assert-integer left) (assert-integer right))
+            0))))
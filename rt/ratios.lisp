@@ -0,0 +1,82 @@
+;; ratios are quotients of integers that are not integers themselves, with the
+;; numerator as their car and the denominator as their cdr. they are kept in
+;; lowest terms with a denominator greater than one, so that every rational
+;; number has a single representation.
+;;
+;; the functions here take any rational numbers, and make an integer if the
+;; denominator of the result is one.
+
+(defun ratio-add (left right)
+    (make-rational
+        (add-2 (mul-2 (numerator left) (denominator right))
+               (mul-2 (numerator right) (denominator left)))
+        (mul-2 (denominator left) (denominator right))))
+
+(defun ratio-subtract (left right)
+    (make-rational
+        (sub-2 (mul-2 (numerator left) (denominator right))
+               (mul-2 (numerator right) (denominator left)))
+        (mul-2 (denominator left) (denominator right))))
+
+(defun ratio-multiply (left right)
+    (make-rational
+        (mul-2 (numerator left) (numerator right))
+        (mul-2 (denominator left) (denominator right))))
+
+;; the divisor must not be zero
+(defun ratio-divide (dividend divisor)
+    (make-rational
+        (mul-2 (numerator dividend) (denominator divisor))
+        (mul-2 (denominator dividend) (numerator divisor))))
+
+;; -1, 0 or 1 if left is less than, equal to or greater than right, which
+;; compares the numerators over a common denominator
+(defun ratio-compare (left right)
+    (let ((left (mul-2 (numerator left) (denominator right)))
+          (right (mul-2 (numerator right) (denominator left))))
+        (cond
+            ((<-2 left right) -1)
+            ((<-2 right left) 1)
+            (t 0))))
+
+(defun ratio-to-float (ratio)
+    (intrinsic:float-div-2
+        (float (intrinsic:car ratio))
+        (float (intrinsic:cdr ratio))))
+
+;; the denominator must not be zero
+(defun make-rational (numerator denominator)
+    (let ((divisor (gcd-2 numerator denominator)))
+        ;; the sign goes to the numerator
+        (if (<-2 denominator 0)
+            (setq divisor (sub-2 0 divisor)))
+        (let ((numerator (truncate-2 numerator divisor))
+              (denominator (truncate-2 denominator divisor)))
+            (if (=-2 denominator 1)
+                numerator
+                (intrinsic:make-ratio numerator denominator)))))
+
+(defun numerator (rational)
+    (if (ratiop rational)
+        (intrinsic:car rational)
+        (assert-rational rational)))
+
+(defun denominator (rational)
+    (if (ratiop (assert-rational rational))
+        (intrinsic:cdr rational)
+        1))
+
+(defun gcd (&rest integers)
+    (let ((divisor 0))
+        (dolist (integer integers divisor)
+            (setq divisor (gcd-2 divisor (assert-integer integer))))))
+
+;; euclid's algorithm in a loop, the result is never negative
+(defun gcd-2 (left right)
+    (do ((left (integer-abs left) right)
+         (right (integer-abs right)
+             (sub-2 left (mul-2 (truncate-2 left right) right))))
+        ((=-2 right 0) left)))
+
+(defun integer-abs (integer)
+    (if (<-2 integer 0) (sub-2 0 integer) integer))
@@ -2,9 +2,19 @@
 ;; ============
 
 ;; runtime assumes the following variables are available and pre-initialized:
+;; (global $stack_start i32 (i32.const {}))
 ;; (global $stack_bottom (mut i32) (i32.const {}))
 ;; (global $stack_top i32 (i32.const {}))
+;; (global $heap_base i32 (i32.const {}))
 ;; (global $heap_start (mut i32) (i32.const {}))
+;; (global $free_list (mut i32) (i32.const 0))
+;; (global $heap_live (mut i32) (i32.const 0))
+;; (global $values (mut i32) (i32.const 0))
+;; (global $max_pages i32 (i32.const {}))
+;; (global $out_of_memory_message i32 (i32.const {}))
+;; (global $thrown (mut i32) (i32.const 0))
+;; (global $catchers (mut i32) (i32.const 0))
+;; (global $uncaught_throw_message i32 (i32.const {}))
 
 (type $user_fun (func (param i32) (param i32) (result i32)))
 
@@ -25,59 +35,199 @@
     call_indirect (type $user_fun)
 )
 
+;; panics with the given message if there is not enough space left on the stack
+;; to grow it by the given number of bytes
+(func $check_stack (param $by i32) (param $message i32)
+    global.get $stack_bottom
+    local.get $by
+    i32.add
+    global.get $stack_top
+    i32.gt_u
+    if
+        local.get $message
+        call $panic_with_message
+    end
+)
+
 (func $inc_stack_bottom (param $by i32)
     global.get $stack_bottom
     local.get $by
     i32.add
     global.set $stack_bottom)
 
-;; allocates space on the heap and returns the start
-(func $alloc_heap (param $bytes i32) (result i32)
-    global.get $heap_start ;; return value
-    global.get $heap_start ;; new value to be written
+;; allocates space on the heap and returns the start, collecting garbage if
+;; the heap is full and then growing memory if that did not help or if most of
+;; the heap is still in use, the size is rounded up to whole words
+(func $alloc_heap (param $bytes i32) (result i32) (local $addr i32)
     local.get $bytes
+    i32.const 3
     i32.add
-    global.set $heap_start
+    i32.const -4
+    i32.and
+    local.set $bytes
+    local.get $bytes
+    call $gc_try_alloc
+    local.tee $addr
+    if
+        local.get $addr
+        return
+    end
+    call $gc_collect
+    call $gc_mostly_live
+    if
+        local.get $bytes
+        call $gc_grow
+        drop
+    end
+    local.get $bytes
+    call $gc_try_alloc
+    local.tee $addr
+    if
+        local.get $addr
+        return
+    end
+    local.get $bytes
+    call $gc_grow
+    i32.eqz
+    if
+        global.get $out_of_memory_message
+        call $panic_with_message
+    end
+    local.get $bytes
+    call $gc_try_alloc
 )
 
-(func $make_function (param $table_idx i32) (param $stack i32) (result i32)
-    global.get $heap_start ;; return value
-    global.get $heap_start ;; target of later store for type
-    global.get $heap_start ;; target of later store for table index
+;; throws unwind to the innermost catcher of their tag, the thrown list of tag
+;; and value is in $thrown while unwinding and $catchers is a list of the tags
+;; of active catchers, innermost first. without exception handling, code
+;; checks $thrown after every call and branches to the nearest region that
+;; catches throws, where tagged catchers pop their tag and either take the
+;; thrown list or continue unwinding.
+
+;; makes the tag the innermost active catcher
+(func $push_catcher (param $tag i32) (local $addr i32)
+    i32.const 12
+    call $alloc_heap
+    local.tee $addr
+    i32.const 2 ;; list node
+    i32.store
+    local.get $addr
     i32.const 4
     i32.add
-    global.get $heap_start ;; target of later store for stack
+    local.get $tag
+    i32.store
+    local.get $addr
     i32.const 8
     i32.add
-    global.get $heap_start ;; calculate and set new top
-    i32.const 12 ;; actual length includes space for type tag, table index and stack
+    global.get $catchers
+    i32.store
+    local.get $addr
+    global.set $catchers
+)
+
+(func $pop_catcher
+    global.get $catchers
+    i32.const 8
     i32.add
-    global.set $heap_start
-    local.get $stack
+    i32.load
+    global.set $catchers
+)
+
+;; starts unwinding with the given list of tag and value, or panics if no
+;; active catcher has the tag, so that nothing is unwound in that case
+(func $throw (param $thrown i32) (local $catcher i32)
+    global.get $catchers
+    local.set $catcher
+    (block $found (loop $next_catcher
+        local.get $catcher
+        i32.eqz
+        if
+            global.get $uncaught_throw_message
+            call $panic_with_message
+        end
+        local.get $catcher
+        i32.const 4
+        i32.add
+        i32.load
+        local.get $thrown
+        i32.const 4
+        i32.add
+        i32.load
+        i32.eq
+        br_if $found
+        local.get $catcher
+        i32.const 8
+        i32.add
+        i32.load
+        local.set $catcher
+        br $next_catcher
+    ))
+    local.get $thrown
+    global.set $thrown
+)
+
+;; prints the given string and panics
+(func $panic_with_message (param $message i32)
+    local.get $message
+    i32.const 8 ;; skip type tag and length
+    i32.add
+    local.get $message
+    i32.const 4
+    i32.add
+    i32.load
+    call $log
+    unreachable
+)
+
+(func $make_function (param $table_idx i32) (param $stack i32) (result i32) (local $addr i32)
+    i32.const 12 ;; actual length includes space for type tag, table index and stack
+    call $alloc_heap
+    local.tee $addr ;; target of store for type
+    i32.const 32 ;; 32 is type for function (=0b10_0000)
     i32.store
+    local.get $addr ;; target of store for table index
+    i32.const 4
+    i32.add
     local.get $table_idx
     i32.store
-    i32.const 32 ;; 32 is type for function (=0b10_0000)
+    local.get $addr ;; target of store for stack
+    i32.const 8
+    i32.add
+    local.get $stack
     i32.store
+    local.get $addr ;; return value
 )
 
 ;; allocates a sized thing with the given length and type tag and returns the beginning of the allocation, where type and length are already set and the rest is uninitialized
-(func $alloc_sized (param $len_without_type_and_len i32) (param $type i32) (result i32)
-    global.get $heap_start ;; return value
-    global.get $heap_start ;; target of later store for type
-    global.get $heap_start ;; target of later store for length
-    i32.const 4
-    i32.add
-    global.get $heap_start ;; calculate and set new top
+(func $alloc_sized (param $len_without_type_and_len i32) (param $type i32) (result i32) (local $addr i32)
     local.get $len_without_type_and_len
     i32.const 8 ;; actual length includes space for type tag and length
     i32.add
+    call $alloc_heap
+    local.tee $addr ;; target of store for type
+    local.get $type
+    i32.store
+    local.get $addr ;; target of store for length
+    i32.const 4
     i32.add
-    global.set $heap_start
     local.get $len_without_type_and_len
     i32.store
-    local.get $type
-    i32.store
+    local.get $addr ;; return value
+)
+
+;; allocates a block of persistent places that initially point to nil and
+;; returns the address of the first place
+(func $alloc_places (param $len i32) (result i32) (local $places i32)
+    local.get $len
+    i32.const 0x20000000 ;; type for a block of places, only used by the collector
+    call $alloc_sized
+    i32.const 8 ;; skip type tag and length
+    i32.add
+    local.tee $places
+    i32.const 0 ;; nil
+    local.get $len
+    memory.fill
+    local.get $places
 )
 
 (func $concat_strings (param $left_string_address i32) (param $right_string_address i32) (result i32) (local $result_addr i32)
@@ -136,20 +286,495 @@
     local.get $result_addr ;; return value is start of the final allocation
 )
 
-(func $make_num (param $value i32) (result i32)
-    global.get $heap_start ;; return value
-    global.get $heap_start ;; target of later store for type
-    global.get $heap_start ;; target of later store for value
+;; integers that fit into 31 bits are immediates, shifted left by one with the
+;; lowest bit set, which addresses never have since all data is word-aligned.
+;; only larger ones are numbers on the heap
+(func $make_num (param $value i32) (result i32) (local $addr i32)
+    local.get $value
+    i32.const 1
+    i32.shl
+    i32.const 1
+    i32.shr_s
+    local.get $value
+    i32.eq
+    if
+        local.get $value
+        i32.const 1
+        i32.shl
+        i32.const 1
+        i32.or
+        return
+    end
+    i32.const 8 ;; actual length includes space for type tag and actual number
+    call $alloc_heap
+    local.tee $addr ;; target of store for type
+    i32.const 4 ;; 4 is type for number (=0b100)
+    i32.store
+    local.get $addr ;; target of store for value
     i32.const 4
     i32.add
-    global.get $heap_start ;; calculate and set new top
-    i32.const 8 ;; actual length includes space for type tag and actual number
+    local.get $value
+    i32.store
+    local.get $addr ;; return value
+)
+
+;; makes a number of a result that was computed with 64 bits, or returns nil if
+;; it does not fit into 32 bits
+(func $make_num_checked (param $value i64) (param $nil i32) (result i32)
+    local.get $value
+    local.get $value
+    i32.wrap_i64
+    i64.extend_i32_s
+    i64.ne
+    if
+        local.get $nil
+        return
+    end
+    local.get $value
+    i32.wrap_i64
+    call $make_num
+)
+
+;; gets the value of an immediate or of a number on the heap
+(func $num_value (param $num i32) (result i32)
+    local.get $num
+    i32.const 1
+    i32.and
+    if
+        local.get $num
+        i32.const 1
+        i32.shr_s
+        return
+    end
+    local.get $num
+    i32.const 4 ;; skip type tag
+    i32.add
+    i32.load
+)
+
+;; gets the type tag of an object, immediates have the one of numbers
+(func $type_tag_of (param $addr i32) (result i32)
+    local.get $addr
+    i32.const 1
+    i32.and
+    if
+        i32.const 4 ;; 4 is type for number (=0b100)
+        return
+    end
+    local.get $addr
+    i32.load
+)
+
+(func $make_float (param $value f32) (result i32) (local $addr i32)
+    i32.const 8 ;; type tag and the float
+    call $alloc_heap
+    local.tee $addr ;; target of store for type
+    i32.const 64 ;; 64 is type for float (=0b100_0000)
+    i32.store
+    local.get $addr ;; target of store for value
+    i32.const 4
     i32.add
-    global.set $heap_start
     local.get $value
+    f32.store
+    local.get $addr ;; return value
+)
+
+(func $make_char (param $code i32) (result i32) (local $addr i32)
+    i32.const 8 ;; type tag and the code
+    call $alloc_heap
+    local.tee $addr ;; target of store for type
+    i32.const 512 ;; 512 is type for character (=0b10_0000_0000)
     i32.store
-    i32.const 4 ;; 4 is type for number (=0b100)
+    local.get $addr ;; target of store for code
+    i32.const 4
+    i32.add
+    local.get $code
     i32.store
+    local.get $addr ;; return value
+)
+
+;; decodes the character at an index of the utf-8 data of a string or
+;; identifier, counting characters and not bytes, or returns nil if the index
+;; is out of range
+(func $char_at (param $string i32) (param $index i32) (param $nil i32) (result i32) (local $byte i32) (local $end i32) (local $code i32) (local $more i32)
+    local.get $index
+    i32.const 0
+    i32.lt_s
+    if
+        local.get $nil
+        return
+    end
+    ;; character data starts after type tag and length
+    local.get $string
+    i32.const 8
+    i32.add
+    local.tee $byte
+    local.get $string
+    i32.const 4
+    i32.add
+    i32.load
+    i32.add
+    local.set $end
+    ;; skip the characters before the index, counting only first bytes, since
+    ;; the other bytes of a character start with the bits 10
+    (block $found (loop $next_byte
+        local.get $byte
+        local.get $end
+        i32.ge_u
+        if
+            local.get $nil
+            return
+        end
+        local.get $byte
+        i32.load8_u
+        i32.const 0xC0
+        i32.and
+        i32.const 0x80
+        i32.ne
+        if
+            local.get $index
+            i32.eqz
+            br_if $found
+            local.get $index
+            i32.const 1
+            i32.sub
+            local.set $index
+        end
+        local.get $byte
+        i32.const 1
+        i32.add
+        local.set $byte
+        br $next_byte
+    ))
+    ;; the leading ones of the first byte are the number of bytes, except for
+    ;; ascii, and the bits after them start the code
+    local.get $byte
+    i32.load8_u
+    local.tee $code
+    i32.const 24
+    i32.shl
+    i32.const -1
+    i32.xor
+    i32.clz
+    local.set $more
+    local.get $code
+    i32.const 0x7F
+    local.get $more
+    i32.shr_u
+    i32.and
+    local.set $code
+    ;; every following byte has six more bits
+    (block $done (loop $next_continuation
+        local.get $more
+        i32.const 1
+        i32.le_u
+        br_if $done
+        local.get $byte
+        i32.const 1
+        i32.add
+        local.set $byte
+        local.get $code
+        i32.const 6
+        i32.shl
+        local.get $byte
+        i32.load8_u
+        i32.const 0x3F
+        i32.and
+        i32.or
+        local.set $code
+        local.get $more
+        i32.const 1
+        i32.sub
+        local.set $more
+        br $next_continuation
+    ))
+    local.get $code
+    call $make_char
+)
+
+;; makes a string of the utf-8 encoding of a single character
+(func $char_string (param $code i32) (result i32) (local $string i32) (local $len i32) (local $prefix i32)
+    ;; one byte for ascii, and one more for every limit the code reaches
+    i32.const 1
+    local.get $code
+    i32.const 0x80
+    i32.ge_u
+    i32.add
+    local.get $code
+    i32.const 0x800
+    i32.ge_u
+    i32.add
+    local.get $code
+    i32.const 0x10000
+    i32.ge_u
+    i32.add
+    local.tee $len
+    i32.const 8 ;; type for character data (=0b1000)
+    call $alloc_sized
+    local.set $string
+    ;; the first byte starts with as many ones as there are bytes, unless it
+    ;; is the only one
+    i32.const 0xFF00
+    local.get $len
+    i32.shr_u
+    i32.const 0xFF
+    i32.and
+    i32.const 0
+    local.get $len
+    i32.const 1
+    i32.gt_u
+    select
+    local.set $prefix
+    ;; the following bytes from the last one, with six bits of the code each
+    (block $done (loop $next_byte
+        local.get $len
+        i32.const 1
+        i32.le_u
+        br_if $done
+        local.get $string
+        i32.const 7 ;; type tag and length, minus one for the index
+        i32.add
+        local.get $len
+        i32.add
+        local.get $code
+        i32.const 0x3F
+        i32.and
+        i32.const 0x80
+        i32.or
+        i32.store8
+        local.get $code
+        i32.const 6
+        i32.shr_u
+        local.set $code
+        local.get $len
+        i32.const 1
+        i32.sub
+        local.set $len
+        br $next_byte
+    ))
+    local.get $string
+    i32.const 8
+    i32.add
+    local.get $code
+    local.get $prefix
+    i32.or
+    i32.store8
+    local.get $string ;; return value
+)
+
+;; allocates a vector with the given number of elements that initially point
+;; to nil, make-array has checked that their size in bytes fits into 32 bits
+(func $make_vector (param $len i32) (result i32) (local $vector i32)
+    local.get $len
+    i32.const 4
+    i32.mul
+    i32.const 1024 ;; 1024 is type for vector (=0b100_0000_0000)
+    call $alloc_sized
+    local.tee $vector
+    i32.const 8 ;; skip type tag and length
+    i32.add
+    i32.const 0 ;; nil
+    local.get $len
+    i32.const 4
+    i32.mul
+    memory.fill
+    local.get $vector ;; return value
+)
+
+;; counts the elements of a vector, or the characters of the utf-8 data of a
+;; string or identifier
+(func $length_of (param $addr i32) (result i32) (local $byte i32) (local $end i32) (local $count i32)
+    local.get $addr
+    i32.load
+    i32.const 1024
+    i32.eq
+    if
+        local.get $addr
+        i32.const 4
+        i32.add
+        i32.load
+        i32.const 4
+        i32.div_u
+        return
+    end
+    ;; character data starts after type tag and length
+    local.get $addr
+    i32.const 8
+    i32.add
+    local.tee $byte
+    local.get $addr
+    i32.const 4
+    i32.add
+    i32.load
+    i32.add
+    local.set $end
+    ;; count only first bytes, since the other bytes of a character start
+    ;; with the bits 10
+    (block $done (loop $next_byte
+        local.get $byte
+        local.get $end
+        i32.ge_u
+        br_if $done
+        local.get $count
+        local.get $byte
+        i32.load8_u
+        i32.const 0xC0
+        i32.and
+        i32.const 0x80
+        i32.ne
+        i32.add
+        local.set $count
+        local.get $byte
+        i32.const 1
+        i32.add
+        local.set $byte
+        br $next_byte
+    ))
+    local.get $count
+)
+
+;; finds a keyword in the given parameters, which are read as alternating
+;; keywords and values, and returns the rest of the parameters starting at the
+;; value after the keyword, or nil if the keyword was not passed
+(func $find_key_param (param $params i32) (param $keyword i32) (param $nil i32) (result i32) (local $value i32)
+    (block $done (loop $next_pair
+        local.get $params
+        local.get $nil
+        i32.eq
+        br_if $done
+        ;; a keyword without a value is not found
+        local.get $params
+        i32.const 8
+        i32.add
+        i32.load
+        local.tee $value
+        local.get $nil
+        i32.eq
+        br_if $done
+        local.get $params
+        i32.const 4
+        i32.add
+        i32.load
+        local.get $keyword
+        i32.eq
+        if
+            local.get $value
+            return
+        end
+        ;; skip the keyword and the value
+        local.get $value
+        i32.const 8
+        i32.add
+        i32.load
+        local.set $params
+        br $next_pair
+    ))
+    local.get $nil
+)
+
+;; returns 1 if the given parameters are pairs of keywords and values with
+;; every keyword in the given list of keywords, otherwise 0
+(func $key_params_known (param $params i32) (param $keywords i32) (param $nil i32) (result i32) (local $value i32) (local $known i32)
+    (block $done (loop $next_pair
+        local.get $params
+        local.get $nil
+        i32.eq
+        br_if $done
+        ;; a keyword without a value
+        local.get $params
+        i32.const 8
+        i32.add
+        i32.load
+        local.tee $value
+        local.get $nil
+        i32.eq
+        if
+            i32.const 0
+            return
+        end
+        ;; look for the keyword in the known keywords
+        local.get $keywords
+        local.set $known
+        (block $found (loop $next_known
+            local.get $known
+            local.get $nil
+            i32.eq
+            if
+                i32.const 0
+                return
+            end
+            local.get $known
+            i32.const 4
+            i32.add
+            i32.load
+            local.get $params
+            i32.const 4
+            i32.add
+            i32.load
+            i32.eq
+            br_if $found
+            local.get $known
+            i32.const 8
+            i32.add
+            i32.load
+            local.set $known
+            br $next_known
+        ))
+        ;; skip the keyword and the value
+        local.get $value
+        i32.const 8
+        i32.add
+        i32.load
+        local.set $params
+        br $next_pair
+    ))
+    i32.const 1
+)
+
+;; returns 1 if the number of given parameters is at least min and at most
+;; max, otherwise 0, a max of -1 means there is no upper limit and the
+;; parameters are only counted as far as needed
+(func $arity_matches (param $params i32) (param $min i32) (param $max i32) (param $nil i32) (result i32) (local $limit i32) (local $count i32)
+    local.get $max
+    i32.const 1
+    i32.add
+    local.get $min
+    local.get $max
+    i32.const -1
+    i32.ne
+    select
+    local.set $limit
+    (block $done (loop $next_param
+        local.get $params
+        local.get $nil
+        i32.eq
+        br_if $done
+        local.get $count
+        local.get $limit
+        i32.ge_u
+        br_if $done
+        local.get $count
+        i32.const 1
+        i32.add
+        local.set $count
+        local.get $params
+        i32.const 8
+        i32.add
+        i32.load
+        local.set $params
+        br $next_param
+    ))
+    local.get $count
+    local.get $min
+    i32.ge_u
+    local.get $count
+    local.get $max
+    i32.le_u
+    local.get $max
+    i32.const -1
+    i32.eq
+    i32.or
+    i32.and
 )
 
 ;; rt.wat end
@@ -12,4 +12,12 @@
                 (concatenate-string-list
                     (cons (intrinsic:concat-string-like-2 first second) more)))))
 
+;; indexes count characters and not the bytes of their utf-8 encoding
+(defun char (string index)
+    (or (and (not (bignump (assert-integer index)))
+             (intrinsic:char-at (assert-string string) index))
+        (error "string index out of range")))
 
+;; all strings are simple strings
+(defun schar (string index)
+    (char string index))
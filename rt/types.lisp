@@ -4,6 +4,11 @@
 (defparameter *tag-string* 8)
 (defparameter *tag-identifier* 16)
 (defparameter *tag-function* 32)
+(defparameter *tag-float* 64)
+(defparameter *tag-bignum* 128)
+(defparameter *tag-ratio* 256)
+(defparameter *tag-character* 512)
+(defparameter *tag-vector* 1024)
 
 (defun listp (thingy)
     ;; nil and cons are lists
@@ -13,7 +18,25 @@
     (= (intrinsic:type-tag-of thingy) *tag-list*))
 
 (defun numberp (thingy)
-    (= (intrinsic:type-tag-of thingy) *tag-sint32*))
+    (or (rationalp thingy) (floatp thingy)))
+
+(defun rationalp (thingy)
+    (or (integerp thingy) (ratiop thingy)))
+
+;; these compare with the intrinsic because = itself checks for floats,
+;; bignums and ratios
+(defun integerp (thingy)
+    (let ((tag (intrinsic:type-tag-of thingy)))
+        (or (intrinsic:=-2 tag *tag-sint32*) (intrinsic:=-2 tag *tag-bignum*))))
+
+(defun floatp (thingy)
+    (intrinsic:=-2 (intrinsic:type-tag-of thingy) *tag-float*))
+
+(defun bignump (thingy)
+    (intrinsic:=-2 (intrinsic:type-tag-of thingy) *tag-bignum*))
+
+(defun ratiop (thingy)
+    (intrinsic:=-2 (intrinsic:type-tag-of thingy) *tag-ratio*))
 
 (defun stringp (thingy)
     (= (intrinsic:type-tag-of thingy) *tag-string*))
@@ -24,20 +47,42 @@
 (defun functionp (thingy)
     (= (intrinsic:type-tag-of thingy) *tag-function*))
 
+(defun characterp (thingy)
+    (= (intrinsic:type-tag-of thingy) *tag-character*))
+
+(defun simple-vector-p (thingy)
+    (= (intrinsic:type-tag-of thingy) *tag-vector*))
+
+;; strings are vectors of characters
+(defun vectorp (thingy)
+    (or (simple-vector-p thingy) (stringp thingy)))
+
 (defun assert-list (thingy)
-    (if (listp thingy) thingy (panic "type error: expected list")))
+    (if (listp thingy) thingy (error 'type-error :datum thingy :expected-type 'list)))
 
 (defun assert-cons (thingy)
-    (if (consp thingy) thingy (panic "type error: expected cons")))
+    (if (consp thingy) thingy (error 'type-error :datum thingy :expected-type 'cons)))
 
 (defun assert-number (thingy)
-    (if (numberp thingy) thingy (panic "type error: expected number")))
+    (if (numberp thingy) thingy (error 'type-error :datum thingy :expected-type 'number)))
+
+(defun assert-integer (thingy)
+    (if (integerp thingy) thingy (error 'type-error :datum thingy :expected-type 'integer)))
+
+(defun assert-rational (thingy)
+    (if (rationalp thingy) thingy (error 'type-error :datum thingy :expected-type 'rational)))
 
 (defun assert-string (thingy)
-    (if (stringp thingy) thingy (panic "type error: expected string")))
+    (if (stringp thingy) thingy (error 'type-error :datum thingy :expected-type 'string)))
 
 (defun assert-symbol (thingy)
-    (if (symbolp thingy) thingy (panic "type error: expected symbol")))
+    (if (symbolp thingy) thingy (error 'type-error :datum thingy :expected-type 'symbol)))
+
+(defun assert-character (thingy)
+    (if (characterp thingy) thingy (error 'type-error :datum thingy :expected-type 'character)))
+
+(defun assert-simple-vector (thingy)
+    (if (simple-vector-p thingy) thingy (error 'type-error :datum thingy :expected-type 'simple-vector)))
 
 (defun assert-function (thingy)
-    (if (functionp thingy) thingy (panic "type error: expected function")))
+    (if (functionp thingy) thingy (error 'type-error :datum thingy :expected-type 'function)))
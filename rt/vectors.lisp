@@ -0,0 +1,55 @@
+;; simple vectors hold their length in bytes after the type tag, followed by a
+;; place for each element
+
+;; only one dimension is supported, as an integer or a list of one integer
+(defun make-array (dimensions &key initial-element)
+    (let ((length (if (consp dimensions) (car dimensions) dimensions)))
+        (if (and (consp dimensions) (cdr dimensions))
+            (error "only one-dimensional arrays are supported"))
+        ;; the size in bytes, with the type tag and length, must fit into 32
+        ;; bits, which bignums never do
+        (if (or (bignump (assert-integer length))
+                (< length 0)
+                (> length 536870909))
+            (error "array dimension out of range"))
+        (let ((vector (intrinsic:make-vector length)))
+            ;; new elements are nil already
+            (if initial-element
+                (dotimes (index length)
+                    (intrinsic:vector-set vector index initial-element)))
+            vector)))
+
+(defun vector (&rest objects)
+    (let ((vector (intrinsic:make-vector (length objects)))
+          (index 0))
+        (dolist (object objects vector)
+            (intrinsic:vector-set vector index object)
+            (setq index (+ index 1)))))
+
+(defun check-vector-index (vector index)
+    (if (or (< (assert-integer index) 0) (>= index (intrinsic:length vector)))
+        (error "vector index out of range")))
+
+;; strings are vectors of characters
+(defun aref (vector index)
+    (if (stringp vector)
+        (char vector index)
+        (progn
+            (check-vector-index (assert-simple-vector vector) index)
+            (intrinsic:vector-ref vector index))))
+
+;; called for (setf (aref vector index) value), strings cannot be modified
+(defun set-aref (vector index value)
+    (check-vector-index (assert-simple-vector vector) index)
+    (intrinsic:vector-set vector index value))
+
+;; counts lists in a loop, since they can be longer than the stack has room
+;; for calls
+(defun length (sequence)
+    (cond
+        ((listp sequence)
+            (let ((count 0))
+                (dolist (element sequence count)
+                    (setq count (+ count 1)))))
+        ((vectorp sequence) (intrinsic:length sequence))
+        (t (error 'type-error :datum sequence :expected-type 'sequence))))
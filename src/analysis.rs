@@ -1,8 +1,11 @@
 mod datatype;
+mod expand;
 mod form;
 mod funcdef;
 mod globaldef;
 mod irgen;
+mod macrodef;
+mod params;
 mod semantic;
 mod strings;
 
@@ -0,0 +1,1422 @@
+use std::{cell::Cell, fmt};
+
+use crate::{
+    diagnostic::Diagnostic,
+    parse::{AstNode, Atom, List, Parser, TokenKind},
+    source::{Source, SourceSet},
+};
+
+use super::{
+    form::FormError,
+    macrodef::{MacroDefinition, MacroDefinitionError},
+};
+
+/// Macros that expand into more macro calls give up after this many nested
+/// expansions, which most likely means they are infinitely recursive.
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// Calls to functions in macro bodies give up after this many nested calls,
+/// which most likely means they are infinitely recursive.
+const MAX_CALL_DEPTH: usize = 256;
+
+/// The macros that are defined in a unit of source code.
+///
+/// Macros are expanded by evaluating their bodies at compile time, with the
+/// unevaluated ast nodes of the arguments bound to the parameters. Only a
+/// small set of operations is available for that: `quote`, `if`, `cond`,
+/// `progn`, `let`, `let*`, `and`, `or`, `not`, `null`, `eq`, `list`, `cons`,
+/// `car`, `cdr`, `append`, `gensym`, quasiquoted templates and calls to
+/// functions defined with `defun` before the macro call. The bodies of these
+/// functions are evaluated the same way.
+///
+/// Everything else is not supported, e.g. numbers can be passed around but
+/// not computed with, variables cannot be assigned, there are no lambdas and
+/// macro bodies cannot use other macros. Lists that were built during
+/// expansion are never `eq`, not even to themselves, while atoms are `eq` if
+/// they are written the same.
+///
+/// The resulting value is written out as source code and parsed again, so
+/// that every expansion has a source of its own that diagnostics can point
+/// into, along with the call and definition it came from. Forms and
+/// definitions borrow their ast nodes for the rest of the compilation, so
+/// these sources are never freed.
+pub struct Macros<'s, 't> {
+    definitions: Vec<MacroDefinition<'s, 't>>,
+    /// Functions that macro bodies can call, in the order they were defined.
+    functions: Vec<MacroDefinition<'s, 't>>,
+    /// Expansions currently being extracted into forms.
+    depth: Cell<usize>,
+    /// Symbols made by `gensym` so far, which numbers the next one.
+    gensyms: Cell<usize>,
+}
+
+/// A value at compile time, which is either a node from the macro definition
+/// or the macro call, or a list that was built during expansion.
+#[derive(Clone)]
+enum Value<'s, 't> {
+    Node(Source<'s>, &'t AstNode<'s>),
+    List(Vec<Value<'s, 't>>),
+    /// The `t` returned by `null` and the other predicates.
+    True,
+    /// A value that keeps a quote, quasiquote or unquote in front of it
+    /// because it is part of a template, e.g. `',name` or a nested
+    /// quasiquote.
+    Prefixed(&'static str, Box<Value<'s, 't>>),
+    /// A symbol made by `gensym`, like `g<1>`, which no other symbol in the
+    /// program is written as unless it was copied from an expansion.
+    Symbol(String),
+}
+
+/// Evaluates the body of a macro or function with the variables that are in
+/// scope, innermost last.
+struct Evaluator<'a, 's, 't> {
+    functions: &'a [MacroDefinition<'s, 't>],
+    variables: Vec<(&'s str, Value<'s, 't>)>,
+    /// Function calls currently being evaluated.
+    depth: usize,
+    gensyms: &'a Cell<usize>,
+}
+
+impl<'s, 't> Macros<'s, 't> {
+    pub fn new() -> Self {
+        Self {
+            definitions: vec![],
+            functions: vec![],
+            depth: Cell::new(0),
+            gensyms: Cell::new(0),
+        }
+    }
+
+    /// Makes a function available to the macro calls after it. Functions can
+    /// be defined again, and calls go to the latest definition.
+    pub fn define_function(&mut self, definition: MacroDefinition<'s, 't>) {
+        self.functions.push(definition);
+    }
+
+    pub fn define(
+        &mut self,
+        definition: MacroDefinition<'s, 't>,
+    ) -> Result<(), MacroDefinitionError<'s, 't>> {
+        if let Some(previous) = self
+            .definitions
+            .iter()
+            .find(|d| d.name_str() == definition.name_str())
+        {
+            return Err(MacroDefinitionError::DuplicateName {
+                source: definition.source(),
+                name: definition.name(),
+                previous_source: previous.source(),
+                previous: previous.name(),
+            });
+        }
+        self.definitions.push(definition);
+        Ok(())
+    }
+
+    /// Finds the macro that the list calls, if any.
+    pub fn find(
+        &self,
+        source: Source<'s>,
+        call: &List<'s>,
+    ) -> Option<&MacroDefinition<'s, 't>> {
+        let head = call.elements().first()?.atom()?;
+        if !matches!(head.token().kind(), TokenKind::Ident) {
+            return None;
+        }
+        let name = head.fragment(source).source();
+        self.definitions.iter().find(|d| d.name_str() == name)
+    }
+
+    /// Expands a call to the given macro once, returning the source of the
+    /// expansion and the node it was parsed into.
+    pub fn expand(
+        &self,
+        source: Source<'s>,
+        call: &'t List<'s>,
+        definition: &MacroDefinition<'s, 't>,
+    ) -> Result<(Source<'s>, &'t AstNode<'s>), MacroError<'s, 't>> {
+        let error = |kind| MacroError::new(source, call, definition, kind);
+        if self.depth.get() >= MAX_EXPANSION_DEPTH {
+            return Err(error(MacroErrorKind::ExpansionTooDeep));
+        }
+
+        let args = &call.elements()[1..];
+        let required = definition.required_params().len();
+        let optional = definition.optional_params().len();
+        if args.len() < required {
+            return Err(error(MacroErrorKind::TooFewArguments {
+                expected: required,
+                rest: optional > 0 || definition.rest_param().is_some(),
+            }));
+        }
+        if !accepts(definition, args.len()) {
+            return Err(error(MacroErrorKind::TooManyArguments {
+                expected: required + optional,
+                optional: optional > 0,
+            }));
+        }
+        let mut evaluator = Evaluator {
+            functions: &self.functions,
+            variables: vec![],
+            depth: 0,
+            gensyms: &self.gensyms,
+        };
+        let args = args.iter().map(|arg| Value::Node(source, arg)).collect();
+        let result = evaluator.apply(definition, args).map_err(error)?;
+
+        let mut text = String::new();
+        result.write(&mut text);
+        let call_position = call.fragment(source).from_position();
+        let description = format!(
+            "<expansion of `{}` in {} at line {}:{}>",
+            definition.name_str(),
+            source.name(),
+            call_position.line_no(),
+            call_position.col_no()
+        );
+        let origin = format!(
+            "in the macro call:\n{}\nof the macro defined here:\n{}",
+            call.fragment(source).source_context(),
+            definition.name().fragment(definition.source()).source_context()
+        );
+        let sources = Box::leak(Box::new(SourceSet::new()));
+        let expansion_source =
+            sources.load_generated(description, origin, &text);
+        let expansion = Parser::new(expansion_source)
+            .parse_single()
+            .expect("expansions are written from nodes that were parsed");
+        Ok((expansion_source, Box::leak(Box::new(expansion))))
+    }
+
+    /// Expands the node until it is no longer a macro call, so that macros
+    /// can expand into definitions at root level.
+    pub fn expand_fully(
+        &self,
+        mut source: Source<'s>,
+        mut node: &'t AstNode<'s>,
+    ) -> Result<(Source<'s>, &'t AstNode<'s>), MacroError<'s, 't>> {
+        let mut expansions = 0;
+        let mut started = None;
+        while let Some(call) = node.list()
+            && let Some(definition) = self.find(source, call)
+        {
+            let (first_source, first_call, first_definition) =
+                *started.get_or_insert((source, call, definition));
+            if expansions == MAX_EXPANSION_DEPTH {
+                return Err(MacroError::new(
+                    first_source,
+                    first_call,
+                    first_definition,
+                    MacroErrorKind::ExpansionTooDeep,
+                ));
+            }
+            (source, node) =
+                self.expand(source, call, definition).map_err(|error| {
+                    error.moved_to(first_source, first_call, first_definition)
+                })?;
+            expansions += 1;
+        }
+        Ok((source, node))
+    }
+
+    /// Runs the extraction of forms from an expansion, keeping track of how
+    /// deeply expansions are nested.
+    pub fn nested<T>(&self, extract: impl FnOnce() -> T) -> T {
+        self.depth.set(self.depth.get() + 1);
+        let result = extract();
+        self.depth.set(self.depth.get() - 1);
+        result
+    }
+}
+
+/// Checks that an operator in a macro body got the expected number of
+/// arguments.
+fn arg_count<'s, 't>(
+    source: Source<'s>,
+    form: &'t List<'s>,
+    expected: usize,
+) -> Result<(), MacroErrorKind<'s, 't>> {
+    if form.elements().len() - 1 == expected {
+        Ok(())
+    } else {
+        Err(MacroErrorKind::WrongArgumentCount {
+            source,
+            form,
+            expected,
+        })
+    }
+}
+
+/// Whether a macro or function takes the number of arguments.
+fn accepts(definition: &MacroDefinition, count: usize) -> bool {
+    let required = definition.required_params().len();
+    let optional = definition.optional_params().len();
+    count >= required
+        && (count <= required + optional || definition.rest_param().is_some())
+}
+
+impl<'a, 's, 't> Evaluator<'a, 's, 't> {
+    /// Evaluates the body of a macro or function with its parameters bound
+    /// to the arguments, which must be accepted. Default values of optional
+    /// parameters are evaluated with the parameters before them in scope.
+    fn apply(
+        &mut self,
+        definition: &MacroDefinition<'s, 't>,
+        args: Vec<Value<'s, 't>>,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let source = definition.source();
+        let mut args = args.into_iter();
+        for param in definition.required_params() {
+            let name = param.fragment(source).source();
+            self.variables.push((name, args.next().unwrap()));
+        }
+        for (param, default) in definition.optional_params() {
+            let value = match (args.next(), default) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.evaluate(source, default)?,
+                (None, None) => Value::List(vec![]),
+            };
+            self.variables
+                .push((param.fragment(source).source(), value));
+        }
+        if let Some(rest) = definition.rest_param() {
+            let name = rest.fragment(source).source();
+            self.variables.push((name, Value::List(args.collect())));
+        }
+        self.evaluate_body(source, definition.body())
+    }
+
+    /// Evaluates forms in order and returns the value of the last one, or nil
+    /// if there are none.
+    fn evaluate_body(
+        &mut self,
+        source: Source<'s>,
+        body: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let mut result = Value::List(vec![]);
+        for form in body {
+            result = self.evaluate(source, form)?;
+        }
+        Ok(result)
+    }
+
+    fn evaluate(
+        &mut self,
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        match node {
+            AstNode::Quoted(quoted) => Ok(Value::Node(source, quoted.quoted())),
+            AstNode::Quasiquoted(quasiquoted) => {
+                self.evaluate_template(source, quasiquoted.quasiquoted(), 0)
+            }
+            AstNode::Unquoted(_) | AstNode::UnquotedSplicing(_) => {
+                Err(MacroErrorKind::UnquoteOutsideQuasiquote {
+                    source,
+                    form: node,
+                })
+            }
+            AstNode::Atom(atom)
+                if matches!(atom.token().kind(), TokenKind::Ident)
+                    && !atom.fragment(source).source().starts_with(':') =>
+            {
+                self.resolve(source, atom)
+            }
+            // numbers, strings, keywords, function names and vectors
+            // evaluate to themselves
+            AstNode::Atom(_) | AstNode::Vector(_) => {
+                Ok(Value::Node(source, node))
+            }
+            AstNode::List(list) => self.evaluate_list(source, list),
+        }
+    }
+
+    /// Evaluates a list by its operator. Each operator has a function of its
+    /// own, which keeps the stack frames of recursive calls small.
+    fn evaluate_list(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let Some((head, args)) = list.elements().split_first() else {
+            return Ok(Value::List(vec![]));
+        };
+        let operator = head
+            .atom()
+            .map(|a| a.fragment(source).source())
+            .unwrap_or_default();
+        match operator {
+            "quote" => {
+                arg_count(source, list, 1)?;
+                Ok(Value::Node(source, &args[0]))
+            }
+            "if" => self.evaluate_if(source, list, args),
+            "cond" => self.evaluate_cond(source, args),
+            "progn" => self.evaluate_body(source, args),
+            "let" | "let*" => {
+                self.evaluate_let(source, list, args, operator == "let*")
+            }
+            "and" => self.evaluate_and(source, args),
+            "or" => self.evaluate_or(source, args),
+            "list" => Ok(Value::List(self.evaluate_args(source, args)?)),
+            "cons" => self.evaluate_cons(source, list, args),
+            "car" | "cdr" => {
+                self.evaluate_car_or_cdr(source, list, args, operator == "car")
+            }
+            "append" => self.evaluate_append(source, args),
+            "null" | "not" => {
+                arg_count(source, list, 1)?;
+                Ok(Value::from_bool(self.evaluate(source, &args[0])?.is_nil()))
+            }
+            "eq" => {
+                arg_count(source, list, 2)?;
+                let left = self.evaluate(source, &args[0])?;
+                let right = self.evaluate(source, &args[1])?;
+                Ok(Value::from_bool(left.is_eq(&right)))
+            }
+            "gensym" => self.evaluate_gensym(source, list, args),
+            _ => self.call(source, list, head, args),
+        }
+    }
+
+    fn evaluate_args(
+        &mut self,
+        source: Source<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Vec<Value<'s, 't>>, MacroErrorKind<'s, 't>> {
+        args.iter().map(|arg| self.evaluate(source, arg)).collect()
+    }
+
+    /// Evaluates to a list, where nil is the empty list.
+    fn evaluate_elements(
+        &mut self,
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    ) -> Result<Vec<Value<'s, 't>>, MacroErrorKind<'s, 't>> {
+        self.evaluate(source, node)?
+            .elements()
+            .ok_or(MacroErrorKind::NotAList { source, form: node })
+    }
+
+    fn evaluate_if(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        if args.len() != 2 {
+            arg_count(source, list, 3)?;
+        }
+        if !self.evaluate(source, &args[0])?.is_nil() {
+            self.evaluate(source, &args[1])
+        } else if let Some(else_form) = args.get(2) {
+            self.evaluate(source, else_form)
+        } else {
+            Ok(Value::List(vec![]))
+        }
+    }
+
+    fn evaluate_cond(
+        &mut self,
+        source: Source<'s>,
+        clauses: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        for clause in clauses {
+            let Some((test, body)) = clause
+                .list()
+                .and_then(|clause| clause.elements().split_first())
+            else {
+                return Err(MacroErrorKind::NotAList {
+                    source,
+                    form: clause,
+                });
+            };
+            let test = self.evaluate(source, test)?;
+            if !test.is_nil() {
+                return if body.is_empty() {
+                    Ok(test)
+                } else {
+                    self.evaluate_body(source, body)
+                };
+            }
+        }
+        Ok(Value::List(vec![]))
+    }
+
+    /// `let` evaluates all values before binding any of them, `let*` binds
+    /// each before evaluating the next.
+    fn evaluate_let(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        args: &'t [AstNode<'s>],
+        sequential: bool,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let Some((bindings, body)) = args.split_first() else {
+            return Err(MacroErrorKind::WrongArgumentCount {
+                source,
+                form: list,
+                expected: 1,
+            });
+        };
+        let bindings = bindings.list().ok_or(MacroErrorKind::NotAList {
+            source,
+            form: bindings,
+        })?;
+        let outer = self.variables.len();
+        let mut bound = vec![];
+        for binding in bindings.elements() {
+            let (name, value) = self.evaluate_binding(source, binding)?;
+            if sequential {
+                self.variables.push((name, value));
+            } else {
+                bound.push((name, value));
+            }
+        }
+        self.variables.extend(bound);
+        let result = self.evaluate_body(source, body);
+        self.variables.truncate(outer);
+        result
+    }
+
+    fn evaluate_and(
+        &mut self,
+        source: Source<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let mut result = Value::True;
+        for arg in args {
+            result = self.evaluate(source, arg)?;
+            if result.is_nil() {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    fn evaluate_or(
+        &mut self,
+        source: Source<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        for arg in args {
+            let result = self.evaluate(source, arg)?;
+            if !result.is_nil() {
+                return Ok(result);
+            }
+        }
+        Ok(Value::List(vec![]))
+    }
+
+    fn evaluate_cons(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        arg_count(source, list, 2)?;
+        let car = self.evaluate(source, &args[0])?;
+        let mut cdr = self.evaluate_elements(source, &args[1])?;
+        cdr.insert(0, car);
+        Ok(Value::List(cdr))
+    }
+
+    fn evaluate_car_or_cdr(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        args: &'t [AstNode<'s>],
+        car: bool,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        arg_count(source, list, 1)?;
+        let elements = self.evaluate_elements(source, &args[0])?;
+        if elements.is_empty() {
+            Ok(Value::List(vec![]))
+        } else if car {
+            Ok(elements.into_iter().next().unwrap())
+        } else {
+            Ok(Value::List(elements.into_iter().skip(1).collect()))
+        }
+    }
+
+    fn evaluate_append(
+        &mut self,
+        source: Source<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let mut appended = vec![];
+        for arg in args {
+            appended.extend(self.evaluate_elements(source, arg)?);
+        }
+        Ok(Value::List(appended))
+    }
+
+    /// A new symbol, named after the prefix if there is a string literal for
+    /// it.
+    fn evaluate_gensym(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let prefix = match args {
+            [] => "g",
+            [prefix] => {
+                let error = MacroErrorKind::InvalidPrefix {
+                    source,
+                    form: &args[0],
+                };
+                let Value::Node(prefix_source, AstNode::Atom(prefix)) =
+                    self.evaluate(source, prefix)?
+                else {
+                    return Err(error);
+                };
+                let text = prefix.fragment(prefix_source).source();
+                let name = text.trim_matches('"');
+                if !matches!(prefix.token().kind(), TokenKind::StringLit)
+                    || !name.starts_with(char::is_alphabetic)
+                    || !name.chars().all(|c| c.is_alphanumeric() || c == '-')
+                {
+                    return Err(error);
+                }
+                name
+            }
+            _ => return Err(arg_count(source, list, 1).unwrap_err()),
+        };
+        let number = self.gensyms.get() + 1;
+        self.gensyms.set(number);
+        Ok(Value::Symbol(format!("{prefix}<{number}>")))
+    }
+
+    /// A binding of `let` like `name`, `(name)` or `(name value)`.
+    fn evaluate_binding(
+        &mut self,
+        source: Source<'s>,
+        binding: &'t AstNode<'s>,
+    ) -> Result<(&'s str, Value<'s, 't>), MacroErrorKind<'s, 't>> {
+        let (name, value) = match binding.list().map(|list| list.elements()) {
+            Some([name]) => (name, None),
+            Some([name, value]) => (name, Some(value)),
+            Some(_) => (binding, None),
+            None => (binding, None),
+        };
+        let name = name
+            .atom()
+            .filter(|name| matches!(name.token().kind(), TokenKind::Ident))
+            .ok_or(MacroErrorKind::MalformedBinding {
+                source,
+                form: binding,
+            })?;
+        let value = match value {
+            Some(value) => self.evaluate(source, value)?,
+            None => Value::List(vec![]),
+        };
+        Ok((name.fragment(source).source(), value))
+    }
+
+    /// Calls the latest function with the name, which sees only its own
+    /// parameters and not the variables of the caller.
+    fn call(
+        &mut self,
+        source: Source<'s>,
+        list: &'t List<'s>,
+        head: &'t AstNode<'s>,
+        args: &'t [AstNode<'s>],
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let name = head
+            .atom()
+            .filter(|name| matches!(name.token().kind(), TokenKind::Ident))
+            .map(|name| name.fragment(source).source());
+        let functions = self.functions;
+        let Some(function) = functions
+            .iter()
+            .rev()
+            .find(|function| Some(function.name_str()) == name)
+        else {
+            return Err(MacroErrorKind::UnsupportedOperator {
+                source,
+                form: head,
+            });
+        };
+        if !accepts(function, args.len()) {
+            return Err(MacroErrorKind::WrongFunctionArgumentCount {
+                source,
+                form: list,
+            });
+        }
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(MacroErrorKind::CallsTooDeep { source, form: list });
+        }
+        let args = self.evaluate_args(source, args)?;
+        let caller = std::mem::take(&mut self.variables);
+        self.depth += 1;
+        let result = self.apply(function, args);
+        self.depth -= 1;
+        self.variables = caller;
+        // problems inside of functions are reported at the call in the macro
+        // body too, since the functions may be far away, e.g. in the runtime
+        result.map_err(|error| {
+            if self.depth == 0 {
+                MacroErrorKind::FailedCall {
+                    source,
+                    form: list,
+                    function: function.name_str(),
+                    error: Box::new(error),
+                }
+            } else {
+                error
+            }
+        })
+    }
+
+    /// Fills in a quasiquoted template.
+    ///
+    /// Only unquotes of the outermost quasiquote are evaluated, the ones in
+    /// nested quasiquotes are kept for when the expansion is evaluated.
+    fn evaluate_template(
+        &mut self,
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+        nesting: usize,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        Ok(match node {
+            AstNode::Atom(_) | AstNode::Vector(_) => Value::Node(source, node),
+            AstNode::Quoted(quoted) => Value::Prefixed(
+                "'",
+                Box::new(self.evaluate_template(
+                    source,
+                    quoted.quoted(),
+                    nesting,
+                )?),
+            ),
+            AstNode::Quasiquoted(quasiquoted) => Value::Prefixed(
+                "`",
+                Box::new(self.evaluate_template(
+                    source,
+                    quasiquoted.quasiquoted(),
+                    nesting + 1,
+                )?),
+            ),
+            AstNode::Unquoted(unquoted) if nesting == 0 => {
+                self.evaluate(source, unquoted.unquoted())?
+            }
+            AstNode::Unquoted(unquoted) => Value::Prefixed(
+                ",",
+                Box::new(self.evaluate_template(
+                    source,
+                    unquoted.unquoted(),
+                    nesting - 1,
+                )?),
+            ),
+            AstNode::UnquotedSplicing(_) if nesting == 0 => {
+                return Err(MacroErrorKind::SpliceOutsideList {
+                    source,
+                    form: node,
+                });
+            }
+            AstNode::UnquotedSplicing(unquoted) => Value::Prefixed(
+                ",@",
+                Box::new(self.evaluate_template(
+                    source,
+                    unquoted.unquoted(),
+                    nesting - 1,
+                )?),
+            ),
+            AstNode::List(list) => {
+                let mut elements = vec![];
+                for element in list.elements() {
+                    match element {
+                        AstNode::UnquotedSplicing(spliced) if nesting == 0 => {
+                            elements.extend(
+                                self.evaluate(source, spliced.unquoted())?
+                                    .elements()
+                                    .ok_or(MacroErrorKind::NotAList {
+                                        source,
+                                        form: element,
+                                    })?,
+                            )
+                        }
+                        _ => elements.push(
+                            self.evaluate_template(source, element, nesting)?,
+                        ),
+                    }
+                }
+                Value::List(elements)
+            }
+        })
+    }
+
+    fn resolve(
+        &self,
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    ) -> Result<Value<'s, 't>, MacroErrorKind<'s, 't>> {
+        let name = atom.fragment(source).source();
+        if let Some((_, value)) = self
+            .variables
+            .iter()
+            .rev()
+            .find(|(bound, _)| *bound == name)
+        {
+            return Ok(value.clone());
+        }
+        match name {
+            "nil" => Ok(Value::List(vec![])),
+            "t" => Ok(Value::True),
+            _ => Err(MacroErrorKind::UnboundVariable { source, atom }),
+        }
+    }
+}
+
+impl<'s, 't> Value<'s, 't> {
+    /// The elements if the value is a list, including nil.
+    fn elements(self) -> Option<Vec<Value<'s, 't>>> {
+        match self {
+            Value::List(elements) => Some(elements),
+            Value::Node(source, AstNode::List(list)) => Some(
+                list.elements()
+                    .iter()
+                    .map(|element| Value::Node(source, element))
+                    .collect(),
+            ),
+            Value::Node(source, node @ AstNode::Atom(_))
+                if node.fragment(source).source() == "nil" =>
+            {
+                Some(vec![])
+            }
+            Value::Node(..)
+            | Value::True
+            | Value::Prefixed(..)
+            | Value::Symbol(_) => None,
+        }
+    }
+
+    fn is_nil(&self) -> bool {
+        self.clone().elements().is_some_and(|e| e.is_empty())
+    }
+
+    fn from_bool(value: bool) -> Self {
+        if value {
+            Value::True
+        } else {
+            Value::List(vec![])
+        }
+    }
+
+    /// The written form of atoms, where nil and t are the same however they
+    /// were made, and None for other values.
+    fn atom(&self) -> Option<&str> {
+        match self {
+            Value::True => Some("t"),
+            _ if self.is_nil() => Some("nil"),
+            Value::Node(source, node @ AstNode::Atom(_)) => {
+                Some(node.fragment(*source).source())
+            }
+            Value::Symbol(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn is_eq(&self, other: &Value<'s, 't>) -> bool {
+        self.atom().is_some() && self.atom() == other.atom()
+    }
+
+    /// Writes the value as source code, copying nodes verbatim.
+    fn write(&self, text: &mut String) {
+        match self {
+            Value::Node(source, node) => {
+                text.push_str(node.fragment(*source).source())
+            }
+            Value::List(elements) => {
+                text.push('(');
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx > 0 {
+                        text.push(' ');
+                    }
+                    element.write(text);
+                }
+                text.push(')');
+            }
+            Value::True => text.push('t'),
+            Value::Symbol(name) => text.push_str(name),
+            Value::Prefixed(prefix, value) => {
+                text.push_str(prefix);
+                value.write(text);
+            }
+        }
+    }
+}
+
+/// A problem expanding a macro call, reported with both the call and the
+/// definition of the macro.
+#[derive(Debug)]
+pub struct MacroError<'s, 't> {
+    call_source: Source<'s>,
+    call: &'t List<'s>,
+    definition_source: Source<'s>,
+    definition: &'t Atom<'s>,
+    kind: MacroErrorKind<'s, 't>,
+}
+
+#[derive(Debug)]
+pub enum MacroErrorKind<'s, 't> {
+    TooFewArguments {
+        expected: usize,
+        /// More arguments would have been fine.
+        rest: bool,
+    },
+    TooManyArguments {
+        expected: usize,
+        /// Fewer arguments would have been fine.
+        optional: bool,
+    },
+    /// An operator in the macro body that cannot be evaluated at compile
+    /// time.
+    UnsupportedOperator {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    WrongArgumentCount {
+        source: Source<'s>,
+        form: &'t List<'s>,
+        expected: usize,
+    },
+    /// A function called at compile time got a number of arguments that
+    /// its parameters do not accept.
+    WrongFunctionArgumentCount {
+        source: Source<'s>,
+        form: &'t List<'s>,
+    },
+    CallsTooDeep {
+        source: Source<'s>,
+        form: &'t List<'s>,
+    },
+    MalformedBinding {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    UnboundVariable {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    NotAList {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    /// The prefix of `gensym` is not a string literal with a name that
+    /// symbols can start with.
+    InvalidPrefix {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    UnquoteOutsideQuasiquote {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    SpliceOutsideList {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    /// A function called directly from the macro body failed, with the
+    /// problem inside of it.
+    FailedCall {
+        source: Source<'s>,
+        form: &'t List<'s>,
+        function: &'s str,
+        error: Box<MacroErrorKind<'s, 't>>,
+    },
+    ExpansionTooDeep,
+    /// The expansion is not a valid form.
+    InvalidExpansion(FormError<'s, 't>),
+}
+
+impl<'s, 't> MacroError<'s, 't> {
+    pub fn new(
+        call_source: Source<'s>,
+        call: &'t List<'s>,
+        definition: &MacroDefinition<'s, 't>,
+        kind: MacroErrorKind<'s, 't>,
+    ) -> Self {
+        Self {
+            call_source,
+            call,
+            definition_source: definition.source(),
+            definition: definition.name(),
+            kind,
+        }
+    }
+
+    /// Moves an error about expansions that never stop to a call further
+    /// out, so that it is reported where expanding started rather than deep
+    /// inside of generated sources.
+    pub fn moved_to(
+        self,
+        call_source: Source<'s>,
+        call: &'t List<'s>,
+        definition: &MacroDefinition<'s, 't>,
+    ) -> Self {
+        match self.kind {
+            MacroErrorKind::ExpansionTooDeep => {
+                Self::new(call_source, call, definition, self.kind)
+            }
+            _ => self,
+        }
+    }
+}
+
+impl<'s, 't> Diagnostic for MacroError<'s, 't> {
+    fn kind(&self) -> crate::diagnostic::DiagnosticKind {
+        crate::diagnostic::DiagnosticKind::Error
+    }
+}
+
+impl<'s, 't> fmt::Display for MacroError<'s, 't> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.definition.fragment(self.definition_source).source();
+        self.kind.describe(f, name)?;
+        writeln!(f, "in the macro call:")?;
+        writeln!(
+            f,
+            "{}",
+            self.call.fragment(self.call_source).source_context()
+        )?;
+        writeln!(f, "of the macro defined here:")?;
+        writeln!(
+            f,
+            "{}",
+            self.definition
+                .fragment(self.definition_source)
+                .source_context()
+        )
+    }
+}
+
+impl<'s, 't> MacroErrorKind<'s, 't> {
+    /// Writes what went wrong in the macro with the name, with the forms it
+    /// concerns, but without the call and definition of the macro.
+    fn describe(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        match self {
+            MacroErrorKind::TooFewArguments {
+                expected,
+                rest: true,
+            } => {
+                writeln!(
+                    f,
+                    "macro `{name}` expects at least {expected} arguments:"
+                )?;
+            }
+            MacroErrorKind::TooManyArguments {
+                expected,
+                optional: true,
+            } => {
+                writeln!(
+                    f,
+                    "macro `{name}` expects at most {expected} arguments:"
+                )?;
+            }
+            MacroErrorKind::TooFewArguments { expected, .. }
+            | MacroErrorKind::TooManyArguments { expected, .. } => {
+                writeln!(f, "macro `{name}` expects {expected} arguments:")?;
+            }
+            MacroErrorKind::UnsupportedOperator { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` uses an operator that cannot be evaluated at compile time, or a function that is not defined before the call:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::WrongArgumentCount {
+                source,
+                form,
+                expected,
+            } => {
+                writeln!(
+                    f,
+                    "macro `{name}` passes a wrong number of arguments, expected {expected}:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::WrongFunctionArgumentCount { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` calls a function with a wrong number of arguments:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::CallsTooDeep { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` nests function calls too deeply, they may never return:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::MalformedBinding { source, form } => {
+                writeln!(f, "macro `{name}` uses a malformed binding:")?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::UnboundVariable { source, atom } => {
+                writeln!(
+                    f,
+                    "macro `{name}` uses a variable that is not a parameter:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::NotAList { source, form } => {
+                writeln!(f, "macro `{name}` expected a list:")?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::InvalidPrefix { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` expected a string of letters, digits and dashes that starts with a letter:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::UnquoteOutsideQuasiquote { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` uses an unquote outside of a quasiquote:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::SpliceOutsideList { source, form } => {
+                writeln!(
+                    f,
+                    "macro `{name}` uses unquote-splicing outside of a list:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+            }
+            MacroErrorKind::FailedCall {
+                source,
+                form,
+                function,
+                error,
+            } => {
+                writeln!(
+                    f,
+                    "macro `{name}` calls function `{function}`, which cannot be evaluated at compile time:"
+                )?;
+                writeln!(f, "{}", form.fragment(*source).source_context())?;
+                error.describe(f, name)?;
+            }
+            MacroErrorKind::ExpansionTooDeep => {
+                writeln!(
+                    f,
+                    "expansion of macro `{name}` is nested too deeply, it may never stop expanding:"
+                )?;
+            }
+            MacroErrorKind::InvalidExpansion(error) => {
+                writeln!(f, "macro `{name}` expanded into invalid code:")?;
+                write!(f, "{}", error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{analysis::form::Form, parse::Parser, source::SourceSet};
+
+    use super::*;
+
+    fn expand_last(code: &str) -> String {
+        let source_set = SourceSet::new_debug(code);
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        let (call, definitions) = ast.root_nodes().split_last().unwrap();
+        for definition in definitions {
+            if let Some(function) =
+                MacroDefinition::extract_function(source, definition).unwrap()
+            {
+                macros.define_function(function);
+                continue;
+            }
+            let definition = MacroDefinition::extract(source, definition)
+                .unwrap()
+                .unwrap();
+            macros.define(definition).unwrap();
+        }
+        let (expansion_source, expansion) =
+            macros.expand_fully(source, call).unwrap();
+        expansion.fragment(expansion_source).source().to_string()
+    }
+
+    #[test]
+    fn expand_with_list_operations() {
+        assert_eq!(
+            expand_last(
+                "(defmacro swap (f a b) (list f b a))
+                (swap - x \"y\")"
+            ),
+            "(- \"y\" x)"
+        );
+        assert_eq!(
+            expand_last(
+                "(defmacro my-when (test &body body)
+                  (list 'if test (cons 'progn body)))
+                (my-when (> a 1) (print a) 'done)"
+            ),
+            "(if (> a 1) (progn (print a) 'done))"
+        );
+        assert_eq!(
+            expand_last(
+                "(defmacro firsts (a b) (append (car a) (cdr b) '(z)))
+                (firsts ((1 2)) (x y))"
+            ),
+            "(1 2 y z)"
+        );
+    }
+
+    #[test]
+    fn expand_quasiquote() {
+        assert_eq!(
+            expand_last(
+                "(defmacro my-when (test &body body)
+                  `(if ,test (progn ,@body) 'none))
+                (my-when (> a 1) (print a) 'done)"
+            ),
+            "(if (> a 1) (progn (print a) 'done) 'none)"
+        );
+        // unquotes of nested quasiquotes are left for the expansion
+        assert_eq!(
+            expand_last(
+                "(defmacro wrap (name) `(list ',name `(,x ,',name)))
+                (wrap y)"
+            ),
+            "(list 'y `(,x ,'y))"
+        );
+    }
+
+    #[test]
+    fn expand_with_let_cond_and_predicates() {
+        let pick = "(defmacro pick (kind a b)
+            (let* ((first a) (pair (list first b)))
+              (cond ((eq kind 'first) first)
+                    ((and (not (null kind)) (or (eq kind 'both) nil))
+                     (cons 'list pair))
+                    (t ''none))))";
+        assert_eq!(expand_last(&format!("{pick} (pick first x y)")), "x");
+        assert_eq!(
+            expand_last(&format!("{pick} (pick both x y)")),
+            "(list x y)"
+        );
+        assert_eq!(expand_last(&format!("{pick} (pick nil x y)")), "'none");
+    }
+
+    #[test]
+    fn expand_with_optional_params() {
+        assert_eq!(
+            expand_last(
+                "(defmacro inc (place &optional (by 1) other)
+                  (list 'setq place (list '+ place by) other))
+                (inc x)"
+            ),
+            "(setq x (+ x 1) ())"
+        );
+    }
+
+    #[test]
+    fn expand_with_earlier_functions() {
+        assert_eq!(
+            expand_last(
+                "(defun wrap-all (head forms)
+                  (if (null forms)
+                    nil
+                    (cons (list head (car forms)) (wrap-all head (cdr forms)))))
+                (defun head-of (&optional (default 'print)) default)
+                (defmacro print-all (&rest forms)
+                  (cons 'progn (wrap-all (head-of) forms)))
+                (print-all a b)"
+            ),
+            "(progn (print a) (print b))"
+        );
+    }
+
+    #[test]
+    fn expand_nested_macros_at_root() {
+        assert_eq!(
+            expand_last(
+                "(defmacro defconst (name value) (list 'defun name () value))
+                (defmacro defzero (name) (list 'defconst name 0))
+                (defzero zero)"
+            ),
+            "(defun zero () 0)"
+        );
+    }
+
+    #[test]
+    fn errors_point_at_call_and_definition() {
+        let source_set = SourceSet::new_debug(
+            "(defmacro two (a b) (list a b))
+            (two 1)
+            (defmacro bad (a) (print a))
+            (bad 1)",
+        );
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        for node in [&ast.root_nodes()[0], &ast.root_nodes()[2]] {
+            let definition =
+                MacroDefinition::extract(source, node).unwrap().unwrap();
+            macros.define(definition).unwrap();
+        }
+
+        let error = Form::extract(source, &ast.root_nodes()[1], &macros)
+            .err()
+            .unwrap();
+        let message = error.to_string();
+        assert!(message.contains("expects 2 arguments"));
+        assert!(message.contains("(two 1)"));
+        assert!(message.contains("(defmacro two (a b) (list a b))"));
+
+        let error = Form::extract(source, &ast.root_nodes()[3], &macros)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            FormError::MacroExpansion(ref e)
+                if matches!(e.kind, MacroErrorKind::UnsupportedOperator { .. })
+        ));
+    }
+
+    #[test]
+    fn infinite_expansion_stops() {
+        let source_set =
+            SourceSet::new_debug("(defmacro forever () '(progn (forever)))");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        let node = &ast.root_nodes()[0];
+        let definition =
+            MacroDefinition::extract(source, node).unwrap().unwrap();
+        macros.define(definition).unwrap();
+        let source_set = SourceSet::new_debug("(forever)");
+        let call_source = source_set.one();
+        let call = Parser::new(call_source).parse().unwrap();
+        let error = Form::extract(call_source, &call.root_nodes()[0], &macros)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            FormError::MacroExpansion(ref e)
+                if matches!(e.kind, MacroErrorKind::ExpansionTooDeep)
+        ));
+        // reported at the call that started expanding
+        let message = error.to_string();
+        assert!(message.contains("(forever)"));
+        assert!(!message.contains("<expansion of"));
+    }
+
+    #[test]
+    fn recursive_functions_stop() {
+        let source_set = SourceSet::new_debug(
+            "(defun forever (x) (forever x))
+            (defmacro m () (forever 1))",
+        );
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        let function =
+            MacroDefinition::extract_function(source, &ast.root_nodes()[0])
+                .unwrap()
+                .unwrap();
+        macros.define_function(function);
+        let definition = MacroDefinition::extract(source, &ast.root_nodes()[1])
+            .unwrap()
+            .unwrap();
+        macros.define(definition).unwrap();
+        let source_set = SourceSet::new_debug("(m)");
+        let call_source = source_set.one();
+        let call = Parser::new(call_source).parse().unwrap();
+        let error = Form::extract(call_source, &call.root_nodes()[0], &macros)
+            .err()
+            .unwrap();
+        // reported at the call in the macro body
+        assert!(matches!(
+            error,
+            FormError::MacroExpansion(ref e)
+                if matches!(
+                    e.kind,
+                    MacroErrorKind::FailedCall { ref error, .. }
+                        if matches!(**error, MacroErrorKind::CallsTooDeep { .. })
+                )
+        ));
+        assert!(error.to_string().contains("(forever 1)"));
+    }
+
+    #[test]
+    fn failed_calls_point_at_macro_body() {
+        let source_set = SourceSet::new_debug(
+            "(defun plus (a b) (+ a b))
+            (defmacro m (x) (list 'quote (plus x 1)))",
+        );
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        let function =
+            MacroDefinition::extract_function(source, &ast.root_nodes()[0])
+                .unwrap()
+                .unwrap();
+        macros.define_function(function);
+        let definition = MacroDefinition::extract(source, &ast.root_nodes()[1])
+            .unwrap()
+            .unwrap();
+        macros.define(definition).unwrap();
+        let source_set = SourceSet::new_debug("(m 2)");
+        let call_source = source_set.one();
+        let call = Parser::new(call_source).parse().unwrap();
+        let error = Form::extract(call_source, &call.root_nodes()[0], &macros)
+            .err()
+            .unwrap();
+        let message = error.to_string();
+        assert!(message.contains("calls function `plus`"));
+        assert!(message.contains("(plus x 1)"));
+        assert!(message.contains("(+ a b)"));
+    }
+
+    #[test]
+    fn expand_gensym() {
+        assert_eq!(
+            expand_last(
+                "(defmacro m ()
+                  (let ((a (gensym)) (b (gensym \"tmp\")))
+                    (list a b (eq a a) (eq a b))))
+                (m)"
+            ),
+            "(g<1> tmp<2> t ())"
+        );
+    }
+
+    #[test]
+    fn expansions_point_at_call_and_definition() {
+        let source_set =
+            SourceSet::new_debug("(defmacro m (x) (list x 'y))\n(m f)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let mut macros = Macros::new();
+        let definition = MacroDefinition::extract(source, &ast.root_nodes()[0])
+            .unwrap()
+            .unwrap();
+        macros.define(definition).unwrap();
+        let (expansion_source, expansion) =
+            macros.expand_fully(source, &ast.root_nodes()[1]).unwrap();
+        let context = expansion
+            .fragment(expansion_source)
+            .source_context()
+            .to_string();
+        assert!(context.starts_with(
+            "<expansion of `m` in <builtin> at line 2:1> at line 1:1\n\
+            (f y)\n\
+            ^^^^^\n"
+        ));
+        assert!(context.contains(
+            "in the macro call:\n<builtin> at line 2:1\n(m f)"
+        ));
+        assert!(context.contains(
+            "of the macro defined here:\n<builtin> at line 1:11\n\
+            (defmacro m (x) (list x 'y))"
+        ));
+    }
+}
@@ -1,4 +1,8 @@
-use std::fmt;
+use std::{
+    fmt,
+    iter::{self, Peekable},
+    slice,
+};
 
 use crate::{
     diagnostic::Diagnostic,
@@ -6,15 +10,60 @@ use crate::{
     source::Source,
 };
 
+use super::expand::{MacroError, MacroErrorKind, Macros};
+use super::params::{DestructuringList, Parameters, extract_name};
+
 pub enum Form<'s, 't> {
     /// A variable (not function) name.
     Name(Name<'s, 't>),
     FunctionName(FunctionName<'s, 't>),
     Constant(Constant<'s, 't>),
     LetForm(LetForm<'s, 't>),
+    /// Binds names to parts of a list.
+    DestructuringBind(DestructuringBind<'s, 't>),
+    /// Local functions defined with `flet` or `labels`.
+    FletForm(FletForm<'s, 't>),
     IfForm(IfForm<'s, 't>),
     AndForm(AndForm<'s, 't>),
     OrForm(OrForm<'s, 't>),
+    CondForm(CondForm<'s, 't>),
+    WhenForm(WhenForm<'s, 't>),
+    UnlessForm(UnlessForm<'s, 't>),
+    PrognForm(PrognForm<'s, 't>),
+    /// Assignment to variables only.
+    SetqForm(SetqForm<'s, 't>),
+    /// Assignment to variables or parts of lists.
+    SetfForm(SetfForm<'s, 't>),
+    /// Returns its arguments as multiple values.
+    ValuesForm(ValuesForm<'s, 't>),
+    MultipleValueBind(MultipleValueBind<'s, 't>),
+    MultipleValueList(MultipleValueList<'s, 't>),
+    /// Named exit point for `return-from` in its body.
+    BlockForm(BlockForm<'s, 't>),
+    /// Leaves a block, `return` leaves the block named nil.
+    ReturnFrom(ReturnFrom<'s, 't>),
+    /// Exit point for `throw` to a tag while the body runs.
+    CatchForm(CatchForm<'s, 't>),
+    ThrowForm(ThrowForm<'s, 't>),
+    /// Runs cleanup forms even when leaving the protected form early.
+    UnwindProtect(UnwindProtect<'s, 't>),
+    /// Leaves the form for the first clause that matches a signaled
+    /// condition.
+    HandlerCase(HandlerCase<'s, 't>),
+    /// Calls handler functions for signaled conditions without leaving.
+    HandlerBind(HandlerBind<'s, 't>),
+    /// Returns nil and the condition if an error is signaled.
+    IgnoreErrors(IgnoreErrors<'s, 't>),
+    /// Runs the body with a counter, inside a block named nil like the other
+    /// iteration forms.
+    DotimesForm(DotimesForm<'s, 't>),
+    /// Runs the body for each element of a list.
+    DolistForm(DolistForm<'s, 't>),
+    /// Steps variables until the end test is true, in parallel or, for
+    /// `do*`, in sequence.
+    DoForm(DoForm<'s, 't>),
+    /// The loop macro with a subset of its clauses.
+    LoopForm(LoopForm<'s, 't>),
     /// Usual function application like (+ 1 2)
     Call(Call<'s, 't>),
     /// Apply builtin with function as first argument and param list second.
@@ -22,6 +71,12 @@ pub enum Form<'s, 't> {
     /// Similar to apply, but arguments are passed individually and not as list
     Funcall(Funcall<'s, 't>),
     Lambda(Lambda<'s, 't>),
+    /// A call to a macro, which is replaced with its expansion.
+    MacroCall(MacroCall<'s, 't>),
+    /// A quasiquoted template with unquoted parts evaluated at runtime.
+    ///
+    /// Templates without any unquotes are extracted as constants instead.
+    Quasiquote(Quasiquote<'s, 't>),
 }
 
 pub struct Name<'s, 't> {
@@ -56,6 +111,61 @@ pub struct OrForm<'s, 't> {
     forms: Vec<Form<'s, 't>>,
 }
 
+pub struct CondForm<'s, 't> {
+    source: Source<'s>,
+    clauses: Vec<CondClause<'s, 't>>,
+}
+
+/// A test and the forms to evaluate if it is not nil.
+pub struct CondClause<'s, 't> {
+    test: Form<'s, 't>,
+    /// If empty, the clause evaluates to the result of the test.
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct WhenForm<'s, 't> {
+    source: Source<'s>,
+    test: Box<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct UnlessForm<'s, 't> {
+    source: Source<'s>,
+    test: Box<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct PrognForm<'s, 't> {
+    source: Source<'s>,
+    forms: Vec<Form<'s, 't>>,
+}
+
+pub struct SetqForm<'s, 't> {
+    source: Source<'s>,
+    assignments: Vec<Assignment<'s, 't>>,
+}
+
+pub struct SetfForm<'s, 't> {
+    source: Source<'s>,
+    assignments: Vec<Assignment<'s, 't>>,
+}
+
+/// A place and the value to write to it, in the order they are evaluated.
+pub struct Assignment<'s, 't> {
+    place: AssignedPlace<'s, 't>,
+    value: Form<'s, 't>,
+}
+
+pub enum AssignedPlace<'s, 't> {
+    Variable(&'t Atom<'s>),
+    /// The car of the list that the form evaluates to.
+    Car(Form<'s, 't>),
+    /// The cdr of the list that the form evaluates to.
+    Cdr(Form<'s, 't>),
+    /// The element of a vector at an index, from the first and second form.
+    Aref(Form<'s, 't>, Form<'s, 't>),
+}
+
 pub struct Call<'s, 't> {
     source: Source<'s>,
     function: &'t Atom<'s>,
@@ -69,16 +179,237 @@ pub struct Binding<'s, 't> {
 
 pub struct LetForm<'s, 't> {
     source: Source<'s>,
+    /// Set for `let*`, where each value sees the names bound before it.
+    sequential: bool,
     bindings: Vec<Binding<'s, 't>>,
     body: Vec<Form<'s, 't>>,
 }
 
+pub struct DestructuringBind<'s, 't> {
+    source: Source<'s>,
+    list: DestructuringList<'s, 't>,
+    value: Box<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct ValuesForm<'s, 't> {
+    source: Source<'s>,
+    forms: Vec<Form<'s, 't>>,
+}
+
+pub struct MultipleValueBind<'s, 't> {
+    source: Source<'s>,
+    names: Vec<&'t Atom<'s>>,
+    form: Box<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct MultipleValueList<'s, 't> {
+    source: Source<'s>,
+    form: Box<Form<'s, 't>>,
+}
+
+pub struct BlockForm<'s, 't> {
+    source: Source<'s>,
+    name: &'t Atom<'s>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct ReturnFrom<'s, 't> {
+    source: Source<'s>,
+    /// Either `return-from` or `return`.
+    head: &'t Atom<'s>,
+    /// Missing for `return`.
+    name: Option<&'t Atom<'s>>,
+    value: Option<Box<Form<'s, 't>>>,
+}
+
+pub struct CatchForm<'s, 't> {
+    source: Source<'s>,
+    tag: Box<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct ThrowForm<'s, 't> {
+    source: Source<'s>,
+    tag: Box<Form<'s, 't>>,
+    value: Box<Form<'s, 't>>,
+}
+
+pub struct UnwindProtect<'s, 't> {
+    source: Source<'s>,
+    protected: Box<Form<'s, 't>>,
+    cleanup: Vec<Form<'s, 't>>,
+}
+
+pub struct HandlerCase<'s, 't> {
+    source: Source<'s>,
+    form: Box<Form<'s, 't>>,
+    clauses: Vec<HandlerClause<'s, 't>>,
+}
+
+pub struct HandlerClause<'s, 't> {
+    condition_type: &'t Atom<'s>,
+    name: Option<&'t Atom<'s>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct HandlerBind<'s, 't> {
+    source: Source<'s>,
+    handlers: Vec<Handler<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct Handler<'s, 't> {
+    condition_type: &'t Atom<'s>,
+    function: Form<'s, 't>,
+}
+
+pub struct IgnoreErrors<'s, 't> {
+    source: Source<'s>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct DotimesForm<'s, 't> {
+    source: Source<'s>,
+    name: &'t Atom<'s>,
+    count: Box<Form<'s, 't>>,
+    result: Option<Box<Form<'s, 't>>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct DolistForm<'s, 't> {
+    source: Source<'s>,
+    name: &'t Atom<'s>,
+    list: Box<Form<'s, 't>>,
+    result: Option<Box<Form<'s, 't>>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct DoForm<'s, 't> {
+    source: Source<'s>,
+    /// Set for `do*`, where each value sees the variables before it.
+    sequential: bool,
+    variables: Vec<DoVariable<'s, 't>>,
+    end_test: Box<Form<'s, 't>>,
+    result: Vec<Form<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct DoVariable<'s, 't> {
+    name: &'t Atom<'s>,
+    init: Option<Form<'s, 't>>,
+    step: Option<Form<'s, 't>>,
+}
+
+pub struct LoopForm<'s, 't> {
+    source: Source<'s>,
+    variables: Vec<LoopVariable<'s, 't>>,
+    clauses: Vec<LoopClause<'s, 't>>,
+    accumulation: LoopAccumulation,
+    finally: Vec<Form<'s, 't>>,
+}
+
+pub enum LoopVariable<'s, 't> {
+    /// `with name = value`, which is only bound once.
+    With {
+        name: &'t Atom<'s>,
+        value: Option<Form<'s, 't>>,
+    },
+    /// `for name in list`, which ends the loop at the end of the list.
+    In {
+        name: &'t Atom<'s>,
+        list: Form<'s, 't>,
+    },
+    /// `for name from start to end by step`, where every part but the name is
+    /// optional. With `below` instead of `to`, the end is excluded.
+    Range {
+        name: &'t Atom<'s>,
+        from: Option<Box<Form<'s, 't>>>,
+        to: Option<Box<Form<'s, 't>>>,
+        inclusive: bool,
+        by: Option<Box<Form<'s, 't>>>,
+    },
+}
+
+pub enum LoopClause<'s, 't> {
+    Do(Vec<Form<'s, 't>>),
+    Collect(Form<'s, 't>),
+    Sum(Form<'s, 't>),
+    /// Leaves the loop without running the finally forms.
+    Return(Form<'s, 't>),
+    /// Ends the loop if the test is nil.
+    While(Form<'s, 't>),
+    /// Ends the loop if the test is true.
+    Until(Form<'s, 't>),
+    /// Runs the clause if the test is true, or nil for `unless`.
+    When {
+        test: Form<'s, 't>,
+        negated: bool,
+        clause: Box<LoopClause<'s, 't>>,
+    },
+}
+
+/// What a loop returns when it ends, a loop can only either collect or sum.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LoopAccumulation {
+    None,
+    Collect,
+    Sum,
+}
+
+pub struct FletForm<'s, 't> {
+    source: Source<'s>,
+    /// Set for `labels`, where the functions are in scope in their own
+    /// bodies, so they can call themselves and each other.
+    recursive: bool,
+    functions: Vec<LocalFunction<'s, 't>>,
+    body: Vec<Form<'s, 't>>,
+}
+
+pub struct LocalFunction<'s, 't> {
+    name: &'t Atom<'s>,
+    lambda: Lambda<'s, 't>,
+}
+
 pub struct Lambda<'s, 't> {
     source: Source<'s>,
-    positional_args: Vec<&'t Atom<'s>>,
+    parameters: Parameters<'s, 't>,
     body: Vec<Form<'s, 't>>,
 }
 
+pub struct MacroCall<'s, 't> {
+    source: Source<'s>,
+    call: &'t List<'s>,
+    /// Every expansion has its own generated source.
+    expansion_source: Source<'s>,
+    expansion: Box<Form<'s, 't>>,
+}
+
+pub struct Quasiquote<'s, 't> {
+    template: Template<'s, 't>,
+}
+
+/// Part of a quasiquoted template.
+///
+/// Quotes and quasiquotes nested in a template are transparent, just like
+/// nested quotes are in quoted data, so their unquotes are evaluated along
+/// with the outermost template.
+pub enum Template<'s, 't> {
+    /// Part without unquotes, which is put into static data.
+    Constant(&'t AstNode<'s>),
+    /// Evaluated and inserted as is.
+    Unquoted(Box<Form<'s, 't>>),
+    List(Vec<TemplateElement<'s, 't>>),
+}
+
+pub enum TemplateElement<'s, 't> {
+    Element(Template<'s, 't>),
+    /// Evaluated to a list whose elements are spliced into the surrounding
+    /// list.
+    Spliced(Form<'s, 't>),
+}
+
 pub struct Apply<'s, 't> {
     source: Source<'s>,
     /// Something that can be resolved to a function.
@@ -99,14 +430,44 @@ impl<'s, 't> Form<'s, 't> {
     pub fn extract(
         source: Source<'s>,
         form: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Form<'s, 't>, FormError<'s, 't>> {
         Ok(match form {
-            AstNode::Quoted(quoted) => Self::Constant(Constant {
-                source,
-                node: quoted.quoted(),
-            }),
+            AstNode::Quoted(quoted) => {
+                if let Some(unquote) = find_unquote(quoted.quoted()) {
+                    return Err(FormError::UnquoteOutsideQuasiquote {
+                        source,
+                        node: unquote,
+                    });
+                }
+                Self::Constant(Constant {
+                    source,
+                    node: quoted.quoted(),
+                })
+            }
+            AstNode::Quasiquoted(quasiquoted) => {
+                let template = quasiquoted.quasiquoted();
+                if find_unquote(template).is_none() {
+                    Self::Constant(Constant {
+                        source,
+                        node: template,
+                    })
+                } else {
+                    Self::Quasiquote(Quasiquote {
+                        template: Template::extract(source, template, macros)?,
+                    })
+                }
+            }
+            AstNode::Unquoted(_) | AstNode::UnquotedSplicing(_) => {
+                return Err(FormError::UnquoteOutsideQuasiquote {
+                    source,
+                    node: form,
+                });
+            }
+            // keywords evaluate to themselves
             AstNode::Atom(atom)
-                if matches!(atom.token().kind(), TokenKind::Ident) =>
+                if matches!(atom.token().kind(), TokenKind::Ident)
+                    && !atom.fragment(source).source().starts_with(':') =>
             {
                 Self::Name(Name {
                     source,
@@ -118,7 +479,14 @@ impl<'s, 't> Form<'s, 't> {
             {
                 Self::FunctionName(FunctionName { source, name: atom })
             }
-            AstNode::Atom(_) => {
+            // vectors are self-evaluating data like quoted lists
+            AstNode::Atom(_) | AstNode::Vector(_) => {
+                if let Some(unquote) = find_unquote(form) {
+                    return Err(FormError::UnquoteOutsideQuasiquote {
+                        source,
+                        node: unquote,
+                    });
+                }
                 Self::Constant(Constant { source, node: form })
             }
             AstNode::List(list) if list.elements().is_empty() => {
@@ -126,45 +494,179 @@ impl<'s, 't> Form<'s, 't> {
             }
             AstNode::List(non_empty) => {
                 if let Some(if_form) =
-                    IfForm::extract_assume_nonempty(source, non_empty)?
+                    IfForm::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::IfForm(if_form));
                 }
                 if let Some(and_form) =
-                    AndForm::extract_assume_nonempty(source, non_empty)?
+                    AndForm::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::AndForm(and_form));
                 }
                 if let Some(or_form) =
-                    OrForm::extract_assume_nonempty(source, non_empty)?
+                    OrForm::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::OrForm(or_form));
                 }
+                if let Some(cond_form) = CondForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::CondForm(cond_form));
+                }
+                if let Some(when_form) = WhenForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::WhenForm(when_form));
+                }
+                if let Some(unless_form) = UnlessForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::UnlessForm(unless_form));
+                }
+                if let Some(progn_form) = PrognForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::PrognForm(progn_form));
+                }
+                if let Some(setq_form) = SetqForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::SetqForm(setq_form));
+                }
+                if let Some(setf_form) = SetfForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::SetfForm(setf_form));
+                }
                 if let Some(let_form) =
-                    LetForm::extract_assume_nonempty(source, non_empty)?
+                    LetForm::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::LetForm(let_form));
                 }
+                if let Some(form) = DestructuringBind::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::DestructuringBind(form));
+                }
+                if let Some(form) = ValuesForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::ValuesForm(form));
+                }
+                if let Some(form) = MultipleValueBind::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::MultipleValueBind(form));
+                }
+                if let Some(form) = MultipleValueList::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::MultipleValueList(form));
+                }
+                if let Some(form) = BlockForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::BlockForm(form));
+                }
+                if let Some(form) = ReturnFrom::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::ReturnFrom(form));
+                }
+                if let Some(form) = CatchForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::CatchForm(form));
+                }
+                if let Some(form) = ThrowForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::ThrowForm(form));
+                }
+                if let Some(form) = UnwindProtect::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::UnwindProtect(form));
+                }
+                if let Some(form) = HandlerCase::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::HandlerCase(form));
+                }
+                if let Some(form) = HandlerBind::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::HandlerBind(form));
+                }
+                if let Some(form) = IgnoreErrors::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::IgnoreErrors(form));
+                }
+                if let Some(form) =
+                    Self::extract_iteration(source, non_empty, macros)?
+                {
+                    return Ok(form);
+                }
+                if let Some(flet_form) = FletForm::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::FletForm(flet_form));
+                }
                 if let Some(apply_static) =
-                    Apply::extract_assume_nonempty(source, non_empty)?
+                    Apply::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::Apply(apply_static));
                 }
                 if let Some(lambda) =
-                    Lambda::extract_assume_nonempty(source, non_empty)?
+                    Lambda::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::Lambda(lambda));
                 }
                 if let Some(form) =
-                    Funcall::extract_assume_nonempty(source, non_empty)?
+                    Funcall::extract_assume_nonempty(source, non_empty, macros)?
                 {
                     return Ok(Form::Funcall(form));
                 }
-                Form::Call(Call::extract_assume_nonempty(source, non_empty)?)
+                if let Some(macro_call) = MacroCall::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )? {
+                    return Ok(Form::MacroCall(macro_call));
+                }
+                Form::Call(Call::extract_assume_nonempty(
+                    source, non_empty, macros,
+                )?)
             }
         })
     }
 
+    /// Extracts the iteration forms, in a function of its own so that the
+    /// frames of `extract` stay small when it recurses through deeply
+    /// nested forms and macro expansions.
+    fn extract_iteration(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<Form<'s, 't>>, FormError<'s, 't>> {
+        if let Some(form) =
+            DotimesForm::extract_assume_nonempty(source, form, macros)?
+        {
+            return Ok(Some(Form::DotimesForm(form)));
+        }
+        if let Some(form) =
+            DolistForm::extract_assume_nonempty(source, form, macros)?
+        {
+            return Ok(Some(Form::DolistForm(form)));
+        }
+        if let Some(form) =
+            DoForm::extract_assume_nonempty(source, form, macros)?
+        {
+            return Ok(Some(Form::DoForm(form)));
+        }
+        Ok(LoopForm::extract_assume_nonempty(source, form, macros)?
+            .map(Form::LoopForm))
+    }
+
     #[cfg(test)]
     pub fn name(&self) -> Option<&Name<'s, 't>> {
         match self {
@@ -195,6 +697,22 @@ impl<'s, 't> Form<'s, 't> {
         }
     }
 
+    #[cfg(test)]
+    pub fn cond_form(&self) -> Option<&CondForm<'s, 't>> {
+        match self {
+            Self::CondForm(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn setf_form(&self) -> Option<&SetfForm<'s, 't>> {
+        match self {
+            Self::SetfForm(s) => Some(s),
+            _ => None,
+        }
+    }
+
     #[cfg(test)]
     pub fn call(&self) -> Option<&Call<'s, 't>> {
         match self {
@@ -203,6 +721,14 @@ impl<'s, 't> Form<'s, 't> {
         }
     }
 
+    #[cfg(test)]
+    pub fn flet_form(&self) -> Option<&FletForm<'s, 't>> {
+        match self {
+            Self::FletForm(f) => Some(f),
+            _ => None,
+        }
+    }
+
     #[cfg(test)]
     pub fn lambda(&self) -> Option<&Lambda<'s, 't>> {
         match self {
@@ -218,6 +744,29 @@ impl<'s, 't> Form<'s, 't> {
             _ => None,
         }
     }
+
+    #[cfg(test)]
+    pub fn quasiquote(&self) -> Option<&Quasiquote<'s, 't>> {
+        match self {
+            Self::Quasiquote(q) => Some(q),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the first unquote or unquote-splicing in quoted or quasiquoted data.
+fn find_unquote<'s, 't>(node: &'t AstNode<'s>) -> Option<&'t AstNode<'s>> {
+    match node {
+        AstNode::Atom(_) => None,
+        AstNode::List(list) | AstNode::Vector(list) => {
+            list.elements().iter().find_map(find_unquote)
+        }
+        AstNode::Quoted(quoted) => find_unquote(quoted.quoted()),
+        AstNode::Quasiquoted(quasiquoted) => {
+            find_unquote(quasiquoted.quasiquoted())
+        }
+        AstNode::Unquoted(_) | AstNode::UnquotedSplicing(_) => Some(node),
+    }
 }
 
 impl<'s, 't> Name<'s, 't> {
@@ -256,6 +805,7 @@ impl<'s, 't> IfForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Option<IfForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
@@ -276,17 +826,17 @@ impl<'s, 't> IfForm<'s, 't> {
         let test_form = elements
             .next()
             .ok_or_else(|| FormError::IfMissingTest { source, form })?;
-        let test_form = Form::extract(source, test_form)?;
+        let test_form = Form::extract(source, test_form, macros)?;
 
         let then_form = elements
             .next()
             .ok_or_else(|| FormError::IfMissingTest { source, form })?;
-        let then_form = Form::extract(source, then_form)?;
+        let then_form = Form::extract(source, then_form, macros)?;
 
         let else_form = match elements.next() {
             None => None,
             Some(else_form) => {
-                Some(Box::new(Form::extract(source, else_form)?))
+                Some(Box::new(Form::extract(source, else_form, macros)?))
             }
         };
 
@@ -329,6 +879,7 @@ impl<'s, 't> AndForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Option<AndForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
@@ -346,7 +897,7 @@ impl<'s, 't> AndForm<'s, 't> {
         }
 
         let forms = elements
-            .map(|a| Form::extract(source, a))
+            .map(|a| Form::extract(source, a, macros))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Some(AndForm { source, forms }))
@@ -365,6 +916,7 @@ impl<'s, 't> OrForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Option<OrForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
@@ -382,7 +934,7 @@ impl<'s, 't> OrForm<'s, 't> {
         }
 
         let forms = elements
-            .map(|a| Form::extract(source, a))
+            .map(|a| Form::extract(source, a, macros))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Some(OrForm { source, forms }))
@@ -397,219 +949,1843 @@ impl<'s, 't> OrForm<'s, 't> {
     }
 }
 
-impl<'s, 't> Apply<'s, 't> {
+impl<'s, 't> CondForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
-    ) -> Result<Option<Apply<'s, 't>>, FormError<'s, 't>> {
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<CondForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
         let head = elements.next().unwrap();
-        let is_apply = match head {
+        let is_cond = match head {
             AstNode::Atom(first)
-                if first.source_range().of(source).source() == "apply" =>
+                if first.source_range().of(source).source() == "cond" =>
             {
                 true
             }
             _ => false,
         };
-        if !is_apply {
-            // ignore root-level directive that is not defun
+        if !is_cond {
             return Ok(None);
         }
-        let head = head.atom().unwrap();
 
-        let func =
-            Form::extract(
-                source,
-                elements.next().ok_or_else(|| {
-                    FormError::ApplyStaticTooShort { source, atom: head }
-                })?,
-            )?;
-        let args =
-            elements
-                .next()
-                .ok_or_else(|| FormError::ApplyStaticTooShort {
+        let mut clauses = vec![];
+        for clause in elements {
+            let clause_list = clause.list().ok_or_else(|| {
+                FormError::CondClauseNotList {
                     source,
-                    atom: head,
-                })?;
-        let args = Form::extract(source, args)?;
-
-        if elements.next().is_some() {
-            return Err(FormError::ApplyStaticTooLong { source, atom: head });
+                    atom: clause,
+                }
+            })?;
+            let mut clause_elements = clause_list.elements().iter();
+            let test = clause_elements.next().ok_or_else(|| {
+                FormError::CondClauseMissingTest {
+                    source,
+                    atom: clause,
+                }
+            })?;
+            let test = Form::extract(source, test, macros)?;
+            let body = clause_elements
+                .map(|f| Form::extract(source, f, macros))
+                .collect::<Result<Vec<_>, _>>()?;
+            clauses.push(CondClause { test, body });
         }
 
-        Ok(Some(Apply {
-            source,
-            function: Box::new(func),
-            args: Box::new(args),
-        }))
+        Ok(Some(CondForm { source, clauses }))
     }
 
     pub fn source(&self) -> Source<'s> {
         self.source
     }
 
-    pub fn function<'a>(&'a self) -> &'a Form<'s, 't> {
-        self.function.as_ref()
+    pub fn clauses(&self) -> &[CondClause<'s, 't>] {
+        &self.clauses
     }
+}
 
-    pub fn args<'a>(&'a self) -> &'a Form<'s, 't> {
-        self.args.as_ref()
+impl<'s, 't> CondClause<'s, 't> {
+    pub fn test(&self) -> &Form<'s, 't> {
+        &self.test
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
     }
 }
 
-impl<'s, 't> Funcall<'s, 't> {
+impl<'s, 't> WhenForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
-    ) -> Result<Option<Funcall<'s, 't>>, FormError<'s, 't>> {
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<WhenForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
         let head = elements.next().unwrap();
-        let is_funcall = match head {
+        let is_when = match head {
             AstNode::Atom(first)
-                if first.source_range().of(source).source() == "funcall" =>
+                if first.source_range().of(source).source() == "when" =>
             {
                 true
             }
             _ => false,
         };
-        if !is_funcall {
+        if !is_when {
             return Ok(None);
         }
         let head = head.atom().unwrap();
 
-        let func = Form::extract(
+        let test = elements
+            .next()
+            .ok_or_else(|| FormError::WhenMissingTest { source, atom: head })?;
+        let test = Form::extract(source, test, macros)?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(WhenForm {
             source,
-            elements.next().ok_or_else(|| FormError::FuncallTooShort {
-                source,
-                atom: head,
-            })?,
+            test: Box::new(test),
+            body,
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn test_form(&self) -> &Form<'s, 't> {
+        &self.test
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> UnlessForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<UnlessForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_unless = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "unless" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_unless {
+            return Ok(None);
+        }
+        let head = head.atom().unwrap();
+
+        let test = elements.next().ok_or_else(|| {
+            FormError::UnlessMissingTest { source, atom: head }
+        })?;
+        let test = Form::extract(source, test, macros)?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(UnlessForm {
+            source,
+            test: Box::new(test),
+            body,
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn test_form(&self) -> &Form<'s, 't> {
+        &self.test
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> PrognForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<PrognForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_progn = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "progn" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_progn {
+            return Ok(None);
+        }
+
+        let forms = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(PrognForm { source, forms }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn forms(&self) -> &[Form<'s, 't>] {
+        &self.forms
+    }
+}
+
+impl<'s, 't> SetqForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<SetqForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_setq = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "setq" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_setq {
+            return Ok(None);
+        }
+        let head = head.atom().unwrap();
+
+        let pairs = &form.elements()[1..];
+        if pairs.len() % 2 != 0 {
+            return Err(FormError::SetqOddArguments { source, atom: head });
+        }
+        let assignments = pairs
+            .chunks(2)
+            .map(|pair| {
+                let name = pair[0]
+                    .atom()
+                    .filter(|a| a.token().kind() == TokenKind::Ident)
+                    .ok_or_else(|| FormError::SetqTargetNotVariable {
+                        source,
+                        atom: &pair[0],
+                    })?;
+                Ok(Assignment {
+                    place: AssignedPlace::Variable(name),
+                    value: Form::extract(source, &pair[1], macros)?,
+                })
+            })
+            .collect::<Result<Vec<_>, FormError>>()?;
+
+        Ok(Some(SetqForm {
+            source,
+            assignments,
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn assignments(&self) -> &[Assignment<'s, 't>] {
+        &self.assignments
+    }
+}
+
+impl<'s, 't> SetfForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<SetfForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_setf = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "setf" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_setf {
+            return Ok(None);
+        }
+        let head = head.atom().unwrap();
+
+        let pairs = &form.elements()[1..];
+        if pairs.len() % 2 != 0 {
+            return Err(FormError::SetfOddArguments { source, atom: head });
+        }
+        let assignments = pairs
+            .chunks(2)
+            .map(|pair| {
+                Ok(Assignment {
+                    place: AssignedPlace::extract(source, &pair[0], macros)?,
+                    value: Form::extract(source, &pair[1], macros)?,
+                })
+            })
+            .collect::<Result<Vec<_>, FormError>>()?;
+
+        Ok(Some(SetfForm {
+            source,
+            assignments,
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn assignments(&self) -> &[Assignment<'s, 't>] {
+        &self.assignments
+    }
+}
+
+impl<'s, 't> Assignment<'s, 't> {
+    pub fn place(&self) -> &AssignedPlace<'s, 't> {
+        &self.place
+    }
+
+    pub fn value(&self) -> &Form<'s, 't> {
+        &self.value
+    }
+}
+
+impl<'s, 't> AssignedPlace<'s, 't> {
+    /// Variables, `(car list)`, `(cdr list)` and `(aref vector index)` are
+    /// supported.
+    fn extract(
+        source: Source<'s>,
+        place: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<AssignedPlace<'s, 't>, FormError<'s, 't>> {
+        let unsupported = || FormError::SetfUnsupportedPlace {
+            source,
+            atom: place,
+        };
+        match place {
+            AstNode::Atom(atom) if atom.token().kind() == TokenKind::Ident => {
+                Ok(AssignedPlace::Variable(atom))
+            }
+            AstNode::List(list) if !list.elements().is_empty() => {
+                let (accessor, args) = list.elements().split_first().unwrap();
+                let accessor =
+                    accessor.atom().map(|a| a.fragment(source).source());
+                let arg =
+                    |idx: usize| Form::extract(source, &args[idx], macros);
+                match (accessor, args.len()) {
+                    (Some("car"), 1) => Ok(AssignedPlace::Car(arg(0)?)),
+                    (Some("cdr"), 1) => Ok(AssignedPlace::Cdr(arg(0)?)),
+                    (Some("aref"), 2) => {
+                        Ok(AssignedPlace::Aref(arg(0)?, arg(1)?))
+                    }
+                    _ => Err(unsupported()),
+                }
+            }
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+impl<'s, 't> Apply<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<Apply<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_apply = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "apply" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_apply {
+            // ignore root-level directive that is not defun
+            return Ok(None);
+        }
+        let head = head.atom().unwrap();
+
+        let func =
+            Form::extract(
+                source,
+                elements.next().ok_or_else(|| {
+                    FormError::ApplyStaticTooShort { source, atom: head }
+                })?,
+                macros,
+            )?;
+        let args =
+            elements
+                .next()
+                .ok_or_else(|| FormError::ApplyStaticTooShort {
+                    source,
+                    atom: head,
+                })?;
+        let args = Form::extract(source, args, macros)?;
+
+        if elements.next().is_some() {
+            return Err(FormError::ApplyStaticTooLong { source, atom: head });
+        }
+
+        Ok(Some(Apply {
+            source,
+            function: Box::new(func),
+            args: Box::new(args),
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn function<'a>(&'a self) -> &'a Form<'s, 't> {
+        self.function.as_ref()
+    }
+
+    pub fn args<'a>(&'a self) -> &'a Form<'s, 't> {
+        self.args.as_ref()
+    }
+}
+
+impl<'s, 't> Funcall<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<Funcall<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let is_funcall = match head {
+            AstNode::Atom(first)
+                if first.source_range().of(source).source() == "funcall" =>
+            {
+                true
+            }
+            _ => false,
+        };
+        if !is_funcall {
+            return Ok(None);
+        }
+        let head = head.atom().unwrap();
+
+        let func = Form::extract(
+            source,
+            elements.next().ok_or_else(|| FormError::FuncallTooShort {
+                source,
+                atom: head,
+            })?,
+            macros,
         )?;
         let args = elements
-            .map(|e| Form::extract(source, e))
+            .map(|e| Form::extract(source, e, macros))
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Some(Funcall {
+        Ok(Some(Funcall {
+            source,
+            function: Box::new(func),
+            args,
+        }))
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn function<'a>(&'a self) -> &'a Form<'s, 't> {
+        self.function.as_ref()
+    }
+
+    pub fn args<'a>(&'a self) -> &'a [Form<'s, 't>] {
+        &self.args
+    }
+}
+
+impl<'s, 't> LetForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<LetForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+
+        let head = elements.next().unwrap();
+        let sequential = match head {
+            AstNode::Atom(first) => {
+                match first.source_range().of(source).source() {
+                    "let" => false,
+                    "let*" => true,
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        let head = head.atom().unwrap();
+
+        let bindings =
+            elements
+                .next()
+                .ok_or_else(|| FormError::LetMissingBindings {
+                    source,
+                    atom: head,
+                })?;
+        let bindings =
+            bindings
+                .list()
+                .ok_or_else(|| FormError::LetBindingsNotList {
+                    source,
+                    atom: bindings,
+                })?;
+        let mut bindings_parsed = vec![];
+        for binding in bindings.elements() {
+            let binding = binding.list().ok_or_else(|| {
+                FormError::LetBindingEntryNotList {
+                    source,
+                    atom: binding,
+                }
+            })?;
+            if binding.elements().len() < 2 {
+                return Err(FormError::LetBindingEntryTooShort {
+                    source,
+                    atom: binding,
+                });
+            }
+            if binding.elements().len() > 2 {
+                return Err(FormError::LetBindingEntryTooLong {
+                    source,
+                    atom: binding,
+                });
+            }
+            let name = binding.elements()[0]
+                .atom()
+                .filter(|a| a.token().kind() == TokenKind::Ident)
+                .ok_or_else(|| FormError::LetBindingVarNotIdent {
+                    source,
+                    atom: &binding.elements()[0],
+                })?;
+            let value = Form::extract(source, &binding.elements()[1], macros)?;
+            bindings_parsed.push(Binding { name, value });
+        }
+
+        let body = &form.elements()[2..];
+        if body.is_empty() {
+            return Err(FormError::LetMissingBody { source, atom: head });
+        }
+        let body = body
+            .iter()
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(LetForm {
+            source,
+            sequential,
+            bindings: bindings_parsed,
+            body,
+        }))
+    }
+
+    pub fn sequential(&self) -> bool {
+        self.sequential
+    }
+
+    pub fn bindings(&self) -> &[Binding<'s, 't>] {
+        &self.bindings
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> Binding<'s, 't> {
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    pub fn value(&self) -> &Form<'s, 't> {
+        &self.value
+    }
+}
+
+impl<'s, 't> DestructuringBind<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<DestructuringBind<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "destructuring-bind" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let (Some(list), Some(value)) = (elements.next(), elements.next())
+        else {
+            return Err(FormError::DestructuringBindTooShort {
+                source,
+                atom: head,
+            });
+        };
+        let list = DestructuringList::extract(source, list, macros)?;
+        let value = Box::new(Form::extract(source, value, macros)?);
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(DestructuringBind {
+            source,
+            list,
+            value,
+            body,
+        }))
+    }
+
+    pub fn list(&self) -> &DestructuringList<'s, 't> {
+        &self.list
+    }
+
+    pub fn value(&self) -> &Form<'s, 't> {
+        &self.value
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> ValuesForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<ValuesForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "values" => {}
+            _ => return Ok(None),
+        }
+
+        let forms = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(ValuesForm { source, forms }))
+    }
+
+    /// The first form is the primary value, which is nil if there are none.
+    pub fn forms(&self) -> &[Form<'s, 't>] {
+        &self.forms
+    }
+}
+
+impl<'s, 't> MultipleValueBind<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<MultipleValueBind<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "multiple-value-bind" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let (Some(names), Some(value)) = (elements.next(), elements.next())
+        else {
+            return Err(FormError::MultipleValueBindTooShort {
+                source,
+                atom: head,
+            });
+        };
+        let names = names
+            .list()
+            .ok_or(FormError::MultipleValueBindNamesNotList {
+                source,
+                atom: names,
+            })?
+            .elements()
+            .iter()
+            .map(|name| extract_name(source, name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let form = Box::new(Form::extract(source, value, macros)?);
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(MultipleValueBind {
+            source,
+            names,
+            form,
+            body,
+        }))
+    }
+
+    /// Names without a value are bound to nil.
+    pub fn names(&self) -> &[&'t Atom<'s>] {
+        &self.names
+    }
+
+    pub fn form(&self) -> &Form<'s, 't> {
+        &self.form
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> MultipleValueList<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<MultipleValueList<'s, 't>>, FormError<'s, 't>> {
+        let head = match &form.elements()[0] {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "multiple-value-list" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let [_, value] = form.elements() else {
+            return Err(FormError::MultipleValueListArguments {
+                source,
+                atom: head,
+            });
+        };
+        let form = Box::new(Form::extract(source, value, macros)?);
+        Ok(Some(MultipleValueList { source, form }))
+    }
+
+    pub fn form(&self) -> &Form<'s, 't> {
+        &self.form
+    }
+}
+
+impl<'s, 't> BlockForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<BlockForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "block" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let name = elements
+            .next()
+            .ok_or(FormError::BlockMissingName { source, atom: head })?;
+        let name = extract_name(source, name)
+            .map_err(|_| FormError::BlockNameNotIdent { source, atom: name })?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(BlockForm { source, name, body }))
+    }
+
+    pub fn name(&self) -> &'s str {
+        self.name.fragment(self.source).source()
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> ReturnFrom<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<ReturnFrom<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let (head, named) = match elements.next().unwrap() {
+            AstNode::Atom(head) => match head.fragment(source).source() {
+                "return-from" => (head, true),
+                "return" => (head, false),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+
+        let name = if named {
+            let name = elements
+                .next()
+                .ok_or(FormError::ReturnFromArguments { source, atom: head })?;
+            Some(extract_name(source, name).map_err(|_| {
+                FormError::BlockNameNotIdent { source, atom: name }
+            })?)
+        } else {
+            None
+        };
+        let value = elements
+            .next()
+            .map(|value| Form::extract(source, value, macros))
+            .transpose()?
+            .map(Box::new);
+        if elements.next().is_some() {
+            return Err(FormError::ReturnFromArguments { source, atom: head });
+        }
+        Ok(Some(ReturnFrom {
+            source,
+            head,
+            name,
+            value,
+        }))
+    }
+
+    pub fn block_name(&self) -> &'s str {
+        match self.name {
+            Some(name) => name.fragment(self.source).source(),
+            None => "nil",
+        }
+    }
+
+    /// The name of the block, or the head of `return`.
+    pub fn atom(&self) -> &'t Atom<'s> {
+        self.name.unwrap_or(self.head)
+    }
+
+    /// The block evaluates to nil if there is no value.
+    pub fn value(&self) -> Option<&Form<'s, 't>> {
+        self.value.as_deref()
+    }
+}
+
+impl<'s, 't> CatchForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<CatchForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "catch" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let tag = elements
+            .next()
+            .ok_or(FormError::CatchMissingTag { source, atom: head })?;
+        let tag = Box::new(Form::extract(source, tag, macros)?);
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(CatchForm { source, tag, body }))
+    }
+
+    /// Evaluated before the body, throws to an eq tag are caught.
+    pub fn tag(&self) -> &Form<'s, 't> {
+        &self.tag
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> ThrowForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<ThrowForm<'s, 't>>, FormError<'s, 't>> {
+        let head = match &form.elements()[0] {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "throw" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let [_, tag, value] = form.elements() else {
+            return Err(FormError::ThrowArguments { source, atom: head });
+        };
+        let tag = Box::new(Form::extract(source, tag, macros)?);
+        let value = Box::new(Form::extract(source, value, macros)?);
+        Ok(Some(ThrowForm { source, tag, value }))
+    }
+
+    pub fn tag(&self) -> &Form<'s, 't> {
+        &self.tag
+    }
+
+    /// Becomes the primary value of the catch form.
+    pub fn value(&self) -> &Form<'s, 't> {
+        &self.value
+    }
+}
+
+impl<'s, 't> UnwindProtect<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<UnwindProtect<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "unwind-protect" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let protected =
+            elements.next().ok_or(FormError::UnwindProtectMissingForm {
+                source,
+                atom: head,
+            })?;
+        let protected = Box::new(Form::extract(source, protected, macros)?);
+        let cleanup = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(UnwindProtect {
+            source,
+            protected,
+            cleanup,
+        }))
+    }
+
+    /// Its values are the values of the whole form.
+    pub fn protected(&self) -> &Form<'s, 't> {
+        &self.protected
+    }
+
+    pub fn cleanup(&self) -> &[Form<'s, 't>] {
+        &self.cleanup
+    }
+}
+
+impl<'s, 't> HandlerCase<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<HandlerCase<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "handler-case" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let handled = elements
+            .next()
+            .ok_or(FormError::HandlerCaseMissingForm { source, atom: head })?;
+        let handled = Box::new(Form::extract(source, handled, macros)?);
+        let clauses = elements
+            .map(|clause| HandlerClause::extract(source, clause, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(HandlerCase {
+            source,
+            form: handled,
+            clauses,
+        }))
+    }
+
+    /// Its values are the values of the whole form if nothing is caught.
+    pub fn form(&self) -> &Form<'s, 't> {
+        &self.form
+    }
+
+    pub fn clauses(&self) -> &[HandlerClause<'s, 't>] {
+        &self.clauses
+    }
+}
+
+impl<'s, 't> HandlerClause<'s, 't> {
+    /// A clause is a list like `(type-error (condition) body…)`, where the
+    /// list of the name may be empty.
+    fn extract(
+        source: Source<'s>,
+        clause: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<HandlerClause<'s, 't>, FormError<'s, 't>> {
+        let malformed = || FormError::HandlerClauseMalformed {
+            source,
+            atom: clause,
+        };
+        let mut elements =
+            clause.list().ok_or_else(malformed)?.elements().iter();
+        let condition_type = elements
+            .next()
+            .and_then(|node| extract_name(source, node).ok())
+            .ok_or_else(malformed)?;
+        let names = elements
+            .next()
+            .and_then(|node| node.list())
+            .ok_or_else(malformed)?;
+        let name = match names.elements() {
+            [] => None,
+            [name] => {
+                Some(extract_name(source, name).map_err(|_| malformed())?)
+            }
+            _ => return Err(malformed()),
+        };
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(HandlerClause {
+            condition_type,
+            name,
+            body,
+        })
+    }
+
+    /// The name of the type of conditions that the clause handles.
+    pub fn condition_type(&self) -> &'t Atom<'s> {
+        self.condition_type
+    }
+
+    /// Bound to the condition in the body.
+    pub fn name(&self) -> Option<&'t Atom<'s>> {
+        self.name
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> HandlerBind<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<HandlerBind<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "handler-bind" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let handlers = elements
+            .next()
+            .and_then(|handlers| handlers.list())
+            .ok_or(FormError::HandlerBindMissingHandlers {
+                source,
+                atom: head,
+            })?
+            .elements()
+            .iter()
+            .map(|handler| Handler::extract(source, handler, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(HandlerBind {
+            source,
+            handlers,
+            body,
+        }))
+    }
+
+    pub fn handlers(&self) -> &[Handler<'s, 't>] {
+        &self.handlers
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+impl<'s, 't> Handler<'s, 't> {
+    /// A handler is a list like `(type-error #'function)`.
+    fn extract(
+        source: Source<'s>,
+        handler: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Handler<'s, 't>, FormError<'s, 't>> {
+        let malformed = || FormError::HandlerMalformed {
+            source,
+            atom: handler,
+        };
+        let Some([condition_type, function]) =
+            handler.list().map(|list| list.elements())
+        else {
+            return Err(malformed());
+        };
+        let condition_type =
+            extract_name(source, condition_type).map_err(|_| malformed())?;
+        let function = Form::extract(source, function, macros)?;
+        Ok(Handler {
+            condition_type,
+            function,
+        })
+    }
+
+    /// The name of the type of conditions that the handler is called for.
+    pub fn condition_type(&self) -> &'t Atom<'s> {
+        self.condition_type
+    }
+
+    /// Evaluated when the handler is established, called with the
+    /// condition.
+    pub fn function(&self) -> &Form<'s, 't> {
+        &self.function
+    }
+}
+
+impl<'s, 't> IgnoreErrors<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<IgnoreErrors<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "ignore-errors" => {}
+            _ => return Ok(None),
+        };
+
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(IgnoreErrors { source, body }))
+    }
+
+    /// Evaluates to nil if empty.
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+}
+
+/// The name, value and optional result form of `dotimes` and `dolist`.
+type IterationSpec<'s, 't> = (&'t Atom<'s>, Form<'s, 't>, Option<Form<'s, 't>>);
+
+/// Extracts the list like `(name value [result])` that starts `dotimes` and
+/// `dolist`.
+fn extract_iteration_spec<'s, 't>(
+    source: Source<'s>,
+    head: &'t Atom<'s>,
+    spec: Option<&'t AstNode<'s>>,
+    macros: &Macros<'s, 't>,
+) -> Result<IterationSpec<'s, 't>, FormError<'s, 't>> {
+    let spec =
+        spec.ok_or(FormError::IterationMissingSpec { source, atom: head })?;
+    let malformed = || FormError::IterationSpecMalformed { source, atom: spec };
+    let (name, value, result) = match spec.list().map(|list| list.elements()) {
+        Some([name, value]) => (name, value, None),
+        Some([name, value, result]) => (name, value, Some(result)),
+        _ => return Err(malformed()),
+    };
+    let name = extract_name(source, name).map_err(|_| malformed())?;
+    let value = Form::extract(source, value, macros)?;
+    let result = result
+        .map(|result| Form::extract(source, result, macros))
+        .transpose()?;
+    Ok((name, value, result))
+}
+
+impl<'s, 't> DotimesForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<DotimesForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "dotimes" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let (name, count, result) =
+            extract_iteration_spec(source, head, elements.next(), macros)?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(DotimesForm {
+            source,
+            name,
+            count: Box::new(count),
+            result: result.map(Box::new),
+            body,
+        }))
+    }
+
+    /// Bound to the numbers from zero to below the count.
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    pub fn count(&self) -> &Form<'s, 't> {
+        &self.count
+    }
+
+    /// Evaluated after the last iteration, the form evaluates to nil
+    /// without it.
+    pub fn result(&self) -> Option<&Form<'s, 't>> {
+        self.result.as_deref()
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+
+    pub fn forms(&self) -> impl Iterator<Item = &Form<'s, 't>> + Clone {
+        iter::once(self.count())
+            .chain(self.result())
+            .chain(self.body())
+    }
+}
+
+impl<'s, 't> DolistForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<DolistForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let head = match elements.next().unwrap() {
+            AstNode::Atom(head)
+                if head.fragment(source).source() == "dolist" =>
+            {
+                head
+            }
+            _ => return Ok(None),
+        };
+
+        let (name, list, result) =
+            extract_iteration_spec(source, head, elements.next(), macros)?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(DolistForm {
+            source,
+            name,
+            list: Box::new(list),
+            result: result.map(Box::new),
+            body,
+        }))
+    }
+
+    /// Bound to each element of the list, and to nil for the result.
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    pub fn list(&self) -> &Form<'s, 't> {
+        &self.list
+    }
+
+    /// Evaluated after the last iteration, the form evaluates to nil
+    /// without it.
+    pub fn result(&self) -> Option<&Form<'s, 't>> {
+        self.result.as_deref()
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+
+    pub fn forms(&self) -> impl Iterator<Item = &Form<'s, 't>> + Clone {
+        iter::once(self.list())
+            .chain(self.result())
+            .chain(self.body())
+    }
+}
+
+impl<'s, 't> DoForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<DoForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter();
+        let (head, sequential) = match elements.next().unwrap() {
+            AstNode::Atom(head) => match head.fragment(source).source() {
+                "do" => (head, false),
+                "do*" => (head, true),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+
+        let (Some(variables), Some(end)) = (elements.next(), elements.next())
+        else {
+            return Err(FormError::IterationMissingSpec { source, atom: head });
+        };
+        let variables = variables
+            .list()
+            .ok_or(FormError::IterationSpecMalformed {
+                source,
+                atom: variables,
+            })?
+            .elements()
+            .iter()
+            .map(|variable| DoVariable::extract(source, variable, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut end_clause = end
+            .list()
+            .ok_or(FormError::IterationSpecMalformed { source, atom: end })?
+            .elements()
+            .iter()
+            .map(|f| Form::extract(source, f, macros));
+        let end_test = end_clause
+            .next()
+            .ok_or(FormError::IterationSpecMalformed { source, atom: end })??;
+        let result = end_clause.collect::<Result<Vec<_>, _>>()?;
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(DoForm {
+            source,
+            sequential,
+            variables,
+            end_test: Box::new(end_test),
+            result,
+            body,
+        }))
+    }
+
+    pub fn sequential(&self) -> bool {
+        self.sequential
+    }
+
+    pub fn variables(&self) -> &[DoVariable<'s, 't>] {
+        &self.variables
+    }
+
+    /// Checked before each iteration, the loop ends once it is true.
+    pub fn end_test(&self) -> &Form<'s, 't> {
+        &self.end_test
+    }
+
+    /// Evaluated after the last iteration.
+    pub fn result(&self) -> &[Form<'s, 't>] {
+        &self.result
+    }
+
+    pub fn body(&self) -> &[Form<'s, 't>] {
+        &self.body
+    }
+
+    pub fn forms(&self) -> impl Iterator<Item = &Form<'s, 't>> + Clone {
+        self.variables
+            .iter()
+            .flat_map(|variable| {
+                variable.init().into_iter().chain(variable.step())
+            })
+            .chain(iter::once(self.end_test()))
+            .chain(self.result())
+            .chain(self.body())
+    }
+}
+
+impl<'s, 't> DoVariable<'s, 't> {
+    /// A variable is either just a name, or a list like `(name [init
+    /// [step]])`.
+    fn extract(
+        source: Source<'s>,
+        variable: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<DoVariable<'s, 't>, FormError<'s, 't>> {
+        let malformed = || FormError::IterationSpecMalformed {
+            source,
+            atom: variable,
+        };
+        let (name, init, step) = match variable {
+            AstNode::Atom(_) => (variable, None, None),
+            AstNode::List(list) => match list.elements() {
+                [name] => (name, None, None),
+                [name, init] => (name, Some(init), None),
+                [name, init, step] => (name, Some(init), Some(step)),
+                _ => return Err(malformed()),
+            },
+            _ => return Err(malformed()),
+        };
+        let name = extract_name(source, name).map_err(|_| malformed())?;
+        let init = init
+            .map(|init| Form::extract(source, init, macros))
+            .transpose()?;
+        let step = step
+            .map(|step| Form::extract(source, step, macros))
+            .transpose()?;
+        Ok(DoVariable { name, init, step })
+    }
+
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    /// The variable starts out as nil without it.
+    pub fn init(&self) -> Option<&Form<'s, 't>> {
+        self.init.as_ref()
+    }
+
+    /// Assigned after each iteration, the variable keeps its value without
+    /// it.
+    pub fn step(&self) -> Option<&Form<'s, 't>> {
+        self.step.as_ref()
+    }
+}
+
+impl<'s, 't> LoopForm<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<LoopForm<'s, 't>>, FormError<'s, 't>> {
+        let mut elements = form.elements().iter().peekable();
+        match elements.next().unwrap() {
+            AstNode::Atom(head) if head.fragment(source).source() == "loop" => {
+            }
+            _ => return Ok(None),
+        };
+
+        let mut loop_form = LoopForm {
             source,
-            function: Box::new(func),
-            args,
-        }))
+            variables: vec![],
+            clauses: vec![],
+            accumulation: LoopAccumulation::None,
+            finally: vec![],
+        };
+        // without keywords, the body is repeated until it returns
+        if elements
+            .peek()
+            .is_some_and(|element| element.list().is_some())
+        {
+            let body = elements
+                .map(|f| Form::extract(source, f, macros))
+                .collect::<Result<Vec<_>, _>>()?;
+            loop_form.clauses.push(LoopClause::Do(body));
+            return Ok(Some(loop_form));
+        }
+
+        while let Some(keyword) = elements.next() {
+            match loop_keyword(source, keyword) {
+                Some("for" | "as") => {
+                    let variable = LoopVariable::extract_for(
+                        source,
+                        keyword,
+                        &mut elements,
+                        macros,
+                    )?;
+                    loop_form.variables.push(variable);
+                }
+                Some("with") => {
+                    let name = elements
+                        .next()
+                        .and_then(|name| extract_name(source, name).ok())
+                        .ok_or(FormError::LoopClauseMalformed {
+                            source,
+                            atom: keyword,
+                        })?;
+                    let value = if elements
+                        .next_if(|element| {
+                            loop_keyword(source, element) == Some("=")
+                        })
+                        .is_some()
+                    {
+                        Some(loop_form_after(
+                            source,
+                            keyword,
+                            &mut elements,
+                            macros,
+                        )?)
+                    } else {
+                        None
+                    };
+                    loop_form
+                        .variables
+                        .push(LoopVariable::With { name, value });
+                }
+                Some("finally") => {
+                    let forms = loop_compound_forms(
+                        source,
+                        keyword,
+                        &mut elements,
+                        macros,
+                    )?;
+                    loop_form.finally.extend(forms);
+                }
+                _ => {
+                    let clause = LoopClause::extract(
+                        source,
+                        keyword,
+                        &mut elements,
+                        macros,
+                    )?;
+                    let accumulation = clause.accumulation();
+                    if accumulation != LoopAccumulation::None {
+                        if loop_form.accumulation != LoopAccumulation::None
+                            && loop_form.accumulation != accumulation
+                        {
+                            return Err(FormError::LoopAccumulationsDiffer {
+                                source,
+                                atom: keyword,
+                            });
+                        }
+                        loop_form.accumulation = accumulation;
+                    }
+                    loop_form.clauses.push(clause);
+                }
+            }
+        }
+        Ok(Some(loop_form))
     }
 
-    pub fn source(&self) -> Source<'s> {
-        self.source
+    /// Bound in order before the loop, and checked and stepped in order in
+    /// each iteration.
+    pub fn variables(&self) -> &[LoopVariable<'s, 't>] {
+        &self.variables
     }
 
-    pub fn function<'a>(&'a self) -> &'a Form<'s, 't> {
-        self.function.as_ref()
+    /// Run in order in each iteration.
+    pub fn clauses(&self) -> &[LoopClause<'s, 't>] {
+        &self.clauses
     }
 
-    pub fn args<'a>(&'a self) -> &'a [Form<'s, 't>] {
-        &self.args
+    pub fn accumulation(&self) -> LoopAccumulation {
+        self.accumulation
+    }
+
+    /// Run when the loop ends without a return.
+    pub fn finally(&self) -> &[Form<'s, 't>] {
+        &self.finally
+    }
+
+    pub fn forms(&self) -> impl Iterator<Item = &Form<'s, 't>> + Clone {
+        let mut forms = vec![];
+        for variable in &self.variables {
+            match variable {
+                LoopVariable::With { value, .. } => forms.extend(value),
+                LoopVariable::In { list, .. } => forms.push(list),
+                LoopVariable::Range { from, to, by, .. } => {
+                    for form in [from, to, by].into_iter().flatten() {
+                        forms.push(form);
+                    }
+                }
+            }
+        }
+        for clause in &self.clauses {
+            clause.collect_forms(&mut forms);
+        }
+        forms.extend(&self.finally);
+        forms.into_iter()
     }
 }
 
-impl<'s, 't> LetForm<'s, 't> {
+impl<'s, 't> LoopVariable<'s, 't> {
+    /// Extracts a `for` clause after its keyword.
+    fn extract_for(
+        source: Source<'s>,
+        keyword: &'t AstNode<'s>,
+        elements: &mut Peekable<slice::Iter<'t, AstNode<'s>>>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<LoopVariable<'s, 't>, FormError<'s, 't>> {
+        let malformed = || FormError::LoopClauseMalformed {
+            source,
+            atom: keyword,
+        };
+        let name = elements
+            .next()
+            .and_then(|name| extract_name(source, name).ok())
+            .ok_or_else(malformed)?;
+        if elements
+            .next_if(|element| loop_keyword(source, element) == Some("in"))
+            .is_some()
+        {
+            let list = loop_form_after(source, keyword, elements, macros)?;
+            return Ok(LoopVariable::In { name, list });
+        }
+
+        let (mut from, mut to, mut inclusive, mut by) =
+            (None, None, true, None);
+        while let Some(part) = elements.next_if(|element| {
+            matches!(
+                loop_keyword(source, element),
+                Some("from" | "to" | "upto" | "below" | "by")
+            )
+        }) {
+            let value = loop_form_after(source, part, elements, macros)?;
+            let value = Some(Box::new(value));
+            match loop_keyword(source, part) {
+                Some("from") => from = value,
+                Some("by") => by = value,
+                Some("below") => (to, inclusive) = (value, false),
+                _ => (to, inclusive) = (value, true),
+            }
+        }
+        if from.is_none() && to.is_none() && by.is_none() {
+            return Err(malformed());
+        }
+        Ok(LoopVariable::Range {
+            name,
+            from,
+            to,
+            inclusive,
+            by,
+        })
+    }
+}
+
+impl<'s, 't> LoopClause<'s, 't> {
+    /// Extracts a main clause after its keyword.
+    fn extract(
+        source: Source<'s>,
+        keyword: &'t AstNode<'s>,
+        elements: &mut Peekable<slice::Iter<'t, AstNode<'s>>>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<LoopClause<'s, 't>, FormError<'s, 't>> {
+        let mut form = || loop_form_after(source, keyword, elements, macros);
+        Ok(match loop_keyword(source, keyword) {
+            Some("do") => LoopClause::Do(loop_compound_forms(
+                source, keyword, elements, macros,
+            )?),
+            Some("collect") => LoopClause::Collect(form()?),
+            Some("sum") => LoopClause::Sum(form()?),
+            Some("return") => LoopClause::Return(form()?),
+            Some("while") => LoopClause::While(form()?),
+            Some("until") => LoopClause::Until(form()?),
+            Some(when @ ("when" | "if" | "unless")) => {
+                let test = form()?;
+                let keyword =
+                    elements.next().ok_or(FormError::LoopClauseMalformed {
+                        source,
+                        atom: keyword,
+                    })?;
+                let clause =
+                    LoopClause::extract(source, keyword, elements, macros)?;
+                LoopClause::When {
+                    test,
+                    negated: when == "unless",
+                    clause: Box::new(clause),
+                }
+            }
+            _ => {
+                return Err(FormError::LoopClauseMalformed {
+                    source,
+                    atom: keyword,
+                });
+            }
+        })
+    }
+
+    fn accumulation(&self) -> LoopAccumulation {
+        match self {
+            LoopClause::Collect(_) => LoopAccumulation::Collect,
+            LoopClause::Sum(_) => LoopAccumulation::Sum,
+            LoopClause::When { clause, .. } => clause.accumulation(),
+            _ => LoopAccumulation::None,
+        }
+    }
+
+    fn collect_forms<'a>(&'a self, forms: &mut Vec<&'a Form<'s, 't>>) {
+        match self {
+            LoopClause::Do(body) => forms.extend(body),
+            LoopClause::Collect(form)
+            | LoopClause::Sum(form)
+            | LoopClause::Return(form)
+            | LoopClause::While(form)
+            | LoopClause::Until(form) => forms.push(form),
+            LoopClause::When { test, clause, .. } => {
+                forms.push(test);
+                clause.collect_forms(forms);
+            }
+        }
+    }
+}
+
+/// Loop keywords are identifiers, which are compared by name.
+fn loop_keyword<'s>(source: Source<'s>, node: &AstNode<'s>) -> Option<&'s str> {
+    node.atom()
+        .filter(|atom| matches!(atom.token().kind(), TokenKind::Ident))
+        .map(|atom| atom.fragment(source).source())
+}
+
+/// Extracts the form that the keyword of a loop clause needs.
+fn loop_form_after<'s, 't>(
+    source: Source<'s>,
+    keyword: &'t AstNode<'s>,
+    elements: &mut Peekable<slice::Iter<'t, AstNode<'s>>>,
+    macros: &Macros<'s, 't>,
+) -> Result<Form<'s, 't>, FormError<'s, 't>> {
+    let form = elements.next().ok_or(FormError::LoopClauseMalformed {
+        source,
+        atom: keyword,
+    })?;
+    Form::extract(source, form, macros)
+}
+
+/// Extracts the lists after `do` or `finally` up to the next keyword, of
+/// which there must be at least one.
+fn loop_compound_forms<'s, 't>(
+    source: Source<'s>,
+    keyword: &'t AstNode<'s>,
+    elements: &mut Peekable<slice::Iter<'t, AstNode<'s>>>,
+    macros: &Macros<'s, 't>,
+) -> Result<Vec<Form<'s, 't>>, FormError<'s, 't>> {
+    let mut forms = vec![];
+    while let Some(form) = elements.next_if(|element| element.list().is_some())
+    {
+        forms.push(Form::extract(source, form, macros)?);
+    }
+    if forms.is_empty() {
+        return Err(FormError::LoopClauseMalformed {
+            source,
+            atom: keyword,
+        });
+    }
+    Ok(forms)
+}
+
+impl<'s, 't> FletForm<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
-    ) -> Result<Option<LetForm<'s, 't>>, FormError<'s, 't>> {
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<FletForm<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
         let head = elements.next().unwrap();
-        let is_let = match head {
-            AstNode::Atom(first)
-                if first.source_range().of(source).source() == "let" =>
-            {
-                true
+        let recursive = match head {
+            AstNode::Atom(first) => {
+                match first.source_range().of(source).source() {
+                    "flet" => false,
+                    "labels" => true,
+                    _ => return Ok(None),
+                }
             }
-            _ => false,
+            _ => return Ok(None),
         };
-        if !is_let {
-            // ignore root-level directive that is not defun
-            return Ok(None);
-        }
         let head = head.atom().unwrap();
 
-        let bindings =
+        let definitions =
             elements
                 .next()
-                .ok_or_else(|| FormError::LetMissingBindings {
+                .ok_or_else(|| FormError::FletMissingFunctions {
                     source,
                     atom: head,
                 })?;
-        let bindings =
-            bindings
-                .list()
-                .ok_or_else(|| FormError::LetBindingsNotList {
-                    source,
-                    atom: bindings,
-                })?;
-        let mut bindings_parsed = vec![];
-        for binding in bindings.elements() {
-            let binding = binding.list().ok_or_else(|| {
-                FormError::LetBindingEntryNotList {
-                    source,
-                    atom: binding,
-                }
-            })?;
-            if binding.elements().len() < 2 {
-                return Err(FormError::LetBindingEntryTooShort {
-                    source,
-                    atom: binding,
-                });
-            }
-            if binding.elements().len() > 2 {
-                return Err(FormError::LetBindingEntryTooLong {
-                    source,
-                    atom: binding,
-                });
+        let definitions = definitions.list().ok_or_else(|| {
+            FormError::FletFunctionsNotList {
+                source,
+                atom: definitions,
             }
-            let name = binding.elements()[0]
-                .atom()
+        })?;
+        let mut functions = vec![];
+        for definition in definitions.elements() {
+            let name = definition
+                .list()
+                .and_then(|l| l.elements().first())
+                .and_then(AstNode::atom)
                 .filter(|a| a.token().kind() == TokenKind::Ident)
-                .ok_or_else(|| FormError::LetBindingVarNotIdent {
+                .ok_or_else(|| FormError::FletFunctionMissingName {
                     source,
-                    atom: &binding.elements()[0],
+                    atom: definition,
                 })?;
-            let value = Form::extract(source, &binding.elements()[1])?;
-            bindings_parsed.push(Binding { name, value });
+            let elements = definition.list().unwrap().elements().iter();
+            let lambda = Lambda::extract_after_head(
+                source,
+                name,
+                elements.skip(1),
+                macros,
+            )?;
+            functions.push(LocalFunction { name, lambda });
         }
 
-        let body = &form.elements()[2..];
-        if body.is_empty() {
-            return Err(FormError::LetMissingBody { source, atom: head });
-        }
-        let body = body
-            .iter()
-            .map(|f| Form::extract(source, f))
+        let body = elements
+            .map(|f| Form::extract(source, f, macros))
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Some(LetForm {
+        Ok(Some(FletForm {
             source,
-            bindings: bindings_parsed,
+            recursive,
+            functions,
             body,
         }))
     }
 
-    pub fn bindings(&self) -> &[Binding<'s, 't>] {
-        &self.bindings
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn recursive(&self) -> bool {
+        self.recursive
+    }
+
+    pub fn functions(&self) -> &[LocalFunction<'s, 't>] {
+        &self.functions
     }
 
+    /// Evaluates to nil if empty.
     pub fn body(&self) -> &[Form<'s, 't>] {
         &self.body
     }
 }
 
-impl<'s, 't> Binding<'s, 't> {
+impl<'s, 't> LocalFunction<'s, 't> {
     pub fn name(&self) -> &'t Atom<'s> {
         self.name
     }
 
-    pub fn value(&self) -> &Form<'s, 't> {
-        &self.value
+    pub fn lambda(&self) -> &Lambda<'s, 't> {
+        &self.lambda
     }
 }
 
@@ -617,6 +2793,7 @@ impl<'s, 't> Lambda<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Option<Lambda<'s, 't>>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
@@ -635,6 +2812,17 @@ impl<'s, 't> Lambda<'s, 't> {
         }
 
         let head = head.atom().unwrap();
+        Self::extract_after_head(source, head, elements, macros).map(Some)
+    }
+
+    /// Extracts the parameters and body that follow the head, which is
+    /// either `lambda` or the name of a local function.
+    fn extract_after_head(
+        source: Source<'s>,
+        head: &'t Atom<'s>,
+        mut elements: impl Iterator<Item = &'t AstNode<'s>>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Lambda<'s, 't>, FormError<'s, 't>> {
         let params = elements
             .next()
             .ok_or_else(|| FormError::LambdaTooShort { source, atom: head })?;
@@ -646,36 +2834,27 @@ impl<'s, 't> Lambda<'s, 't> {
         })?;
 
         let body = elements
-            .map(|e| Form::extract(source, e))
+            .map(|e| Form::extract(source, e, macros))
             .collect::<Result<Vec<_>, _>>()?;
         if body.is_empty() {
             return Err(FormError::LambdaTooShort { source, atom: head });
         }
 
-        let positional_args = params_list
-            .elements()
-            .iter()
-            .map(|p| {
-                p.atom().ok_or_else(|| FormError::MalformedLambdaArguments {
-                    source,
-                    atom: p,
-                })
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let parameters = Parameters::extract(source, params_list, macros)?;
 
-        Ok(Some(Lambda {
+        Ok(Lambda {
             source,
-            positional_args,
+            parameters,
             body,
-        }))
+        })
     }
 
     pub fn source(&self) -> Source<'s> {
         self.source
     }
 
-    pub fn positional_args(&self) -> &[&'t Atom<'s>] {
-        &self.positional_args
+    pub fn parameters(&self) -> &Parameters<'s, 't> {
+        &self.parameters
     }
 
     pub fn body(&self) -> &[Form<'s, 't>] {
@@ -691,6 +2870,7 @@ impl<'s, 't> Call<'s, 't> {
     fn extract_assume_nonempty(
         source: Source<'s>,
         form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Call<'s, 't>, FormError<'s, 't>> {
         let mut elements = form.elements().iter();
 
@@ -704,7 +2884,7 @@ impl<'s, 't> Call<'s, 't> {
         let arg_asts = &form.elements()[1..];
         let arg_forms = arg_asts
             .into_iter()
-            .map(|a| Form::extract(source, a))
+            .map(|a| Form::extract(source, a, macros))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Call {
@@ -727,59 +2907,243 @@ impl<'s, 't> Call<'s, 't> {
     }
 }
 
+impl<'s, 't> MacroCall<'s, 't> {
+    fn extract_assume_nonempty(
+        source: Source<'s>,
+        form: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Option<MacroCall<'s, 't>>, FormError<'s, 't>> {
+        let Some(definition) = macros.find(source, form) else {
+            return Ok(None);
+        };
+        let (expansion_source, expansion) =
+            macros.expand(source, form, definition)?;
+        let expansion = macros
+            .nested(|| Form::extract(expansion_source, expansion, macros))
+            .map_err(|error| match error {
+                // already reported with the innermost macro call, except
+                // that expansions that never stop are reported where they
+                // started
+                FormError::MacroExpansion(error) => FormError::MacroExpansion(
+                    Box::new(error.moved_to(source, form, definition)),
+                ),
+                error => MacroError::new(
+                    source,
+                    form,
+                    definition,
+                    MacroErrorKind::InvalidExpansion(error),
+                )
+                .into(),
+            })?;
+        Ok(Some(MacroCall {
+            source,
+            call: form,
+            expansion_source,
+            expansion: Box::new(expansion),
+        }))
+    }
+
+    pub fn expansion_source(&self) -> Source<'s> {
+        self.expansion_source
+    }
+
+    pub fn expansion(&self) -> &Form<'s, 't> {
+        &self.expansion
+    }
+}
+
+impl<'s, 't> Quasiquote<'s, 't> {
+    pub fn template(&self) -> &Template<'s, 't> {
+        &self.template
+    }
+}
+
+impl<'s, 't> Template<'s, 't> {
+    fn extract(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Template<'s, 't>, FormError<'s, 't>> {
+        if find_unquote(node).is_none() {
+            return Ok(Template::Constant(node));
+        }
+        Ok(match node {
+            AstNode::Quoted(quoted) => {
+                Self::extract(source, quoted.quoted(), macros)?
+            }
+            AstNode::Quasiquoted(quasiquoted) => {
+                Self::extract(source, quasiquoted.quasiquoted(), macros)?
+            }
+            AstNode::Unquoted(unquoted) => Template::Unquoted(Box::new(
+                Form::extract(source, unquoted.unquoted(), macros)?,
+            )),
+            AstNode::UnquotedSplicing(_) => {
+                return Err(FormError::SpliceOutsideList { source, node });
+            }
+            AstNode::Vector(_) => {
+                return Err(FormError::UnquoteInVector { source, node });
+            }
+            AstNode::List(list) => Template::List(
+                list.elements()
+                    .iter()
+                    .map(|element| {
+                        Ok(match element {
+                            AstNode::UnquotedSplicing(spliced) => {
+                                TemplateElement::Spliced(Form::extract(
+                                    source,
+                                    spliced.unquoted(),
+                                    macros,
+                                )?)
+                            }
+                            _ => TemplateElement::Element(Self::extract(
+                                source, element, macros,
+                            )?),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, FormError>>()?,
+            ),
+            AstNode::Atom(_) => unreachable!("atoms contain no unquotes"),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub enum FormError<'s, 't> {
     IfMissingTest {
         source: Source<'s>,
-        form: &'t List<'s>,
+        form: &'t List<'s>,
+    },
+    IfMissingThen {
+        source: Source<'s>,
+        form: &'t List<'s>,
+    },
+    IfExtraneousForm {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    LetMissingBindings {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    LetBindingsNotList {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    LetBindingEntryNotList {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    LetBindingEntryTooShort {
+        source: Source<'s>,
+        atom: &'t List<'s>,
+    },
+    LetBindingEntryTooLong {
+        source: Source<'s>,
+        atom: &'t List<'s>,
+    },
+    LetBindingVarNotIdent {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    LetMissingBody {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    CallTargetNotConstant {
+        source: Source<'s>,
+        form: &'t AstNode<'s>,
+    },
+    ApplyStaticTooShort {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    ApplyStaticTooLong {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    DestructuringBindTooShort {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    MultipleValueBindTooShort {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    MultipleValueBindNamesNotList {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    MultipleValueListArguments {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    BlockMissingName {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    BlockNameNotIdent {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    ReturnFromArguments {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    CatchMissingTag {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
     },
-    IfMissingThen {
+    ThrowArguments {
         source: Source<'s>,
-        form: &'t List<'s>,
+        atom: &'t Atom<'s>,
     },
-    IfExtraneousForm {
+    UnwindProtectMissingForm {
         source: Source<'s>,
-        form: &'t AstNode<'s>,
+        atom: &'t Atom<'s>,
     },
-    LetMissingBindings {
+    HandlerCaseMissingForm {
         source: Source<'s>,
         atom: &'t Atom<'s>,
     },
-    LetBindingsNotList {
+    HandlerClauseMalformed {
         source: Source<'s>,
         atom: &'t AstNode<'s>,
     },
-    LetBindingEntryNotList {
+    HandlerBindMissingHandlers {
         source: Source<'s>,
-        atom: &'t AstNode<'s>,
+        atom: &'t Atom<'s>,
     },
-    LetBindingEntryTooShort {
+    HandlerMalformed {
         source: Source<'s>,
-        atom: &'t List<'s>,
+        atom: &'t AstNode<'s>,
     },
-    LetBindingEntryTooLong {
+    IterationMissingSpec {
         source: Source<'s>,
-        atom: &'t List<'s>,
+        atom: &'t Atom<'s>,
     },
-    LetBindingVarNotIdent {
+    IterationSpecMalformed {
         source: Source<'s>,
         atom: &'t AstNode<'s>,
     },
-    LetMissingBody {
+    LoopClauseMalformed {
         source: Source<'s>,
-        atom: &'t Atom<'s>,
+        atom: &'t AstNode<'s>,
     },
-    CallTargetNotConstant {
+    LoopAccumulationsDiffer {
         source: Source<'s>,
-        form: &'t AstNode<'s>,
+        atom: &'t AstNode<'s>,
     },
-    ApplyStaticTooShort {
+    FletMissingFunctions {
         source: Source<'s>,
         atom: &'t Atom<'s>,
     },
-    ApplyStaticTooLong {
+    FletFunctionsNotList {
         source: Source<'s>,
-        atom: &'t Atom<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    FletFunctionMissingName {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
     },
     LambdaTooShort {
         source: Source<'s>,
@@ -793,6 +3157,74 @@ pub enum FormError<'s, 't> {
         source: Source<'s>,
         atom: &'t Atom<'s>,
     },
+    CondClauseNotList {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    CondClauseMissingTest {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    WhenMissingTest {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    UnlessMissingTest {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    SetqOddArguments {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    SetqTargetNotVariable {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    SetfOddArguments {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    SetfUnsupportedPlace {
+        source: Source<'s>,
+        atom: &'t AstNode<'s>,
+    },
+    UnquoteOutsideQuasiquote {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    SpliceOutsideList {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    UnquoteInVector {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    MalformedParameter {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    /// Unknown, repeated or out of order.
+    MisplacedLambdaListKeyword {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    RestMissingName {
+        source: Source<'s>,
+        rest: &'t Atom<'s>,
+    },
+    RestAdditionalName {
+        source: Source<'s>,
+        additional: &'t AstNode<'s>,
+    },
+    MacroExpansion(Box<MacroError<'s, 't>>),
+}
+
+impl<'s, 't> From<MacroError<'s, 't>> for FormError<'s, 't> {
+    fn from(value: MacroError<'s, 't>) -> Self {
+        Self::MacroExpansion(Box::new(value))
+    }
 }
 
 impl<'s, 't> Diagnostic for FormError<'s, 't> {
@@ -859,6 +3291,117 @@ impl<'s, 't> fmt::Display for FormError<'s, 't> {
                 writeln!(f, "apply has too many arguments")?;
                 writeln!(f, "{}", atom.fragment(*source).source_context())
             }
+            FormError::DestructuringBindTooShort { source, atom } => {
+                writeln!(
+                    f,
+                    "destructuring-bind is missing the lambda list or value:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::MultipleValueBindTooShort { source, atom } => {
+                writeln!(
+                    f,
+                    "multiple-value-bind is missing the names or value:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::MultipleValueBindNamesNotList { source, atom } => {
+                writeln!(f, "multiple-value-bind names must be a list:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::MultipleValueListArguments { source, atom } => {
+                writeln!(f, "multiple-value-list takes exactly one form:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::BlockMissingName { source, atom } => {
+                writeln!(f, "block is missing its name:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::BlockNameNotIdent { source, atom } => {
+                writeln!(f, "block name must be an identifier:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::ReturnFromArguments { source, atom } => {
+                writeln!(
+                    f,
+                    "{} has the wrong number of arguments:",
+                    atom.fragment(*source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::CatchMissingTag { source, atom } => {
+                writeln!(f, "catch is missing its tag:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::ThrowArguments { source, atom } => {
+                writeln!(f, "throw takes exactly a tag and a value:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::UnwindProtectMissingForm { source, atom } => {
+                writeln!(f, "unwind-protect is missing the protected form:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::HandlerCaseMissingForm { source, atom } => {
+                writeln!(f, "handler-case is missing the handled form:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::HandlerClauseMalformed { source, atom } => {
+                writeln!(
+                    f,
+                    "handler-case clause must be a type, a list of at most one name and a body:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::HandlerBindMissingHandlers { source, atom } => {
+                writeln!(f, "handler-bind is missing the list of handlers:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::HandlerMalformed { source, atom } => {
+                writeln!(
+                    f,
+                    "handler-bind handler must be a type and a function:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::IterationMissingSpec { source, atom } => {
+                writeln!(
+                    f,
+                    "{} is missing its variables:",
+                    atom.fragment(*source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::IterationSpecMalformed { source, atom } => {
+                writeln!(f, "iteration variables are malformed:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::LoopClauseMalformed { source, atom } => {
+                writeln!(f, "loop clause is unsupported or incomplete:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::LoopAccumulationsDiffer { source, atom } => {
+                writeln!(f, "loop can only either collect or sum:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::FletMissingFunctions { source, atom } => {
+                writeln!(
+                    f,
+                    "{} form does not define a list of functions:",
+                    atom.fragment(*source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::FletFunctionsNotList { source, atom } => {
+                writeln!(f, "local functions must be defined as a list:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::FletFunctionMissingName { source, atom } => {
+                writeln!(
+                    f,
+                    "local function must be a list starting with its name:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
             FormError::LambdaTooShort { source, atom } => {
                 writeln!(f, "lambda is missing arguments or body")?;
                 writeln!(f, "{}", atom.fragment(*source).source_context())
@@ -871,6 +3414,79 @@ impl<'s, 't> fmt::Display for FormError<'s, 't> {
                 writeln!(f, "funcall is missing the function to call")?;
                 writeln!(f, "{}", atom.fragment(*source).source_context())
             }
+            FormError::CondClauseNotList { source, atom } => {
+                writeln!(f, "cond clause must be a list:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::CondClauseMissingTest { source, atom } => {
+                writeln!(f, "cond clause is missing the test part:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::WhenMissingTest { source, atom } => {
+                writeln!(f, "when form is missing the test part:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::UnlessMissingTest { source, atom } => {
+                writeln!(f, "unless form is missing the test part:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::SetqOddArguments { source, atom } => {
+                writeln!(
+                    f,
+                    "setq is missing the value for the last variable:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::SetqTargetNotVariable { source, atom } => {
+                writeln!(f, "setq can only assign to variables:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::SetfOddArguments { source, atom } => {
+                writeln!(f, "setf is missing the value for the last place:")?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::SetfUnsupportedPlace { source, atom } => {
+                writeln!(
+                    f,
+                    "setf can only assign to variables, car and cdr:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::UnquoteOutsideQuasiquote { source, node } => {
+                writeln!(f, "unquote is not inside a quasiquote:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            FormError::SpliceOutsideList { source, node } => {
+                writeln!(f, "unquote-splicing is not inside a list:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            FormError::UnquoteInVector { source, node } => {
+                writeln!(f, "vectors cannot contain unquotes:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            FormError::MalformedParameter { source, node } => {
+                writeln!(f, "not a valid parameter:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            FormError::MisplacedLambdaListKeyword { source, atom } => {
+                writeln!(
+                    f,
+                    "lambda list keyword is unknown, repeated or out of order:"
+                )?;
+                writeln!(f, "{}", atom.fragment(*source).source_context())
+            }
+            FormError::RestMissingName { source, rest } => {
+                writeln!(f, "&rest specified but no name given after it:")?;
+                writeln!(f, "{}", rest.fragment(*source).source_context())
+            }
+            FormError::RestAdditionalName { source, additional } => {
+                writeln!(
+                    f,
+                    "&rest specified with two or more names after it:"
+                )?;
+                writeln!(f, "{}", additional.fragment(*source).source_context())
+            }
+            FormError::MacroExpansion(error) => write!(f, "{}", error),
         }
     }
 }
@@ -887,19 +3503,157 @@ mod test {
         let src = src.one();
         let ast = Parser::new(src).parse().unwrap();
         let ast = ast.iter().next().unwrap();
-        let form = Form::extract(src, ast).unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
         let form = form.lambda().unwrap();
-        assert_eq!(form.positional_args()[0].fragment(src).source(), "c");
+        assert_eq!(
+            form.parameters().required()[0].fragment(src).source(),
+            "c"
+        );
         assert!(form.body()[0].call().is_some())
     }
 
+    #[test]
+    fn extract_labels() {
+        let src = SourceSet::new_debug(
+            "(labels ((f (x) (g x)) (g (&optional y) y)) (f 1))",
+        );
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
+        let form = form.flet_form().unwrap();
+        assert!(form.recursive());
+        let functions = form.functions();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name().fragment(src).source(), "f");
+        assert!(functions[0].lambda().body()[0].call().is_some());
+        assert_eq!(functions[1].lambda().parameters().optional().len(), 1);
+        assert!(form.body()[0].call().is_some());
+    }
+
+    #[test]
+    fn multiple_value_forms_check_their_shape() {
+        for code in [
+            "(multiple-value-bind (a 1) (f))",
+            "(multiple-value-bind a (f))",
+            "(multiple-value-bind (a))",
+            "(multiple-value-list (f) (g))",
+            "(destructuring-bind (a))",
+        ] {
+            let src = SourceSet::new_debug(code);
+            let src = src.one();
+            let ast = Parser::new(src).parse().unwrap();
+            let ast = ast.iter().next().unwrap();
+            assert!(Form::extract(src, ast, &Macros::new()).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn non_local_exit_forms_check_their_shape() {
+        for code in [
+            "(block)",
+            "(block (a) 1)",
+            "(return-from)",
+            "(return-from b 1 2)",
+            "(return 1 2)",
+            "(catch)",
+            "(throw 'a)",
+            "(throw 'a 1 2)",
+            "(unwind-protect)",
+        ] {
+            let src = SourceSet::new_debug(code);
+            let src = src.one();
+            let ast = Parser::new(src).parse().unwrap();
+            let ast = ast.iter().next().unwrap();
+            assert!(Form::extract(src, ast, &Macros::new()).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn handler_forms_check_their_shape() {
+        for code in [
+            "(handler-case)",
+            "(handler-case 1 error)",
+            "(handler-case 1 (error c))",
+            "(handler-case 1 (error (a b)))",
+            "(handler-case 1 (\"error\" ()))",
+            "(handler-bind)",
+            "(handler-bind a 1)",
+            "(handler-bind ((error)) 1)",
+            "(handler-bind ((error #'f 1)) 1)",
+        ] {
+            let src = SourceSet::new_debug(code);
+            let src = src.one();
+            let ast = Parser::new(src).parse().unwrap();
+            let ast = ast.iter().next().unwrap();
+            assert!(Form::extract(src, ast, &Macros::new()).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn iteration_forms_check_their_shape() {
+        for code in [
+            "(dotimes)",
+            "(dotimes i 1)",
+            "(dotimes (i) 1)",
+            "(dolist (1 '(1)) 1)",
+            "(do ((i 0)))",
+            "(do ((i 0 1 2)) (t))",
+            "(loop for x in)",
+            "(loop for x across y)",
+            "(loop collect 1 sum 2)",
+            "(loop when t)",
+        ] {
+            let src = SourceSet::new_debug(code);
+            let src = src.one();
+            let ast = Parser::new(src).parse().unwrap();
+            let ast = ast.iter().next().unwrap();
+            assert!(Form::extract(src, ast, &Macros::new()).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn simple_loop_is_one_do_clause() {
+        let src = SourceSet::new_debug("(loop (f) (return 1))");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        let Ok(Form::LoopForm(form)) = Form::extract(src, ast, &Macros::new())
+        else {
+            panic!("not a loop");
+        };
+        assert!(form.variables().is_empty());
+        let [LoopClause::Do(forms)] = form.clauses() else {
+            panic!("not one do clause");
+        };
+        assert_eq!(forms.len(), 2);
+    }
+
+    #[test]
+    fn flet_functions_need_names() {
+        for code in ["(flet (((x) x)) 1)", "(flet (f) 1)", "(flet f 1)"] {
+            let src = SourceSet::new_debug(code);
+            let src = src.one();
+            let ast = Parser::new(src).parse().unwrap();
+            let ast = ast.iter().next().unwrap();
+            assert!(
+                matches!(
+                    Form::extract(src, ast, &Macros::new()),
+                    Err(FormError::FletFunctionMissingName { .. }
+                        | FormError::FletFunctionsNotList { .. })
+                ),
+                "{code}"
+            );
+        }
+    }
+
     #[test]
     fn extract_funcall() {
         let src = SourceSet::new_debug("(funcall #'+ 1 2)");
         let src = src.one();
         let ast = Parser::new(src).parse().unwrap();
         let ast = ast.iter().next().unwrap();
-        let form = Form::extract(src, ast).unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
         let form = form.funcall().unwrap();
         assert_eq!(form.function().function_name().unwrap().as_str(), "+");
         assert_eq!(
@@ -921,4 +3675,119 @@ mod test {
             "2"
         );
     }
+
+    #[test]
+    fn extract_cond() {
+        let src = SourceSet::new_debug("(cond ((= a 1) 'one) (b) (t 'other))");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
+        let form = form.cond_form().unwrap();
+        assert_eq!(form.clauses().len(), 3);
+        assert!(form.clauses()[0].test().call().is_some());
+        assert!(form.clauses()[0].body()[0].constant().is_some());
+        assert_eq!(form.clauses()[1].test().name().unwrap().as_str(), "b");
+        assert!(form.clauses()[1].body().is_empty());
+    }
+
+    #[test]
+    fn cond_clause_must_be_list() {
+        let src = SourceSet::new_debug("(cond (a 1) b)");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        assert!(matches!(
+            Form::extract(src, ast, &Macros::new()),
+            Err(FormError::CondClauseNotList { .. })
+        ));
+    }
+
+    #[test]
+    fn extract_setf() {
+        let src = SourceSet::new_debug(
+            "(setf a 1 (car b) 2 (cdr (f)) 3 (aref v 0) 4)",
+        );
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
+        let form = form.setf_form().unwrap();
+        let places = form
+            .assignments()
+            .iter()
+            .map(|a| a.place())
+            .collect::<Vec<_>>();
+        assert!(matches!(places[0], AssignedPlace::Variable(_)));
+        assert!(matches!(places[1], AssignedPlace::Car(Form::Name(_))));
+        assert!(matches!(places[2], AssignedPlace::Cdr(Form::Call(_))));
+        assert!(matches!(
+            places[3],
+            AssignedPlace::Aref(Form::Name(_), Form::Constant(_))
+        ));
+    }
+
+    #[test]
+    fn setf_rejects_other_places() {
+        let src = SourceSet::new_debug("(setf (cadr b) 2)");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        assert!(matches!(
+            Form::extract(src, ast, &Macros::new()),
+            Err(FormError::SetfUnsupportedPlace { .. })
+        ));
+    }
+
+    #[test]
+    fn extract_quasiquote() {
+        let src = SourceSet::new_debug("(list `(a ,b ,@c (d ,e)) `(1 (2)))");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        let form = Form::extract(src, ast, &Macros::new()).unwrap();
+        let args = form.call().unwrap().args();
+        let Template::List(elements) = args[0].quasiquote().unwrap().template()
+        else {
+            panic!("expected a list template");
+        };
+        assert!(matches!(
+            elements[0],
+            TemplateElement::Element(Template::Constant(_))
+        ));
+        assert!(matches!(
+            elements[1],
+            TemplateElement::Element(Template::Unquoted(_))
+        ));
+        assert!(matches!(
+            elements[2],
+            TemplateElement::Spliced(Form::Name(_))
+        ));
+        assert!(matches!(
+            elements[3],
+            TemplateElement::Element(Template::List(_))
+        ));
+        // without unquotes, the template is just data
+        assert!(args[1].constant().is_some());
+    }
+
+    #[test]
+    fn unquote_needs_quasiquote() {
+        let src = SourceSet::new_debug("(list '(a ,b))");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        assert!(matches!(
+            Form::extract(src, ast, &Macros::new()),
+            Err(FormError::UnquoteOutsideQuasiquote { .. })
+        ));
+        let src = SourceSet::new_debug("(list `,@c)");
+        let src = src.one();
+        let ast = Parser::new(src).parse().unwrap();
+        let ast = ast.iter().next().unwrap();
+        assert!(matches!(
+            Form::extract(src, ast, &Macros::new()),
+            Err(FormError::SpliceOutsideList { .. })
+        ));
+    }
 }
@@ -4,13 +4,14 @@ use crate::diagnostic::Diagnostic;
 use crate::parse::{AstNode, Atom, List, TokenKind};
 use crate::source::Source;
 
+use super::expand::Macros;
 use super::form::{Form, FormError};
+use super::params::Parameters;
 
 pub struct FunctionDefinition<'s, 't> {
     source: Source<'s>,
     name: &'t Atom<'s>,
-    positional_args: Vec<&'t Atom<'s>>,
-    rest_arg: Option<&'t Atom<'s>>,
+    parameters: Parameters<'s, 't>,
     doc_string: Option<&'t Atom<'s>>,
     body: Vec<Form<'s, 't>>,
 }
@@ -26,6 +27,7 @@ impl<'s, 't> FunctionDefinition<'s, 't> {
     pub fn extract(
         source: Source<'s>,
         node: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<
         Option<FunctionDefinition<'s, 't>>,
         FunctionDefinitionError<'s, 't>,
@@ -80,59 +82,7 @@ impl<'s, 't> FunctionDefinition<'s, 't> {
                 node: param_node,
             }
         })?;
-        let found_non_ident_param =
-            param_list.elements().iter().any(|p| match p {
-                AstNode::Atom(a)
-                    if matches!(a.token().kind(), TokenKind::Ident) =>
-                {
-                    false
-                }
-                _ => true,
-            });
-        if found_non_ident_param {
-            return Err(FunctionDefinitionError::MalformedParams {
-                source,
-                node: param_node,
-            });
-        }
-        let rest_pos = param_list
-            .elements()
-            .iter()
-            .enumerate()
-            .find(|(_, a)| {
-                a.atom()
-                    .map(|a| a.fragment(source).source() == "&rest")
-                    .unwrap_or(false)
-            })
-            .map(|(idx, _)| idx);
-        let (positional_params, rest_param) = match rest_pos {
-            // rest and maybe also positional
-            Some(rest_idx) => {
-                let elements = param_list.elements();
-                let after_rest_count = elements.len() - rest_idx;
-                if after_rest_count == 0 {
-                    return Err(FunctionDefinitionError::RestMissingName {
-                        source,
-                        rest: &param_list.elements()[rest_idx],
-                    });
-                } else if after_rest_count > 2 {
-                    return Err(FunctionDefinitionError::RestAdditionalName {
-                        source,
-                        additional: &param_list.elements()[rest_idx + 2],
-                    });
-                }
-                let positional = &elements[0..rest_idx];
-                let rest = &elements[rest_idx + 1];
-                (positional, Some(rest))
-            }
-            // only positional arguments
-            None => (param_list.elements(), None),
-        };
-        let positional_params = positional_params
-            .iter()
-            .map(|p| p.atom().unwrap())
-            .collect();
-        let rest_param = rest_param.map(|r| r.atom().unwrap());
+        let parameters = Parameters::extract(source, param_list, macros)?;
 
         let mut rest = &list.elements()[3..];
 
@@ -150,14 +100,13 @@ impl<'s, 't> FunctionDefinition<'s, 't> {
         let body = rest;
         let mut body_forms = vec![];
         for body in body {
-            body_forms.push(Form::extract(source, body)?);
+            body_forms.push(Form::extract(source, body, macros)?);
         }
 
         Ok(Some(FunctionDefinition {
             source,
             name,
-            positional_args: positional_params,
-            rest_arg: rest_param,
+            parameters,
             doc_string,
             body: body_forms,
         }))
@@ -171,12 +120,8 @@ impl<'s, 't> FunctionDefinition<'s, 't> {
         self.name
     }
 
-    pub fn positional_args(&self) -> &[&'t Atom<'s>] {
-        &self.positional_args
-    }
-
-    pub fn rest_arg(&self) -> Option<&'t Atom<'s>> {
-        self.rest_arg
+    pub fn parameters(&self) -> &Parameters<'s, 't> {
+        &self.parameters
     }
 
     pub fn doc_string(&self) -> Option<&'t Atom<'s>> {
@@ -206,14 +151,6 @@ pub enum FunctionDefinitionError<'s, 't> {
         source: Source<'s>,
         node: &'t AstNode<'s>,
     },
-    RestMissingName {
-        source: Source<'s>,
-        rest: &'t AstNode<'s>,
-    },
-    RestAdditionalName {
-        source: Source<'s>,
-        additional: &'t AstNode<'s>,
-    },
     FormError(FormError<'s, 't>),
 }
 
@@ -251,24 +188,6 @@ impl<'s, 't> fmt::Display for FunctionDefinitionError<'s, 't> {
                 writeln!(f, "not a valid function parameter list:")?;
                 writeln!(f, "{}", node.fragment(*source).source_context())
             }
-            FunctionDefinitionError::RestMissingName { source, rest } => {
-                writeln!(f, "&rest specified but no name given after it:")?;
-                writeln!(f, "{}", rest.fragment(*source).source_context())
-            }
-            FunctionDefinitionError::RestAdditionalName {
-                source,
-                additional,
-            } => {
-                writeln!(
-                    f,
-                    "&rest specified with two or more names after it:"
-                )?;
-                writeln!(
-                    f,
-                    "{}",
-                    additional.fragment(*source).source_context()
-                )
-            }
             FunctionDefinitionError::FormError(e) => write!(f, "{}", e),
         }
     }
@@ -297,19 +216,25 @@ mod test {
         let source = source_set.one();
         let ast = Parser::new(source).parse().unwrap();
 
-        let definition =
-            FunctionDefinition::extract(source, &ast.root_nodes()[0])
-                .unwrap()
-                .unwrap();
+        let definition = FunctionDefinition::extract(
+            source,
+            &ast.root_nodes()[0],
+            &Macros::new(),
+        )
+        .unwrap()
+        .unwrap();
         assert_eq!(
             definition.name.source_range().of(source).source(),
             "max-2"
         );
 
-        let definition =
-            FunctionDefinition::extract(source, &ast.root_nodes()[1])
-                .unwrap()
-                .unwrap();
+        let definition = FunctionDefinition::extract(
+            source,
+            &ast.root_nodes()[1],
+            &Macros::new(),
+        )
+        .unwrap()
+        .unwrap();
         assert_eq!(
             definition.name.source_range().of(source).source(),
             "max-inner"
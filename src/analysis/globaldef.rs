@@ -5,6 +5,7 @@ use crate::parse::{AstNode, Atom, TokenKind};
 
 use crate::source::Source;
 
+use super::expand::Macros;
 use super::form::{Form, FormError};
 
 pub struct GlobalDefinition<'s, 't> {
@@ -17,6 +18,7 @@ impl<'s, 't> GlobalDefinition<'s, 't> {
     pub fn extract(
         source: Source<'s>,
         node: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
     ) -> Result<Option<GlobalDefinition<'s, 't>>, GlobalDefinitionError<'s, 't>>
     {
         let list = match node.list() {
@@ -75,7 +77,7 @@ impl<'s, 't> GlobalDefinition<'s, 't> {
             }
         };
 
-        let value = Form::extract(source, value)?;
+        let value = Form::extract(source, value, macros)?;
 
         Ok(Some(GlobalDefinition {
             source,
@@ -171,10 +173,13 @@ mod test {
         );
         let source = source_set.one();
         let ast = Parser::new(source).parse().unwrap();
-        let definition =
-            GlobalDefinition::extract(source, &ast.root_nodes()[0])
-                .unwrap()
-                .unwrap();
+        let definition = GlobalDefinition::extract(
+            source,
+            &ast.root_nodes()[0],
+            &Macros::new(),
+        )
+        .unwrap()
+        .unwrap();
         let name = definition.name().source_range().of(source).source();
         assert_eq!(name, "*list*");
         let value_code = definition
@@ -187,8 +192,12 @@ mod test {
             .source();
         assert_eq!(value_code, "(1 2 3 4)");
 
-        let non_definition =
-            GlobalDefinition::extract(source, &ast.root_nodes()[1]).unwrap();
+        let non_definition = GlobalDefinition::extract(
+            source,
+            &ast.root_nodes()[1],
+            &Macros::new(),
+        )
+        .unwrap();
         assert!(non_definition.is_none());
     }
 }
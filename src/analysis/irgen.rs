@@ -1,16 +1,20 @@
-use std::fmt;
+use std::{collections::HashMap, fmt, slice};
 
 use address::LocalPlaceGenerator;
 use code::generate_intrinsic_functions;
+use exits::{block_is_returned_from, block_needs_tag};
 use lambdas::{contains_form_lambdas, contains_function_lambdas};
-use scope::{FunctionScope, VariableScope};
+use scope::{
+    BlockPlace, BlockScope, FunctionScope, LocalFunctionPlace,
+    LocalFunctionScope, NotInScope, VariablePlace, VariableScope,
+};
 use statics::{StaticDataError, StaticsGen};
 
 use crate::{
     analysis::FunctionDefinition,
     ir::{
-        FunctionAttribute, FunctionsBuilder, PlaceAddress,
-        Program, StaticFunctionAddress,
+        AddressingMode, DataAddress, FunctionAttribute, FunctionsBuilder,
+        InstructionBuilder, PlaceAddress, Program, StaticFunctionAddress,
     },
     parse::{Atom, TokenKind},
     source::Source,
@@ -19,12 +23,22 @@ use crate::{
 use super::{
     SemanticAnalysis,
     form::{
-        AndForm, Apply, Call, Form, Funcall, IfForm, Lambda, LetForm, OrForm,
+        AndForm, Apply, AssignedPlace, Assignment, BlockForm, Call, CatchForm,
+        CondForm, DestructuringBind, DoForm, DolistForm, DotimesForm, FletForm,
+        Form, Funcall, HandlerBind, HandlerCase, IfForm, IgnoreErrors, Lambda,
+        LetForm, LoopAccumulation, LoopClause, LoopForm, LoopVariable,
+        MultipleValueBind, OrForm, ReturnFrom, Template, TemplateElement,
+        ThrowForm, UnlessForm, UnwindProtect, ValuesForm, WhenForm,
+    },
+    params::{
+        Arity, DestructuringList, KeyParameters, OptionalParameter, Parameters,
+        Pattern,
     },
 };
 
 mod address;
 mod code;
+mod exits;
 mod lambdas;
 mod scope;
 mod statics;
@@ -35,21 +49,95 @@ pub struct IrGen<'a, 's, 't> {
     functions: FunctionsBuilder,
     function_scope: FunctionScope<'s>,
     variable_scope: VariableScope<'s>,
+    /// Functions from `flet` and `labels`, which are kept in places like
+    /// variables and shadow the functions in `function_scope`.
+    local_function_scope: LocalFunctionScope<'s>,
+    block_scope: BlockScope<'s>,
+    /// Number of lambdas that the code being generated is nested in.
+    lambda_level: u32,
+    /// Number of regions that catch throws that the code being generated is
+    /// nested in, see `Instruction::EnterCatch`.
+    catch_depth: u32,
+    /// Arities of functions from the source code, intrinsics are missing.
+    arities: HashMap<StaticFunctionAddress, Arity>,
 }
 
 impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
     pub fn new(analysis: &'a SemanticAnalysis<'s, 't>) -> Self {
         let static_data = StaticsGen::new();
         let functions = FunctionsBuilder::new();
-        let mut global_variables = VariableScope::new();
-        global_variables.add_binding("nil", static_data.nil_place());
-        global_variables.add_binding("t", static_data.t_place());
-        Self {
+        let nil_place = static_data.nil_place();
+        let t_place = static_data.t_place();
+        let mut generator = Self {
             static_data,
             functions,
             function_scope: FunctionScope::new(),
-            variable_scope: global_variables,
+            variable_scope: VariableScope::new(),
+            local_function_scope: LocalFunctionScope::new(),
+            block_scope: BlockScope::new(),
+            lambda_level: 0,
+            catch_depth: 0,
+            arities: HashMap::new(),
             analysis,
+        };
+        generator.bind_variable("nil", nil_place);
+        generator.bind_variable("t", t_place);
+        generator
+    }
+
+    /// Binds a name to a place in the current function or lambda.
+    fn bind_variable(&mut self, name: &'s str, place: PlaceAddress) {
+        let variable = self.variable_place(place);
+        self.variable_scope.add_binding(name, variable);
+    }
+
+    /// Binds a name to a place holding a local function.
+    fn bind_local_function(
+        &mut self,
+        name: &'s str,
+        place: PlaceAddress,
+        arity: Arity,
+    ) {
+        let function = LocalFunctionPlace {
+            variable: self.variable_place(place),
+            arity,
+        };
+        self.local_function_scope.add_binding(name, function);
+    }
+
+    fn variable_place(&self, place: PlaceAddress) -> VariablePlace {
+        VariablePlace {
+            lambda_level: self.lambda_level,
+            place,
+        }
+    }
+
+    fn resolve_variable(
+        &self,
+        name: &'s str,
+    ) -> Result<PlaceAddress, NotInScope<'_>> {
+        let variable = self.variable_scope.resolve(name)?;
+        Ok(self.place_from_current_lambda(variable))
+    }
+
+    /// Finds the place of a function from `flet` or `labels`, if any.
+    fn resolve_local_function(&self, name: &'s str) -> Option<PlaceAddress> {
+        let function = self.local_function_scope.resolve(name).ok()?;
+        Some(self.place_from_current_lambda(function.variable))
+    }
+
+    /// Local places of functions that enclose the current lambda are reached
+    /// through the links of lambdas.
+    fn place_from_current_lambda(
+        &self,
+        variable: VariablePlace,
+    ) -> PlaceAddress {
+        let depth = self.lambda_level - variable.lambda_level;
+        match variable.place.mode() {
+            AddressingMode::Local if depth > 0 => {
+                PlaceAddress::new_captured(depth, variable.place.offset())
+            }
+            _ => variable.place,
         }
     }
 
@@ -84,7 +172,7 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
             let data_address =
                 self.static_data.for_node(global.source(), value.node())?;
             let data_place = self.static_data.static_place(data_address);
-            self.variable_scope.add_binding(name, data_place);
+            self.bind_variable(name, data_place);
         }
         Ok(())
     }
@@ -95,6 +183,7 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
             let name = function.name().fragment(function.source()).source();
             let address = self.functions.add_exported_function(name);
             self.function_scope.add_binding(name.into(), address);
+            self.arities.insert(address, function.parameters().arity());
         }
         // then generate the actual code for named functions
         for function in self.analysis.function_definitions() {
@@ -122,38 +211,240 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
             );
         }
         self.functions.implement_function(func_address);
-        for arg in definition.positional_args().iter() {
-            let ident = arg.fragment(definition.source()).source();
+        self.generate_code_for_parameters(
+            definition.source(),
+            definition.name().fragment(definition.source()).source(),
+            definition.parameters(),
+            func_address,
+            &mut locals,
+        )?;
+        let last_place = self.generate_code_for_function_body(
+            definition.source(),
+            Some(definition.name().fragment(definition.source()).source()),
+            definition.body(),
+            func_address,
+            &mut locals,
+        )?;
+        self.functions
+            .implement_function(func_address)
+            .add_return(last_place);
+        self.variable_scope.exit_scope();
+        Ok(())
+    }
+
+    /// Consumes the arguments in the prologue of a function and binds them to
+    /// its parameters in the current scope, after checking that the number
+    /// of arguments fits the parameters.
+    ///
+    /// Default values are only evaluated for missing arguments, with the
+    /// parameters before them in scope.
+    fn generate_code_for_parameters(
+        &mut self,
+        source: Source<'s>,
+        name: &'s str,
+        parameters: &Parameters<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        let arity = parameters.arity();
+        if !arity.is_any() {
+            self.generate_code_for_arity_check(name, arity, addr, locals);
+        }
+        for param in parameters.required() {
             let address = locals.next();
             self.functions
-                .implement_function(func_address)
+                .implement_function(addr)
                 .consume_param(address);
-            self.variable_scope.add_binding(ident, address);
+            self.bind_variable(param.fragment(source).source(), address);
+        }
+        for param in parameters.optional() {
+            let present = locals.next();
+            self.functions
+                .implement_function(addr)
+                .param_present(present);
+            self.generate_code_for_optional_parameter(
+                source,
+                param,
+                present,
+                |instructions, place| {
+                    instructions.consume_param(place);
+                },
+                addr,
+                locals,
+            )?;
         }
-        if let Some(rest_arg) = definition.rest_arg() {
-            let rest_ident = rest_arg.fragment(definition.source()).source();
-            let rest_address = locals.next();
+        // the rest is not consumed, so that keys can still be found in it
+        if let Some(rest) = parameters.rest() {
+            let address = locals.next();
             self.functions
-                .implement_function(func_address)
-                .consume_rest(rest_address);
-            self.variable_scope.add_binding(rest_ident, rest_address);
+                .implement_function(addr)
+                .consume_rest(address);
+            self.bind_variable(rest.fragment(source).source(), address);
         }
-        let mut last_place = None;
-        for code in definition.body() {
-            last_place = Some(self.generate_code(
-                definition.source(),
-                code,
-                func_address,
-                &mut locals,
-            )?);
+        if let Some(key) = parameters.key() {
+            if !key.allow_other_keys() {
+                self.generate_code_for_key_check(
+                    source, name, key, addr, locals,
+                );
+            }
+            for param in key.params() {
+                let keyword = self
+                    .static_data
+                    .keyword(param.name().fragment(source).source());
+                let found = locals.next();
+                self.functions
+                    .implement_function(addr)
+                    .find_key_param(keyword, found);
+                self.generate_code_for_optional_parameter(
+                    source,
+                    param,
+                    found,
+                    |instructions, place| {
+                        instructions.load_car(found, place);
+                    },
+                    addr,
+                    locals,
+                )?;
+            }
         }
+        Ok(())
+    }
+
+    /// Panics at runtime with a message that names the function if it got
+    /// the wrong number of arguments.
+    fn generate_code_for_arity_check(
+        &mut self,
+        name: &'s str,
+        arity: Arity,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) {
+        let name = self.static_data.static_str(name);
+        let expected = self.static_data.static_str(arity.to_string());
+        let matches = locals.next();
+        let name_place = locals.next();
+        let expected_place = locals.next();
+        let arguments = locals.next();
+
+        // a:{ if matches { break a } arity-error }
         self.functions
-            .implement_function(func_address)
-            .add_return(last_place.unwrap_or_else(|| self.static_data.nil_place()));
-        self.variable_scope.exit_scope();
+            .implement_function(addr)
+            .arity_matches(
+                arity.min() as u32,
+                arity.max().map(|max| max as u32),
+                matches,
+            )
+            .enter_block()
+            .break_if_not_nil(1, matches)
+            .load_data(name, name_place)
+            .load_data(expected, expected_place)
+            .consume_rest(arguments);
+        self.generate_code_for_runtime_call(
+            "arity-error",
+            &[name_place, expected_place, arguments],
+            addr,
+            locals,
+        );
+        self.functions.implement_function(addr).exit_block();
+    }
+
+    /// Binds an optional or key parameter to the argument if the passed place
+    /// is not nil, otherwise to its default value.
+    fn generate_code_for_optional_parameter(
+        &mut self,
+        source: Source<'s>,
+        param: &OptionalParameter<'s, 't>,
+        passed: PlaceAddress,
+        read_argument: impl FnOnce(&mut InstructionBuilder, PlaceAddress),
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        let place = locals.next();
+        let supplied = param.supplied().map(|_| locals.next());
+
+        // like an if form:
+        // a:{ b:{ if passed != nil { break b; } … default … break a } … arg … }
+        self.functions
+            .implement_function(addr)
+            .enter_block()
+            .enter_block()
+            .break_if_not_nil(1, passed);
+        let default = match param.default() {
+            Some(default) => {
+                self.generate_code(source, default, addr, locals)?
+            }
+            None => self.static_data.nil_place(),
+        };
+        let instructions = self.functions.implement_function(addr);
+        instructions.write_place(default, place);
+        if let Some(supplied) = supplied {
+            instructions.write_place(self.static_data.nil_place(), supplied);
+        }
+        instructions.add_break(2).exit_block();
+        read_argument(instructions, place);
+        if let Some(supplied) = supplied {
+            instructions.load_data(self.static_data.t_data(), supplied);
+        }
+        instructions.exit_block();
+
+        self.bind_variable(param.name().fragment(source).source(), place);
+        if let (Some(name), Some(supplied)) = (param.supplied(), supplied) {
+            self.bind_variable(name.fragment(source).source(), supplied);
+        }
         Ok(())
     }
 
+    /// Signals an error at runtime that names the function and the keyword if
+    /// a keyword is passed that is not one of the key parameters, unless
+    /// `:allow-other-keys` is passed with a value that is not nil.
+    fn generate_code_for_key_check(
+        &mut self,
+        source: Source<'s>,
+        name: &'s str,
+        key: &KeyParameters<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) {
+        let allow_other_keys = self.static_data.keyword("allow-other-keys");
+        let mut keywords = vec![allow_other_keys];
+        for param in key.params() {
+            keywords.push(
+                self.static_data
+                    .keyword(param.name().fragment(source).source()),
+            );
+        }
+        let keywords = self.static_data.static_list(&keywords);
+        let name = self.static_data.static_str(name);
+        let known = locals.next();
+        let allowed = locals.next();
+        let name_place = locals.next();
+        let keywords_place = locals.next();
+        let arguments = locals.next();
+
+        // a:{ if known { break a } b:{ if allowed { break a } } key-error }
+        self.functions
+            .implement_function(addr)
+            .key_params_known(keywords, known)
+            .enter_block()
+            .break_if_not_nil(1, known)
+            .enter_block()
+            .find_key_param(allow_other_keys, allowed)
+            .break_if_nil(1, allowed)
+            .load_car(allowed, allowed)
+            .break_if_not_nil(2, allowed)
+            .exit_block()
+            .load_data(name, name_place)
+            .load_data(keywords, keywords_place)
+            .consume_rest(arguments);
+        self.generate_code_for_runtime_call(
+            "key-error",
+            &[name_place, keywords_place, arguments],
+            addr,
+            locals,
+        );
+        self.functions.implement_function(addr).exit_block();
+    }
+
     fn generate_root_code(&mut self) -> Result<(), IrGenError<'s, 't>> {
         let conflicting_definition = self
             .analysis
@@ -204,31 +495,64 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         code: &Form<'s, 't>,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_position(
+            source,
+            code,
+            Position::Primary,
+            addr,
+            locals,
+        )
+    }
+
+    /// Generates code for a form whose values are used according to the
+    /// position, and returns the place of its primary value.
+    ///
+    /// Calls in tail position are tail calls that never return here, the
+    /// place returned for them is never written.
+    fn generate_code_in_position(
+        &mut self,
+        source: Source<'s>,
+        code: &Form<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
         Ok(match code {
-            // names refer directly to the place they are bound to, so they can be written
-            Form::Name(name) => self
-                .variable_scope
-                .resolve(name.as_str())
-                .map_err(|_| IrGenError::NotInScope {
-                    source,
-                    atom: name.ident(),
-                })?,
-            Form::FunctionName(name) => {
-                // functions don't really have a scope here, but should probably consider stuff like flet in the future
-                let value = name.as_str();
-                let static_address = self
-                    .function_scope
-                    .resolve(value)
-                    .map_err(|_| IrGenError::FunctionNotFound {
+            // names are bound to places that can be assigned to later, so
+            // the value is copied to a place that keeps what was read
+            Form::Name(name) => {
+                let variable_place = self
+                    .resolve_variable(name.as_str())
+                    .map_err(|_| IrGenError::NotInScope {
                         source,
-                        ident: name.ident(),
+                        atom: name.ident(),
                     })?;
-                let static_func_address = self.static_data.static_function(static_address);
                 let place_address = locals.next();
                 self.functions
                     .implement_function(addr)
-                    .load_data(static_func_address, place_address);
+                    .write_place(variable_place, place_address);
+                place_address
+            }
+            Form::FunctionName(name) => {
+                let target = self.resolve_call_target(
+                    source,
+                    name.ident(),
+                    name.as_str(),
+                )?;
+                let place_address = locals.next();
+                let instructions = self.functions.implement_function(addr);
+                match target {
+                    CallTarget::Static(static_address) => {
+                        let static_func_address =
+                            self.static_data.static_function(static_address);
+                        instructions
+                            .load_data(static_func_address, place_address);
+                    }
+                    CallTarget::Local(function_place) => {
+                        instructions.write_place(function_place, place_address);
+                    }
+                }
                 place_address
             }
             // numbers, strings and quoted stuff evaluate to a reference to static data stored in a local place
@@ -241,29 +565,129 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
                     .load_data(data_address, place_address);
                 place_address
             }
-            Form::IfForm(form) => {
-                self.generate_code_for_if_form(source, form, addr, locals)?
+            Form::IfForm(form) => self.generate_code_for_if_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::AndForm(form) => self.generate_code_for_and_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::OrForm(form) => self.generate_code_for_or_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::CondForm(form) => self.generate_code_for_cond_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::WhenForm(form) => self.generate_code_for_when_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::UnlessForm(form) => self.generate_code_for_unless_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::PrognForm(form) => self.generate_code_for_body(
+                source,
+                form.forms(),
+                position,
+                addr,
+                locals,
+            )?,
+            Form::SetqForm(form) => self.generate_code_for_assignments(
+                source,
+                form.assignments(),
+                addr,
+                locals,
+            )?,
+            Form::SetfForm(form) => self.generate_code_for_assignments(
+                source,
+                form.assignments(),
+                addr,
+                locals,
+            )?,
+            Form::LetForm(let_form) => self.generate_code_for_let_form(
+                source, let_form, position, addr, locals,
+            )?,
+            Form::FletForm(form) => self.generate_code_for_flet_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::DestructuringBind(form) => self
+                .generate_code_for_destructuring_bind(
+                    source, form, position, addr, locals,
+                )?,
+            Form::ValuesForm(form) => self.generate_code_for_values_form(
+                source, form, position, addr, locals,
+            )?,
+            Form::MultipleValueBind(form) => self
+                .generate_code_for_multiple_value_bind(
+                    source, form, position, addr, locals,
+                )?,
+            Form::MultipleValueList(form) => self
+                .generate_code_for_values_list(
+                    source,
+                    form.form(),
+                    addr,
+                    locals,
+                )?,
+            Form::BlockForm(form) => self.generate_code_for_block(
+                source, form, position, addr, locals,
+            )?,
+            Form::ReturnFrom(form) => {
+                self.generate_code_for_return_from(source, form, addr, locals)?
             }
-            Form::AndForm(form) => {
-                self.generate_code_for_and_form(source, form, addr, locals)?
+            Form::CatchForm(form) => self.generate_code_for_catch(
+                source, form, position, addr, locals,
+            )?,
+            Form::ThrowForm(form) => {
+                self.generate_code_for_throw(source, form, addr, locals)?
             }
-            Form::OrForm(form) => {
-                self.generate_code_for_or_form(source, form, addr, locals)?
+            Form::UnwindProtect(form) => self
+                .generate_code_for_unwind_protect(
+                    source, form, position, addr, locals,
+                )?,
+            Form::HandlerCase(form) => self.generate_code_for_handler_case(
+                source, form, position, addr, locals,
+            )?,
+            Form::HandlerBind(form) => self.generate_code_for_handler_bind(
+                source, form, position, addr, locals,
+            )?,
+            Form::IgnoreErrors(form) => self.generate_code_for_ignore_errors(
+                source, form, position, addr, locals,
+            )?,
+            Form::DotimesForm(form) => self.generate_code_for_dotimes(
+                source, form, position, addr, locals,
+            )?,
+            Form::DolistForm(form) => self.generate_code_for_dolist(
+                source, form, position, addr, locals,
+            )?,
+            Form::DoForm(form) => {
+                self.generate_code_for_do(source, form, position, addr, locals)?
             }
-            Form::LetForm(let_form) => self
-                .generate_code_for_let_form(source, let_form, addr, locals)?,
+            Form::LoopForm(form) => self
+                .generate_code_for_loop(source, form, position, addr, locals)?,
             Form::Call(call) => self.generate_code_for_function_application(
-                source, call, addr, locals,
+                source, call, position, addr, locals,
+            )?,
+            Form::Apply(form) => self.generate_code_for_apply(
+                source, form, position, addr, locals,
+            )?,
+            Form::Funcall(form) => self.generate_code_for_funcall(
+                source, form, position, addr, locals,
+            )?,
+            Form::Lambda(lambda) => self.generate_code_for_lambda(
+                source, None, lambda, addr, locals,
+            )?,
+            // the expansion has a source of its own
+            Form::MacroCall(call) => self.generate_code_in_position(
+                call.expansion_source(),
+                call.expansion(),
+                position,
+                addr,
+                locals,
+            )?,
+            Form::Quasiquote(form) => self.generate_code_for_template(
+                source,
+                form.template(),
+                addr,
+                locals,
             )?,
-            Form::Apply(form) => {
-                self.generate_code_for_apply(source, form, addr, locals)?
-            }
-            Form::Funcall(form) => {
-                self.generate_code_for_funcall(source, form, addr, locals)?
-            }
-            Form::Lambda(lambda) => {
-                self.generate_code_for_lambda(source, lambda, addr, locals)?
-            }
         })
     }
 
@@ -271,6 +695,7 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         &mut self,
         source: Source<'s>,
         form: &IfForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
@@ -288,9 +713,9 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
             .enter_block()
             .break_if_not_nil(1, test_result_place);
         let else_result_place = match form.else_form() {
-            Some(else_form) => {
-                self.generate_code(source, else_form, addr, locals)?
-            }
+            Some(else_form) => self.generate_code_in_position(
+                source, else_form, position, addr, locals,
+            )?,
             None => self.static_data.nil_place(),
         };
         self.functions
@@ -300,8 +725,13 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
             .exit_block();
 
         // and the then branch after the inner block
-        let then_result_place =
-            self.generate_code(source, form.then_form(), addr, locals)?;
+        let then_result_place = self.generate_code_in_position(
+            source,
+            form.then_form(),
+            position,
+            addr,
+            locals,
+        )?;
 
         self.functions
             .implement_function(addr)
@@ -314,12 +744,19 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         &mut self,
         source: Source<'s>,
         form: &AndForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
         match form.forms().len() {
             0 => Ok(self.static_data.t_place()),
-            1 => self.generate_code(source, &form.forms()[0], addr, locals),
+            1 => self.generate_code_in_position(
+                source,
+                &form.forms()[0],
+                position,
+                addr,
+                locals,
+            ),
             // short-circuiting makes sense for two or more forms
             _ => {
                 let result_place = locals.next();
@@ -329,9 +766,20 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
                     .load_data(self.static_data.t_data(), result_place)
                     .enter_block();
 
-                for form in form.forms() {
-                    let form_result =
-                        self.generate_code(source, form, addr, locals)?;
+                let last = form.forms().len() - 1;
+                for (idx, form) in form.forms().iter().enumerate() {
+                    let form_position = if idx == last {
+                        position
+                    } else {
+                        Position::Primary
+                    };
+                    let form_result = self.generate_code_in_position(
+                        source,
+                        form,
+                        form_position,
+                        addr,
+                        locals,
+                    )?;
                     self.functions
                         .implement_function(addr)
                         .write_place(form_result, result_place)
@@ -349,12 +797,19 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         &mut self,
         source: Source<'s>,
         form: &OrForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
         match form.forms().len() {
             0 => Ok(self.static_data.nil_place()),
-            1 => self.generate_code(source, &form.forms()[0], addr, locals),
+            1 => self.generate_code_in_position(
+                source,
+                &form.forms()[0],
+                position,
+                addr,
+                locals,
+            ),
             // short-circuiting makes sense for two or more forms
             _ => {
                 let result_place = locals.next();
@@ -364,9 +819,20 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
                     .load_data(self.static_data.nil_data(), result_place)
                     .enter_block();
 
-                for form in form.forms() {
-                    let form_result =
-                        self.generate_code(source, form, addr, locals)?;
+                let last = form.forms().len() - 1;
+                for (idx, form) in form.forms().iter().enumerate() {
+                    let form_position = if idx == last {
+                        position
+                    } else {
+                        Position::Primary
+                    };
+                    let form_result = self.generate_code_in_position(
+                        source,
+                        form,
+                        form_position,
+                        addr,
+                        locals,
+                    )?;
                     self.functions
                         .implement_function(addr)
                         .write_place(form_result, result_place)
@@ -380,180 +846,2097 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         }
     }
 
-    fn generate_code_for_let_form(
+    fn generate_code_for_cond_form(
         &mut self,
         source: Source<'s>,
-        form: &LetForm<'s, 't>,
+        form: &CondForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
-        let mut places_to_add_simultaneously =
-            Vec::with_capacity(form.bindings().len());
-        for binding in form.bindings() {
-            let place =
-                self.generate_code(source, binding.value(), addr, locals)?;
-            places_to_add_simultaneously
-                .push((binding.name().fragment(source).source(), place));
-        }
-        self.variable_scope.enter_scope();
-        for (name, address) in places_to_add_simultaneously {
-            self.variable_scope.add_binding(name, address);
-        }
+        // generate something like: result = nil; a:{ b:{ test = …; if test == nil { break b; } result = … body …; break a; } c:{ … } }
+        let result_place = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), result_place)
+            .enter_block();
 
-        let mut last_result = None;
-        for body in form.body() {
-            last_result =
-                Some(self.generate_code(source, body, addr, locals)?);
+        for clause in form.clauses() {
+            self.functions.implement_function(addr).enter_block();
+            let test_place =
+                self.generate_code(source, clause.test(), addr, locals)?;
+            self.functions
+                .implement_function(addr)
+                .break_if_nil(1, test_place);
+            // clauses without a body evaluate to the test result
+            let clause_result = if clause.body().is_empty() {
+                test_place
+            } else {
+                self.generate_code_for_body(
+                    source,
+                    clause.body(),
+                    position,
+                    addr,
+                    locals,
+                )?
+            };
+            self.functions
+                .implement_function(addr)
+                .write_place(clause_result, result_place)
+                .add_break(2)
+                .exit_block();
         }
-        let last_result = last_result.unwrap_or(self.static_data.nil_place());
-        self.variable_scope.exit_scope();
-        Ok(last_result)
+
+        self.functions.implement_function(addr).exit_block();
+        Ok(result_place)
     }
 
-    fn generate_code_for_function_application(
+    fn generate_code_for_when_form(
         &mut self,
         source: Source<'s>,
-        call: &Call<'s, 't>,
+        form: &WhenForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
-        let args = call.args();
-        let mut evaluated_arg_places: Vec<PlaceAddress> =
-            Vec::with_capacity(args.len());
-        for arg in args {
-            let place = self.generate_code(source, arg, addr, locals)?;
-            evaluated_arg_places.push(place);
-        }
-
-        let arguments_place = locals.next();
+        // generate something like: result = nil; a:{ if test() == nil { break a; } result = … body … }
         let result_place = locals.next();
-        // start with empty argument list
-        let instructions = self.functions.implement_function(addr);
-        instructions.load_data(self.static_data.nil_data(), arguments_place);
-        // and then start pushing elements from the back to the front
-        for &arg in evaluated_arg_places.iter().rev() {
-            instructions.cons(arg, arguments_place, arguments_place);
-        }
-
-        let func_ident = call.function();
-        let func_address = self
-            .function_scope
-            .resolve(func_ident.fragment(source).source())
-            .map_err(|_| IrGenError::FunctionNotFound {
-                ident: func_ident,
-                source,
-            })?;
-        instructions.call(func_address, arguments_place, result_place);
-
+        let test_place =
+            self.generate_code(source, form.test_form(), addr, locals)?;
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), result_place)
+            .enter_block()
+            .break_if_nil(1, test_place);
+        let body_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.functions
+            .implement_function(addr)
+            .write_place(body_result, result_place)
+            .exit_block();
         Ok(result_place)
     }
 
-    fn generate_code_for_apply(
+    fn generate_code_for_unless_form(
         &mut self,
         source: Source<'s>,
-        apply: &Apply<'s, 't>,
+        form: &UnlessForm<'s, 't>,
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
-        let arg_list =
-            self.generate_code(source, apply.args(), addr, locals)?;
+        // same as when, but breaking if the test is not nil
         let result_place = locals.next();
-        let function = apply.function();
-        if let Some(func_name) = function.function_name().map(|c| c.ident()) {
-            // when the target is a static function identify, we can do a fast direct call
-            assert!(matches!(func_name.token().kind(), TokenKind::FuncIdent));
-            let func_name_str =
-                func_name.fragment(source).source()[2..].trim();
-            let func_address = self
-                .function_scope
-                .resolve(func_name_str)
-                .map_err(|_| IrGenError::FunctionNotFound {
-                    ident: func_name,
-                    source,
-                })?;
-            self.functions.implement_function(addr).call(
-                func_address,
-                arg_list,
-                result_place,
-            );
-        } else {
-            // function calculated at runtime, need an indirect call
-            let function_place =
-                self.generate_code(source, function, addr, locals)?;
-            self.functions.implement_function(addr).call_indirect(
-                function_place,
-                arg_list,
-                result_place,
-            );
-        }
-
+        let test_place =
+            self.generate_code(source, form.test_form(), addr, locals)?;
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), result_place)
+            .enter_block()
+            .break_if_not_nil(1, test_place);
+        let body_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.functions
+            .implement_function(addr)
+            .write_place(body_result, result_place)
+            .exit_block();
         Ok(result_place)
     }
 
-    fn generate_code_for_funcall(
+    /// Evaluates forms in order and returns the place of the last result, or
+    /// of nil if there are no forms. Only the values of the last form are
+    /// used beyond its primary value.
+    fn generate_code_for_body(
         &mut self,
         source: Source<'s>,
-        funcall: &Funcall<'s, 't>,
+        forms: &[Form<'s, 't>],
+        position: Position,
         addr: StaticFunctionAddress,
         locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
-        let args = funcall.args();
-        let mut evaluated_arg_places: Vec<PlaceAddress> =
-            Vec::with_capacity(args.len());
-        for arg in args {
-            let place = self.generate_code(source, arg, addr, locals)?;
-            evaluated_arg_places.push(place);
+        let Some((last, forms)) = forms.split_last() else {
+            return Ok(self.static_data.nil_place());
+        };
+        for form in forms {
+            self.generate_code(source, form, addr, locals)?;
         }
+        self.generate_code_in_position(source, last, position, addr, locals)
+    }
 
-        let arguments_place = locals.next();
-        let result_place = locals.next();
-        // start with empty argument list
-        let instructions = self.functions.implement_function(addr);
-        instructions.load_data(self.static_data.nil_data(), arguments_place);
-        // and then start pushing elements from the back to the front
-        for &arg in evaluated_arg_places.iter().rev() {
-            instructions.cons(arg, arguments_place, arguments_place);
+    /// Bodies of named functions are implicit blocks named after them, which
+    /// are only set up if something returns from them.
+    fn generate_code_for_function_body(
+        &mut self,
+        source: Source<'s>,
+        name: Option<&'s str>,
+        body: &[Form<'s, 't>],
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        match name {
+            Some(name) if block_is_returned_from(name, body.iter()) => self
+                .generate_code_in_block(
+                    name,
+                    block_needs_tag(name, body.iter()),
+                    |this, position, locals| {
+                        this.generate_code_for_body(
+                            source, body, position, addr, locals,
+                        )
+                    },
+                    Position::Tail,
+                    addr,
+                    locals,
+                ),
+            _ => self.generate_code_for_body(
+                source,
+                body,
+                Position::Tail,
+                addr,
+                locals,
+            ),
         }
+    }
 
-        let function = funcall.function();
+    /// Assigns in order and returns the place of the last value, or of nil if
+    /// there are no assignments.
+    fn generate_code_for_assignments(
+        &mut self,
+        source: Source<'s>,
+        assignments: &[Assignment<'s, 't>],
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let mut last_value = self.static_data.nil_place();
+        for assignment in assignments {
+            last_value = match assignment.place() {
+                AssignedPlace::Variable(ident) => {
+                    let variable_place = self
+                        .resolve_variable(ident.fragment(source).source())
+                        .map_err(|_| IrGenError::NotInScope {
+                            source,
+                            atom: ident,
+                        })?;
+                    if variable_place == self.static_data.nil_place()
+                        || variable_place == self.static_data.t_place()
+                    {
+                        return Err(IrGenError::AssignmentToConstant {
+                            source,
+                            ident,
+                        });
+                    }
+                    let value = self.generate_code(
+                        source,
+                        assignment.value(),
+                        addr,
+                        locals,
+                    )?;
+                    self.functions
+                        .implement_function(addr)
+                        .write_place(value, variable_place);
+                    value
+                }
+                // the runtime functions check that there is a list node or a
+                // vector
+                AssignedPlace::Car(list) => self.generate_code_for_store(
+                    source,
+                    "rplaca",
+                    &[list],
+                    assignment,
+                    addr,
+                    locals,
+                )?,
+                AssignedPlace::Cdr(list) => self.generate_code_for_store(
+                    source,
+                    "rplacd",
+                    &[list],
+                    assignment,
+                    addr,
+                    locals,
+                )?,
+                AssignedPlace::Aref(vector, index) => self
+                    .generate_code_for_store(
+                        source,
+                        "set-aref",
+                        &[vector, index],
+                        assignment,
+                        addr,
+                        locals,
+                    )?,
+            };
+        }
+        Ok(last_value)
+    }
+
+    /// Calls a function like rplaca with the forms of the place and the value
+    /// of an assignment and returns the place of the value.
+    fn generate_code_for_store(
+        &mut self,
+        source: Source<'s>,
+        function_name: &'static str,
+        forms: &[&Form<'s, 't>],
+        assignment: &Assignment<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let mut args = Vec::with_capacity(forms.len() + 1);
+        for form in forms {
+            args.push(self.generate_code(source, form, addr, locals)?);
+        }
+        let value =
+            self.generate_code(source, assignment.value(), addr, locals)?;
+        args.push(value);
+        self.generate_code_for_runtime_call(function_name, &args, addr, locals);
+        Ok(value)
+    }
+
+    /// Calls a function from the runtime with already evaluated arguments
+    /// and returns the place of the result.
+    fn generate_code_for_runtime_call(
+        &mut self,
+        function_name: &'static str,
+        args: &[PlaceAddress],
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> PlaceAddress {
+        let function = self
+            .function_scope
+            .resolve(function_name)
+            .expect("runtime functions are always in scope");
+        let arguments_place = locals.next();
+        let result_place = locals.next();
+        let instructions = self.functions.implement_function(addr);
+        instructions.load_data(self.static_data.nil_data(), arguments_place);
+        for &arg in args.iter().rev() {
+            instructions.cons(arg, arguments_place, arguments_place);
+        }
+        instructions.call(function, arguments_place, result_place);
+        result_place
+    }
+
+    /// Builds a quasiquoted template with calls to cons and append, from the
+    /// back to the front of each list.
+    fn generate_code_for_template(
+        &mut self,
+        source: Source<'s>,
+        template: &Template<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        Ok(match template {
+            Template::Constant(node) => {
+                let data_address = self.static_data.for_node(source, node)?;
+                let place_address = locals.next();
+                self.functions
+                    .implement_function(addr)
+                    .load_data(data_address, place_address);
+                place_address
+            }
+            Template::Unquoted(form) => {
+                self.generate_code(source, form, addr, locals)?
+            }
+            Template::List(elements) => {
+                // evaluate in order first, so that side effects happen from
+                // left to right
+                let mut evaluated = Vec::with_capacity(elements.len());
+                for element in elements {
+                    evaluated.push(match element {
+                        TemplateElement::Element(template) => (
+                            "cons",
+                            self.generate_code_for_template(
+                                source, template, addr, locals,
+                            )?,
+                        ),
+                        TemplateElement::Spliced(form) => (
+                            "append",
+                            self.generate_code(source, form, addr, locals)?,
+                        ),
+                    });
+                }
+                let mut list = self.static_data.nil_place();
+                for (function_name, element) in evaluated.into_iter().rev() {
+                    list = self.generate_code_for_runtime_call(
+                        function_name,
+                        &[element, list],
+                        addr,
+                        locals,
+                    );
+                }
+                list
+            }
+        })
+    }
+
+    fn generate_code_for_let_form(
+        &mut self,
+        source: Source<'s>,
+        form: &LetForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        if form.sequential() {
+            self.variable_scope.enter_scope();
+            for binding in form.bindings() {
+                let place =
+                    self.generate_code(source, binding.value(), addr, locals)?;
+                self.bind_variable(
+                    binding.name().fragment(source).source(),
+                    place,
+                );
+            }
+        } else {
+            let mut places_to_add_simultaneously =
+                Vec::with_capacity(form.bindings().len());
+            for binding in form.bindings() {
+                let place =
+                    self.generate_code(source, binding.value(), addr, locals)?;
+                places_to_add_simultaneously
+                    .push((binding.name().fragment(source).source(), place));
+            }
+            self.variable_scope.enter_scope();
+            for (name, address) in places_to_add_simultaneously {
+                self.bind_variable(name, address);
+            }
+        }
+
+        let last_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.variable_scope.exit_scope();
+        Ok(last_result)
+    }
+
+    fn generate_code_for_destructuring_bind(
+        &mut self,
+        source: Source<'s>,
+        form: &DestructuringBind<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let value = self.generate_code(source, form.value(), addr, locals)?;
+        self.variable_scope.enter_scope();
+        self.generate_code_for_destructuring(
+            source,
+            form.list(),
+            value,
+            addr,
+            locals,
+        )?;
+        let last_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.variable_scope.exit_scope();
+        Ok(last_result)
+    }
+
+    /// Binds the names of the lambda list to the elements of the list in the
+    /// place, which is left alone. Nested lambda lists are matched against
+    /// their elements in turn.
+    fn generate_code_for_destructuring(
+        &mut self,
+        source: Source<'s>,
+        list: &DestructuringList<'s, 't>,
+        value: PlaceAddress,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        let lambda_list = self
+            .static_data
+            .static_str(list.node().fragment(source).source());
+        let lambda_list_place = locals.next();
+        let rest = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(lambda_list, lambda_list_place)
+            .write_place(value, rest);
+
+        for pattern in list.required() {
+            self.generate_code_for_destructuring_check(
+                lambda_list_place,
+                value,
+                rest,
+                true,
+                addr,
+                locals,
+            );
+            let element = locals.next();
+            self.functions
+                .implement_function(addr)
+                .load_car(rest, element)
+                .load_cdr(rest, rest);
+            match pattern {
+                Pattern::Name(name) => {
+                    self.bind_variable(name.fragment(source).source(), element)
+                }
+                Pattern::List(nested) => self.generate_code_for_destructuring(
+                    source, nested, element, addr, locals,
+                )?,
+            }
+        }
+        for param in list.optional() {
+            self.generate_code_for_optional_parameter(
+                source,
+                param,
+                rest,
+                |instructions, place| {
+                    instructions.load_car(rest, place);
+                },
+                addr,
+                locals,
+            )?;
+            self.functions.implement_function(addr).load_cdr(rest, rest);
+        }
+        match list.rest() {
+            Some(name) => {
+                self.bind_variable(name.fragment(source).source(), rest)
+            }
+            None => self.generate_code_for_destructuring_check(
+                lambda_list_place,
+                value,
+                rest,
+                false,
+                addr,
+                locals,
+            ),
+        }
+        Ok(())
+    }
+
+    /// Panics at runtime unless the rest of the list has more elements or no
+    /// more elements, as expected.
+    fn generate_code_for_destructuring_check(
+        &mut self,
+        lambda_list: PlaceAddress,
+        value: PlaceAddress,
+        rest: PlaceAddress,
+        expect_more: bool,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) {
+        // a:{ if rest is as expected { break a } destructuring-error }
+        let instructions = self.functions.implement_function(addr);
+        instructions.enter_block();
+        if expect_more {
+            instructions.break_if_not_nil(1, rest);
+        } else {
+            instructions.break_if_nil(1, rest);
+        }
+        self.generate_code_for_runtime_call(
+            "destructuring-error",
+            &[lambda_list, value],
+            addr,
+            locals,
+        );
+        self.functions.implement_function(addr).exit_block();
+    }
+
+    /// Returns the primary value right away, and the list of all values only
+    /// if they are used.
+    fn generate_code_for_values_form(
+        &mut self,
+        source: Source<'s>,
+        form: &ValuesForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let mut value_places = Vec::with_capacity(form.forms().len());
+        for value in form.forms() {
+            value_places.push(self.generate_code(source, value, addr, locals)?);
+        }
+        let primary = value_places
+            .first()
+            .copied()
+            .unwrap_or(self.static_data.nil_place());
+        // a single value is the same as the primary value alone
+        if position == Position::Primary || value_places.len() == 1 {
+            return Ok(primary);
+        }
+
+        let list = locals.next();
+        let values = locals.next();
+        let instructions = self.functions.implement_function(addr);
+        instructions.load_data(self.static_data.nil_data(), list);
+        for &value in value_places.iter().rev() {
+            instructions.cons(value, list, list);
+        }
+        instructions
+            .load_data(self.static_data.nil_data(), values)
+            .cons(list, values, values);
+        match position {
+            Position::Primary => unreachable!(),
+            Position::Values(place) => {
+                instructions.write_place(values, place);
+            }
+            Position::Tail => {
+                instructions.add_return_values(primary, values);
+            }
+        }
+        Ok(primary)
+    }
+
+    fn generate_code_for_multiple_value_bind(
+        &mut self,
+        source: Source<'s>,
+        form: &MultipleValueBind<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let rest = self.generate_code_for_values_list(
+            source,
+            form.form(),
+            addr,
+            locals,
+        )?;
+        self.variable_scope.enter_scope();
+        for name in form.names() {
+            let place = locals.next();
+            self.functions
+                .implement_function(addr)
+                .load_car(rest, place)
+                .load_cdr(rest, rest);
+            self.bind_variable(name.fragment(source).source(), place);
+        }
+        let last_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.variable_scope.exit_scope();
+        Ok(last_result)
+    }
+
+    /// Evaluates the form and returns the place of a new list of all its
+    /// values.
+    fn generate_code_for_values_list(
+        &mut self,
+        source: Source<'s>,
+        form: &Form<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let values = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), values);
+        let primary = self.generate_code_in_position(
+            source,
+            form,
+            Position::Values(values),
+            addr,
+            locals,
+        )?;
+        let list = locals.next();
+
+        // like an if form, a single value is not in the values place:
+        // a:{ b:{ if values != nil { break b; } list = (primary) break a }
+        //   list = car values }
+        self.functions
+            .implement_function(addr)
+            .enter_block()
+            .enter_block()
+            .break_if_not_nil(1, values)
+            .load_data(self.static_data.nil_data(), list)
+            .cons(primary, list, list)
+            .add_break(2)
+            .exit_block()
+            .load_car(values, list)
+            .exit_block();
+        Ok(list)
+    }
+
+    fn generate_code_for_block(
+        &mut self,
+        source: Source<'s>,
+        form: &BlockForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_block(
+            form.name(),
+            block_needs_tag(form.name(), form.body().iter()),
+            |this, position, locals| {
+                this.generate_code_for_body(
+                    source,
+                    form.body(),
+                    position,
+                    addr,
+                    locals,
+                )
+            },
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Blocks that are left from lambdas or regions that catch throws are
+    /// regions themselves, with a fresh list as their tag, otherwise
+    /// `return-from` just breaks out of them.
+    fn generate_code_in_block(
+        &mut self,
+        name: &'s str,
+        needs_tag: bool,
+        body: impl FnOnce(
+            &mut Self,
+            Position,
+            &mut LocalPlaceGenerator,
+        ) -> Result<PlaceAddress, IrGenError<'s, 't>>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let result = locals.next();
+        let thrown = locals.next();
+        let tag = if needs_tag {
+            let tag = locals.next();
+            let nil = self.static_data.nil_place();
+            self.functions
+                .implement_function(addr)
+                .cons(nil, nil, tag)
+                .load_data(self.static_data.nil_data(), thrown)
+                .enter_catch(tag, thrown);
+            self.catch_depth += 1;
+            Some(tag)
+        } else {
+            None
+        };
+        let body_position = match tag {
+            Some(_) => self.region_position(position, addr, locals),
+            None => position,
+        };
+
+        let block_depth = self
+            .functions
+            .implement_function(addr)
+            .enter_block()
+            .block_depth();
+        self.block_scope.enter_scope();
+        let block = BlockPlace {
+            lambda_level: self.lambda_level,
+            catch_depth: self.catch_depth,
+            block_depth,
+            result,
+            position: body_position,
+            tag: tag.map(|tag| self.variable_place(tag)),
+        };
+        self.block_scope.add_binding(name, block);
+        let body_result = body(self, body_position, locals)?;
+        self.block_scope.exit_scope();
+        self.functions
+            .implement_function(addr)
+            .write_place(body_result, result)
+            .exit_block();
+
+        if tag.is_some() {
+            self.catch_depth -= 1;
+            self.functions.implement_function(addr).exit_catch();
+            self.generate_code_after_region(
+                thrown,
+                result,
+                body_position,
+                position,
+                addr,
+            );
+        }
+        Ok(result)
+    }
+
+    fn generate_code_for_return_from(
+        &mut self,
+        source: Source<'s>,
+        form: &ReturnFrom<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let block =
+            self.block_scope.resolve(form.block_name()).map_err(|_| {
+                IrGenError::BlockNotFound {
+                    source,
+                    atom: form.atom(),
+                }
+            })?;
+        self.generate_code_for_return(source, block, form.value(), addr, locals)
+    }
+
+    /// Breaks out of the block if it is in the same function and region,
+    /// otherwise throws the primary value to the tag of the block.
+    fn generate_code_for_return(
+        &mut self,
+        source: Source<'s>,
+        block: BlockPlace,
+        value: Option<&Form<'s, 't>>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let local = block.lambda_level == self.lambda_level
+            && block.catch_depth == self.catch_depth;
+
+        if local {
+            let value_position = block.position;
+            if let Position::Values(values) = value_position {
+                self.functions
+                    .implement_function(addr)
+                    .load_data(self.static_data.nil_data(), values);
+            }
+            let value = match value {
+                Some(value) => self.generate_code_in_position(
+                    source,
+                    value,
+                    value_position,
+                    addr,
+                    locals,
+                )?,
+                None => self.static_data.nil_place(),
+            };
+            let instructions = self.functions.implement_function(addr);
+            let block_up = instructions.block_depth() - block.block_depth + 1;
+            instructions
+                .write_place(value, block.result)
+                .add_break(block_up);
+        } else {
+            let tag = block
+                .tag
+                .expect("blocks that are left from afar have a tag");
+            let tag = self.place_from_current_lambda(tag);
+            let value = match value {
+                Some(value) => {
+                    self.generate_code(source, value, addr, locals)?
+                }
+                None => self.static_data.nil_place(),
+            };
+            let thrown = locals.next();
+            self.functions
+                .implement_function(addr)
+                .cons(tag, value, thrown)
+                .throw(thrown);
+        }
+        // never reached
+        Ok(self.static_data.nil_place())
+    }
+
+    fn generate_code_for_catch(
+        &mut self,
+        source: Source<'s>,
+        form: &CatchForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let tag = self.generate_code(source, form.tag(), addr, locals)?;
+        let result = locals.next();
+        let thrown = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), thrown)
+            .enter_catch(tag, thrown);
+        self.catch_depth += 1;
+        let body_position = self.region_position(position, addr, locals);
+        let body_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            body_position,
+            addr,
+            locals,
+        )?;
+        self.catch_depth -= 1;
+        self.functions
+            .implement_function(addr)
+            .write_place(body_result, result)
+            .exit_catch();
+        self.generate_code_after_region(
+            thrown,
+            result,
+            body_position,
+            position,
+            addr,
+        );
+        Ok(result)
+    }
+
+    fn generate_code_for_throw(
+        &mut self,
+        source: Source<'s>,
+        form: &ThrowForm<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let tag = self.generate_code(source, form.tag(), addr, locals)?;
+        let value = self.generate_code(source, form.value(), addr, locals)?;
+        let thrown = locals.next();
+        self.functions
+            .implement_function(addr)
+            .cons(tag, value, thrown)
+            .throw(thrown);
+        // never reached
+        Ok(self.static_data.nil_place())
+    }
+
+    /// Catches every throw from the protected form and throws it again
+    /// after the cleanup forms:
+    /// pending = nil; catch all to pending { result = … protected … }
+    /// … cleanup … a:{ if pending == nil { break a; } throw pending }
+    fn generate_code_for_unwind_protect(
+        &mut self,
+        source: Source<'s>,
+        form: &UnwindProtect<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let result = locals.next();
+        let pending = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), pending)
+            .enter_catch_all(pending);
+        self.catch_depth += 1;
+        let protected_position = self.region_position(position, addr, locals);
+        let protected_result = self.generate_code_in_position(
+            source,
+            form.protected(),
+            protected_position,
+            addr,
+            locals,
+        )?;
+        self.catch_depth -= 1;
+        self.functions
+            .implement_function(addr)
+            .write_place(protected_result, result)
+            .exit_catch();
+
+        for cleanup in form.cleanup() {
+            self.generate_code(source, cleanup, addr, locals)?;
+        }
+        let instructions = self.functions.implement_function(addr);
+        instructions
+            .enter_block()
+            .break_if_nil(1, pending)
+            .throw(pending)
+            .exit_block();
+        if let (Position::Tail, Position::Values(values)) =
+            (position, protected_position)
+        {
+            instructions.add_return_values(result, values);
+        }
+        Ok(result)
+    }
+
+    /// Regions that catch throws cannot contain tail calls, so their values
+    /// are collected in a place instead and returned after the region.
+    fn region_position(
+        &mut self,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Position {
+        match position {
+            Position::Tail => {
+                let values = locals.next();
+                self.functions
+                    .implement_function(addr)
+                    .load_data(self.static_data.nil_data(), values);
+                Position::Values(values)
+            }
+            position => position,
+        }
+    }
+
+    /// Takes the thrown value if the region caught a throw, which has only a
+    /// primary value, and returns if the region is in tail position:
+    /// a:{ if thrown == nil { break a; } result = cdr thrown }
+    fn generate_code_after_region(
+        &mut self,
+        thrown: PlaceAddress,
+        result: PlaceAddress,
+        region_position: Position,
+        position: Position,
+        addr: StaticFunctionAddress,
+    ) {
+        let instructions = self.functions.implement_function(addr);
+        instructions
+            .enter_block()
+            .break_if_nil(1, thrown)
+            .load_cdr(thrown, result);
+        if let Position::Values(values) = region_position {
+            instructions.load_data(self.static_data.nil_data(), values);
+        }
+        instructions.exit_block();
+        if let (Position::Tail, Position::Values(values)) =
+            (position, region_position)
+        {
+            instructions.add_return_values(result, values);
+        }
+    }
+
+    /// Runs the first clause that handles a condition signaled by the form,
+    /// after leaving the form:
+    /// … handled region … a:{ b:{ if thrown == nil { break b; }
+    ///   result = … clause … break a } … }
+    fn generate_code_for_handler_case(
+        &mut self,
+        source: Source<'s>,
+        form: &HandlerCase<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let types = form
+            .clauses()
+            .iter()
+            .map(|clause| {
+                let name = clause.condition_type().fragment(source).source();
+                self.static_data.symbol(name)
+            })
+            .collect::<Vec<_>>();
+        let body_position = self.region_position(position, addr, locals);
+        let (result, thrown) = self.generate_code_for_handled_region(
+            source,
+            &types,
+            slice::from_ref(form.form()),
+            body_position,
+            addr,
+            locals,
+        )?;
+
+        self.functions.implement_function(addr).enter_block();
+        for (clause, thrown) in form.clauses().iter().zip(thrown) {
+            let instructions = self.functions.implement_function(addr);
+            instructions.enter_block().break_if_nil(1, thrown);
+            if let Position::Values(values) = body_position {
+                instructions.load_data(self.static_data.nil_data(), values);
+            }
+            self.variable_scope.enter_scope();
+            if let Some(name) = clause.name() {
+                let condition = locals.next();
+                self.functions
+                    .implement_function(addr)
+                    .load_cdr(thrown, condition);
+                self.bind_variable(name.fragment(source).source(), condition);
+            }
+            let clause_result = self.generate_code_for_body(
+                source,
+                clause.body(),
+                body_position,
+                addr,
+                locals,
+            )?;
+            self.variable_scope.exit_scope();
+            self.functions
+                .implement_function(addr)
+                .write_place(clause_result, result)
+                .add_break(2)
+                .exit_block();
+        }
+        let instructions = self.functions.implement_function(addr);
+        instructions.exit_block();
+        if let (Position::Tail, Position::Values(values)) =
+            (position, body_position)
+        {
+            instructions.add_return_values(result, values);
+        }
+        Ok(result)
+    }
+
+    /// Returns nil and the condition as a second value if an error is
+    /// signaled by the body, after leaving it.
+    fn generate_code_for_ignore_errors(
+        &mut self,
+        source: Source<'s>,
+        form: &IgnoreErrors<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let error = self.static_data.symbol("error");
+        let body_position = self.region_position(position, addr, locals);
+        let (result, thrown) = self.generate_code_for_handled_region(
+            source,
+            &[error],
+            form.body(),
+            body_position,
+            addr,
+            locals,
+        )?;
+
+        // a:{ if thrown == nil { break a; } result = nil }
+        let thrown = thrown[0];
+        let instructions = self.functions.implement_function(addr);
+        instructions
+            .enter_block()
+            .break_if_nil(1, thrown)
+            .load_data(self.static_data.nil_data(), result);
+        if let Position::Values(values) = body_position {
+            // a list with the list of values, like the values register
+            let nil = self.static_data.nil_place();
+            let condition = locals.next();
+            instructions
+                .load_cdr(thrown, condition)
+                .cons(condition, nil, values)
+                .cons(nil, values, values)
+                .cons(values, nil, values);
+        }
+        instructions.exit_block();
+        if let (Position::Tail, Position::Values(values)) =
+            (position, body_position)
+        {
+            instructions.add_return_values(result, values);
+        }
+        Ok(result)
+    }
+
+    /// Establishes a handler for each condition type that throws the
+    /// condition to a fresh tag of its own, and catches these throws around
+    /// the forms:
+    /// tag = cons nil nil; thrown = nil; … catch tag to thrown { … }
+    ///
+    /// Returns the result and the place of the thrown value for each type.
+    fn generate_code_for_handled_region(
+        &mut self,
+        source: Source<'s>,
+        types: &[DataAddress],
+        forms: &[Form<'s, 't>],
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(PlaceAddress, Vec<PlaceAddress>), IrGenError<'s, 't>> {
+        let nil = self.static_data.nil_place();
+        let result = locals.next();
+        let handlers = locals.next();
+        let mut thrown_places = Vec::with_capacity(types.len());
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), handlers);
+        for &condition_type in types.iter().rev() {
+            let tag = locals.next();
+            let thrown = locals.next();
+            let handler = locals.next();
+            self.functions
+                .implement_function(addr)
+                .cons(nil, nil, tag)
+                .load_data(condition_type, handler)
+                .cons(handler, tag, handler)
+                .cons(handler, handlers, handlers)
+                .load_data(self.static_data.nil_data(), thrown)
+                .enter_catch(tag, thrown);
+            self.catch_depth += 1;
+            thrown_places.push(thrown);
+        }
+        thrown_places.reverse();
+
+        let body_result = self.generate_code_with_handlers(
+            source, handlers, forms, position, addr, locals,
+        )?;
+        let instructions = self.functions.implement_function(addr);
+        instructions.write_place(body_result, result);
+        for _ in types {
+            instructions.exit_catch();
+        }
+        self.catch_depth -= types.len() as u32;
+        Ok((result, thrown_places))
+    }
+
+    /// Calls the handler functions for conditions signaled by the body,
+    /// which are established in the order they are listed.
+    fn generate_code_for_handler_bind(
+        &mut self,
+        source: Source<'s>,
+        form: &HandlerBind<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let mut handlers = Vec::with_capacity(form.handlers().len());
+        for handler in form.handlers() {
+            let function =
+                self.generate_code(source, handler.function(), addr, locals)?;
+            let name = handler.condition_type().fragment(source).source();
+            let condition_type = self.static_data.symbol(name);
+            let handler = locals.next();
+            self.functions
+                .implement_function(addr)
+                .load_data(condition_type, handler)
+                .cons(handler, function, handler);
+            handlers.push(handler);
+        }
+        let cluster = locals.next();
+        let instructions = self.functions.implement_function(addr);
+        instructions.load_data(self.static_data.nil_data(), cluster);
+        for &handler in handlers.iter().rev() {
+            instructions.cons(handler, cluster, cluster);
+        }
+        self.generate_code_with_handlers(
+            source,
+            cluster,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Makes the cluster of handlers the innermost one while the forms run,
+    /// and restores the handlers from before however the forms are left,
+    /// like `unwind-protect`:
+    /// outer = push-handlers(cluster); pending = nil;
+    /// catch all to pending { result = … forms … } restore-handlers(outer);
+    /// a:{ if pending == nil { break a; } throw pending }
+    fn generate_code_with_handlers(
+        &mut self,
+        source: Source<'s>,
+        cluster: PlaceAddress,
+        forms: &[Form<'s, 't>],
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let outer = self.generate_code_for_runtime_call(
+            "push-handlers",
+            &[cluster],
+            addr,
+            locals,
+        );
+        let result = locals.next();
+        let pending = locals.next();
+        self.functions
+            .implement_function(addr)
+            .load_data(self.static_data.nil_data(), pending)
+            .enter_catch_all(pending);
+        self.catch_depth += 1;
+        let body_position = self.region_position(position, addr, locals);
+        let body_result = self.generate_code_for_body(
+            source,
+            forms,
+            body_position,
+            addr,
+            locals,
+        )?;
+        self.catch_depth -= 1;
+        self.functions
+            .implement_function(addr)
+            .write_place(body_result, result)
+            .exit_catch();
+
+        self.generate_code_for_runtime_call(
+            "restore-handlers",
+            &[outer],
+            addr,
+            locals,
+        );
+        let instructions = self.functions.implement_function(addr);
+        instructions
+            .enter_block()
+            .break_if_nil(1, pending)
+            .throw(pending)
+            .exit_block();
+        if let (Position::Tail, Position::Values(values)) =
+            (position, body_position)
+        {
+            instructions.add_return_values(result, values);
+        }
+        Ok(result)
+    }
+
+    /// Counts up from zero inside a block named nil, like the other
+    /// iteration forms, and loops without calls:
+    /// name = 0; a:{ if name >= count { break a; } … body …
+    ///   name = name + 1; continue a } … result …
+    fn generate_code_for_dotimes(
+        &mut self,
+        source: Source<'s>,
+        form: &DotimesForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_block(
+            "nil",
+            block_needs_tag("nil", form.forms()),
+            |this, position, locals| {
+                let count =
+                    this.generate_code(source, form.count(), addr, locals)?;
+                let counter = locals.next();
+                this.functions
+                    .implement_function(addr)
+                    .load_data(this.static_data.number(0), counter);
+                this.variable_scope.enter_scope();
+                this.bind_variable(
+                    form.name().fragment(source).source(),
+                    counter,
+                );
+
+                this.functions.implement_function(addr).enter_block();
+                let more = this.generate_code_for_runtime_call(
+                    "<-2",
+                    &[counter, count],
+                    addr,
+                    locals,
+                );
+                this.functions
+                    .implement_function(addr)
+                    .break_if_nil(1, more);
+                for form in form.body() {
+                    this.generate_code(source, form, addr, locals)?;
+                }
+                this.generate_code_for_increment(counter, None, addr, locals);
+                this.functions
+                    .implement_function(addr)
+                    .add_continue(1)
+                    .exit_block();
+
+                let result = match form.result() {
+                    Some(result) => this.generate_code_in_position(
+                        source, result, position, addr, locals,
+                    )?,
+                    None => this.static_data.nil_place(),
+                };
+                this.variable_scope.exit_scope();
+                Ok(result)
+            },
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Goes through the list inside a block named nil:
+    /// rest = list; a:{ if rest == nil { break a; } name = car rest
+    ///   … body … rest = cdr rest; continue a } name = nil … result …
+    fn generate_code_for_dolist(
+        &mut self,
+        source: Source<'s>,
+        form: &DolistForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_block(
+            "nil",
+            block_needs_tag("nil", form.forms()),
+            |this, position, locals| {
+                let list =
+                    this.generate_code(source, form.list(), addr, locals)?;
+                let rest = locals.next();
+                let element = locals.next();
+                this.functions
+                    .implement_function(addr)
+                    .write_place(list, rest)
+                    .load_data(this.static_data.nil_data(), element);
+                this.variable_scope.enter_scope();
+                this.bind_variable(
+                    form.name().fragment(source).source(),
+                    element,
+                );
+
+                this.functions
+                    .implement_function(addr)
+                    .enter_block()
+                    .break_if_nil(1, rest)
+                    .load_car(rest, element);
+                for form in form.body() {
+                    this.generate_code(source, form, addr, locals)?;
+                }
+                this.functions
+                    .implement_function(addr)
+                    .load_cdr(rest, rest)
+                    .add_continue(1)
+                    .exit_block()
+                    .load_data(this.static_data.nil_data(), element);
+
+                let result = match form.result() {
+                    Some(result) => this.generate_code_in_position(
+                        source, result, position, addr, locals,
+                    )?,
+                    None => this.static_data.nil_place(),
+                };
+                this.variable_scope.exit_scope();
+                Ok(result)
+            },
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Steps the variables until the end test is true, inside a block named
+    /// nil:
+    /// … init … a:{ if end test { break a; } … body … … step … continue a }
+    /// … result …
+    fn generate_code_for_do(
+        &mut self,
+        source: Source<'s>,
+        form: &DoForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_block(
+            "nil",
+            block_needs_tag("nil", form.forms()),
+            |this, position, locals| {
+                let places = form
+                    .variables()
+                    .iter()
+                    .map(|_| locals.next())
+                    .collect::<Vec<_>>();
+                this.generate_code_for_do_assignments(
+                    source, form, &places, true, addr, locals,
+                )?;
+
+                this.functions.implement_function(addr).enter_block();
+                let done =
+                    this.generate_code(source, form.end_test(), addr, locals)?;
+                this.functions
+                    .implement_function(addr)
+                    .break_if_not_nil(1, done);
+                for form in form.body() {
+                    this.generate_code(source, form, addr, locals)?;
+                }
+                this.generate_code_for_do_assignments(
+                    source, form, &places, false, addr, locals,
+                )?;
+                this.functions
+                    .implement_function(addr)
+                    .add_continue(1)
+                    .exit_block();
+
+                let result = this.generate_code_for_body(
+                    source,
+                    form.result(),
+                    position,
+                    addr,
+                    locals,
+                )?;
+                this.variable_scope.exit_scope();
+                Ok(result)
+            },
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Assigns the init forms, or else the step forms, of the variables to
+    /// their places, in parallel or in sequence like `let` and `let*`. Along
+    /// with the init forms, it also binds the variables in a new scope, and
+    /// variables without an init form start out as nil.
+    fn generate_code_for_do_assignments(
+        &mut self,
+        source: Source<'s>,
+        form: &DoForm<'s, 't>,
+        places: &[PlaceAddress],
+        init: bool,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        if init && form.sequential() {
+            self.variable_scope.enter_scope();
+        }
+        let mut values = Vec::with_capacity(places.len());
+        for (variable, &place) in form.variables().iter().zip(places) {
+            let assigned = if init {
+                variable.init()
+            } else {
+                variable.step()
+            };
+            let value = match assigned {
+                Some(value) => {
+                    Some(self.generate_code(source, value, addr, locals)?)
+                }
+                None if init => Some(self.static_data.nil_place()),
+                None => None,
+            };
+            if form.sequential() {
+                if let Some(value) = value {
+                    self.functions
+                        .implement_function(addr)
+                        .write_place(value, place);
+                }
+                if init {
+                    let name = variable.name().fragment(source).source();
+                    self.bind_variable(name, place);
+                }
+            } else {
+                values.push(value);
+            }
+        }
+        if !form.sequential() {
+            if init {
+                self.variable_scope.enter_scope();
+            }
+            for ((variable, &place), value) in
+                form.variables().iter().zip(places).zip(values)
+            {
+                if let Some(value) = value {
+                    self.functions
+                        .implement_function(addr)
+                        .write_place(value, place);
+                }
+                if init {
+                    let name = variable.name().fragment(source).source();
+                    self.bind_variable(name, place);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the clauses in a loop inside a block named nil, after checking
+    /// and before stepping the variables, and returns what the clauses
+    /// accumulated:
+    /// … variables … a:{ … checks … … clauses … … steps … continue a }
+    /// … finally …
+    fn generate_code_for_loop(
+        &mut self,
+        source: Source<'s>,
+        form: &LoopForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.generate_code_in_block(
+            "nil",
+            block_needs_tag("nil", form.forms()),
+            |this, _, locals| {
+                this.variable_scope.enter_scope();
+                let mut steps = Vec::with_capacity(form.variables().len());
+                for variable in form.variables() {
+                    let step = this.generate_code_for_loop_variable(
+                        source, variable, addr, locals,
+                    )?;
+                    steps.push(step);
+                }
+                let result = locals.next();
+                let initial = match form.accumulation() {
+                    LoopAccumulation::Sum => this.static_data.number(0),
+                    _ => this.static_data.nil_data(),
+                };
+                let accumulator = LoopAccumulator {
+                    result,
+                    last: locals.next(),
+                    block_depth: this
+                        .functions
+                        .implement_function(addr)
+                        .load_data(initial, result)
+                        .enter_block()
+                        .block_depth(),
+                };
+
+                for step in &steps {
+                    let instructions = this.functions.implement_function(addr);
+                    match *step {
+                        LoopStep::Rest { rest, element } => {
+                            instructions
+                                .break_if_nil(1, rest)
+                                .load_car(rest, element);
+                        }
+                        LoopStep::Count {
+                            counter,
+                            end: Some((end, inclusive)),
+                            ..
+                        } => {
+                            let compare =
+                                if inclusive { "<=-2" } else { "<-2" };
+                            let more = this.generate_code_for_runtime_call(
+                                compare,
+                                &[counter, end],
+                                addr,
+                                locals,
+                            );
+                            this.functions
+                                .implement_function(addr)
+                                .break_if_nil(1, more);
+                        }
+                        LoopStep::Count { end: None, .. } | LoopStep::None => {}
+                    }
+                }
+                for clause in form.clauses() {
+                    this.generate_code_for_loop_clause(
+                        source,
+                        clause,
+                        &accumulator,
+                        addr,
+                        locals,
+                    )?;
+                }
+                for step in steps {
+                    match step {
+                        LoopStep::Rest { rest, .. } => {
+                            this.functions
+                                .implement_function(addr)
+                                .load_cdr(rest, rest);
+                        }
+                        LoopStep::Count { counter, by, .. } => {
+                            this.generate_code_for_increment(
+                                counter, by, addr, locals,
+                            );
+                        }
+                        LoopStep::None => {}
+                    }
+                }
+                this.functions
+                    .implement_function(addr)
+                    .add_continue(1)
+                    .exit_block();
+
+                for form in form.finally() {
+                    this.generate_code(source, form, addr, locals)?;
+                }
+                this.variable_scope.exit_scope();
+                Ok(result)
+            },
+            position,
+            addr,
+            locals,
+        )
+    }
+
+    /// Binds the variable of a loop to a place with its initial value, and
+    /// returns how it is checked and stepped in each iteration.
+    fn generate_code_for_loop_variable(
+        &mut self,
+        source: Source<'s>,
+        variable: &LoopVariable<'s, 't>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<LoopStep, IrGenError<'s, 't>> {
+        let place = locals.next();
+        let (name, step) = match variable {
+            LoopVariable::With { name, value } => {
+                let value = match value {
+                    Some(value) => {
+                        self.generate_code(source, value, addr, locals)?
+                    }
+                    None => self.static_data.nil_place(),
+                };
+                self.functions
+                    .implement_function(addr)
+                    .write_place(value, place);
+                (name, LoopStep::None)
+            }
+            LoopVariable::In { name, list } => {
+                let list = self.generate_code(source, list, addr, locals)?;
+                let rest = locals.next();
+                self.functions
+                    .implement_function(addr)
+                    .write_place(list, rest)
+                    .load_data(self.static_data.nil_data(), place);
+                (
+                    name,
+                    LoopStep::Rest {
+                        rest,
+                        element: place,
+                    },
+                )
+            }
+            LoopVariable::Range {
+                name,
+                from,
+                to,
+                inclusive,
+                by,
+            } => {
+                match from {
+                    Some(from) => {
+                        let from =
+                            self.generate_code(source, from, addr, locals)?;
+                        self.functions
+                            .implement_function(addr)
+                            .write_place(from, place);
+                    }
+                    None => {
+                        self.functions
+                            .implement_function(addr)
+                            .load_data(self.static_data.number(0), place);
+                    }
+                }
+                let end = match to {
+                    Some(to) => Some((
+                        self.generate_code(source, to, addr, locals)?,
+                        *inclusive,
+                    )),
+                    None => None,
+                };
+                let by = match by {
+                    Some(by) => {
+                        Some(self.generate_code(source, by, addr, locals)?)
+                    }
+                    None => None,
+                };
+                (
+                    name,
+                    LoopStep::Count {
+                        counter: place,
+                        end,
+                        by,
+                    },
+                )
+            }
+        };
+        self.bind_variable(name.fragment(source).source(), place);
+        Ok(step)
+    }
+
+    fn generate_code_for_loop_clause(
+        &mut self,
+        source: Source<'s>,
+        clause: &LoopClause<'s, 't>,
+        accumulator: &LoopAccumulator,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        match clause {
+            LoopClause::Do(forms) => {
+                for form in forms {
+                    self.generate_code(source, form, addr, locals)?;
+                }
+            }
+            LoopClause::Collect(form) => {
+                // a:{ b:{ if last != nil { break b; } result = cell; break a }
+                //   cdr last = cell } last = cell
+                let value = self.generate_code(source, form, addr, locals)?;
+                let cell = locals.next();
+                let nil = self.static_data.nil_place();
+                self.functions
+                    .implement_function(addr)
+                    .cons(value, nil, cell)
+                    .enter_block()
+                    .enter_block()
+                    .break_if_not_nil(1, accumulator.last)
+                    .write_place(cell, accumulator.result)
+                    .add_break(2)
+                    .exit_block()
+                    .store_cdr(accumulator.last, cell)
+                    .exit_block()
+                    .write_place(cell, accumulator.last);
+            }
+            LoopClause::Sum(form) => {
+                let value = self.generate_code(source, form, addr, locals)?;
+                let sum = self.generate_code_for_runtime_call(
+                    "add-2",
+                    &[accumulator.result, value],
+                    addr,
+                    locals,
+                );
+                self.functions
+                    .implement_function(addr)
+                    .write_place(sum, accumulator.result);
+            }
+            LoopClause::Return(form) => {
+                let block = self
+                    .block_scope
+                    .resolve("nil")
+                    .expect("loops are blocks named nil");
+                self.generate_code_for_return(
+                    source,
+                    block,
+                    Some(form),
+                    addr,
+                    locals,
+                )?;
+            }
+            LoopClause::While(test) | LoopClause::Until(test) => {
+                let test = self.generate_code(source, test, addr, locals)?;
+                let instructions = self.functions.implement_function(addr);
+                let block_up =
+                    instructions.block_depth() - accumulator.block_depth + 1;
+                if let LoopClause::While(_) = clause {
+                    instructions.break_if_nil(block_up, test);
+                } else {
+                    instructions.break_if_not_nil(block_up, test);
+                }
+            }
+            LoopClause::When {
+                test,
+                negated,
+                clause,
+            } => {
+                // a:{ if test == nil { break a; } … clause … }
+                let test = self.generate_code(source, test, addr, locals)?;
+                let instructions = self.functions.implement_function(addr);
+                instructions.enter_block();
+                if *negated {
+                    instructions.break_if_not_nil(1, test);
+                } else {
+                    instructions.break_if_nil(1, test);
+                }
+                self.generate_code_for_loop_clause(
+                    source,
+                    clause,
+                    accumulator,
+                    addr,
+                    locals,
+                )?;
+                self.functions.implement_function(addr).exit_block();
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the step, or one without it, to the counter.
+    fn generate_code_for_increment(
+        &mut self,
+        counter: PlaceAddress,
+        step: Option<PlaceAddress>,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) {
+        let step = step.unwrap_or_else(|| {
+            let one = locals.next();
+            self.functions
+                .implement_function(addr)
+                .load_data(self.static_data.number(1), one);
+            one
+        });
+        let next = self.generate_code_for_runtime_call(
+            "add-2",
+            &[counter, step],
+            addr,
+            locals,
+        );
+        self.functions
+            .implement_function(addr)
+            .write_place(next, counter);
+    }
+
+    /// Local functions are lambdas that are bound in the local function
+    /// scope. With `labels`, they are bound before the lambdas are created,
+    /// so that the lambdas can find each other in the places of the creator.
+    fn generate_code_for_flet_form(
+        &mut self,
+        source: Source<'s>,
+        form: &FletForm<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        self.local_function_scope.enter_scope();
+        if form.recursive() {
+            let places = form
+                .functions()
+                .iter()
+                .map(|function| {
+                    let place = locals.next();
+                    let name = function.name().fragment(source).source();
+                    let arity = function.lambda().parameters().arity();
+                    self.bind_local_function(name, place, arity);
+                    place
+                })
+                .collect::<Vec<_>>();
+            for (function, place) in form.functions().iter().zip(places) {
+                let name = function.name().fragment(source).source();
+                let lambda_place = self.generate_code_for_lambda(
+                    source,
+                    Some(name),
+                    function.lambda(),
+                    addr,
+                    locals,
+                )?;
+                self.functions
+                    .implement_function(addr)
+                    .write_place(lambda_place, place);
+            }
+        } else {
+            let mut places_to_add_simultaneously =
+                Vec::with_capacity(form.functions().len());
+            for function in form.functions() {
+                let name = function.name().fragment(source).source();
+                let place = self.generate_code_for_lambda(
+                    source,
+                    Some(name),
+                    function.lambda(),
+                    addr,
+                    locals,
+                )?;
+                let arity = function.lambda().parameters().arity();
+                places_to_add_simultaneously.push((name, place, arity));
+            }
+            for (name, place, arity) in places_to_add_simultaneously {
+                self.bind_local_function(name, place, arity);
+            }
+        }
+
+        let last_result = self.generate_code_for_body(
+            source,
+            form.body(),
+            position,
+            addr,
+            locals,
+        )?;
+        self.local_function_scope.exit_scope();
+        Ok(last_result)
+    }
+
+    fn generate_code_for_function_application(
+        &mut self,
+        source: Source<'s>,
+        call: &Call<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let args = call.args();
+        let mut evaluated_arg_places: Vec<PlaceAddress> =
+            Vec::with_capacity(args.len());
+        for arg in args {
+            let place = self.generate_code(source, arg, addr, locals)?;
+            evaluated_arg_places.push(place);
+        }
+
+        let arguments_place = locals.next();
+        let result_place = locals.next();
+        // start with empty argument list
+        let instructions = self.functions.implement_function(addr);
+        instructions.load_data(self.static_data.nil_data(), arguments_place);
+        // and then start pushing elements from the back to the front
+        for &arg in evaluated_arg_places.iter().rev() {
+            instructions.cons(arg, arguments_place, arguments_place);
+        }
+
+        let func_ident = call.function();
+        let target = self.resolve_call_target(
+            source,
+            func_ident,
+            func_ident.fragment(source).source(),
+        )?;
+        let name = func_ident.fragment(source).source();
+        self.check_argument_count(
+            source,
+            func_ident,
+            name,
+            target,
+            args.len(),
+        )?;
+        self.generate_code_for_call_to(
+            target,
+            arguments_place,
+            result_place,
+            position,
+            addr,
+        );
+
+        Ok(result_place)
+    }
+
+    /// Local functions from `flet` and `labels` shadow static functions.
+    fn resolve_call_target(
+        &self,
+        source: Source<'s>,
+        ident: &'t Atom<'s>,
+        name: &'s str,
+    ) -> Result<CallTarget, IrGenError<'s, 't>> {
+        if let Some(function_place) = self.resolve_local_function(name) {
+            return Ok(CallTarget::Local(function_place));
+        }
+        self.function_scope
+            .resolve(name)
+            .map(CallTarget::Static)
+            .map_err(|_| IrGenError::FunctionNotFound { ident, source })
+    }
+
+    /// Reports calls with a number of arguments that the function does not
+    /// accept, if its arity is known.
+    fn check_argument_count(
+        &self,
+        source: Source<'s>,
+        ident: &'t Atom<'s>,
+        name: &'s str,
+        target: CallTarget,
+        got: usize,
+    ) -> Result<(), IrGenError<'s, 't>> {
+        let arity = match target {
+            CallTarget::Static(function) => self.arities.get(&function).copied(),
+            // the call target came from the local function with that name
+            CallTarget::Local(_) => self
+                .local_function_scope
+                .resolve(name)
+                .ok()
+                .map(|function| function.arity),
+        };
+        if let Some(arity) = arity
+            && !arity.accepts(got)
+        {
+            return Err(IrGenError::WrongArgumentCount {
+                source,
+                ident,
+                name,
+                arity,
+                got,
+            });
+        }
+        Ok(())
+    }
+
+    /// Calls static functions directly and other functions through their
+    /// function objects.
+    fn generate_code_for_call_to(
+        &mut self,
+        target: CallTarget,
+        arguments: PlaceAddress,
+        result: PlaceAddress,
+        position: Position,
+        addr: StaticFunctionAddress,
+    ) {
+        let instructions = self.functions.implement_function(addr);
+        match (target, position) {
+            (CallTarget::Static(function), Position::Tail) => {
+                instructions.tail_call(function, arguments);
+            }
+            (CallTarget::Static(function), _) => {
+                instructions.call(function, arguments, result);
+            }
+            (CallTarget::Local(function), Position::Tail) => {
+                instructions.tail_call_indirect(function, arguments);
+            }
+            (CallTarget::Local(function), _) => {
+                instructions.call_indirect(function, arguments, result);
+            }
+        }
+        if let Position::Values(values) = position {
+            instructions.load_values(values);
+        }
+    }
+
+    fn generate_code_for_apply(
+        &mut self,
+        source: Source<'s>,
+        apply: &Apply<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let arg_list =
+            self.generate_code(source, apply.args(), addr, locals)?;
+        let result_place = locals.next();
+        let function = apply.function();
+        if let Some(func_name) = function.function_name().map(|c| c.ident()) {
+            // when the target is a static function identify, we can do a fast direct call
+            assert!(matches!(func_name.token().kind(), TokenKind::FuncIdent));
+            let func_name_str =
+                func_name.fragment(source).source()[2..].trim();
+            let target =
+                self.resolve_call_target(source, func_name, func_name_str)?;
+            self.generate_code_for_call_to(
+                target,
+                arg_list,
+                result_place,
+                position,
+                addr,
+            );
+        } else {
+            // function calculated at runtime, need an indirect call
+            let function_place =
+                self.generate_code(source, function, addr, locals)?;
+            self.generate_code_for_call_to(
+                CallTarget::Local(function_place),
+                arg_list,
+                result_place,
+                position,
+                addr,
+            );
+        }
+
+        Ok(result_place)
+    }
+
+    fn generate_code_for_funcall(
+        &mut self,
+        source: Source<'s>,
+        funcall: &Funcall<'s, 't>,
+        position: Position,
+        addr: StaticFunctionAddress,
+        locals: &mut LocalPlaceGenerator,
+    ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
+        let args = funcall.args();
+        let mut evaluated_arg_places: Vec<PlaceAddress> =
+            Vec::with_capacity(args.len());
+        for arg in args {
+            let place = self.generate_code(source, arg, addr, locals)?;
+            evaluated_arg_places.push(place);
+        }
+
+        let arguments_place = locals.next();
+        let result_place = locals.next();
+        // start with empty argument list
+        let instructions = self.functions.implement_function(addr);
+        instructions.load_data(self.static_data.nil_data(), arguments_place);
+        // and then start pushing elements from the back to the front
+        for &arg in evaluated_arg_places.iter().rev() {
+            instructions.cons(arg, arguments_place, arguments_place);
+        }
+
+        let function = funcall.function();
         if let Some(func_name) = function.function_name().map(|c| c.ident()) {
             // when the target is a static function identifier, we can do a fast direct call
             assert!(matches!(func_name.token().kind(), TokenKind::FuncIdent));
             let func_name_str =
                 func_name.fragment(source).source()[2..].trim();
-            let func_address = self
-                .function_scope
-                .resolve(func_name_str)
-                .map_err(|_| IrGenError::FunctionNotFound {
-                    ident: func_name,
-                    source,
-                })?;
-            self.functions.implement_function(addr).call(
-                func_address,
+            let target =
+                self.resolve_call_target(source, func_name, func_name_str)?;
+            self.check_argument_count(
+                source,
+                func_name,
+                func_name_str,
+                target,
+                args.len(),
+            )?;
+            self.generate_code_for_call_to(
+                target,
                 arguments_place,
                 result_place,
+                position,
+                addr,
             );
         } else {
             // function calculated at runtime, need an indirect call
             // bug: I think the function should be evaluated first, same for apply
             let function_place =
                 self.generate_code(source, function, addr, locals)?;
-            self.functions.implement_function(addr).call_indirect(
-                function_place,
+            self.generate_code_for_call_to(
+                CallTarget::Local(function_place),
                 arguments_place,
                 result_place,
+                position,
+                addr,
             );
         }
 
         Ok(result_place)
     }
 
+    /// Local functions have a name, which is used in error messages of the
+    /// lambda and names its implicit block. Anonymous ones are called
+    /// `lambda` in error messages.
     fn generate_code_for_lambda(
         &mut self,
         source: Source<'s>,
+        name: Option<&'s str>,
         lambda: &Lambda<'s, 't>,
         parent_func_addr: StaticFunctionAddress,
-        locals: &mut LocalPlaceGenerator, // we share the locals but not the function address
+        locals: &mut LocalPlaceGenerator,
     ) -> Result<PlaceAddress, IrGenError<'s, 't>> {
         let parent_func_lambda_place = locals.next();
         let lambda_fun_name = format!(
@@ -563,6 +2946,7 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         );
 
         self.variable_scope.enter_scope();
+        self.lambda_level += 1;
         let lambda_func_addr =
             self.functions.add_private_function(&lambda_fun_name);
         let lambda_func_table_idx =
@@ -573,27 +2957,30 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
         );
         // the name of the lambda function is not added to any scopes (lambdas cannot be named)
 
-        for arg in lambda.positional_args().iter() {
-            let ident = arg.fragment(source).source();
-            let address = locals.next();
-            self.functions
-                .implement_function(lambda_func_addr)
-                .consume_param(address);
-            self.variable_scope.add_binding(ident, address);
-        }
-        let mut last_place = None;
-        for code in lambda.body() {
-            last_place = Some(self.generate_code(
-                source,
-                code,
-                lambda_func_addr,
-                locals,
-            )?);
-        }
+        // each call gets its own places, so that closures created by
+        // different calls don't share them, the first one links to the
+        // places of the parent
+        let mut lambda_locals = LocalPlaceGenerator::new();
+        lambda_locals.next();
+        self.generate_code_for_parameters(
+            source,
+            name.unwrap_or("lambda"),
+            lambda.parameters(),
+            lambda_func_addr,
+            &mut lambda_locals,
+        )?;
+        let last_place = self.generate_code_for_function_body(
+            source,
+            name,
+            lambda.body(),
+            lambda_func_addr,
+            &mut lambda_locals,
+        )?;
         self.functions
             .implement_function(lambda_func_addr)
-            .add_return(last_place.unwrap_or_else(|| self.static_data.nil_place()));
+            .add_return(last_place);
 
+        self.lambda_level -= 1;
         self.variable_scope.exit_scope();
 
         self.functions
@@ -604,6 +2991,55 @@ impl<'a: 't, 's, 't> IrGen<'a, 's, 't> {
     }
 }
 
+/// A function to call.
+#[derive(Clone, Copy)]
+enum CallTarget {
+    Static(StaticFunctionAddress),
+    /// A place holding a function object, like the ones of local functions.
+    Local(PlaceAddress),
+}
+
+/// Where the clauses of a loop accumulate their values.
+struct LoopAccumulator {
+    /// The list of collected values or the sum, which the loop returns.
+    result: PlaceAddress,
+    /// The last cell of the collected list, to append to it.
+    last: PlaceAddress,
+    /// Depth of the IR block of the loop, which `while` and `until` leave.
+    block_depth: u32,
+}
+
+/// How a loop variable changes in each iteration.
+#[derive(Clone, Copy)]
+enum LoopStep {
+    None,
+    /// Bound to the car of the rest of a list, the loop ends at its end.
+    Rest {
+        rest: PlaceAddress,
+        element: PlaceAddress,
+    },
+    /// Counts up by the step, or one, until after or up to the end.
+    Count {
+        counter: PlaceAddress,
+        end: Option<(PlaceAddress, bool)>,
+        by: Option<PlaceAddress>,
+    },
+}
+
+/// What happens with the values of a form.
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    /// Only the primary value is used.
+    Primary,
+    /// All values are written to the place in the format of the values
+    /// register, see `Instruction::LoadValues`. The place must be nil
+    /// before, since forms with a single value leave it alone.
+    Values(PlaceAddress),
+    /// The function returns the values right after, so calls are tail calls
+    /// and `values` returns right away.
+    Tail,
+}
+
 pub enum IrGenError<'s, 't> {
     NotInScope {
         source: Source<'s>,
@@ -621,6 +3057,22 @@ pub enum IrGenError<'s, 't> {
         source: Source<'s>,
         ident: &'t Atom<'s>,
     },
+    AssignmentToConstant {
+        source: Source<'s>,
+        ident: &'t Atom<'s>,
+    },
+    WrongArgumentCount {
+        source: Source<'s>,
+        ident: &'t Atom<'s>,
+        /// Name of the function, without `#'` when called with `funcall`.
+        name: &'s str,
+        arity: Arity,
+        got: usize,
+    },
+    BlockNotFound {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
     StaticData {
         error: StaticDataError<'s, 't>
     },
@@ -670,6 +3122,32 @@ impl<'s, 't> fmt::Display for IrGenError<'s, 't> {
                 )?;
                 writeln!(f, "{}", ident.fragment(source).source_context())
             }
+            &IrGenError::AssignmentToConstant { source, ident } => {
+                writeln!(
+                    f,
+                    "{} is a constant and cannot be assigned to:",
+                    ident.fragment(source).source()
+                )?;
+                writeln!(f, "{}", ident.fragment(source).source_context())
+            }
+            &IrGenError::WrongArgumentCount {
+                source,
+                ident,
+                name,
+                arity,
+                got,
+            } => {
+                writeln!(f, "function {name} expects {arity}, got {got}:")?;
+                writeln!(f, "{}", ident.fragment(source).source_context())
+            }
+            &IrGenError::BlockNotFound { source, atom } => {
+                writeln!(
+                    f,
+                    "no enclosing block for `{}`:",
+                    atom.fragment(source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(source).source_context())
+            }
             IrGenError::StaticData { error } => write!(f, "{}", error)
         }
     }
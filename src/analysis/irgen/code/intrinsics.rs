@@ -47,16 +47,38 @@ impl<'i, 's> Intrinsics<'i, 's> {
         self.generate_cons();
         self.generate_car();
         self.generate_cdr();
+        self.generate_rplaca();
+        self.generate_rplacd();
+        self.generate_make_bignum();
+        self.generate_make_ratio();
         self.generate_add2();
         self.generate_sub2();
         self.generate_mul2();
         self.generate_div2();
         self.generate_eq2();
+        self.generate_same2();
         self.generate_ne2();
         self.generate_gt2();
         self.generate_lt2();
         self.generate_gte2();
         self.generate_lte2();
+        self.generate_float_add2();
+        self.generate_float_sub2();
+        self.generate_float_mul2();
+        self.generate_float_div2();
+        self.generate_float_eq2();
+        self.generate_float_lt2();
+        self.generate_float_lte2();
+        self.generate_int_to_float();
+        self.generate_float_truncate();
+        self.generate_char_code();
+        self.generate_code_char();
+        self.generate_char_at();
+        self.generate_char_string();
+        self.generate_make_vector();
+        self.generate_vector_ref();
+        self.generate_vector_set();
+        self.generate_length();
         self.generate_nil_if_0();
         self.generate_panic();
     }
@@ -142,6 +164,70 @@ impl<'i, 's> Intrinsics<'i, 's> {
             .add_return(place);
     }
 
+    /// Function that overwrites the car of a list node and returns the list
+    /// node (no typechecking).
+    fn generate_rplaca(&mut self) {
+        let name = "intrinsic:rplaca";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let list = PlaceAddress::new_local(0);
+        let value = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(list)
+            .consume_param(value)
+            .store_car(list, value)
+            .add_return(list);
+    }
+
+    /// Function that overwrites the cdr of a list node and returns the list
+    /// node (no typechecking).
+    fn generate_rplacd(&mut self) {
+        let name = "intrinsic:rplacd";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let list = PlaceAddress::new_local(0);
+        let value = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(list)
+            .consume_param(value)
+            .store_cdr(list, value)
+            .add_return(list);
+    }
+
+    /// Function that creates a bignum from a sign and a list of digits (no
+    /// typechecking).
+    fn generate_make_bignum(&mut self) {
+        let name = "intrinsic:make-bignum";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let sign = PlaceAddress::new_local(0);
+        let digits = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(sign)
+            .consume_param(digits)
+            .make_bignum(sign, digits, sign)
+            .add_return(sign);
+    }
+
+    /// Function that creates a ratio from a numerator and a denominator (no
+    /// typechecking).
+    fn generate_make_ratio(&mut self) {
+        let name = "intrinsic:make-ratio";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let numerator = PlaceAddress::new_local(0);
+        let denominator = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(numerator)
+            .consume_param(denominator)
+            .make_ratio(numerator, denominator, numerator)
+            .add_return(numerator);
+    }
+
     fn generate_add2(&mut self) {
         let name = "intrinsic:add-2";
         let addr = self.functions.add_private_function(name);
@@ -212,6 +298,20 @@ impl<'i, 's> Intrinsics<'i, 's> {
             .add_return(left);
     }
 
+    fn generate_same2(&mut self) {
+        let name = "intrinsic:eq-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .same(left, right, left)
+            .add_return(left);
+    }
+
     fn generate_ne2(&mut self) {
         let name = "intrinsic:/=-2";
         let addr = self.functions.add_private_function(name);
@@ -282,6 +382,244 @@ impl<'i, 's> Intrinsics<'i, 's> {
             .add_return(left);
     }
 
+    fn generate_float_add2(&mut self) {
+        let name = "intrinsic:float-add-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_add(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_sub2(&mut self) {
+        let name = "intrinsic:float-sub-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_sub(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_mul2(&mut self) {
+        let name = "intrinsic:float-mul-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_mul(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_div2(&mut self) {
+        let name = "intrinsic:float-div-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_div(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_eq2(&mut self) {
+        let name = "intrinsic:float-=-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_eq(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_lt2(&mut self) {
+        let name = "intrinsic:float-<-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_lt(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_float_lte2(&mut self) {
+        let name = "intrinsic:float-<=-2";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let left = PlaceAddress::new_local(0);
+        let right = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(left)
+            .consume_param(right)
+            .float_lte(left, right, left)
+            .add_return(left);
+    }
+
+    fn generate_int_to_float(&mut self) {
+        let name = "intrinsic:int-to-float";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .int_to_float(place, place)
+            .add_return(place);
+    }
+
+    fn generate_float_truncate(&mut self) {
+        let name = "intrinsic:float-truncate";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .float_truncate(place, place)
+            .add_return(place);
+    }
+
+    fn generate_char_code(&mut self) {
+        let name = "intrinsic:char-code";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .char_code(place, place)
+            .add_return(place);
+    }
+
+    /// Function that creates a character from a code, which must be a Unicode
+    /// scalar value (no typechecking).
+    fn generate_code_char(&mut self) {
+        let name = "intrinsic:code-char";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .code_char(place, place)
+            .add_return(place);
+    }
+
+    /// Function that gets the character at an index of a string, or nil if
+    /// the index is out of range (no typechecking).
+    fn generate_char_at(&mut self) {
+        let name = "intrinsic:char-at";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let string = PlaceAddress::new_local(0);
+        let index = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(string)
+            .consume_param(index)
+            .char_at(string, index, string)
+            .add_return(string);
+    }
+
+    fn generate_char_string(&mut self) {
+        let name = "intrinsic:char-string";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .char_string(place, place)
+            .add_return(place);
+    }
+
+    /// Function that creates a vector with a number of elements that are all
+    /// nil (no typechecking).
+    fn generate_make_vector(&mut self) {
+        let name = "intrinsic:make-vector";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .make_vector(place, place)
+            .add_return(place);
+    }
+
+    /// Function that gets the element at an index of a vector (no
+    /// typechecking or bounds checking).
+    fn generate_vector_ref(&mut self) {
+        let name = "intrinsic:vector-ref";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let vector = PlaceAddress::new_local(0);
+        let index = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(vector)
+            .consume_param(index)
+            .load_element(vector, index, vector)
+            .add_return(vector);
+    }
+
+    /// Function that overwrites the element at an index of a vector and
+    /// returns the new element (no typechecking or bounds checking).
+    fn generate_vector_set(&mut self) {
+        let name = "intrinsic:vector-set";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let vector = PlaceAddress::new_local(0);
+        let index = PlaceAddress::new_local(mem::size_of::<i32>() as i32);
+        let value = PlaceAddress::new_local(2 * mem::size_of::<i32>() as i32);
+        self.functions
+            .implement_function(addr)
+            .consume_param(vector)
+            .consume_param(index)
+            .consume_param(value)
+            .store_element(vector, index, value)
+            .add_return(value);
+    }
+
+    /// Function that counts the elements of a vector or the characters of a
+    /// string (no typechecking).
+    fn generate_length(&mut self) {
+        let name = "intrinsic:length";
+        let addr = self.functions.add_private_function(name);
+        self.function_scope.add_binding(name, addr);
+        let place = PlaceAddress::new_local(0);
+        self.functions
+            .implement_function(addr)
+            .consume_param(place)
+            .load_length(place, place)
+            .add_return(place);
+    }
+
     fn generate_nil_if_0(&mut self) {
         let name = "intrinsic:nil-if-0";
         let addr = self.functions.add_private_function(name);
@@ -0,0 +1,237 @@
+use crate::analysis::form::{
+    AssignedPlace, Assignment, Form, Template, TemplateElement,
+};
+
+/// Checks if a `return-from` to the block with the given forms leaves a
+/// lambda or a region that catches throws on the way, so that it has to
+/// throw to a tag of the block instead of just breaking out of it.
+///
+/// Nested blocks with the same name shadow the block, and iteration forms
+/// are blocks named nil.
+pub fn block_needs_tag<'s, 't>(
+    name: &str,
+    mut forms: impl Iterator<Item = &'t Form<'s, 't>>,
+) -> bool
+where
+    's: 't,
+{
+    forms.any(|form| returns_through(name, form, false))
+}
+
+/// Checks if any `return-from` in the given forms goes to the block with the
+/// name, so that implicit blocks of functions are only set up when used.
+pub fn block_is_returned_from<'s, 't>(
+    name: &str,
+    mut forms: impl Iterator<Item = &'t Form<'s, 't>>,
+) -> bool
+where
+    's: 't,
+{
+    forms.any(|form| returns_through(name, form, true))
+}
+
+/// Looks for a `return-from` to the named block in a form, which is inside a
+/// lambda or region already if `nested` is set.
+fn returns_through<'s, 't>(
+    name: &str,
+    form: &'t Form<'s, 't>,
+    nested: bool,
+) -> bool {
+    let any = |forms: &'t [Form<'s, 't>], nested: bool| {
+        forms.iter().any(|form| returns_through(name, form, nested))
+    };
+    match form {
+        Form::Name(..) | Form::FunctionName(..) | Form::Constant(..) => false,
+        Form::ReturnFrom(form) => {
+            (nested && form.block_name() == name)
+                || form
+                    .value()
+                    .is_some_and(|value| returns_through(name, value, nested))
+        }
+        Form::BlockForm(block) if block.name() == name => false,
+        Form::BlockForm(block) => {
+            let nested =
+                nested || block_needs_tag(block.name(), block.body().iter());
+            any(block.body(), nested)
+        }
+        Form::DotimesForm(_)
+        | Form::DolistForm(_)
+        | Form::DoForm(_)
+        | Form::LoopForm(_)
+            if name == "nil" =>
+        {
+            false
+        }
+        Form::DotimesForm(form) => {
+            iteration_returns_through(name, form.forms(), nested)
+        }
+        Form::DolistForm(form) => {
+            iteration_returns_through(name, form.forms(), nested)
+        }
+        Form::DoForm(form) => {
+            iteration_returns_through(name, form.forms(), nested)
+        }
+        Form::LoopForm(form) => {
+            iteration_returns_through(name, form.forms(), nested)
+        }
+        Form::CatchForm(form) => {
+            returns_through(name, form.tag(), nested) || any(form.body(), true)
+        }
+        Form::ThrowForm(form) => {
+            returns_through(name, form.tag(), nested)
+                || returns_through(name, form.value(), nested)
+        }
+        Form::UnwindProtect(form) => {
+            returns_through(name, form.protected(), true)
+                || any(form.cleanup(), nested)
+        }
+        Form::HandlerCase(form) => {
+            returns_through(name, form.form(), true)
+                || form.clauses().iter().any(|c| any(c.body(), nested))
+        }
+        Form::HandlerBind(form) => {
+            form.handlers()
+                .iter()
+                .any(|h| returns_through(name, h.function(), nested))
+                || any(form.body(), true)
+        }
+        Form::IgnoreErrors(form) => any(form.body(), true),
+        Form::Lambda(lambda) => {
+            lambda
+                .parameters()
+                .defaults()
+                .any(|form| returns_through(name, form, true))
+                || any(lambda.body(), true)
+        }
+        Form::FletForm(form) => {
+            form.functions().iter().any(|function| {
+                let lambda = function.lambda();
+                // the implicit block of a local function shadows the block
+                let shadowed =
+                    function.name().fragment(form.source()).source() == name;
+                lambda
+                    .parameters()
+                    .defaults()
+                    .any(|form| returns_through(name, form, true))
+                    || (!shadowed && any(lambda.body(), true))
+            }) || any(form.body(), nested)
+        }
+        Form::LetForm(form) => {
+            form.bindings()
+                .iter()
+                .any(|b| returns_through(name, b.value(), nested))
+                || any(form.body(), nested)
+        }
+        Form::DestructuringBind(form) => {
+            form.list()
+                .defaults()
+                .any(|form| returns_through(name, form, nested))
+                || returns_through(name, form.value(), nested)
+                || any(form.body(), nested)
+        }
+        Form::MultipleValueBind(form) => {
+            returns_through(name, form.form(), nested)
+                || any(form.body(), nested)
+        }
+        Form::MultipleValueList(form) => {
+            returns_through(name, form.form(), nested)
+        }
+        Form::ValuesForm(form) => any(form.forms(), nested),
+        Form::IfForm(form) => {
+            returns_through(name, form.test_form(), nested)
+                || returns_through(name, form.then_form(), nested)
+                || form
+                    .else_form()
+                    .is_some_and(|form| returns_through(name, form, nested))
+        }
+        Form::AndForm(form) => any(form.forms(), nested),
+        Form::OrForm(form) => any(form.forms(), nested),
+        Form::CondForm(form) => form.clauses().iter().any(|c| {
+            returns_through(name, c.test(), nested) || any(c.body(), nested)
+        }),
+        Form::WhenForm(form) => {
+            returns_through(name, form.test_form(), nested)
+                || any(form.body(), nested)
+        }
+        Form::UnlessForm(form) => {
+            returns_through(name, form.test_form(), nested)
+                || any(form.body(), nested)
+        }
+        Form::PrognForm(form) => any(form.forms(), nested),
+        Form::SetqForm(form) => {
+            assignments_return_through(name, form.assignments(), nested)
+        }
+        Form::SetfForm(form) => {
+            assignments_return_through(name, form.assignments(), nested)
+        }
+        Form::Call(form) => any(form.args(), nested),
+        Form::Apply(form) => {
+            returns_through(name, form.function(), nested)
+                || returns_through(name, form.args(), nested)
+        }
+        Form::Funcall(form) => {
+            returns_through(name, form.function(), nested)
+                || any(form.args(), nested)
+        }
+        Form::MacroCall(call) => {
+            returns_through(name, call.expansion(), nested)
+        }
+        Form::Quasiquote(form) => {
+            template_returns_through(name, form.template(), nested)
+        }
+    }
+}
+
+/// Iteration forms are blocks named nil around all of their forms.
+fn iteration_returns_through<'s, 't>(
+    name: &str,
+    mut forms: impl Iterator<Item = &'t Form<'s, 't>> + Clone,
+    nested: bool,
+) -> bool
+where
+    's: 't,
+{
+    let nested = nested || block_needs_tag("nil", forms.clone());
+    forms.any(|form| returns_through(name, form, nested))
+}
+
+fn template_returns_through<'s, 't>(
+    name: &str,
+    template: &'t Template<'s, 't>,
+    nested: bool,
+) -> bool {
+    match template {
+        Template::Constant(_) => false,
+        Template::Unquoted(form) => returns_through(name, form, nested),
+        Template::List(elements) => {
+            elements.iter().any(|element| match element {
+                TemplateElement::Element(template) => {
+                    template_returns_through(name, template, nested)
+                }
+                TemplateElement::Spliced(form) => {
+                    returns_through(name, form, nested)
+                }
+            })
+        }
+    }
+}
+
+fn assignments_return_through<'s, 't>(
+    name: &str,
+    assignments: &'t [Assignment<'s, 't>],
+    nested: bool,
+) -> bool {
+    assignments.iter().any(|assignment| {
+        let place = match assignment.place() {
+            AssignedPlace::Variable(_) => false,
+            AssignedPlace::Car(list) | AssignedPlace::Cdr(list) => {
+                returns_through(name, list, nested)
+            }
+            AssignedPlace::Aref(vector, index) => {
+                returns_through(name, vector, nested)
+                    || returns_through(name, index, nested)
+            }
+        };
+        place || returns_through(name, assignment.value(), nested)
+    })
+}
@@ -1,4 +1,7 @@
-use crate::analysis::{FunctionDefinition, form::Form};
+use crate::analysis::{
+    FunctionDefinition,
+    form::{AssignedPlace, Assignment, Form, Template, TemplateElement},
+};
 
 /// Checks if the function contains any lambdas.
 ///
@@ -7,8 +10,11 @@ use crate::analysis::{FunctionDefinition, form::Form};
 pub fn contains_function_lambdas<'s, 't>(
     definition: &'t FunctionDefinition<'s, 't>,
 ) -> bool {
-    // if we supported default arguments, we would have to check them here too
-    definition.body().iter().any(contains_form_lambdas)
+    definition
+        .parameters()
+        .defaults()
+        .any(contains_form_lambdas)
+        || definition.body().iter().any(contains_form_lambdas)
 }
 
 pub fn contains_form_lambdas<'s, 't>(form: &'t Form<'s, 't>) -> bool {
@@ -21,6 +27,60 @@ pub fn contains_form_lambdas<'s, 't>(form: &'t Form<'s, 't>) -> bool {
                 .any(|b| contains_form_lambdas(b.value()))
                 || form.body().iter().any(contains_form_lambdas)
         }
+        Form::DestructuringBind(form) => {
+            form.list().defaults().any(contains_form_lambdas)
+                || contains_form_lambdas(form.value())
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::MultipleValueBind(form) => {
+            contains_form_lambdas(form.form())
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::MultipleValueList(form) => contains_form_lambdas(form.form()),
+        Form::ValuesForm(form) => {
+            form.forms().iter().any(contains_form_lambdas)
+        }
+        Form::BlockForm(form) => form.body().iter().any(contains_form_lambdas),
+        Form::ReturnFrom(form) => {
+            form.value().is_some_and(contains_form_lambdas)
+        }
+        Form::CatchForm(form) => {
+            contains_form_lambdas(form.tag())
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::ThrowForm(form) => {
+            contains_form_lambdas(form.tag())
+                || contains_form_lambdas(form.value())
+        }
+        Form::UnwindProtect(form) => {
+            contains_form_lambdas(form.protected())
+                || form.cleanup().iter().any(contains_form_lambdas)
+        }
+        Form::HandlerCase(form) => {
+            contains_form_lambdas(form.form())
+                || form
+                    .clauses()
+                    .iter()
+                    .any(|c| c.body().iter().any(contains_form_lambdas))
+        }
+        Form::HandlerBind(form) => {
+            form.handlers()
+                .iter()
+                .any(|h| contains_form_lambdas(h.function()))
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::IgnoreErrors(form) => {
+            form.body().iter().any(contains_form_lambdas)
+        }
+        Form::DotimesForm(form) => form.forms().any(contains_form_lambdas),
+        Form::DolistForm(form) => form.forms().any(contains_form_lambdas),
+        Form::DoForm(form) => form.forms().any(contains_form_lambdas),
+        Form::LoopForm(form) => form.forms().any(contains_form_lambdas),
+        // local functions are lambdas
+        Form::FletForm(form) => {
+            !form.functions().is_empty()
+                || form.body().iter().any(contains_form_lambdas)
+        }
         Form::IfForm(form) => {
             contains_form_lambdas(form.test_form())
                 || contains_form_lambdas(form.then_form())
@@ -28,6 +88,21 @@ pub fn contains_form_lambdas<'s, 't>(form: &'t Form<'s, 't>) -> bool {
         }
         Form::AndForm(form) => form.forms().iter().any(contains_form_lambdas),
         Form::OrForm(form) => form.forms().iter().any(contains_form_lambdas),
+        Form::CondForm(form) => form.clauses().iter().any(|c| {
+            contains_form_lambdas(c.test())
+                || c.body().iter().any(contains_form_lambdas)
+        }),
+        Form::WhenForm(form) => {
+            contains_form_lambdas(form.test_form())
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::UnlessForm(form) => {
+            contains_form_lambdas(form.test_form())
+                || form.body().iter().any(contains_form_lambdas)
+        }
+        Form::PrognForm(form) => form.forms().iter().any(contains_form_lambdas),
+        Form::SetqForm(form) => contains_assignment_lambdas(form.assignments()),
+        Form::SetfForm(form) => contains_assignment_lambdas(form.assignments()),
         Form::Call(form) => form.args().iter().any(contains_form_lambdas),
         Form::Apply(form) => {
             contains_form_lambdas(form.function())
@@ -37,5 +112,39 @@ pub fn contains_form_lambdas<'s, 't>(form: &'t Form<'s, 't>) -> bool {
             contains_form_lambdas(form.function())
                 || form.args().iter().any(contains_form_lambdas)
         }
+        Form::MacroCall(call) => contains_form_lambdas(call.expansion()),
+        Form::Quasiquote(form) => contains_template_lambdas(form.template()),
     }
 }
+
+fn contains_template_lambdas<'s, 't>(template: &'t Template<'s, 't>) -> bool {
+    match template {
+        Template::Constant(_) => false,
+        Template::Unquoted(form) => contains_form_lambdas(form),
+        Template::List(elements) => {
+            elements.iter().any(|element| match element {
+                TemplateElement::Element(template) => {
+                    contains_template_lambdas(template)
+                }
+                TemplateElement::Spliced(form) => contains_form_lambdas(form),
+            })
+        }
+    }
+}
+
+fn contains_assignment_lambdas<'s, 't>(
+    assignments: &'t [Assignment<'s, 't>],
+) -> bool {
+    assignments.iter().any(|assignment| {
+        let place_lambdas = match assignment.place() {
+            AssignedPlace::Variable(_) => false,
+            AssignedPlace::Car(list) | AssignedPlace::Cdr(list) => {
+                contains_form_lambdas(list)
+            }
+            AssignedPlace::Aref(vector, index) => {
+                contains_form_lambdas(vector) || contains_form_lambdas(index)
+            }
+        };
+        place_lambdas || contains_form_lambdas(assignment.value())
+    })
+}
@@ -2,10 +2,48 @@ use std::fmt;
 
 use crate::{diagnostic::Diagnostic, ir::{PlaceAddress, StaticFunctionAddress}};
 
-pub type VariableScope<'s> = Scope<'s, PlaceAddress>;
+use super::{Arity, Position};
+
+pub type VariableScope<'s> = Scope<'s, VariablePlace>;
 
 pub type FunctionScope<'s> = Scope<'s, StaticFunctionAddress>;
 
+pub type LocalFunctionScope<'s> = Scope<'s, LocalFunctionPlace>;
+
+pub type BlockScope<'s> = Scope<'s, BlockPlace>;
+
+/// The place a variable is bound to, along with the number of lambdas that
+/// the binding is nested in.
+#[derive(Clone, Copy)]
+pub struct VariablePlace {
+    pub lambda_level: u32,
+    pub place: PlaceAddress,
+}
+
+/// The place a function from `flet` or `labels` is bound to, along with the
+/// number of arguments that its lambda list accepts.
+#[derive(Clone, Copy)]
+pub struct LocalFunctionPlace {
+    pub variable: VariablePlace,
+    pub arity: Arity,
+}
+
+/// Where a `return-from` goes, breaking out of the block when it is in the
+/// same function and region, or throwing to the tag of the block otherwise.
+#[derive(Clone, Copy)]
+pub struct BlockPlace {
+    pub lambda_level: u32,
+    /// Number of regions that catch throws that the body is in.
+    pub catch_depth: u32,
+    /// Number of IR blocks that the body is in.
+    pub block_depth: u32,
+    pub result: PlaceAddress,
+    /// Position of the body, which values from local returns go to as well.
+    pub position: Position,
+    /// Only blocks that are left from lambdas or regions have a tag.
+    pub tag: Option<VariablePlace>,
+}
+
 pub struct Scope<'s, T> {
     /// Bindings, duplicates are allowed. To the right is more local,
     /// and resolving will give the most local.
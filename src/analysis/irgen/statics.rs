@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashMap, fmt};
 
-use crate::{analysis::strings::decode_string, diagnostic::Diagnostic, ir::{DataAddress, FunctionTableIndex, PlaceAddress, StaticData, StaticDataBuilder, StaticFunctionAddress}, parse::{AstNode, Atom, TokenKind}, source::Source};
+use crate::{analysis::strings::{decode_character, decode_string}, diagnostic::Diagnostic, ir::{DataAddress, FunctionTableIndex, PlaceAddress, StaticData, StaticDataBuilder, StaticFunctionAddress, BIGNUM_RADIX, fixnum}, parse::{AstNode, Atom, TokenKind}, source::Source};
 
 pub struct StaticsGen<'s> {
     static_data: StaticDataBuilder,
@@ -8,8 +8,12 @@ pub struct StaticsGen<'s> {
     t_place: PlaceAddress,
     func_table_indexes: HashMap<StaticFunctionAddress, FunctionTableIndex>,
     global_string_addresses: HashMap<Cow<'s, str>, DataAddress>,
-    global_identifier_addresses: HashMap<&'s str, DataAddress>,
+    global_identifier_addresses: HashMap<Cow<'s, str>, DataAddress>,
+    /// Only numbers too large for immediates, which have no address.
     global_number_addresses: HashMap<i32, DataAddress>,
+    /// Keyed by the bits of the float so that equal bits share an address.
+    global_float_addresses: HashMap<u32, DataAddress>,
+    global_character_addresses: HashMap<char, DataAddress>,
     global_function_addresses: HashMap<StaticFunctionAddress, DataAddress>,
 }
 
@@ -26,6 +30,8 @@ impl<'s> StaticsGen<'s> {
             global_string_addresses: HashMap::new(),
             global_identifier_addresses: HashMap::new(),
             global_number_addresses: HashMap::new(),
+            global_float_addresses: HashMap::new(),
+            global_character_addresses: HashMap::new(),
             global_function_addresses: HashMap::new(),
         }
     }
@@ -64,8 +70,12 @@ impl<'s> StaticsGen<'s> {
                     TokenKind::Comment
                     | TokenKind::Ws
                     | TokenKind::LeftParen
+                    | TokenKind::VectorLeftParen
                     | TokenKind::RightParen
-                    | TokenKind::Quote => unreachable!(),
+                    | TokenKind::Quote
+                    | TokenKind::Quasiquote
+                    | TokenKind::Unquote
+                    | TokenKind::UnquoteSplicing => unreachable!(),
                     TokenKind::StringLit => {
                         let decoded =
                             decode_string(atom.fragment(source).source());
@@ -78,41 +88,52 @@ impl<'s> StaticsGen<'s> {
                             })
                     }
                     TokenKind::IntLit => {
-                        let decoded = i32::from_str_radix(
-                            atom.fragment(source).source(),
-                            10,
-                        )
-                        .map_err(|_| {
-                            StaticDataError::NumberParseError {
+                        let literal = atom.fragment(source).source();
+                        let number = match i32::from_str_radix(literal, 10) {
+                            Ok(decoded) => Some(self.number(decoded)),
+                            Err(_) => self.big_number(literal),
+                        };
+                        number.ok_or(StaticDataError::NumberParseError {
+                            atom,
+                            source,
+                        })?
+                    }
+                    TokenKind::FloatLit => {
+                        let decoded = atom
+                            .fragment(source)
+                            .source()
+                            .parse::<f32>()
+                            .map_err(|_| StaticDataError::FloatParseError {
                                 atom,
-                                source: source,
-                            }
-                        })?;
+                                source,
+                            })?;
                         *self
-                            .global_number_addresses
-                            .entry(decoded)
-                            .or_insert_with(|| self.static_data.static_number(decoded))
+                            .global_float_addresses
+                            .entry(decoded.to_bits())
+                            .or_insert_with(|| self.static_data.static_float(decoded))
                     }
-                    TokenKind::FloatLit => todo!("floats are unimplemented"),
-                    // identifiers in an escaped context, here the #' will be included for functions
-                    TokenKind::FuncIdent => {
-                        let value = atom.fragment(source).source();
+                    TokenKind::CharLit => {
+                        let literal = atom.fragment(source).source();
+                        let unknown = StaticDataError::UnknownCharacterName {
+                            atom,
+                            source,
+                        };
+                        let decoded =
+                            decode_character(literal).ok_or(unknown)?;
                         *self
-                            .global_identifier_addresses
-                            .entry(value)
+                            .global_character_addresses
+                            .entry(decoded)
                             .or_insert_with(|| {
-                                self.static_data.static_identifier(value)
+                                self.static_data.static_character(decoded)
                             })
                     }
+                    // identifiers in an escaped context, here the #' will be included for functions
+                    TokenKind::FuncIdent => {
+                        self.identifier(atom.fragment(source).source().into())
+                    }
                     // variable identifiers are as-is in an escaped context
                     TokenKind::Ident => {
-                        let value = atom.fragment(source).source();
-                        *self
-                            .global_identifier_addresses
-                            .entry(value)
-                            .or_insert_with(|| {
-                                self.static_data.static_identifier(value)
-                            })
+                        self.identifier(atom.fragment(source).source().into())
                     }
                 }
             }
@@ -127,12 +148,113 @@ impl<'s> StaticsGen<'s> {
                 }
                 successor
             }
+            AstNode::Vector(v) => {
+                let elements = v
+                    .elements()
+                    .iter()
+                    .map(|element| self.for_node(source, element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.static_data.static_vector(&elements)
+            }
             AstNode::Quoted(q) => {
                 self.for_node(source, q.quoted())?
             }
+            AstNode::Quasiquoted(q) => {
+                self.for_node(source, q.quasiquoted())?
+            }
+            // templates with unquotes are evaluated at runtime instead
+            AstNode::Unquoted(_) | AstNode::UnquotedSplicing(_) => {
+                unreachable!("unquotes are never static data")
+            }
         })
     }
 
+    /// Gets a number, re-using the one of an equal literal if it is too
+    /// large to be an immediate.
+    pub fn number(&mut self, value: i32) -> DataAddress {
+        if fixnum(value).is_some() {
+            return self.static_data.static_number(value);
+        }
+        *self
+            .global_number_addresses
+            .entry(value)
+            .or_insert_with(|| self.static_data.static_number(value))
+    }
+
+    /// Creates a bignum for an integer literal that does not fit into 32 bits,
+    /// or none if the literal is not made of decimal digits.
+    fn big_number(&mut self, literal: &str) -> Option<DataAddress> {
+        let (sign, decimal) = match literal.strip_prefix('-') {
+            Some(decimal) => (-1, decimal),
+            None => (1, literal.strip_prefix('+').unwrap_or(literal)),
+        };
+        if decimal.is_empty() || !decimal.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        // multiply the digits so far by ten and add each decimal digit
+        let mut digits: Vec<i32> = vec![];
+        for decimal_digit in decimal.bytes().map(|b| (b - b'0') as i32) {
+            let mut carry = decimal_digit;
+            for digit in &mut digits {
+                let value = *digit * 10 + carry;
+                *digit = value % BIGNUM_RADIX;
+                carry = value / BIGNUM_RADIX;
+            }
+            if carry != 0 {
+                digits.push(carry);
+            }
+        }
+        let digits: Vec<_> = digits
+            .into_iter()
+            .map(|digit| self.static_data.static_number(digit))
+            .collect();
+        let digits = self.static_list(&digits);
+        let sign = self.static_data.static_number(sign);
+        Some(self.static_data.static_bignum(sign, digits))
+    }
+
+    /// Gets the identifier that `'name` evaluates to for the given name.
+    pub fn symbol(&mut self, name: &'s str) -> DataAddress {
+        self.identifier(name.into())
+    }
+
+    /// Gets the identifier that `:name` evaluates to for the given name.
+    pub fn keyword(&mut self, name: &str) -> DataAddress {
+        self.identifier(format!(":{name}").into())
+    }
+
+    fn identifier(&mut self, value: Cow<'s, str>) -> DataAddress {
+        *self
+            .global_identifier_addresses
+            .entry(value)
+            .or_insert_with_key(|value| {
+                self.static_data.static_identifier(value.as_ref())
+            })
+    }
+
+    /// Gets a string that is not from the source code, e.g. for messages.
+    pub fn static_str(
+        &mut self,
+        value: impl Into<Cow<'s, str>>,
+    ) -> DataAddress {
+        *self
+            .global_string_addresses
+            .entry(value.into())
+            .or_insert_with_key(|value| {
+                self.static_data.static_string(value.as_ref())
+            })
+    }
+
+    /// Creates a static list of already existing data.
+    pub fn static_list(&mut self, elements: &[DataAddress]) -> DataAddress {
+        let mut successor = self.static_data.nil_data();
+        for &predecessor in elements.iter().rev() {
+            successor =
+                self.static_data.static_list_node(predecessor, successor);
+        }
+        successor
+    }
+
     pub fn static_function(&mut self, addr: StaticFunctionAddress) -> DataAddress {
         *self
             .global_function_addresses
@@ -165,6 +287,14 @@ pub enum StaticDataError<'s, 't> {
         source: Source<'s>,
         atom: &'t Atom<'s>,
     },
+    FloatParseError {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
+    UnknownCharacterName {
+        source: Source<'s>,
+        atom: &'t Atom<'s>,
+    },
 }
 
 impl<'s, 't> fmt::Display for StaticDataError<'s, 't> {
@@ -173,7 +303,23 @@ impl<'s, 't> fmt::Display for StaticDataError<'s, 't> {
             &StaticDataError::NumberParseError { atom, source } => {
                 writeln!(
                     f,
-                    "number cannot be parsed as a 32-bit signed integer `{}`:",
+                    "number cannot be parsed as an integer `{}`:",
+                    atom.source_range().of(source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(source).source_context())
+            }
+            &StaticDataError::FloatParseError { atom, source } => {
+                writeln!(
+                    f,
+                    "number cannot be parsed as a 32-bit float `{}`:",
+                    atom.source_range().of(source).source()
+                )?;
+                writeln!(f, "{}", atom.fragment(source).source_context())
+            }
+            &StaticDataError::UnknownCharacterName { atom, source } => {
+                writeln!(
+                    f,
+                    "unknown character name `{}`:",
                     atom.source_range().of(source).source()
                 )?;
                 writeln!(f, "{}", atom.fragment(source).source_context())
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use crate::diagnostic::Diagnostic;
+use crate::parse::{AstNode, Atom, TokenKind};
+use crate::source::Source;
+
+/// A macro defined with `defmacro`, which is expanded at compile time by
+/// evaluating its body over the unevaluated arguments of each call.
+///
+/// Functions defined with `defun` have the same shape, and are kept like this
+/// too so that macro bodies can call them at compile time.
+pub struct MacroDefinition<'s, 't> {
+    source: Source<'s>,
+    name: &'t Atom<'s>,
+    required_params: Vec<&'t Atom<'s>>,
+    /// Parameters after `&optional`, with the forms evaluated for their
+    /// default values.
+    optional_params: Vec<(&'t Atom<'s>, Option<&'t AstNode<'s>>)>,
+    /// Parameter after `&rest` or `&body`.
+    rest_param: Option<&'t Atom<'s>>,
+    body: &'t [AstNode<'s>],
+}
+
+impl<'s, 't> MacroDefinition<'s, 't> {
+    /// Checks if the node looks like a macro definition, without checking
+    /// whether it is well-formed.
+    pub fn is_definition(source: Source<'s>, node: &'t AstNode<'s>) -> bool {
+        starts_with(source, node, "defmacro")
+    }
+
+    /// Try to parse the ast node as a macro definition.
+    ///
+    /// Ok(None) if not a macro definition, and an error if a macro
+    /// definition, but malformed.
+    pub fn extract(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    ) -> Result<Option<MacroDefinition<'s, 't>>, MacroDefinitionError<'s, 't>>
+    {
+        if !Self::is_definition(source, node) {
+            return Ok(None);
+        }
+        Self::extract_assume_definition(source, node).map(Some)
+    }
+
+    /// Try to parse the ast node as a function definition that macros can
+    /// call at compile time.
+    ///
+    /// Ok(None) if not a function definition, and an error if it is malformed
+    /// or has parameters that macros do not support, like `&key`.
+    pub fn extract_function(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    ) -> Result<Option<MacroDefinition<'s, 't>>, MacroDefinitionError<'s, 't>>
+    {
+        if !starts_with(source, node, "defun") {
+            return Ok(None);
+        }
+        Self::extract_assume_definition(source, node).map(Some)
+    }
+
+    fn extract_assume_definition(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    ) -> Result<MacroDefinition<'s, 't>, MacroDefinitionError<'s, 't>> {
+        let list = node.list().unwrap();
+        let mut elements = list.elements().iter().skip(1);
+
+        let name_node = elements
+            .next()
+            .ok_or(MacroDefinitionError::MissingName { source, node })?;
+        let name =
+            name_node
+                .atom()
+                .ok_or(MacroDefinitionError::MalformedName {
+                    source,
+                    node: name_node,
+                })?;
+        if !matches!(name.token().kind(), TokenKind::Ident) {
+            return Err(MacroDefinitionError::MalformedName {
+                source,
+                node: name_node,
+            });
+        }
+
+        let param_node = elements
+            .next()
+            .ok_or(MacroDefinitionError::MissingParams { source, node })?;
+        let param_list =
+            param_node
+                .list()
+                .ok_or(MacroDefinitionError::MalformedParams {
+                    source,
+                    node: param_node,
+                })?;
+        let malformed = || MacroDefinitionError::MalformedParams {
+            source,
+            node: param_node,
+        };
+        let mut required_params = vec![];
+        let mut optional_params = vec![];
+        let mut rest_param = None;
+        let mut rest_keyword = None;
+        let mut optional = false;
+        for param in param_list.elements() {
+            if let Some(rest) = rest_keyword {
+                if rest_param.is_some() {
+                    return Err(MacroDefinitionError::RestAdditionalName {
+                        source,
+                        additional: param,
+                    });
+                }
+                rest_param = Some(param_name(source, param).ok_or(
+                    MacroDefinitionError::RestMissingName { source, rest },
+                )?);
+                continue;
+            }
+            match param.atom().map(|a| a.fragment(source).source()) {
+                Some("&optional") if !optional => optional = true,
+                Some("&rest" | "&body") => rest_keyword = Some(param),
+                _ if optional => optional_params
+                    .push(optional_param(source, param).ok_or_else(malformed)?),
+                _ => required_params
+                    .push(param_name(source, param).ok_or_else(malformed)?),
+            }
+        }
+        if let (Some(rest), None) = (rest_keyword, rest_param) {
+            return Err(MacroDefinitionError::RestMissingName { source, rest });
+        }
+
+        // a doc string is only a doc string if something comes after it
+        let mut body = &list.elements()[3..];
+        if let [AstNode::Atom(doc), _, ..] = body
+            && matches!(doc.token().kind(), TokenKind::StringLit)
+        {
+            body = &body[1..];
+        }
+
+        Ok(MacroDefinition {
+            source,
+            name,
+            required_params,
+            optional_params,
+            rest_param,
+            body,
+        })
+    }
+
+    pub fn source(&self) -> Source<'s> {
+        self.source
+    }
+
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    pub fn name_str(&self) -> &'s str {
+        self.name.fragment(self.source).source()
+    }
+
+    pub fn required_params(&self) -> &[&'t Atom<'s>] {
+        &self.required_params
+    }
+
+    pub fn optional_params(
+        &self,
+    ) -> &[(&'t Atom<'s>, Option<&'t AstNode<'s>>)] {
+        &self.optional_params
+    }
+
+    pub fn rest_param(&self) -> Option<&'t Atom<'s>> {
+        self.rest_param
+    }
+
+    /// Forms that are evaluated at compile time, the last one evaluating to
+    /// the expansion.
+    pub fn body(&self) -> &'t [AstNode<'s>] {
+        self.body
+    }
+}
+
+fn starts_with<'s>(source: Source<'s>, node: &AstNode<'s>, head: &str) -> bool {
+    match node.list().and_then(|l| l.elements().first()) {
+        Some(AstNode::Atom(first)) => first.fragment(source).source() == head,
+        _ => false,
+    }
+}
+
+/// A parameter name, which must not be a lambda list keyword.
+fn param_name<'s, 't>(
+    source: Source<'s>,
+    node: &'t AstNode<'s>,
+) -> Option<&'t Atom<'s>> {
+    node.atom().filter(|atom| {
+        matches!(atom.token().kind(), TokenKind::Ident)
+            && !atom.fragment(source).source().starts_with('&')
+    })
+}
+
+/// An optional parameter like `x` or `(x default)`.
+fn optional_param<'s, 't>(
+    source: Source<'s>,
+    node: &'t AstNode<'s>,
+) -> Option<(&'t Atom<'s>, Option<&'t AstNode<'s>>)> {
+    match node.list().map(|list| list.elements()) {
+        Some([name]) => Some((param_name(source, name)?, None)),
+        Some([name, default]) => {
+            Some((param_name(source, name)?, Some(default)))
+        }
+        Some(_) => None,
+        None => Some((param_name(source, node)?, None)),
+    }
+}
+
+#[derive(Debug)]
+pub enum MacroDefinitionError<'s, 't> {
+    MissingName {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    MissingParams {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    MalformedName {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    MalformedParams {
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+    },
+    RestMissingName {
+        source: Source<'s>,
+        rest: &'t AstNode<'s>,
+    },
+    RestAdditionalName {
+        source: Source<'s>,
+        additional: &'t AstNode<'s>,
+    },
+    DuplicateName {
+        source: Source<'s>,
+        name: &'t Atom<'s>,
+        previous_source: Source<'s>,
+        previous: &'t Atom<'s>,
+    },
+}
+
+impl<'s, 't> Diagnostic for MacroDefinitionError<'s, 't> {
+    fn kind(&self) -> crate::diagnostic::DiagnosticKind {
+        crate::diagnostic::DiagnosticKind::Error
+    }
+}
+
+impl<'s, 't> fmt::Display for MacroDefinitionError<'s, 't> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacroDefinitionError::MissingName { source, node } => {
+                writeln!(f, "macro definition is lacking a name:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            MacroDefinitionError::MalformedName { source, node } => {
+                writeln!(f, "not a valid macro name:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            MacroDefinitionError::MissingParams { source, node } => {
+                writeln!(f, "macro definition is lacking the parameter list:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            MacroDefinitionError::MalformedParams { source, node } => {
+                writeln!(f, "not a valid macro parameter list:")?;
+                writeln!(f, "{}", node.fragment(*source).source_context())
+            }
+            MacroDefinitionError::RestMissingName { source, rest } => {
+                writeln!(f, "&rest specified but no name given after it:")?;
+                writeln!(f, "{}", rest.fragment(*source).source_context())
+            }
+            MacroDefinitionError::RestAdditionalName { source, additional } => {
+                writeln!(
+                    f,
+                    "&rest specified with two or more names after it:"
+                )?;
+                writeln!(f, "{}", additional.fragment(*source).source_context())
+            }
+            MacroDefinitionError::DuplicateName {
+                source,
+                name,
+                previous_source,
+                previous,
+            } => {
+                writeln!(f, "macro is defined more than once:")?;
+                writeln!(f, "{}", name.fragment(*source).source_context())?;
+                writeln!(f, "previously defined here:")?;
+                writeln!(
+                    f,
+                    "{}",
+                    previous.fragment(*previous_source).source_context()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse::Parser, source::SourceSet};
+
+    use super::*;
+
+    #[test]
+    fn extract_macro() {
+        let source_set = SourceSet::new_debug(
+            "(defmacro swap-args (f a b &body more) \"Doc.\" (list f b a))",
+        );
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let definition = MacroDefinition::extract(source, &ast.root_nodes()[0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(definition.name_str(), "swap-args");
+        assert_eq!(definition.required_params().len(), 3);
+        assert_eq!(
+            definition.rest_param().unwrap().fragment(source).source(),
+            "more"
+        );
+        assert_eq!(definition.body().len(), 1);
+    }
+
+    #[test]
+    fn extract_optional_params() {
+        let source_set =
+            SourceSet::new_debug("(defmacro m (a &optional b (c 'd)) a)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let definition = MacroDefinition::extract(source, &ast.root_nodes()[0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(definition.required_params().len(), 1);
+        let defaults = definition
+            .optional_params()
+            .iter()
+            .map(|(_, default)| default.map(|d| d.fragment(source).source()))
+            .collect::<Vec<_>>();
+        assert_eq!(defaults, [None, Some("'d")]);
+    }
+
+    #[test]
+    fn rest_needs_name() {
+        let source_set = SourceSet::new_debug("(defmacro m (a &rest) a)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        assert!(matches!(
+            MacroDefinition::extract(source, &ast.root_nodes()[0]),
+            Err(MacroDefinitionError::RestMissingName { .. })
+        ));
+    }
+}
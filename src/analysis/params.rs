@@ -0,0 +1,539 @@
+use std::fmt;
+
+use crate::parse::{AstNode, Atom, List, TokenKind};
+use crate::source::Source;
+
+use super::expand::Macros;
+use super::form::{Form, FormError};
+
+/// Parameters of a function or lambda, in the order the arguments are
+/// consumed: required parameters, then after `&optional`, `&rest`, `&key`
+/// and `&allow-other-keys`.
+pub struct Parameters<'s, 't> {
+    required: Vec<&'t Atom<'s>>,
+    optional: Vec<OptionalParameter<'s, 't>>,
+    rest: Option<&'t Atom<'s>>,
+    /// Present if `&key` was given, even without any keys after it.
+    key: Option<KeyParameters<'s, 't>>,
+}
+
+/// An optional positional or keyword parameter like `x`, `(x 1)` or
+/// `(x 1 x-supplied-p)`.
+pub struct OptionalParameter<'s, 't> {
+    name: &'t Atom<'s>,
+    /// Evaluated if the argument is missing, otherwise it is nil.
+    default: Option<Form<'s, 't>>,
+    /// Bound to t if the argument was passed, otherwise nil.
+    supplied: Option<&'t Atom<'s>>,
+}
+
+pub struct KeyParameters<'s, 't> {
+    /// Each is passed with a keyword with the same name, e.g. `:x` for `x`.
+    params: Vec<OptionalParameter<'s, 't>>,
+    /// Unknown keywords are an error unless this is set or
+    /// `:allow-other-keys` is passed with a value that is not nil.
+    allow_other_keys: bool,
+}
+
+/// A lambda list of `destructuring-bind` like `(a (b c) &optional d &rest e)`,
+/// which is matched against list structure instead of arguments.
+pub struct DestructuringList<'s, 't> {
+    node: &'t AstNode<'s>,
+    required: Vec<Pattern<'s, 't>>,
+    optional: Vec<OptionalParameter<'s, 't>>,
+    /// Parameter after `&rest` or `&body`.
+    rest: Option<&'t Atom<'s>>,
+}
+
+/// A required element of a destructuring lambda list, which is either bound
+/// to a name or matched against a nested lambda list.
+pub enum Pattern<'s, 't> {
+    Name(&'t Atom<'s>),
+    List(DestructuringList<'s, 't>),
+}
+
+/// The number of arguments that parameters accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    min: usize,
+    /// None if any number of additional arguments is accepted.
+    max: Option<usize>,
+}
+
+/// Parts of a parameter list started by lambda list keywords, in the order
+/// they have to appear in.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Section {
+    Required,
+    Optional,
+    Rest,
+    Key,
+    AllowOtherKeys,
+}
+
+impl<'s, 't> Parameters<'s, 't> {
+    pub fn extract(
+        source: Source<'s>,
+        list: &'t List<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<Parameters<'s, 't>, FormError<'s, 't>> {
+        let mut parameters = Parameters {
+            required: vec![],
+            optional: vec![],
+            rest: None,
+            key: None,
+        };
+        let mut section = Section::Required;
+        let mut rest_keyword = None;
+        for node in list.elements() {
+            if let Some(atom) = node.atom()
+                && atom.fragment(source).source().starts_with('&')
+            {
+                let next = match atom.fragment(source).source() {
+                    "&optional" => Section::Optional,
+                    "&rest" => Section::Rest,
+                    "&key" => Section::Key,
+                    "&allow-other-keys" if section == Section::Key => {
+                        Section::AllowOtherKeys
+                    }
+                    _ => {
+                        return Err(FormError::MisplacedLambdaListKeyword {
+                            source,
+                            atom,
+                        });
+                    }
+                };
+                if next <= section {
+                    return Err(FormError::MisplacedLambdaListKeyword {
+                        source,
+                        atom,
+                    });
+                }
+                if section == Section::Rest && parameters.rest.is_none() {
+                    return Err(FormError::RestMissingName {
+                        source,
+                        rest: rest_keyword.unwrap(),
+                    });
+                }
+                match next {
+                    Section::Rest => rest_keyword = Some(atom),
+                    Section::Key => {
+                        parameters.key = Some(KeyParameters {
+                            params: vec![],
+                            allow_other_keys: false,
+                        })
+                    }
+                    Section::AllowOtherKeys => {
+                        parameters.key.as_mut().unwrap().allow_other_keys = true
+                    }
+                    _ => {}
+                }
+                section = next;
+                continue;
+            }
+
+            match section {
+                Section::Required => {
+                    parameters.required.push(extract_name(source, node)?)
+                }
+                Section::Optional => parameters
+                    .optional
+                    .push(OptionalParameter::extract(source, node, macros)?),
+                Section::Rest if parameters.rest.is_none() => {
+                    parameters.rest = Some(extract_name(source, node)?)
+                }
+                Section::Rest => {
+                    return Err(FormError::RestAdditionalName {
+                        source,
+                        additional: node,
+                    });
+                }
+                Section::Key => parameters
+                    .key
+                    .as_mut()
+                    .unwrap()
+                    .params
+                    .push(OptionalParameter::extract(source, node, macros)?),
+                Section::AllowOtherKeys => {
+                    return Err(FormError::MalformedParameter { source, node });
+                }
+            }
+        }
+        if let Some(rest) = rest_keyword
+            && parameters.rest.is_none()
+        {
+            return Err(FormError::RestMissingName { source, rest });
+        }
+        Ok(parameters)
+    }
+
+    pub fn required(&self) -> &[&'t Atom<'s>] {
+        &self.required
+    }
+
+    pub fn optional(&self) -> &[OptionalParameter<'s, 't>] {
+        &self.optional
+    }
+
+    pub fn rest(&self) -> Option<&'t Atom<'s>> {
+        self.rest
+    }
+
+    pub fn key(&self) -> Option<&KeyParameters<'s, 't>> {
+        self.key.as_ref()
+    }
+
+    /// Keys are passed after the positional arguments and can be repeated,
+    /// so like a rest parameter, they allow any number of arguments.
+    pub fn arity(&self) -> Arity {
+        let min = self.required.len();
+        let max = match (self.rest, &self.key) {
+            (None, None) => Some(min + self.optional.len()),
+            _ => None,
+        };
+        Arity { min, max }
+    }
+
+    /// Forms that are evaluated when arguments are missing.
+    pub fn defaults(&self) -> impl Iterator<Item = &Form<'s, 't>> {
+        let keys = self.key.iter().flat_map(|key| key.params.iter());
+        self.optional
+            .iter()
+            .chain(keys)
+            .filter_map(|param| param.default.as_ref())
+    }
+}
+
+impl<'s, 't> DestructuringList<'s, 't> {
+    /// Like parameters, but without keys and with nested lambda lists in
+    /// place of required parameters.
+    pub fn extract(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<DestructuringList<'s, 't>, FormError<'s, 't>> {
+        let list = node
+            .list()
+            .ok_or(FormError::MalformedParameter { source, node })?;
+        let mut destructuring = DestructuringList {
+            node,
+            required: vec![],
+            optional: vec![],
+            rest: None,
+        };
+        let mut section = Section::Required;
+        let mut rest_keyword = None;
+        for node in list.elements() {
+            if let Some(atom) = node.atom()
+                && atom.fragment(source).source().starts_with('&')
+            {
+                let next = match atom.fragment(source).source() {
+                    "&optional" => Section::Optional,
+                    "&rest" | "&body" => Section::Rest,
+                    _ => {
+                        return Err(FormError::MisplacedLambdaListKeyword {
+                            source,
+                            atom,
+                        });
+                    }
+                };
+                if next <= section {
+                    return Err(FormError::MisplacedLambdaListKeyword {
+                        source,
+                        atom,
+                    });
+                }
+                if next == Section::Rest {
+                    rest_keyword = Some(atom);
+                }
+                section = next;
+                continue;
+            }
+
+            match section {
+                Section::Required if node.list().is_some() => destructuring
+                    .required
+                    .push(Pattern::List(Self::extract(source, node, macros)?)),
+                Section::Required => destructuring
+                    .required
+                    .push(Pattern::Name(extract_name(source, node)?)),
+                Section::Optional => destructuring
+                    .optional
+                    .push(OptionalParameter::extract(source, node, macros)?),
+                Section::Rest if destructuring.rest.is_none() => {
+                    destructuring.rest = Some(extract_name(source, node)?)
+                }
+                _ => {
+                    return Err(FormError::RestAdditionalName {
+                        source,
+                        additional: node,
+                    });
+                }
+            }
+        }
+        if let Some(rest) = rest_keyword
+            && destructuring.rest.is_none()
+        {
+            return Err(FormError::RestMissingName { source, rest });
+        }
+        Ok(destructuring)
+    }
+
+    /// The whole lambda list, for error messages.
+    pub fn node(&self) -> &'t AstNode<'s> {
+        self.node
+    }
+
+    pub fn required(&self) -> &[Pattern<'s, 't>] {
+        &self.required
+    }
+
+    pub fn optional(&self) -> &[OptionalParameter<'s, 't>] {
+        &self.optional
+    }
+
+    pub fn rest(&self) -> Option<&'t Atom<'s>> {
+        self.rest
+    }
+
+    /// Forms that are evaluated when elements are missing, including those of
+    /// nested lambda lists.
+    pub fn defaults(&self) -> Box<dyn Iterator<Item = &Form<'s, 't>> + '_> {
+        let nested = self.required.iter().flat_map(|pattern| match pattern {
+            Pattern::Name(_) => Box::new(std::iter::empty()),
+            Pattern::List(list) => list.defaults(),
+        });
+        let own = self
+            .optional
+            .iter()
+            .filter_map(|param| param.default.as_ref());
+        Box::new(nested.chain(own))
+    }
+}
+
+impl<'s, 't> OptionalParameter<'s, 't> {
+    fn extract(
+        source: Source<'s>,
+        node: &'t AstNode<'s>,
+        macros: &Macros<'s, 't>,
+    ) -> Result<OptionalParameter<'s, 't>, FormError<'s, 't>> {
+        let Some(list) = node.list() else {
+            return Ok(OptionalParameter {
+                name: extract_name(source, node)?,
+                default: None,
+                supplied: None,
+            });
+        };
+        match list.elements() {
+            [name, rest @ ..] if rest.len() <= 2 => Ok(OptionalParameter {
+                name: extract_name(source, name)?,
+                default: rest
+                    .first()
+                    .map(|default| Form::extract(source, default, macros))
+                    .transpose()?,
+                supplied: rest
+                    .get(1)
+                    .map(|supplied| extract_name(source, supplied))
+                    .transpose()?,
+            }),
+            _ => Err(FormError::MalformedParameter { source, node }),
+        }
+    }
+
+    pub fn name(&self) -> &'t Atom<'s> {
+        self.name
+    }
+
+    pub fn default(&self) -> Option<&Form<'s, 't>> {
+        self.default.as_ref()
+    }
+
+    pub fn supplied(&self) -> Option<&'t Atom<'s>> {
+        self.supplied
+    }
+}
+
+impl<'s, 't> KeyParameters<'s, 't> {
+    pub fn params(&self) -> &[OptionalParameter<'s, 't>] {
+        &self.params
+    }
+
+    pub fn allow_other_keys(&self) -> bool {
+        self.allow_other_keys
+    }
+}
+
+impl Arity {
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+
+    /// True if any number of arguments is accepted, so nothing needs to be
+    /// checked.
+    pub fn is_any(&self) -> bool {
+        self.min == 0 && self.max.is_none()
+    }
+}
+
+/// Reads like "expects 1 to 2 arguments".
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}")?,
+            Some(max) => write!(f, "{} to {max}", self.min)?,
+            None => write!(f, "at least {}", self.min)?,
+        }
+        let last = self.max.unwrap_or(self.min);
+        write!(f, " argument{}", if last == 1 { "" } else { "s" })
+    }
+}
+
+/// Parameters are named by identifiers that are neither keywords nor lambda
+/// list keywords.
+pub fn extract_name<'s, 't>(
+    source: Source<'s>,
+    node: &'t AstNode<'s>,
+) -> Result<&'t Atom<'s>, FormError<'s, 't>> {
+    match node.atom() {
+        Some(atom)
+            if matches!(atom.token().kind(), TokenKind::Ident)
+                && !atom.fragment(source).source().starts_with([':', '&']) =>
+        {
+            Ok(atom)
+        }
+        _ => Err(FormError::MalformedParameter { source, node }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse::Parser, source::SourceSet};
+
+    use super::*;
+
+    fn extract<'s, 't>(
+        source: Source<'s>,
+        ast: &'t AstNode<'s>,
+    ) -> Result<Parameters<'s, 't>, FormError<'s, 't>> {
+        Parameters::extract(source, ast.list().unwrap(), &Macros::new())
+    }
+
+    #[test]
+    fn extract_all_sections() {
+        let source_set = SourceSet::new_debug(
+            "(a &optional b (c 1) (d 2 d-p) &rest more \
+             &key e (f 3 f-p) &allow-other-keys)",
+        );
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let params = extract(source, &ast.root_nodes()[0]).unwrap();
+        assert_eq!(params.required().len(), 1);
+        let optional = params.optional();
+        assert_eq!(optional.len(), 3);
+        assert!(optional[0].default().is_none());
+        assert!(optional[1].default().is_some());
+        assert_eq!(
+            optional[2].supplied().unwrap().fragment(source).source(),
+            "d-p"
+        );
+        assert_eq!(params.rest().unwrap().fragment(source).source(), "more");
+        let key = params.key().unwrap();
+        assert_eq!(key.params().len(), 2);
+        assert!(key.allow_other_keys());
+        assert_eq!(params.defaults().count(), 3);
+        assert_eq!(params.arity().to_string(), "at least 1 argument");
+    }
+
+    #[test]
+    fn arity() {
+        for (params, expected, accepted, rejected) in [
+            ("()", "0 arguments", 0, Some(1)),
+            ("(a)", "1 argument", 1, Some(2)),
+            ("(a &optional b c)", "1 to 3 arguments", 3, Some(0)),
+            ("(a b &rest c)", "at least 2 arguments", 7, Some(1)),
+            ("(&key a)", "at least 0 arguments", 4, None),
+        ] {
+            let source_set = SourceSet::new_debug(params);
+            let source = source_set.one();
+            let ast = Parser::new(source).parse().unwrap();
+            let arity = extract(source, &ast.root_nodes()[0]).unwrap().arity();
+            assert_eq!(arity.to_string(), expected);
+            assert!(arity.accepts(accepted), "{params}");
+            assert_eq!(arity.is_any(), rejected.is_none());
+            if let Some(rejected) = rejected {
+                assert!(!arity.accepts(rejected), "{params}");
+            }
+        }
+    }
+
+    #[test]
+    fn extract_destructuring_list() {
+        let source_set =
+            SourceSet::new_debug("(a (b (c)) &optional (d 1) &body e)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        let list = DestructuringList::extract(
+            source,
+            &ast.root_nodes()[0],
+            &Macros::new(),
+        )
+        .unwrap();
+        let required = list.required();
+        assert!(matches!(required[0], Pattern::Name(_)));
+        let Pattern::List(nested) = &required[1] else {
+            panic!("expected a nested lambda list");
+        };
+        assert!(matches!(nested.required()[1], Pattern::List(_)));
+        assert_eq!(list.optional().len(), 1);
+        assert_eq!(list.rest().unwrap().fragment(source).source(), "e");
+
+        let source_set = SourceSet::new_debug("(a &key b)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        assert!(matches!(
+            DestructuringList::extract(
+                source,
+                &ast.root_nodes()[0],
+                &Macros::new()
+            ),
+            Err(FormError::MisplacedLambdaListKeyword { .. })
+        ));
+    }
+
+    #[test]
+    fn sections_must_be_in_order() {
+        for params in [
+            "(&rest a &optional b)",
+            "(&key a &key b)",
+            "(a &allow-other-keys)",
+            "(&body a)",
+        ] {
+            let source_set = SourceSet::new_debug(params);
+            let source = source_set.one();
+            let ast = Parser::new(source).parse().unwrap();
+            assert!(
+                matches!(
+                    extract(source, &ast.root_nodes()[0]),
+                    Err(FormError::MisplacedLambdaListKeyword { .. })
+                ),
+                "{params}"
+            );
+        }
+
+        let source_set = SourceSet::new_debug("(a &rest &key b)");
+        let source = source_set.one();
+        let ast = Parser::new(source).parse().unwrap();
+        assert!(matches!(
+            extract(source, &ast.root_nodes()[0]),
+            Err(FormError::RestMissingName { .. })
+        ));
+    }
+}
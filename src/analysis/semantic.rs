@@ -1,6 +1,9 @@
 use crate::{diagnostic::Diagnostics, parse::AstSet, source::Source};
 
-use super::{FunctionDefinition, GlobalDefinition, form::Form};
+use super::{
+    FunctionDefinition, GlobalDefinition, expand::Macros, form::Form,
+    macrodef::MacroDefinition,
+};
 
 pub struct SemanticAnalysis<'s, 't> {
     // REVIEW could it be a problem that function definitions and root code are not ordered with respect to each other?
@@ -27,14 +30,51 @@ impl<'s, 't> SemanticAnalysis<'s, 't> {
         let mut function_definitions = vec![];
         let mut global_definitions = vec![];
 
+        // macros are collected first so that they can be used anywhere,
+        // including in other macro definitions and earlier source files
+        let mut macros = Macros::new();
+        for ast in asts.iter() {
+            for root_node in ast.iter() {
+                match MacroDefinition::extract(ast.source(), root_node) {
+                    Ok(Some(def)) => {
+                        diagnostics.report_if_err(macros.define(def))
+                    }
+                    Ok(None) => {}
+                    Err(ref error) => diagnostics.report(error),
+                }
+            }
+        }
+
         for ast in asts.iter() {
             let mut root_code = vec![];
             for root_node in ast.iter() {
+                if MacroDefinition::is_definition(ast.source(), root_node) {
+                    continue;
+                }
+
+                // macros may expand into definitions
+                let (source, node) =
+                    match macros.expand_fully(ast.source(), root_node) {
+                        Ok(expansion) => expansion,
+                        Err(ref error) => {
+                            diagnostics.report(error);
+                            continue;
+                        }
+                    };
+
                 // try parsing root-level element as a function first
-                let def = FunctionDefinition::extract(ast.source(), root_node);
+                let def = FunctionDefinition::extract(source, node, &macros);
                 match def {
                     Ok(Some(def)) => {
                         function_definitions.push(def);
+                        // macro calls after the definition can call it at
+                        // compile time, unless it has parameters that
+                        // macros do not support
+                        if let Ok(Some(def)) =
+                            MacroDefinition::extract_function(source, node)
+                        {
+                            macros.define_function(def);
+                        }
                         continue;
                     }
                     Ok(None) => {}
@@ -46,7 +86,7 @@ impl<'s, 't> SemanticAnalysis<'s, 't> {
                 }
 
                 // then as a global
-                let def = GlobalDefinition::extract(ast.source(), root_node);
+                let def = GlobalDefinition::extract(source, node, &macros);
                 match def {
                     Ok(Some(def)) => {
                         global_definitions.push(def);
@@ -59,10 +99,14 @@ impl<'s, 't> SemanticAnalysis<'s, 't> {
                     }
                 }
 
-                // all other cases are considered to be top-level code
-                if let Some(next_root) =
-                    diagnostics.ok(Form::extract(ast.source(), root_node))
-                {
+                // all other cases are considered to be top-level code, which
+                // is extracted from the macro call again so that it keeps
+                // the source it was written in
+                if let Some(next_root) = diagnostics.ok(Form::extract(
+                    ast.source(),
+                    root_node,
+                    &macros,
+                )) {
                     root_code.push(next_root);
                 }
             }
@@ -25,6 +25,19 @@ pub fn decode_string(raw: &str) -> Cow<str> {
     }
 }
 
+/// Decodes a character literal like `#\a` or `#\Space`, with names that are
+/// not case-sensitive, or returns none for an unknown name.
+pub fn decode_character(raw: &str) -> Option<char> {
+    let name = raw.strip_prefix("#\\")?;
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => Some(character),
+        _ if name.eq_ignore_ascii_case("space") => Some(' '),
+        _ if name.eq_ignore_ascii_case("newline") => Some('\n'),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -35,6 +48,15 @@ mod test {
         assert_eq!(decoded.as_ref(), "\"Hey\" she said");
     }
 
+    #[test]
+    fn characters() {
+        assert_eq!(decode_character("#\\a"), Some('a'));
+        assert_eq!(decode_character("#\\("), Some('('));
+        assert_eq!(decode_character("#\\Space"), Some(' '));
+        assert_eq!(decode_character("#\\NEWLINE"), Some('\n'));
+        assert_eq!(decode_character("#\\Tapir"), None);
+    }
+
     #[test]
     fn plain_string_borrowed() {
         let decoded = decode_string("\"tapirs\"");
@@ -1,12 +1,19 @@
 use std::path::{Path, PathBuf};
 
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::codegen::{
+    DEFAULT_INITIAL_PAGES, DEFAULT_MAX_PAGES, DEFAULT_STACK_SIZE,
+    FeatureOptions, MemoryOptions,
+};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 #[command(name = "proboscis")]
 #[command(about = "Haphazard experimental LISP compiler", long_about = None)]
 pub struct TopLevelArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// files to compile or check
     files: Vec<PathBuf>,
     /// output file, if omitted write to stdout
@@ -14,12 +21,65 @@ pub struct TopLevelArgs {
     output: Option<PathBuf>,
     #[arg(short, long, value_enum, default_value_t)]
     format: OutputFormat,
+    #[command(flatten)]
+    memory: MemoryArgs,
+    /// use ordinary calls instead of tail calls, for web assembly engines
+    /// without support for the tail call proposal
+    #[arg(long)]
+    no_tail_calls: bool,
+    /// unwind throws with the exception handling proposal instead of checking
+    /// for them after every call
+    #[arg(long)]
+    exceptions: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Compile and immediately execute with the built-in interpreter
+    Run(RunArgs),
+    /// Run test programs and compare their output with .expected files next
+    /// to them
+    Test(TestArgs),
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// files to run, the root code of all files is executed in order
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    memory: MemoryArgs,
+}
+
+#[derive(Args)]
+pub struct MemoryArgs {
+    /// pages of 64KiB that memory initially has
+    #[arg(long, value_name = "PAGES", default_value_t = DEFAULT_INITIAL_PAGES)]
+    initial_pages: u32,
+    /// pages of 64KiB that memory can grow to when the heap is full
+    #[arg(long, value_name = "PAGES", default_value_t = DEFAULT_MAX_PAGES)]
+    max_pages: u32,
+    /// bytes of stack space, rounded up to whole words
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_STACK_SIZE)]
+    stack_size: u32,
+}
+
+#[derive(Args)]
+pub struct TestArgs {
+    /// test programs or directories containing them
+    #[arg(default_value = "test/lisp")]
+    paths: Vec<PathBuf>,
+    /// overwrite the expected output with the actual output
+    #[arg(long)]
+    bless: bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum OutputFormat {
     /// web assembly text
     Wat,
+    /// binary web assembly module
+    Wasm,
     /// raw AST for debugging
     Ast,
     /// Intermediate representation generated by the frontend, also for
@@ -34,6 +94,10 @@ impl Default for OutputFormat {
 }
 
 impl TopLevelArgs {
+    pub fn command(&self) -> Option<&Command> {
+        self.command.as_ref()
+    }
+
     pub fn files(&self) -> &[PathBuf] {
         &self.files
     }
@@ -45,4 +109,38 @@ impl TopLevelArgs {
     pub fn output_format(&self) -> OutputFormat {
         self.format
     }
+
+    pub fn memory_options(&self) -> MemoryOptions {
+        self.memory.memory_options()
+    }
+
+    pub fn feature_options(&self) -> FeatureOptions {
+        FeatureOptions::new(!self.no_tail_calls, self.exceptions)
+    }
+}
+
+impl RunArgs {
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn memory_options(&self) -> MemoryOptions {
+        self.memory.memory_options()
+    }
+}
+
+impl MemoryArgs {
+    fn memory_options(&self) -> MemoryOptions {
+        MemoryOptions::new(self.initial_pages, self.max_pages, self.stack_size)
+    }
+}
+
+impl TestArgs {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn bless(&self) -> bool {
+        self.bless
+    }
 }
@@ -1,4 +1,8 @@
 mod compile;
 mod err;
+mod golden;
+mod run;
 
 pub use compile::compile;
+pub use golden::test;
+pub use run::run;
@@ -7,7 +7,7 @@ use std::{
 use crate::{
     analysis::{IrGen, SemanticAnalysis},
     args::{OutputFormat, TopLevelArgs},
-    codegen::{write_pirt, write_wat},
+    codegen::{MemoryLayout, write_pirt, write_wasm, write_wat},
     diagnostic::Diagnostics,
     ir::Program,
     parse::{AstSet, Parser},
@@ -49,12 +49,19 @@ pub fn compile(args: &TopLevelArgs) -> CommandResult<()> {
         return Ok(());
     }
 
+    let layout = MemoryLayout::new(&program, &args.memory_options())?;
+
+    if let OutputFormat::Wasm = format {
+        write_wasm_out(args, &program, &layout)?;
+        return Ok(());
+    }
+
     assert!(matches!(format, OutputFormat::Wat)); // last option
-    write_wat_out(args, &program)?;
+    write_wat_out(args, &program, &layout)?;
     Ok(())
 }
 
-fn load_sources(
+pub(super) fn load_sources(
     diagnostics: &mut Diagnostics,
     user_files: &[PathBuf],
 ) -> CommandResult<SourceSet> {
@@ -81,7 +88,7 @@ fn load_sources(
     Ok(sources)
 }
 
-fn parse<'d, 's>(
+pub(super) fn parse<'d, 's>(
     diagnostics: &'d mut Diagnostics,
     source: &'s SourceSet,
 ) -> AstSet<'s> {
@@ -128,15 +135,39 @@ fn write_pirt_out(args: &TopLevelArgs, ir: &Program) -> io::Result<()> {
     Ok(())
 }
 
-fn write_wat_out(args: &TopLevelArgs, program: &Program) -> io::Result<()> {
+fn write_wat_out(
+    args: &TopLevelArgs,
+    program: &Program,
+    layout: &MemoryLayout,
+) -> io::Result<()> {
+    let features = args.feature_options();
+    match args.output_path() {
+        Some(path) => {
+            let mut file = File::create(path)?;
+            write_wat(&mut file, program, layout, &features)?;
+        }
+        None => {
+            let mut stdout = stdout().lock();
+            write_wat(&mut stdout, program, layout, &features)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_wasm_out(
+    args: &TopLevelArgs,
+    program: &Program,
+    layout: &MemoryLayout,
+) -> io::Result<()> {
+    let features = args.feature_options();
     match args.output_path() {
         Some(path) => {
             let mut file = File::create(path)?;
-            write_wat(&mut file, program)?;
+            write_wasm(&mut file, program, layout, &features)?;
         }
         None => {
             let mut stdout = stdout().lock();
-            write_wat(&mut stdout, program)?;
+            write_wasm(&mut stdout, program, layout, &features)?;
         }
     }
     Ok(())
@@ -2,11 +2,15 @@ use std::fmt::Display;
 
 use crate::{
     analysis::{FunctionDefinitionError, GlobalDefinitionError, IrGenError},
+    codegen::LayoutError,
     diagnostic::DiagnosticError,
+    interp::RuntimeError,
     parse::ParserError,
     source::SourceError,
 };
 
+use super::golden::TestFailures;
+
 pub type CommandResult<T> = Result<T, CommandError>;
 
 /// Top level for presentation to users, without borrowed data.
@@ -75,3 +79,27 @@ impl From<DiagnosticError> for CommandError {
         }
     }
 }
+
+impl From<RuntimeError> for CommandError {
+    fn from(value: RuntimeError) -> Self {
+        CommandError {
+            msg: value.to_string(),
+        }
+    }
+}
+
+impl From<TestFailures> for CommandError {
+    fn from(value: TestFailures) -> Self {
+        CommandError {
+            msg: value.to_string(),
+        }
+    }
+}
+
+impl From<LayoutError> for CommandError {
+    fn from(value: LayoutError) -> Self {
+        CommandError {
+            msg: value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    fmt, fs,
+    io::{self, Write, stdout},
+    path::{Path, PathBuf},
+};
+
+use crate::{args::TestArgs, codegen::MemoryOptions};
+
+use super::{err::CommandResult, run::run_files};
+
+/// Extension of the files with the expected output, placed next to the
+/// test programs.
+const EXPECTED_EXTENSION: &str = "expected";
+
+/// Starts the last line of the expected output if the program is expected
+/// to return normally, followed by the returned value.
+const RETURN_VALUE_PREFIX: &str = "=> ";
+
+/// Starts the last line of the expected output if the program is expected
+/// to stop with a runtime error like a panic, followed by the error.
+const RUNTIME_ERROR_PREFIX: &str = "!! ";
+
+pub fn test(args: &TestArgs) -> CommandResult<()> {
+    let mut stdout = stdout().lock();
+    let summary = run_golden_tests(args.paths(), args.bless(), &mut stdout)?;
+    if summary.failed > 0 {
+        Err(summary.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Some of the test programs did not behave as expected.
+pub struct TestFailures {
+    failed: usize,
+    total: usize,
+}
+
+impl fmt::Display for TestFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} of {} tests failed", self.failed, self.total)
+    }
+}
+
+/// Compiles and runs every test program and compares the results to the
+/// expected output, reporting to the given writer.
+///
+/// If bless is set, the expected output is overwritten with the actual
+/// output instead.
+fn run_golden_tests<W: Write>(
+    paths: &[PathBuf],
+    bless: bool,
+    report: &mut W,
+) -> CommandResult<TestFailures> {
+    let programs = collect_test_programs(paths)?;
+    let mut failed = 0;
+    for program in &programs {
+        write!(report, "test {} ... ", program.display())?;
+        if !run_golden_test(program, bless, report)? {
+            failed += 1;
+        }
+    }
+    writeln!(
+        report,
+        "\ntest result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        programs.len() - failed,
+        failed
+    )?;
+    Ok(TestFailures {
+        failed,
+        total: programs.len(),
+    })
+}
+
+/// Finds the LISP files in the given directories, and takes other paths as
+/// they are.
+fn collect_test_programs(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut programs = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let mut in_dir = vec![];
+            for entry in fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                let is_lisp =
+                    entry_path.extension().is_some_and(|ext| ext == "lisp");
+                if entry_path.is_file() && is_lisp {
+                    in_dir.push(entry_path);
+                }
+            }
+            in_dir.sort();
+            programs.extend(in_dir);
+        } else {
+            programs.push(path.clone());
+        }
+    }
+    Ok(programs)
+}
+
+/// Runs a single test and reports the result, returning whether it passed.
+fn run_golden_test<W: Write>(
+    program: &Path,
+    bless: bool,
+    report: &mut W,
+) -> CommandResult<bool> {
+    let mut output = vec![];
+    let outcome = match run_files(
+        &[program.to_path_buf()],
+        &MemoryOptions::default(),
+        &mut output,
+    ) {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            writeln!(report, "FAILED")?;
+            write!(report, "failed to compile: {}", err)?;
+            return Ok(false);
+        }
+    };
+
+    let mut actual = String::from_utf8_lossy(&output).into_owned();
+    match outcome {
+        Ok(value) => {
+            actual.push_str(RETURN_VALUE_PREFIX);
+            actual.push_str(&value);
+        }
+        Err(err) => {
+            actual.push_str(RUNTIME_ERROR_PREFIX);
+            actual.push_str(err.to_string().trim_end());
+        }
+    }
+    actual.push('\n');
+
+    let expected_path = program.with_extension(EXPECTED_EXTENSION);
+    if bless {
+        fs::write(&expected_path, &actual)?;
+        writeln!(report, "blessed")?;
+        return Ok(true);
+    }
+
+    let expected = match fs::read_to_string(&expected_path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            writeln!(report, "FAILED")?;
+            writeln!(
+                report,
+                "could not read {}: {}",
+                expected_path.display(),
+                err
+            )?;
+            return Ok(false);
+        }
+    };
+
+    if expected == actual {
+        writeln!(report, "ok")?;
+        Ok(true)
+    } else {
+        writeln!(report, "FAILED")?;
+        writeln!(report, "--- {}", expected_path.display())?;
+        writeln!(report, "+++ actual")?;
+        write_diff(report, &expected, &actual)?;
+        Ok(false)
+    }
+}
+
+/// Writes a line diff, prefixing lines that are only expected with `-` and
+/// lines that only occur in the actual output with `+`.
+fn write_diff<W: Write>(
+    w: &mut W,
+    expected: &str,
+    actual: &str,
+) -> io::Result<()> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // lengths of the longest common subsequences of the suffixes
+    let mut common = vec![vec![0_usize; actual.len() + 1]; expected.len() + 1];
+    for e in (0..expected.len()).rev() {
+        for a in (0..actual.len()).rev() {
+            common[e][a] = if expected[e] == actual[a] {
+                common[e + 1][a + 1] + 1
+            } else {
+                common[e + 1][a].max(common[e][a + 1])
+            };
+        }
+    }
+
+    let (mut e, mut a) = (0, 0);
+    while e < expected.len() || a < actual.len() {
+        if e < expected.len() && a < actual.len() && expected[e] == actual[a] {
+            writeln!(w, " {}", expected[e])?;
+            e += 1;
+            a += 1;
+        } else if a < actual.len()
+            && (e == expected.len() || common[e][a + 1] >= common[e + 1][a])
+        {
+            writeln!(w, "+{}", actual[a])?;
+            a += 1;
+        } else {
+            writeln!(w, "-{}", expected[e])?;
+            e += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn diff(expected: &str, actual: &str) -> String {
+        let mut out = vec![];
+        write_diff(&mut out, expected, actual).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn diff_changed_line() {
+        assert_eq!(diff("a\nb\nc\n", "a\nx\nc\n"), " a\n+x\n-b\n c\n");
+    }
+
+    #[test]
+    fn diff_added_and_removed_lines() {
+        assert_eq!(diff("a\nb\n", "a\nb\nc\n"), " a\n b\n+c\n");
+        assert_eq!(diff("a\nb\nc\n", "b\nc\n"), "-a\n b\n c\n");
+    }
+
+    #[test]
+    fn test_lisp_programs_behave_as_expected() {
+        let mut report = vec![];
+        let failures =
+            run_golden_tests(&[PathBuf::from("test/lisp")], false, &mut report)
+                .unwrap_or_else(|err| panic!("{}", err));
+        assert!(failures.failed == 0, "{}", String::from_utf8_lossy(&report));
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+use crate::{
+    analysis::{IrGen, SemanticAnalysis},
+    args::RunArgs,
+    codegen::{MemoryLayout, MemoryOptions},
+    diagnostic::Diagnostics,
+    interp::{Interpreter, RuntimeError},
+};
+
+use super::{
+    compile::{load_sources, parse},
+    err::CommandResult,
+};
+
+pub fn run(args: &RunArgs) -> CommandResult<()> {
+    let mut stdout = stdout().lock();
+    let outcome = run_files(args.files(), &args.memory_options(), &mut stdout)?;
+    stdout.flush()?;
+    outcome?;
+    Ok(())
+}
+
+/// Compiles the files together with the runtime and interprets the result
+/// with the given memory limits, printing to out.
+///
+/// If the program compiles, returns either the value returned from the root
+/// code printed as LISP data, or the runtime error that stopped the program.
+pub(super) fn run_files<W: Write>(
+    files: &[PathBuf],
+    options: &MemoryOptions,
+    out: W,
+) -> CommandResult<Result<String, RuntimeError>> {
+    let mut diagnostics = Diagnostics::new();
+    let sources = load_sources(&mut diagnostics, files)?;
+    let asts = parse(&mut diagnostics, &sources);
+    diagnostics.ensure_no_errors()?;
+
+    let analysis = SemanticAnalysis::analyze(&mut diagnostics, &asts);
+    diagnostics.ensure_no_errors()?;
+
+    let program = IrGen::generate(&analysis)?;
+    let layout = MemoryLayout::new(&program, options)?;
+    let outcome =
+        Interpreter::new(&program, &layout, out).and_then(|mut interpreter| {
+            let value = interpreter.run_main()?;
+            Ok(interpreter.display_value(value).to_string())
+        });
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::codegen::{
+        DEFAULT_INITIAL_PAGES, DEFAULT_MAX_PAGES, DEFAULT_STACK_SIZE,
+    };
+
+    use std::{env, fs, slice};
+
+    use super::*;
+
+    fn run_test_program(
+        name: &str,
+        options: &MemoryOptions,
+    ) -> (String, Result<String, RuntimeError>) {
+        let mut out = vec![];
+        let path = PathBuf::from(format!("test/lisp/{}.lisp", name));
+        let result = run_files(&[path], options, &mut out)
+            .unwrap_or_else(|err| panic!("{}", err));
+        (String::from_utf8(out).unwrap(), result)
+    }
+
+    /// Runs a program that is written to a temporary file first.
+    fn run_test_source(
+        name: &str,
+        program: &str,
+    ) -> CommandResult<Result<String, RuntimeError>> {
+        let path = env::temp_dir().join(format!("proboscis-{}.lisp", name));
+        fs::write(&path, program).unwrap();
+        let result = run_files(
+            slice::from_ref(&path),
+            &MemoryOptions::default(),
+            vec![],
+        );
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn memory_grows_from_a_single_page() {
+        let options =
+            MemoryOptions::new(1, DEFAULT_MAX_PAGES, DEFAULT_STACK_SIZE);
+        let (out, result) = run_test_program("memory-growth", &options);
+        assert_eq!(out, "1024\n");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn out_of_memory_panics() {
+        let options =
+            MemoryOptions::new(DEFAULT_INITIAL_PAGES, 20, DEFAULT_STACK_SIZE);
+        let (out, result) = run_test_program("memory-growth", &options);
+        assert_eq!(out, "out of memory\n");
+        assert!(matches!(result, Err(RuntimeError::Unreachable)));
+    }
+
+    #[test]
+    fn stack_size_is_configurable() {
+        let small =
+            MemoryOptions::new(DEFAULT_INITIAL_PAGES, DEFAULT_MAX_PAGES, 64);
+        let (out, result) = run_test_program("garbage-collection", &small);
+        assert!(out.starts_with("stack overflow in "));
+        assert!(matches!(result, Err(RuntimeError::Unreachable)));
+
+        let large = MemoryOptions::new(
+            DEFAULT_INITIAL_PAGES,
+            DEFAULT_MAX_PAGES,
+            64 * 1024,
+        );
+        let (out, _) = run_test_program("stack-overflow", &large);
+        assert_eq!(out, "\"nesting\"\nstack overflow in nest\n");
+    }
+
+    #[test]
+    fn fixnums_used_as_addresses_are_runtime_errors() {
+        // the largest fixnum is stored as 0x7FFFFFFF, so offsets from it go
+        // past the largest address
+        let programs = [
+            "(dump (car 1073741823))",
+            "(cdr 1073741823)",
+            "(funcall 1073741823)",
+            "(dolist (x 1073741823) x)",
+            "(destructuring-bind (a) 1073741823 a)",
+        ];
+        for (idx, program) in programs.iter().enumerate() {
+            let name = format!("fixnum-address-{}", idx);
+            let result = run_test_source(&name, program)
+                .unwrap_or_else(|err| panic!("{}", err));
+            assert!(
+                matches!(result, Err(RuntimeError::OutOfBounds { .. })),
+                "{}",
+                program
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_argument_counts_are_compile_errors() {
+        let programs = [
+            ("call", "(defun pair (a b) (list a b)) (pair 1)", "pair"),
+            (
+                "funcall",
+                "(defun pair (a b) (list a b)) (funcall #'pair 1)",
+                "pair",
+            ),
+            ("flet", "(flet ((pair (a b) (list a b))) (pair 1))", "pair"),
+            (
+                "labels",
+                "(labels ((pair (a b) (list a b))) (funcall #'pair 1))",
+                "pair",
+            ),
+        ];
+        for (name, program, function) in programs {
+            let name = format!("argument-count-{}", name);
+            let Err(err) = run_test_source(&name, program) else {
+                panic!("{} compiled", program);
+            };
+            let expected =
+                format!("function {} expects 2 arguments, got 1", function);
+            assert!(err.to_string().contains(&expected), "{}", err);
+        }
+    }
+}
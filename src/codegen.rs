@@ -1,6 +1,14 @@
+mod layout;
 mod locals;
 mod pirt;
+mod wasm;
 mod wat;
 
+pub use layout::{
+    DEFAULT_INITIAL_PAGES, DEFAULT_MAX_PAGES, DEFAULT_STACK_SIZE, FREE_BIT, LayoutError, MARK_BIT,
+    MemoryLayout, MemoryOptions, PAGE_SIZE, PLACES_TAG, WORD_SIZE,
+};
+pub use locals::{LocalPlacesInfo, LocalStrategy};
 pub use pirt::write_pirt;
-pub use wat::write_wat;
+pub use wasm::write_wasm;
+pub use wat::{FeatureOptions, write_wat};
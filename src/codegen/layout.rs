@@ -0,0 +1,360 @@
+use std::fmt;
+
+use crate::ir::{IrDataType, Program};
+
+/// Size of a page of web assembly memory in bytes.
+pub const PAGE_SIZE: u32 = 64 * 1024;
+
+/// Default for the pages of memory that the host is expected to provide.
+pub const DEFAULT_INITIAL_PAGES: u32 = 10; // reserves 640KiB
+
+/// Default for the pages that memory may grow to when the heap is full.
+pub const DEFAULT_MAX_PAGES: u32 = 16 * 1024; // 1GiB
+
+/// Pages that 32-bit web assembly memory can have at most.
+const MAX_ADDRESSABLE_PAGES: u32 = 64 * 1024;
+
+/// Default for the bytes of stack space reserved before the heap.
+pub const DEFAULT_STACK_SIZE: u32 = 10 * 1024;
+
+/// Printed by the runtime before panicking because the heap is full and memory
+/// cannot grow any further.
+const OUT_OF_MEMORY_MESSAGE: &str = "out of memory";
+
+/// Printed by the runtime before panicking because a function needs more
+/// stack space than is left, followed by the function name.
+const STACK_OVERFLOW_MESSAGE: &str = "stack overflow in ";
+
+/// Printed by the runtime before panicking because nothing catches a throw,
+/// which is also the case for a return from a block that was already left.
+const UNCAUGHT_THROW_MESSAGE: &str =
+    "throw to a catch or block that is not active";
+
+/// Alignment of the stack and of all blocks on the heap.
+pub const WORD_SIZE: u32 = 4;
+
+/// Set in the type tag of heap objects that the garbage collector found to be
+/// reachable, and cleared again when sweeping.
+pub const MARK_BIT: u32 = 1 << 31;
+
+/// Set in the first word of free blocks on the heap, the other bits are the
+/// size of the free block in bytes, including the first word.
+///
+/// Free blocks of at least two words use the second word to point to the next
+/// free block, or zero for the last one.
+pub const FREE_BIT: u32 = 1 << 30;
+
+/// Type tag of a block of persistent places on the heap, followed by the
+/// length of the places in bytes and the places themselves.
+///
+/// Functions that create lambdas and lambdas themselves keep their places in
+/// these blocks, and the persistent bottom of a lambda points to the first
+/// place in the block of the function that created it. The first place of a
+/// lambda points to the start of that block.
+pub const PLACES_TAG: u32 = 1 << 29;
+
+/// Limits for memory that can be chosen by users.
+#[derive(Debug, Copy, Clone)]
+pub struct MemoryOptions {
+    initial_pages: u32,
+    max_pages: u32,
+    stack_size: u32,
+}
+
+impl MemoryOptions {
+    /// The stack size is in bytes and rounded up to whole words.
+    pub fn new(initial_pages: u32, max_pages: u32, stack_size: u32) -> Self {
+        Self {
+            initial_pages,
+            max_pages,
+            stack_size,
+        }
+    }
+}
+
+impl Default for MemoryOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_INITIAL_PAGES, DEFAULT_MAX_PAGES, DEFAULT_STACK_SIZE)
+    }
+}
+
+/// Memory options that cannot work for a program.
+#[derive(Debug)]
+pub enum LayoutError {
+    /// Static data and stack need to fit into the initial memory.
+    InitialPagesTooSmall {
+        initial_pages: u32,
+        required_pages: u32,
+    },
+    MaxPagesBelowInitial {
+        initial_pages: u32,
+        max_pages: u32,
+    },
+    MaxPagesTooLarge {
+        max_pages: u32,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::InitialPagesTooSmall {
+                initial_pages,
+                required_pages,
+            } => writeln!(
+                f,
+                "initial memory of {} pages is too small, static data and stack need {} pages",
+                initial_pages, required_pages
+            ),
+            LayoutError::MaxPagesBelowInitial {
+                initial_pages,
+                max_pages,
+            } => writeln!(
+                f,
+                "maximum memory of {} pages is less than the initial {} pages",
+                max_pages, initial_pages
+            ),
+            LayoutError::MaxPagesTooLarge { max_pages } => writeln!(
+                f,
+                "maximum memory of {} pages is more than the {} pages that web assembly can address",
+                max_pages, MAX_ADDRESSABLE_PAGES
+            ),
+        }
+    }
+}
+
+/// Where things are placed in linear memory.
+///
+/// Static data comes first, starting at address zero. It is followed by data
+/// for the runtime itself, like messages to print when panicking, then by the
+/// stack, which grows upwards by bumping the stack bottom, and then by the
+/// heap, which grows upwards too and is garbage collected. Memory grows when
+/// the heap is full, up to the maximum pages. Runtime data, stack and heap
+/// start at word-aligned addresses.
+#[derive(Debug, Clone)]
+pub struct MemoryLayout {
+    runtime_data_start: u32,
+    runtime_data: Vec<u8>,
+    out_of_memory_message: u32,
+    uncaught_throw_message: u32,
+    /// Message for each function, in the same order as in the program.
+    stack_overflow_messages: Vec<u32>,
+    stack_start: u32,
+    stack_end: u32,
+    initial_pages: u32,
+    max_pages: u32,
+}
+
+impl MemoryLayout {
+    pub fn new(
+        program: &Program,
+        options: &MemoryOptions,
+    ) -> Result<Self, LayoutError> {
+        let MemoryOptions {
+            initial_pages,
+            max_pages,
+            stack_size,
+        } = *options;
+
+        let runtime_data_start =
+            u32::try_from(program.static_data().data().len())
+                .unwrap()
+                .next_multiple_of(WORD_SIZE);
+        let mut runtime_data = vec![];
+        let out_of_memory_message =
+            append_string(&mut runtime_data, runtime_data_start, |buf| {
+                buf.extend_from_slice(OUT_OF_MEMORY_MESSAGE.as_bytes())
+            });
+        let uncaught_throw_message =
+            append_string(&mut runtime_data, runtime_data_start, |buf| {
+                buf.extend_from_slice(UNCAUGHT_THROW_MESSAGE.as_bytes())
+            });
+        let stack_overflow_messages = program
+            .functions()
+            .iter()
+            .map(|function| {
+                append_string(&mut runtime_data, runtime_data_start, |buf| {
+                    buf.extend_from_slice(STACK_OVERFLOW_MESSAGE.as_bytes());
+                    buf.extend_from_slice(function.name().as_bytes());
+                })
+            })
+            .collect();
+        let stack_start = runtime_data_start + runtime_data.len() as u32;
+        // in 64 bits so that huge stack sizes fail below instead of overflowing
+        let stack_end = stack_start as u64
+            + (stack_size as u64).next_multiple_of(WORD_SIZE as u64);
+
+        let required_pages = stack_end.div_ceil(PAGE_SIZE as u64);
+        if (initial_pages as u64) < required_pages {
+            return Err(LayoutError::InitialPagesTooSmall {
+                initial_pages,
+                required_pages: required_pages as u32,
+            });
+        }
+        if max_pages < initial_pages {
+            return Err(LayoutError::MaxPagesBelowInitial {
+                initial_pages,
+                max_pages,
+            });
+        }
+        if max_pages > MAX_ADDRESSABLE_PAGES {
+            return Err(LayoutError::MaxPagesTooLarge { max_pages });
+        }
+
+        Ok(Self {
+            runtime_data_start,
+            runtime_data,
+            out_of_memory_message,
+            uncaught_throw_message,
+            stack_overflow_messages,
+            stack_start,
+            stack_end: stack_end as u32,
+            initial_pages,
+            max_pages,
+        })
+    }
+
+    /// Address of the runtime data, which is the first aligned address after
+    /// the static data.
+    pub fn runtime_data_start(&self) -> u32 {
+        self.runtime_data_start
+    }
+
+    pub fn runtime_data(&self) -> &[u8] {
+        &self.runtime_data
+    }
+
+    /// Address of the string printed when running out of memory.
+    pub fn out_of_memory_message(&self) -> u32 {
+        self.out_of_memory_message
+    }
+
+    /// Address of the string printed when nothing catches a throw.
+    pub fn uncaught_throw_message(&self) -> u32 {
+        self.uncaught_throw_message
+    }
+
+    /// Address of the string printed when the function with the given index
+    /// overflows the stack.
+    pub fn stack_overflow_message(&self, function_idx: usize) -> u32 {
+        self.stack_overflow_messages[function_idx]
+    }
+
+    /// Initial value of the stack bottom, which is the first aligned address
+    /// after the runtime data.
+    pub fn stack_start(&self) -> u32 {
+        self.stack_start
+    }
+
+    /// First address after the stack space.
+    pub fn stack_end(&self) -> u32 {
+        self.stack_end
+    }
+
+    /// Initial start of the heap, right after the stack.
+    pub fn heap_start(&self) -> u32 {
+        self.stack_end
+    }
+
+    /// Pages of memory that the host is expected to provide.
+    pub fn initial_pages(&self) -> u32 {
+        self.initial_pages
+    }
+
+    /// Pages that memory may grow to at most.
+    pub fn max_pages(&self) -> u32 {
+        self.max_pages
+    }
+}
+
+/// Appends a string in the same layout as strings in static data, with the
+/// characters written by the given function, and pads it to whole words.
+///
+/// Returns the address of the string if the buffer is placed at the given
+/// start address.
+fn append_string(
+    buf: &mut Vec<u8>,
+    start: u32,
+    write_chars: impl FnOnce(&mut Vec<u8>),
+) -> u32 {
+    let address = start + buf.len() as u32;
+    buf.extend_from_slice(&IrDataType::CharacterData.to_u32().to_le_bytes());
+    let len_offset = buf.len();
+    buf.extend_from_slice(&[0; WORD_SIZE as usize]);
+    write_chars(buf);
+    let len = (buf.len() - len_offset) as u32 - WORD_SIZE;
+    buf[len_offset..len_offset + WORD_SIZE as usize]
+        .copy_from_slice(&len.to_le_bytes());
+    buf.resize(buf.len().next_multiple_of(WORD_SIZE as usize), 0);
+    address
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ir::StaticDataBuilder;
+
+    use super::*;
+
+    fn empty_program() -> Program {
+        Program::new(StaticDataBuilder::new().build(), vec![])
+    }
+
+    #[test]
+    fn runtime_data_and_stack_are_aligned() {
+        let mut builder = StaticDataBuilder::new();
+        builder.static_string("odd");
+        let program = Program::new(builder.build(), vec![]);
+        let options = MemoryOptions::new(10, 10, 1023);
+        let layout = MemoryLayout::new(&program, &options).unwrap();
+        let static_len = program.static_data().data().len();
+        assert_eq!(layout.runtime_data_start() % WORD_SIZE, 0);
+        assert!(layout.runtime_data_start() as usize >= static_len);
+        assert_eq!(layout.runtime_data().len() as u32 % WORD_SIZE, 0);
+        assert_eq!(layout.stack_start() % WORD_SIZE, 0);
+        assert_eq!(layout.heap_start(), layout.stack_start() + 1024);
+    }
+
+    #[test]
+    fn runtime_data_contains_strings() {
+        let layout =
+            MemoryLayout::new(&empty_program(), &MemoryOptions::default())
+                .unwrap();
+        let offset = (layout.out_of_memory_message()
+            - layout.runtime_data_start()) as usize;
+        let string = &layout.runtime_data()[offset..];
+        assert_eq!(
+            string[..4],
+            IrDataType::CharacterData.to_u32().to_le_bytes()
+        );
+        assert_eq!(string[4..8], 13_u32.to_le_bytes());
+        assert_eq!(&string[8..21], b"out of memory");
+    }
+
+    #[test]
+    fn invalid_page_counts() {
+        let program = empty_program();
+        let stack_size = DEFAULT_STACK_SIZE;
+        assert!(matches!(
+            MemoryLayout::new(&program, &MemoryOptions::new(0, 10, stack_size)),
+            Err(LayoutError::InitialPagesTooSmall { .. })
+        ));
+        assert!(matches!(
+            MemoryLayout::new(
+                &program,
+                &MemoryOptions::new(1, 10, 2 * PAGE_SIZE)
+            ),
+            Err(LayoutError::InitialPagesTooSmall { .. })
+        ));
+        assert!(matches!(
+            MemoryLayout::new(&program, &MemoryOptions::new(10, 9, stack_size)),
+            Err(LayoutError::MaxPagesBelowInitial { .. })
+        ));
+        assert!(matches!(
+            MemoryLayout::new(
+                &program,
+                &MemoryOptions::new(10, 70_000, stack_size)
+            ),
+            Err(LayoutError::MaxPagesTooLarge { .. })
+        ));
+    }
+}
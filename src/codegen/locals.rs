@@ -1,7 +1,7 @@
 use std::mem;
 
 use crate::ir::{
-    Function, FunctionAttribute, Instruction, PlaceAddress, Program,
+    AddressingMode, Function, FunctionAttribute, Instruction, PlaceAddress,
 };
 
 #[derive(Copy, Clone)]
@@ -41,13 +41,27 @@ impl LocalPlacesInfo {
     pub fn strategy(&self) -> LocalStrategy {
         self.strategy
     }
+
+    /// Bytes pushed onto the stack while the function runs, which are the
+    /// argument list followed by either the places themselves or a pointer
+    /// to the block of places on the heap.
+    ///
+    /// The argument list is kept on the stack so the garbage collector finds
+    /// it even after a tail call released the frame of the caller.
+    pub fn stack_len(&self) -> i32 {
+        let word = mem::size_of::<i32>() as i32;
+        match self.strategy {
+            LocalStrategy::Stack => word + self.len,
+            LocalStrategy::Heap => 2 * word,
+        }
+    }
 }
 
 impl LocalPlacesInfo {
-    pub fn extract(function: &Function, program: &Program) -> Option<Self> {
+    pub fn extract(function: &Function) -> Option<Self> {
         let mut acc = LocalSpaceAccumulator::new();
         for &inst in function.instructions() {
-            consider_instruction(&mut acc, inst, program);
+            consider_instruction(&mut acc, inst);
         }
         acc.finish().map(|len| LocalPlacesInfo {
             strategy: LocalStrategy::choose(function),
@@ -70,7 +84,12 @@ impl LocalSpaceAccumulator {
     }
 
     fn must_contain(&mut self, addr: PlaceAddress) {
-        self.max_offset = self.max_offset.max(addr.offset());
+        // global places are in static data and captured places belong to
+        // another function, so they need no local space, which also keeps the
+        // local space word-aligned for the garbage collector
+        if addr.mode() == AddressingMode::Local {
+            self.max_offset = self.max_offset.max(addr.offset());
+        }
     }
 
     fn finish(self) -> Option<i32> {
@@ -82,16 +101,11 @@ impl LocalSpaceAccumulator {
 fn consider_instruction(
     locals: &mut LocalSpaceAccumulator,
     instruction: Instruction,
-    program: &Program,
 ) {
     match instruction {
-        Instruction::CreateFunction { function, to } => {
-            // if the function creates a lambda, it must reserve for the places in the lambda
+        // lambdas have places of their own
+        Instruction::CreateFunction { to, .. } => {
             locals.must_contain(to);
-            let function = program.resolve_function_idx(function);
-            for &inst in function.instructions() {
-                consider_instruction(locals, inst, program);
-            }
         }
         Instruction::Call { params, to, .. } => {
             locals.must_contain(params);
@@ -106,11 +120,24 @@ fn consider_instruction(
             locals.must_contain(params);
             locals.must_contain(to);
         }
+        Instruction::TailCall { params, .. } => {
+            locals.must_contain(params);
+        }
+        Instruction::TailCallIndirect { function, params } => {
+            locals.must_contain(function);
+            locals.must_contain(params);
+        }
         Instruction::CallPrint { string } => {
             locals.must_contain(string);
         }
-        Instruction::Return { value } => {
+        Instruction::Return { value, values } => {
             locals.must_contain(value);
+            if let Some(values) = values {
+                locals.must_contain(values);
+            }
+        }
+        Instruction::LoadValues { to } => {
+            locals.must_contain(to);
         }
         Instruction::EnterBlock => {}
         Instruction::Continue { .. } => {}
@@ -125,12 +152,34 @@ fn consider_instruction(
             locals.must_contain(if_not_nil);
         }
         Instruction::ExitBlock => {}
+        Instruction::EnterCatch { tag, to } => {
+            if let Some(tag) = tag {
+                locals.must_contain(tag);
+            }
+            locals.must_contain(to);
+        }
+        Instruction::ExitCatch => {}
+        Instruction::Throw { thrown } => {
+            locals.must_contain(thrown);
+        }
         Instruction::ConsumeParam { to } => {
             locals.must_contain(to);
         }
         Instruction::ConsumeRest { to } => {
             locals.must_contain(to);
         }
+        Instruction::ParamPresent { to } => {
+            locals.must_contain(to);
+        }
+        Instruction::FindKeyParam { to, .. } => {
+            locals.must_contain(to);
+        }
+        Instruction::KeyParamsKnown { to, .. } => {
+            locals.must_contain(to);
+        }
+        Instruction::ArityMatches { to, .. } => {
+            locals.must_contain(to);
+        }
         Instruction::LoadData { to, .. } => {
             locals.must_contain(to);
         }
@@ -151,11 +200,33 @@ fn consider_instruction(
             locals.must_contain(list);
             locals.must_contain(to);
         }
+        Instruction::StoreCar { list, value } => {
+            locals.must_contain(list);
+            locals.must_contain(value);
+        }
+        Instruction::StoreCdr { list, value } => {
+            locals.must_contain(list);
+            locals.must_contain(value);
+        }
         Instruction::ConcatStringLike { left, right, to } => {
             locals.must_contain(left);
             locals.must_contain(right);
             locals.must_contain(to);
         }
+        Instruction::MakeBignum { sign, digits, to } => {
+            locals.must_contain(sign);
+            locals.must_contain(digits);
+            locals.must_contain(to);
+        }
+        Instruction::MakeRatio {
+            numerator,
+            denominator,
+            to,
+        } => {
+            locals.must_contain(numerator);
+            locals.must_contain(denominator);
+            locals.must_contain(to);
+        }
         Instruction::Add { left, right, to } => {
             locals.must_contain(left);
             locals.must_contain(right);
@@ -181,6 +252,11 @@ fn consider_instruction(
             locals.must_contain(right);
             locals.must_contain(to);
         }
+        Instruction::Same { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
         Instruction::Ne { left, right, to } => {
             locals.must_contain(left);
             locals.must_contain(right);
@@ -206,11 +282,87 @@ fn consider_instruction(
             locals.must_contain(right);
             locals.must_contain(to);
         }
+        Instruction::FloatAdd { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatSub { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatMul { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatDiv { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatEq { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatLt { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::FloatLte { left, right, to } => {
+            locals.must_contain(left);
+            locals.must_contain(right);
+            locals.must_contain(to);
+        }
+        Instruction::IntToFloat { number, to } => {
+            locals.must_contain(number);
+            locals.must_contain(to);
+        }
+        Instruction::FloatTruncate { float, to } => {
+            locals.must_contain(float);
+            locals.must_contain(to);
+        }
         Instruction::NilIfZero { check, to } => {
             locals.must_contain(check);
             locals.must_contain(to);
         }
-        Instruction::LoadTypeTag { of, to } => {
+        Instruction::CharCode { character, to }
+        | Instruction::CharString { character, to } => {
+            locals.must_contain(character);
+            locals.must_contain(to);
+        }
+        Instruction::CodeChar { code, to } => {
+            locals.must_contain(code);
+            locals.must_contain(to);
+        }
+        Instruction::CharAt { string, index, to } => {
+            locals.must_contain(string);
+            locals.must_contain(index);
+            locals.must_contain(to);
+        }
+        Instruction::MakeVector { length, to } => {
+            locals.must_contain(length);
+            locals.must_contain(to);
+        }
+        Instruction::LoadElement { vector, index, to } => {
+            locals.must_contain(vector);
+            locals.must_contain(index);
+            locals.must_contain(to);
+        }
+        Instruction::StoreElement {
+            vector,
+            index,
+            value,
+        } => {
+            locals.must_contain(vector);
+            locals.must_contain(index);
+            locals.must_contain(value);
+        }
+        Instruction::LoadLength { of, to }
+        | Instruction::LoadTypeTag { of, to } => {
             locals.must_contain(of);
             locals.must_contain(to);
         }
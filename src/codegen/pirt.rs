@@ -42,7 +42,7 @@ fn write_function<W: Write>(w: &mut W, function: &Function) -> io::Result<()> {
     write!(w, "{} {{\n", function.name())?;
     let mut indents = 1;
     for inst in function.instructions() {
-        if let Instruction::ExitBlock = inst {
+        if let Instruction::ExitBlock | Instruction::ExitCatch = inst {
             indents -= 1;
         }
         for _ in 0..indents {
@@ -51,9 +51,13 @@ fn write_function<W: Write>(w: &mut W, function: &Function) -> io::Result<()> {
         match inst {
             Instruction::EnterBlock => write!(w, "{{\n"),
             Instruction::ExitBlock => write!(w, "}}\n"),
+            Instruction::EnterCatch { tag, to } => {
+                write!(w, "catch {:?} to {:?} {{\n", tag, to)
+            }
+            Instruction::ExitCatch => write!(w, "}}\n"),
             inst => write!(w, "{:?}\n", inst),
         }?;
-        if let Instruction::EnterBlock = inst {
+        if let Instruction::EnterBlock | Instruction::EnterCatch { .. } = inst {
             indents += 1
         }
     }
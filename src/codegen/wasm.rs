@@ -0,0 +1,34 @@
+//! Binary web assembly output.
+//!
+//! Rather than generating binary code separately from text, the text from
+//! [`write_wat`] is assembled with a small assembler that understands the
+//! subset of web assembly text used by the code generator and `rt/rt.wat`.
+//! This way, no external tools like `wat2wasm` are needed to run programs.
+
+mod assemble;
+mod encode;
+mod err;
+mod opcodes;
+mod sexpr;
+
+use std::io::{self, Write};
+
+use crate::ir::Program;
+
+use super::{FeatureOptions, MemoryLayout, write_wat};
+
+use assemble::assemble;
+
+pub fn write_wasm<W: Write>(
+    w: &mut W,
+    program: &Program,
+    layout: &MemoryLayout,
+    features: &FeatureOptions,
+) -> io::Result<()> {
+    let mut wat = vec![];
+    write_wat(&mut wat, program, layout, features)?;
+    let wat = String::from_utf8(wat)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let wasm = assemble(&wat)?;
+    w.write_all(&wasm)
+}
@@ -0,0 +1,1565 @@
+use std::collections::HashMap;
+
+use super::{
+    encode::{
+        MAGIC, VERSION, write_bytes, write_i32, write_i64, write_len,
+        write_section, write_u32, write_vec_section,
+    },
+    err::{AssembleError, AssembleResult},
+    opcodes::{self, Immediate},
+    sexpr::{self, Sexpr},
+};
+
+const SECTION_CUSTOM: u8 = 0;
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_TABLE: u8 = 4;
+const SECTION_MEMORY: u8 = 5;
+const SECTION_GLOBAL: u8 = 6;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_START: u8 = 8;
+const SECTION_ELEMENT: u8 = 9;
+const SECTION_CODE: u8 = 10;
+const SECTION_DATA: u8 = 11;
+const SECTION_TAG: u8 = 13;
+
+const KIND_FUNC: u8 = 0;
+const KIND_TABLE: u8 = 1;
+const KIND_MEMORY: u8 = 2;
+const KIND_GLOBAL: u8 = 3;
+const KIND_TAG: u8 = 4;
+
+const BLOCK_TYPE_EMPTY: u8 = 0x40;
+const OP_BLOCK: u8 = 0x02;
+const OP_LOOP: u8 = 0x03;
+const OP_IF: u8 = 0x04;
+const OP_ELSE: u8 = 0x05;
+const OP_TRY: u8 = 0x06;
+const OP_CATCH: u8 = 0x07;
+const OP_CATCH_ALL: u8 = 0x19;
+const OP_END: u8 = 0x0B;
+
+/// Assembles a module in web assembly text into the binary format.
+pub fn assemble(text: &str) -> AssembleResult<Vec<u8>> {
+    let toplevel = sexpr::read(text)?;
+    let module = match toplevel.as_slice() {
+        [module] => module.list_of("module").ok_or_else(|| {
+            AssembleError::new(module.line(), "expected (module ...)")
+        })?,
+        _ => {
+            return Err(AssembleError::new(
+                1,
+                "expected exactly one top-level module",
+            ));
+        }
+    };
+    let mut declarations = Declarations::default();
+    for field in module {
+        declarations.declare(field)?;
+    }
+    declarations.intern_inline_types();
+    declarations.encode()
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+struct FuncType {
+    params: Vec<u8>,
+    results: Vec<u8>,
+}
+
+/// Signature of a function or tag, either referring to a type definition or
+/// written inline.
+enum TypeUse {
+    Index(u32),
+    Inline(FuncType),
+}
+
+impl TypeUse {
+    fn idx(&self) -> u32 {
+        match self {
+            TypeUse::Index(idx) => *idx,
+            TypeUse::Inline(_) => {
+                unreachable!("inline types are interned before encoding")
+            }
+        }
+    }
+}
+
+struct Func<'t, 'm> {
+    name: Option<&'t str>,
+    type_use: TypeUse,
+    /// Module and name if imported, otherwise the function is defined here.
+    import: Option<(Vec<u8>, Vec<u8>)>,
+    /// Names of params and locals, mapped to local indexes.
+    local_names: HashMap<&'t str, u32>,
+    locals: Vec<u8>,
+    body: &'m [Sexpr<'t>],
+}
+
+struct Global<'t, 'm> {
+    import: Option<(Vec<u8>, Vec<u8>)>,
+    val_type: u8,
+    mutable: bool,
+    init: &'m [Sexpr<'t>],
+}
+
+struct Limits {
+    min: u32,
+    max: Option<u32>,
+}
+
+struct Memory {
+    import: Option<(Vec<u8>, Vec<u8>)>,
+    limits: Limits,
+}
+
+struct Table {
+    import: Option<(Vec<u8>, Vec<u8>)>,
+    elem_type: u8,
+    limits: Limits,
+}
+
+struct Tag {
+    import: Option<(Vec<u8>, Vec<u8>)>,
+    type_use: TypeUse,
+}
+
+struct Export {
+    name: Vec<u8>,
+    kind: u8,
+    idx: u32,
+}
+
+struct Elem<'t, 'm> {
+    offset: &'m [Sexpr<'t>],
+    funcs: &'m [Sexpr<'t>],
+}
+
+struct Data<'t, 'm> {
+    offset: Option<&'m [Sexpr<'t>]>,
+    bytes: Vec<u8>,
+}
+
+/// Everything declared in a module, with names resolved to indexes but with
+/// code and constant expressions not yet encoded.
+#[derive(Default)]
+struct Declarations<'t, 'm> {
+    types: Vec<FuncType>,
+    type_names: HashMap<&'t str, u32>,
+    funcs: Vec<Func<'t, 'm>>,
+    func_names: HashMap<&'t str, u32>,
+    globals: Vec<Global<'t, 'm>>,
+    global_names: HashMap<&'t str, u32>,
+    memories: Vec<Memory>,
+    memory_names: HashMap<&'t str, u32>,
+    tables: Vec<Table>,
+    table_names: HashMap<&'t str, u32>,
+    tags: Vec<Tag>,
+    tag_names: HashMap<&'t str, u32>,
+    /// Kind and index of imports in the order they were declared.
+    imports: Vec<(u8, usize)>,
+    exports: Vec<Export>,
+    start: Option<&'m Sexpr<'t>>,
+    elems: Vec<Elem<'t, 'm>>,
+    datas: Vec<Data<'t, 'm>>,
+}
+
+impl<'t, 'm> Declarations<'t, 'm> {
+    fn declare(&mut self, field: &'m Sexpr<'t>) -> AssembleResult<()> {
+        let line = field.line();
+        let items = field
+            .list()
+            .ok_or_else(|| AssembleError::new(line, "expected module field"))?;
+        let (keyword, rest) = split_keyword(field)?;
+        match keyword {
+            "type" => self.declare_type(line, rest),
+            "import" => self.declare_import(line, rest),
+            "func" => self.declare_func(line, rest, None),
+            "global" => self.declare_global(line, rest, None),
+            "memory" => self.declare_memory(line, rest, None),
+            "table" => self.declare_table(line, rest, None),
+            "tag" => self.declare_tag(line, rest, None),
+            "export" => self.declare_export(line, rest),
+            "start" => {
+                self.start = rest.first();
+                Ok(())
+            }
+            "elem" => self.declare_elem(line, rest),
+            "data" => self.declare_data(line, rest),
+            _ => Err(AssembleError::new(
+                line,
+                format!("unsupported module field {:?}", items[0]),
+            )),
+        }
+    }
+
+    fn declare_type(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let (name, rest) = split_name(rest);
+        let func = match rest {
+            [func] => func.list_of("func"),
+            _ => None,
+        }
+        .ok_or_else(|| AssembleError::new(line, "expected (func ...)"))?;
+        let mut signature = Signature::default();
+        for item in func {
+            if !signature.parse(item)? {
+                return Err(AssembleError::new(
+                    item.line(),
+                    "expected param or result",
+                ));
+            }
+        }
+        let idx = self.types.len() as u32;
+        self.types.push(signature.func_type);
+        if let Some(name) = name {
+            self.type_names.insert(name, idx);
+        }
+        Ok(())
+    }
+
+    fn declare_import(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let (module, name, desc) = match rest {
+            [module, name, desc] => (module, name, desc),
+            _ => {
+                return Err(AssembleError::new(
+                    line,
+                    "expected module name, field name and description",
+                ));
+            }
+        };
+        let import = Some((string(module)?, string(name)?));
+        let (keyword, desc_rest) = split_keyword(desc)?;
+        match keyword {
+            "func" => {
+                self.imports.push((KIND_FUNC, self.funcs.len()));
+                self.declare_func(line, desc_rest, import)
+            }
+            "global" => {
+                self.imports.push((KIND_GLOBAL, self.globals.len()));
+                self.declare_global(line, desc_rest, import)
+            }
+            "memory" => {
+                self.imports.push((KIND_MEMORY, self.memories.len()));
+                self.declare_memory(line, desc_rest, import)
+            }
+            "table" => {
+                self.imports.push((KIND_TABLE, self.tables.len()));
+                self.declare_table(line, desc_rest, import)
+            }
+            "tag" => {
+                self.imports.push((KIND_TAG, self.tags.len()));
+                self.declare_tag(line, desc_rest, import)
+            }
+            _ => Err(AssembleError::new(
+                line,
+                format!("unsupported import kind {}", keyword),
+            )),
+        }
+    }
+
+    fn declare_func(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+        import: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> AssembleResult<()> {
+        let idx = self.funcs.len() as u32;
+        self.ensure_imports_first(line, import.is_some(), |d| {
+            d.funcs.iter().any(|f| f.import.is_none())
+        })?;
+        let (name, rest) = split_name(rest);
+        let rest = self.inline_exports(rest, KIND_FUNC, idx)?;
+        let (explicit_type, rest) = self.type_use(rest)?;
+
+        let mut signature = Signature::default();
+        let mut local_names = HashMap::new();
+        let mut locals = vec![];
+        let mut body_start = rest.len();
+        for (pos, item) in rest.iter().enumerate() {
+            if signature.parse(item)? {
+                continue;
+            }
+            if let Some(local) = item.list_of("local") {
+                let (local_name, types) = split_name(local);
+                for ty in types {
+                    let local_idx = (signature.func_type.params.len()
+                        + locals.len())
+                        as u32;
+                    if let Some(local_name) = local_name {
+                        local_names.insert(local_name, local_idx);
+                    }
+                    locals.push(val_type(ty)?);
+                }
+                continue;
+            }
+            body_start = pos;
+            break;
+        }
+        for (param_name, param_idx) in signature.param_names {
+            local_names.insert(param_name, param_idx);
+        }
+
+        let type_use = match explicit_type {
+            Some(type_idx) => TypeUse::Index(type_idx),
+            None => TypeUse::Inline(signature.func_type),
+        };
+        if let Some(name) = name {
+            self.func_names.insert(name, idx);
+        }
+        self.funcs.push(Func {
+            name,
+            type_use,
+            import,
+            local_names,
+            locals,
+            body: &rest[body_start..],
+        });
+        Ok(())
+    }
+
+    fn declare_global(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+        import: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> AssembleResult<()> {
+        let idx = self.globals.len() as u32;
+        self.ensure_imports_first(line, import.is_some(), |d| {
+            d.globals.iter().any(|g| g.import.is_none())
+        })?;
+        let (name, rest) = split_name(rest);
+        let rest = self.inline_exports(rest, KIND_GLOBAL, idx)?;
+        let (ty, init) = rest
+            .split_first()
+            .ok_or_else(|| AssembleError::new(line, "expected global type"))?;
+        let (mutable, val_type) = match ty.list_of("mut") {
+            Some([ty]) => (true, val_type(ty)?),
+            _ => (false, val_type(ty)?),
+        };
+        if let Some(name) = name {
+            self.global_names.insert(name, idx);
+        }
+        self.globals.push(Global {
+            import,
+            val_type,
+            mutable,
+            init,
+        });
+        Ok(())
+    }
+
+    fn declare_memory(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+        import: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> AssembleResult<()> {
+        let idx = self.memories.len() as u32;
+        self.ensure_imports_first(line, import.is_some(), |d| {
+            d.memories.iter().any(|m| m.import.is_none())
+        })?;
+        let (name, rest) = split_name(rest);
+        let rest = self.inline_exports(rest, KIND_MEMORY, idx)?;
+        let (limits, _) = limits(line, rest)?;
+        if let Some(name) = name {
+            self.memory_names.insert(name, idx);
+        }
+        self.memories.push(Memory { import, limits });
+        Ok(())
+    }
+
+    fn declare_table(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+        import: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> AssembleResult<()> {
+        let idx = self.tables.len() as u32;
+        self.ensure_imports_first(line, import.is_some(), |d| {
+            d.tables.iter().any(|t| t.import.is_none())
+        })?;
+        let (name, rest) = split_name(rest);
+        let rest = self.inline_exports(rest, KIND_TABLE, idx)?;
+        let (limits, rest) = limits(line, rest)?;
+        let elem_type = match rest {
+            [elem_type] => val_type(elem_type)?,
+            _ => return Err(AssembleError::new(line, "expected table type")),
+        };
+        if let Some(name) = name {
+            self.table_names.insert(name, idx);
+        }
+        self.tables.push(Table {
+            import,
+            elem_type,
+            limits,
+        });
+        Ok(())
+    }
+
+    fn declare_tag(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+        import: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> AssembleResult<()> {
+        let idx = self.tags.len() as u32;
+        self.ensure_imports_first(line, import.is_some(), |d| {
+            d.tags.iter().any(|t| t.import.is_none())
+        })?;
+        let (name, rest) = split_name(rest);
+        let rest = self.inline_exports(rest, KIND_TAG, idx)?;
+        let (explicit_type, rest) = self.type_use(rest)?;
+        let mut signature = Signature::default();
+        for item in rest {
+            if !signature.parse(item)? {
+                return Err(AssembleError::new(item.line(), "expected param"));
+            }
+        }
+        let type_use = match explicit_type {
+            Some(type_idx) => TypeUse::Index(type_idx),
+            None => TypeUse::Inline(signature.func_type),
+        };
+        if let Some(name) = name {
+            self.tag_names.insert(name, idx);
+        }
+        self.tags.push(Tag { import, type_use });
+        Ok(())
+    }
+
+    fn declare_export(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let (name, desc) = match rest {
+            [name, desc] => (string(name)?, desc),
+            _ => {
+                return Err(AssembleError::new(
+                    line,
+                    "expected export name and description",
+                ));
+            }
+        };
+        let (keyword, desc) = split_keyword(desc)?;
+        let target = match desc {
+            [target] => target,
+            _ => {
+                return Err(AssembleError::new(line, "expected export target"));
+            }
+        };
+        let (kind, names) = match keyword {
+            "func" => (KIND_FUNC, &self.func_names),
+            "table" => (KIND_TABLE, &self.table_names),
+            "memory" => (KIND_MEMORY, &self.memory_names),
+            "global" => (KIND_GLOBAL, &self.global_names),
+            "tag" => (KIND_TAG, &self.tag_names),
+            _ => {
+                return Err(AssembleError::new(
+                    line,
+                    format!("unsupported export kind {}", keyword),
+                ));
+            }
+        };
+        let idx = resolve(names, target)?;
+        self.exports.push(Export { name, kind, idx });
+        Ok(())
+    }
+
+    fn declare_elem(
+        &mut self,
+        line: usize,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let (_, rest) = split_name(rest);
+        let (offset, funcs) = match rest.split_first() {
+            Some((offset, funcs)) => (offset, funcs),
+            None => return Err(AssembleError::new(line, "expected offset")),
+        };
+        let offset = offset_expr(offset);
+        let funcs = match funcs.split_first() {
+            Some((first, rest)) if first.atom() == Some("func") => rest,
+            _ => funcs,
+        };
+        self.elems.push(Elem { offset, funcs });
+        Ok(())
+    }
+
+    fn declare_data(
+        &mut self,
+        _line: usize,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let (_, mut rest) = split_name(rest);
+        if let Some((memory, after)) = rest.split_first()
+            && memory.list_of("memory").is_some()
+        {
+            rest = after;
+        }
+        let mut offset = None;
+        if let Some((first, after)) = rest.split_first()
+            && first.list().is_some()
+        {
+            offset = Some(offset_expr(first));
+            rest = after;
+        }
+        let mut bytes = vec![];
+        for string_part in rest {
+            bytes.extend(string(string_part)?);
+        }
+        self.datas.push(Data { offset, bytes });
+        Ok(())
+    }
+
+    /// Imports need to come before definitions of the same kind so that
+    /// indexes in text and binary format agree.
+    fn ensure_imports_first(
+        &self,
+        line: usize,
+        is_import: bool,
+        any_defined: impl Fn(&Self) -> bool,
+    ) -> AssembleResult<()> {
+        if is_import && any_defined(self) {
+            Err(AssembleError::new(
+                line,
+                "imports must come before definitions",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Registers exports like `(export "name")` inside of a declaration and
+    /// returns the rest of the declaration.
+    fn inline_exports(
+        &mut self,
+        mut rest: &'m [Sexpr<'t>],
+        kind: u8,
+        idx: u32,
+    ) -> AssembleResult<&'m [Sexpr<'t>]> {
+        while let Some((first, after)) = rest.split_first() {
+            match first.list_of("export") {
+                Some([name]) => {
+                    self.exports.push(Export {
+                        name: string(name)?,
+                        kind,
+                        idx,
+                    });
+                    rest = after;
+                }
+                _ => break,
+            }
+        }
+        Ok(rest)
+    }
+
+    /// Parses an optional `(type $t)` and returns the rest.
+    fn type_use(
+        &self,
+        rest: &'m [Sexpr<'t>],
+    ) -> AssembleResult<(Option<u32>, &'m [Sexpr<'t>])> {
+        match rest.split_first() {
+            Some((first, after)) => match first.list_of("type") {
+                Some([ty]) => Ok((Some(resolve(&self.type_names, ty)?), after)),
+                _ => Ok((None, rest)),
+            },
+            None => Ok((None, rest)),
+        }
+    }
+
+    /// Replaces inline signatures with the index of the first type with the
+    /// same signature, adding types after the explicitly defined ones where
+    /// no such type exists yet.
+    fn intern_inline_types(&mut self) {
+        let type_uses = self
+            .funcs
+            .iter_mut()
+            .map(|f| &mut f.type_use)
+            .chain(self.tags.iter_mut().map(|t| &mut t.type_use));
+        for type_use in type_uses {
+            if let TypeUse::Inline(func_type) = type_use {
+                let idx = match self.types.iter().position(|t| t == func_type) {
+                    Some(idx) => idx,
+                    None => {
+                        self.types.push(func_type.clone());
+                        self.types.len() - 1
+                    }
+                };
+                *type_use = TypeUse::Index(idx as u32);
+            }
+        }
+    }
+
+    fn encode(&self) -> AssembleResult<Vec<u8>> {
+        let mut module = vec![];
+        module.extend_from_slice(MAGIC);
+        module.extend_from_slice(VERSION);
+
+        let mut types = vec![];
+        for ty in &self.types {
+            types.push(0x60);
+            write_bytes(&mut types, &ty.params);
+            write_bytes(&mut types, &ty.results);
+        }
+        write_vec_section(&mut module, SECTION_TYPE, self.types.len(), &types);
+
+        let mut imports = vec![];
+        for &(kind, idx) in &self.imports {
+            let import = match kind {
+                KIND_FUNC => &self.funcs[idx].import,
+                KIND_TABLE => &self.tables[idx].import,
+                KIND_MEMORY => &self.memories[idx].import,
+                KIND_GLOBAL => &self.globals[idx].import,
+                _ => &self.tags[idx].import,
+            };
+            let (module_name, field_name) = import.as_ref().unwrap();
+            write_bytes(&mut imports, module_name);
+            write_bytes(&mut imports, field_name);
+            imports.push(kind);
+            match kind {
+                KIND_FUNC => {
+                    write_u32(&mut imports, self.funcs[idx].type_use.idx())
+                }
+                KIND_TABLE => {
+                    imports.push(self.tables[idx].elem_type);
+                    write_limits(&mut imports, &self.tables[idx].limits);
+                }
+                KIND_MEMORY => {
+                    write_limits(&mut imports, &self.memories[idx].limits)
+                }
+                KIND_GLOBAL => {
+                    imports.push(self.globals[idx].val_type);
+                    imports.push(self.globals[idx].mutable as u8);
+                }
+                _ => {
+                    imports.push(0); // exception attribute
+                    write_u32(&mut imports, self.tags[idx].type_use.idx());
+                }
+            }
+        }
+        write_vec_section(
+            &mut module,
+            SECTION_IMPORT,
+            self.imports.len(),
+            &imports,
+        );
+
+        let defined_funcs: Vec<&Func> =
+            self.funcs.iter().filter(|f| f.import.is_none()).collect();
+        let mut functions = vec![];
+        for func in &defined_funcs {
+            write_u32(&mut functions, func.type_use.idx());
+        }
+        write_vec_section(
+            &mut module,
+            SECTION_FUNCTION,
+            defined_funcs.len(),
+            &functions,
+        );
+
+        let mut tables = vec![];
+        let mut table_count = 0;
+        for table in self.tables.iter().filter(|t| t.import.is_none()) {
+            table_count += 1;
+            tables.push(table.elem_type);
+            write_limits(&mut tables, &table.limits);
+        }
+        write_vec_section(&mut module, SECTION_TABLE, table_count, &tables);
+
+        let mut memories = vec![];
+        let mut memory_count = 0;
+        for memory in self.memories.iter().filter(|m| m.import.is_none()) {
+            memory_count += 1;
+            write_limits(&mut memories, &memory.limits);
+        }
+        write_vec_section(&mut module, SECTION_MEMORY, memory_count, &memories);
+
+        let mut tags = vec![];
+        let mut tag_count = 0;
+        for tag in self.tags.iter().filter(|t| t.import.is_none()) {
+            tag_count += 1;
+            tags.push(0); // exception attribute
+            write_u32(&mut tags, tag.type_use.idx());
+        }
+        write_vec_section(&mut module, SECTION_TAG, tag_count, &tags);
+
+        let mut globals = vec![];
+        let mut global_count = 0;
+        for global in self.globals.iter().filter(|g| g.import.is_none()) {
+            global_count += 1;
+            globals.push(global.val_type);
+            globals.push(global.mutable as u8);
+            self.const_expr(&mut globals, global.init)?;
+        }
+        write_vec_section(&mut module, SECTION_GLOBAL, global_count, &globals);
+
+        let mut exports = vec![];
+        for export in &self.exports {
+            write_bytes(&mut exports, &export.name);
+            exports.push(export.kind);
+            write_u32(&mut exports, export.idx);
+        }
+        write_vec_section(
+            &mut module,
+            SECTION_EXPORT,
+            self.exports.len(),
+            &exports,
+        );
+
+        if let Some(start) = self.start {
+            let mut content = vec![];
+            write_u32(&mut content, resolve(&self.func_names, start)?);
+            write_section(&mut module, SECTION_START, &content);
+        }
+
+        let mut elems = vec![];
+        for elem in &self.elems {
+            elems.push(0); // active in table 0 with function indexes
+            self.const_expr(&mut elems, elem.offset)?;
+            write_len(&mut elems, elem.funcs.len());
+            for func in elem.funcs {
+                write_u32(&mut elems, resolve(&self.func_names, func)?);
+            }
+        }
+        write_vec_section(
+            &mut module,
+            SECTION_ELEMENT,
+            self.elems.len(),
+            &elems,
+        );
+
+        let mut code = vec![];
+        for func in &defined_funcs {
+            let mut body = vec![];
+            write_local_groups(&mut body, &func.locals);
+            let mut encoder = BodyEncoder::new(self, &func.local_names);
+            encoder.instructions(func.body)?;
+            body.extend_from_slice(&encoder.code);
+            body.push(OP_END);
+            write_bytes(&mut code, &body);
+        }
+        write_vec_section(
+            &mut module,
+            SECTION_CODE,
+            defined_funcs.len(),
+            &code,
+        );
+
+        let mut datas = vec![];
+        for data in &self.datas {
+            match data.offset {
+                Some(offset) => {
+                    datas.push(0); // active in memory 0
+                    self.const_expr(&mut datas, offset)?;
+                }
+                None => datas.push(1), // passive
+            }
+            write_bytes(&mut datas, &data.bytes);
+        }
+        write_vec_section(&mut module, SECTION_DATA, self.datas.len(), &datas);
+
+        self.write_name_section(&mut module);
+        Ok(module)
+    }
+
+    fn const_expr(
+        &self,
+        buf: &mut Vec<u8>,
+        expr: &[Sexpr<'t>],
+    ) -> AssembleResult<()> {
+        let no_locals = HashMap::new();
+        let mut encoder = BodyEncoder::new(self, &no_locals);
+        encoder.instructions(expr)?;
+        buf.extend_from_slice(&encoder.code);
+        buf.push(OP_END);
+        Ok(())
+    }
+
+    /// Writes the custom name section with function names for debugging.
+    fn write_name_section(&self, module: &mut Vec<u8>) {
+        let named: Vec<(usize, &str)> = self
+            .funcs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, f)| f.name.map(|n| (idx, n)))
+            .collect();
+        if named.is_empty() {
+            return;
+        }
+        let mut function_names = vec![];
+        write_len(&mut function_names, named.len());
+        for (idx, name) in named {
+            write_len(&mut function_names, idx);
+            write_bytes(
+                &mut function_names,
+                name.trim_start_matches('$').as_bytes(),
+            );
+        }
+        let mut content = vec![];
+        write_bytes(&mut content, b"name");
+        content.push(1); // function names subsection
+        write_bytes(&mut content, &function_names);
+        write_section(module, SECTION_CUSTOM, &content);
+    }
+}
+
+/// Collects params and results of a function or type definition.
+#[derive(Default)]
+struct Signature<'t> {
+    func_type: FuncType,
+    param_names: Vec<(&'t str, u32)>,
+}
+
+impl<'t> Signature<'t> {
+    /// Adds the item to the signature if it is a `param` or `result` and
+    /// returns whether it was.
+    fn parse(&mut self, item: &Sexpr<'t>) -> AssembleResult<bool> {
+        if let Some(param) = item.list_of("param") {
+            let (name, types) = split_name(param);
+            for ty in types {
+                if let Some(name) = name {
+                    let idx = self.func_type.params.len() as u32;
+                    self.param_names.push((name, idx));
+                }
+                self.func_type.params.push(val_type(ty)?);
+            }
+            Ok(true)
+        } else if let Some(result) = item.list_of("result") {
+            for ty in result {
+                self.func_type.results.push(val_type(ty)?);
+            }
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// Encodes instructions of a function body or constant expression.
+struct BodyEncoder<'d, 't, 'm> {
+    declarations: &'d Declarations<'t, 'm>,
+    local_names: &'d HashMap<&'t str, u32>,
+    /// Labels of the enclosing blocks, innermost last.
+    labels: Vec<Option<&'t str>>,
+    code: Vec<u8>,
+}
+
+impl<'d, 't, 'm> BodyEncoder<'d, 't, 'm> {
+    fn new(
+        declarations: &'d Declarations<'t, 'm>,
+        local_names: &'d HashMap<&'t str, u32>,
+    ) -> Self {
+        Self {
+            declarations,
+            local_names,
+            labels: vec![],
+            code: vec![],
+        }
+    }
+
+    /// Encodes a sequence of plain and folded instructions.
+    fn instructions(&mut self, items: &[Sexpr<'t>]) -> AssembleResult<()> {
+        let mut pos = 0;
+        while pos < items.len() {
+            let item = &items[pos];
+            pos += 1;
+            match item {
+                Sexpr::List { .. } => self.folded(item)?,
+                Sexpr::Atom { text, line } => {
+                    self.plain(text, *line, items, &mut pos)?
+                }
+                Sexpr::Str { line, .. } => {
+                    return Err(AssembleError::new(
+                        *line,
+                        "unexpected string in instructions",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes an instruction in plain syntax, consuming immediates from
+    /// the items starting at pos.
+    fn plain(
+        &mut self,
+        name: &'t str,
+        line: usize,
+        items: &[Sexpr<'t>],
+        pos: &mut usize,
+    ) -> AssembleResult<()> {
+        match name {
+            "block" | "loop" | "if" | "try" => {
+                let opcode = match name {
+                    "block" => OP_BLOCK,
+                    "loop" => OP_LOOP,
+                    "if" => OP_IF,
+                    _ => OP_TRY,
+                };
+                let label = self.label_and_block_type(opcode, items, pos)?;
+                self.labels.push(label);
+            }
+            "else" | "catch_all" => {
+                self.skip_label(items, pos);
+                self.code.push(if name == "else" {
+                    OP_ELSE
+                } else {
+                    OP_CATCH_ALL
+                });
+            }
+            "catch" => {
+                self.code.push(OP_CATCH);
+                let tag = self.next_immediate(line, items, pos)?;
+                let idx = resolve(&self.declarations.tag_names, tag)?;
+                write_u32(&mut self.code, idx);
+            }
+            "end" => {
+                if self.labels.pop().is_none() {
+                    return Err(AssembleError::new(line, "unbalanced end"));
+                }
+                self.skip_label(items, pos);
+                self.code.push(OP_END);
+            }
+            _ => {
+                let opcode = opcodes::lookup(name).ok_or_else(|| {
+                    AssembleError::new(
+                        line,
+                        format!("unknown instruction {}", name),
+                    )
+                })?;
+                self.code.extend_from_slice(opcode.bytes);
+                self.immediate(opcode.immediate, line, items, pos)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes an instruction in folded syntax, e.g. `(i32.add (...) (...))`
+    /// or `(block $label ...)`.
+    fn folded(&mut self, item: &Sexpr<'t>) -> AssembleResult<()> {
+        let line = item.line();
+        let (keyword, rest) = split_keyword(item)?;
+        match keyword {
+            "block" | "loop" => {
+                let opcode = if keyword == "block" {
+                    OP_BLOCK
+                } else {
+                    OP_LOOP
+                };
+                let mut pos = 0;
+                let label =
+                    self.label_and_block_type(opcode, rest, &mut pos)?;
+                self.labels.push(label);
+                self.instructions(&rest[pos..])?;
+                self.labels.pop();
+                self.code.push(OP_END);
+            }
+            "if" => {
+                let mut block_type = vec![];
+                let mut pos = 0;
+                let label = self.label_and_block_type_into(
+                    &mut block_type,
+                    rest,
+                    &mut pos,
+                )?;
+                // conditions come first, followed by the then and else branches
+                let branches_start = rest[pos..]
+                    .iter()
+                    .position(|i| i.keyword() == Some("then"))
+                    .map(|p| p + pos)
+                    .ok_or_else(|| AssembleError::new(line, "expected then"))?;
+                self.instructions(&rest[pos..branches_start])?;
+                self.code.push(OP_IF);
+                self.code.extend_from_slice(&block_type);
+                self.labels.push(label);
+                for branch in &rest[branches_start..] {
+                    if let Some(then) = branch.list_of("then") {
+                        self.instructions(then)?;
+                    } else if let Some(otherwise) = branch.list_of("else") {
+                        self.code.push(OP_ELSE);
+                        self.instructions(otherwise)?;
+                    } else {
+                        return Err(AssembleError::new(
+                            branch.line(),
+                            "expected then or else",
+                        ));
+                    }
+                }
+                self.labels.pop();
+                self.code.push(OP_END);
+            }
+            "try" => {
+                let mut pos = 0;
+                let label =
+                    self.label_and_block_type(OP_TRY, rest, &mut pos)?;
+                self.labels.push(label);
+                for clause in &rest[pos..] {
+                    let (clause_keyword, clause_rest) = split_keyword(clause)?;
+                    match clause_keyword {
+                        "do" => self.instructions(clause_rest)?,
+                        "catch" => {
+                            let (tag, handler) =
+                                clause_rest.split_first().ok_or_else(|| {
+                                    AssembleError::new(
+                                        clause.line(),
+                                        "expected tag",
+                                    )
+                                })?;
+                            self.code.push(OP_CATCH);
+                            let idx =
+                                resolve(&self.declarations.tag_names, tag)?;
+                            write_u32(&mut self.code, idx);
+                            self.instructions(handler)?;
+                        }
+                        "catch_all" => {
+                            self.code.push(OP_CATCH_ALL);
+                            self.instructions(clause_rest)?;
+                        }
+                        _ => {
+                            return Err(AssembleError::new(
+                                clause.line(),
+                                "expected do, catch or catch_all",
+                            ));
+                        }
+                    }
+                }
+                self.labels.pop();
+                self.code.push(OP_END);
+            }
+            _ => {
+                // immediates follow the instruction name, operands come
+                // afterwards and are evaluated first
+                let opcode = opcodes::lookup(keyword).ok_or_else(|| {
+                    AssembleError::new(
+                        line,
+                        format!("unknown instruction {}", keyword),
+                    )
+                })?;
+                let mut instruction = BodyEncoder {
+                    declarations: self.declarations,
+                    local_names: self.local_names,
+                    labels: self.labels.clone(),
+                    code: opcode.bytes.to_vec(),
+                };
+                let mut pos = 0;
+                instruction.immediate(
+                    opcode.immediate,
+                    line,
+                    rest,
+                    &mut pos,
+                )?;
+                self.instructions(&rest[pos..])?;
+                self.code.extend_from_slice(&instruction.code);
+            }
+        }
+        Ok(())
+    }
+
+    fn label_and_block_type(
+        &mut self,
+        opcode: u8,
+        items: &[Sexpr<'t>],
+        pos: &mut usize,
+    ) -> AssembleResult<Option<&'t str>> {
+        self.code.push(opcode);
+        let mut block_type = vec![];
+        let label =
+            self.label_and_block_type_into(&mut block_type, items, pos)?;
+        self.code.extend_from_slice(&block_type);
+        Ok(label)
+    }
+
+    /// Parses an optional label and block type like `(result i32)`.
+    fn label_and_block_type_into(
+        &self,
+        block_type: &mut Vec<u8>,
+        items: &[Sexpr<'t>],
+        pos: &mut usize,
+    ) -> AssembleResult<Option<&'t str>> {
+        let label = match items.get(*pos).and_then(Sexpr::atom) {
+            Some(label) if label.starts_with('$') => {
+                *pos += 1;
+                Some(label)
+            }
+            _ => None,
+        };
+        match items.get(*pos) {
+            Some(item) if item.list_of("type").is_some() => {
+                let ty = &item.list_of("type").unwrap()[0];
+                let idx = resolve(&self.declarations.type_names, ty)?;
+                write_i64(block_type, idx as i64);
+                *pos += 1;
+            }
+            Some(item) => match item.list_of("result") {
+                Some([result]) => {
+                    block_type.push(val_type(result)?);
+                    *pos += 1;
+                }
+                Some(_) => {
+                    return Err(AssembleError::new(
+                        item.line(),
+                        "multiple block results need a type use",
+                    ));
+                }
+                None => block_type.push(BLOCK_TYPE_EMPTY),
+            },
+            None => block_type.push(BLOCK_TYPE_EMPTY),
+        }
+        Ok(label)
+    }
+
+    /// Skips an optional repeated label after `end` or `else`.
+    fn skip_label(&self, items: &[Sexpr<'t>], pos: &mut usize) {
+        if let Some(label) = items.get(*pos).and_then(Sexpr::atom)
+            && label.starts_with('$')
+            && self.labels.contains(&Some(label))
+        {
+            *pos += 1;
+        }
+    }
+
+    fn immediate(
+        &mut self,
+        immediate: Immediate,
+        line: usize,
+        items: &[Sexpr<'t>],
+        pos: &mut usize,
+    ) -> AssembleResult<()> {
+        match immediate {
+            Immediate::None => {}
+            Immediate::I32 => {
+                let value = self.next_immediate(line, items, pos)?;
+                write_i32(&mut self.code, parse_int(value)? as i32);
+            }
+            Immediate::I64 => {
+                let value = self.next_immediate(line, items, pos)?;
+                write_i64(&mut self.code, parse_int(value)? as i64);
+            }
+            Immediate::F32 => {
+                let value = self.next_immediate(line, items, pos)?;
+                let value = parse_float(value)? as f32;
+                self.code.extend_from_slice(&value.to_le_bytes());
+            }
+            Immediate::F64 => {
+                let value = self.next_immediate(line, items, pos)?;
+                let value = parse_float(value)?;
+                self.code.extend_from_slice(&value.to_le_bytes());
+            }
+            Immediate::Label => {
+                let label = self.next_immediate(line, items, pos)?;
+                let depth = self.label_depth(label)?;
+                write_u32(&mut self.code, depth);
+            }
+            Immediate::LabelTable => {
+                let mut depths = vec![];
+                while let Some(label) = items.get(*pos).and_then(Sexpr::atom) {
+                    if !(label.starts_with('$')
+                        || label.starts_with(|c: char| c.is_ascii_digit()))
+                    {
+                        break;
+                    }
+                    depths.push(self.label_depth(items.get(*pos).unwrap())?);
+                    *pos += 1;
+                }
+                let (default, targets) =
+                    depths.split_last().ok_or_else(|| {
+                        AssembleError::new(line, "expected labels")
+                    })?;
+                write_len(&mut self.code, targets.len());
+                for &target in targets {
+                    write_u32(&mut self.code, target);
+                }
+                write_u32(&mut self.code, *default);
+            }
+            Immediate::Local => {
+                let local = self.next_immediate(line, items, pos)?;
+                let idx = resolve(self.local_names, local)?;
+                write_u32(&mut self.code, idx);
+            }
+            Immediate::Global => {
+                let global = self.next_immediate(line, items, pos)?;
+                let idx = resolve(&self.declarations.global_names, global)?;
+                write_u32(&mut self.code, idx);
+            }
+            Immediate::Func => {
+                let func = self.next_immediate(line, items, pos)?;
+                let idx = resolve(&self.declarations.func_names, func)?;
+                write_u32(&mut self.code, idx);
+            }
+            Immediate::Tag => {
+                let tag = self.next_immediate(line, items, pos)?;
+                let idx = resolve(&self.declarations.tag_names, tag)?;
+                write_u32(&mut self.code, idx);
+            }
+            Immediate::CallIndirect => {
+                let ty = items
+                    .get(*pos)
+                    .and_then(|t| t.list_of("type"))
+                    .and_then(|t| t.first())
+                    .ok_or_else(|| {
+                        AssembleError::new(line, "expected (type ...)")
+                    })?;
+                *pos += 1;
+                let idx = resolve(&self.declarations.type_names, ty)?;
+                write_u32(&mut self.code, idx);
+                write_u32(&mut self.code, 0); // table zero
+            }
+            Immediate::Memarg(natural_align) => {
+                let mut offset = 0;
+                let mut align = natural_align;
+                while let Some(arg) = items.get(*pos).and_then(Sexpr::atom) {
+                    if let Some(value) = arg.strip_prefix("offset=") {
+                        offset = parse_int_text(value, line)? as u32;
+                    } else if let Some(value) = arg.strip_prefix("align=") {
+                        align = (parse_int_text(value, line)? as u32)
+                            .trailing_zeros();
+                    } else {
+                        break;
+                    }
+                    *pos += 1;
+                }
+                write_u32(&mut self.code, align);
+                write_u32(&mut self.code, offset);
+            }
+        }
+        Ok(())
+    }
+
+    fn next_immediate<'i>(
+        &self,
+        line: usize,
+        items: &'i [Sexpr<'t>],
+        pos: &mut usize,
+    ) -> AssembleResult<&'i Sexpr<'t>> {
+        let item = items
+            .get(*pos)
+            .filter(|i| i.atom().is_some())
+            .ok_or_else(|| AssembleError::new(line, "expected immediate"))?;
+        *pos += 1;
+        Ok(item)
+    }
+
+    fn label_depth(&self, label: &Sexpr<'t>) -> AssembleResult<u32> {
+        let text = label.atom().unwrap_or_default();
+        if text.starts_with('$') {
+            self.labels
+                .iter()
+                .rev()
+                .position(|l| *l == Some(text))
+                .map(|depth| depth as u32)
+                .ok_or_else(|| {
+                    AssembleError::new(
+                        label.line(),
+                        format!("unknown label {}", text),
+                    )
+                })
+        } else {
+            Ok(parse_int(label)? as u32)
+        }
+    }
+}
+
+/// Splits off an optional `$name` at the start.
+fn split_name<'t, 'm>(
+    items: &'m [Sexpr<'t>],
+) -> (Option<&'t str>, &'m [Sexpr<'t>]) {
+    match items.split_first() {
+        Some((first, rest)) => match first.atom() {
+            Some(name) if name.starts_with('$') => (Some(name), rest),
+            _ => (None, items),
+        },
+        None => (None, items),
+    }
+}
+
+fn split_keyword<'t, 'm>(
+    item: &'m Sexpr<'t>,
+) -> AssembleResult<(&'t str, &'m [Sexpr<'t>])> {
+    match item.list() {
+        Some([head, rest @ ..]) => match head.atom() {
+            Some(keyword) => Ok((keyword, rest)),
+            None => Err(AssembleError::new(item.line(), "expected keyword")),
+        },
+        _ => Err(AssembleError::new(item.line(), "expected list")),
+    }
+}
+
+/// Unwraps `(offset ...)` if present, otherwise the item itself is the
+/// expression.
+fn offset_expr<'t, 'm>(item: &'m Sexpr<'t>) -> &'m [Sexpr<'t>] {
+    item.list_of("offset")
+        .unwrap_or_else(|| std::slice::from_ref(item))
+}
+
+fn limits<'t, 'm>(
+    line: usize,
+    items: &'m [Sexpr<'t>],
+) -> AssembleResult<(Limits, &'m [Sexpr<'t>])> {
+    let (min, rest) = items
+        .split_first()
+        .ok_or_else(|| AssembleError::new(line, "expected limits"))?;
+    let min = parse_int(min)? as u32;
+    match rest.split_first() {
+        Some((max, rest)) if max.atom().is_some_and(is_number) => Ok((
+            Limits {
+                min,
+                max: Some(parse_int(max)? as u32),
+            },
+            rest,
+        )),
+        _ => Ok((Limits { min, max: None }, rest)),
+    }
+}
+
+fn write_limits(buf: &mut Vec<u8>, limits: &Limits) {
+    match limits.max {
+        Some(max) => {
+            buf.push(1);
+            write_u32(buf, limits.min);
+            write_u32(buf, max);
+        }
+        None => {
+            buf.push(0);
+            write_u32(buf, limits.min);
+        }
+    }
+}
+
+/// Writes locals as runs of the same type.
+fn write_local_groups(buf: &mut Vec<u8>, locals: &[u8]) {
+    let groups: Vec<&[u8]> = locals.chunk_by(|a, b| a == b).collect();
+    write_len(buf, groups.len());
+    for group in groups {
+        write_len(buf, group.len());
+        buf.push(group[0]);
+    }
+}
+
+fn val_type(item: &Sexpr) -> AssembleResult<u8> {
+    match item.atom() {
+        Some("i32") => Ok(0x7F),
+        Some("i64") => Ok(0x7E),
+        Some("f32") => Ok(0x7D),
+        Some("f64") => Ok(0x7C),
+        Some("funcref") => Ok(0x70),
+        Some("externref") => Ok(0x6F),
+        _ => Err(AssembleError::new(item.line(), "expected value type")),
+    }
+}
+
+fn string(item: &Sexpr) -> AssembleResult<Vec<u8>> {
+    match item {
+        Sexpr::Str { bytes, .. } => Ok(bytes.clone()),
+        _ => Err(AssembleError::new(item.line(), "expected string")),
+    }
+}
+
+fn resolve(names: &HashMap<&str, u32>, item: &Sexpr) -> AssembleResult<u32> {
+    match item.atom() {
+        Some(name) if name.starts_with('$') => {
+            names.get(name).copied().ok_or_else(|| {
+                AssembleError::new(
+                    item.line(),
+                    format!("unknown name {}", name),
+                )
+            })
+        }
+        Some(_) => Ok(parse_int(item)? as u32),
+        None => Err(AssembleError::new(item.line(), "expected name or index")),
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    text.trim_start_matches(['-', '+'])
+        .starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Parses a decimal or hexadecimal integer, which may be signed or exceed
+/// the signed range as unsigned, leaving the interpretation to the caller.
+fn parse_int(item: &Sexpr) -> AssembleResult<i128> {
+    parse_int_text(item.atom().unwrap_or_default(), item.line())
+}
+
+fn parse_int_text(text: &str, line: usize) -> AssembleResult<i128> {
+    let text = text.replace('_', "");
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => digits.parse::<i128>(),
+    }
+    .map_err(|_| {
+        AssembleError::new(line, format!("invalid integer {}", text))
+    })?;
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+fn parse_float(item: &Sexpr) -> AssembleResult<f64> {
+    let text = item.atom().unwrap_or_default().replace('_', "");
+    text.parse::<f64>().map_err(|_| {
+        AssembleError::new(item.line(), format!("invalid float {}", text))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::{
+        analysis::{IrGen, SemanticAnalysis},
+        codegen::{FeatureOptions, MemoryLayout, MemoryOptions, write_wasm},
+        diagnostic::Diagnostics,
+        parse::Parser,
+        source::SourceSet,
+    };
+
+    use super::*;
+
+    /// Lisp files in the directory, sorted by name.
+    fn lisp_files(dir: &str) -> Vec<PathBuf> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lisp"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Compiles the program together with the runtime and assembles it with
+    /// each of the features.
+    fn assemble_test_program(program: &Path, features: &[FeatureOptions]) {
+        let mut paths = lisp_files("rt");
+        paths.push(program.to_path_buf());
+        let mut sources = SourceSet::new();
+        for path in &paths {
+            sources.load(path).unwrap();
+        }
+        let mut diagnostics = Diagnostics::new();
+        let asts = sources
+            .iter()
+            .filter_map(|source| diagnostics.ok(Parser::new(source).parse()))
+            .collect();
+        let analysis = SemanticAnalysis::analyze(&mut diagnostics, &asts);
+        diagnostics.ensure_no_errors().unwrap();
+        let program_ir = IrGen::generate(&analysis)
+            .unwrap_or_else(|err| panic!("{}", err));
+        let layout =
+            MemoryLayout::new(&program_ir, &MemoryOptions::default()).unwrap();
+        for features in features {
+            let mut wasm = vec![];
+            write_wasm(&mut wasm, &program_ir, &layout, features)
+                .unwrap_or_else(|err| {
+                    panic!("{} with {:?}: {}", program.display(), features, err)
+                });
+            assert_eq!(&wasm[..4], b"\0asm");
+        }
+    }
+
+    #[test]
+    fn empty_module() {
+        assert_eq!(
+            assemble("(module)").unwrap(),
+            vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn exported_function() {
+        let wasm = assemble(
+            "(module (func $add (export \"add\") (param $a i32) (param $b i32) (result i32)
+                local.get $a
+                local.get $b
+                i32.add))",
+        )
+        .unwrap();
+        let expected_code_section = [
+            SECTION_CODE,
+            9,    // section size
+            1,    // function count
+            7,    // body size
+            0,    // no local groups
+            0x20, // local.get 0
+            0,
+            0x20, // local.get 1
+            1,
+            0x6A, // i32.add
+            OP_END,
+        ];
+        assert!(
+            wasm.windows(expected_code_section.len())
+                .any(|w| w == expected_code_section)
+        );
+        let expected_type_section =
+            [SECTION_TYPE, 7, 1, 0x60, 2, 0x7F, 0x7F, 1, 0x7F];
+        assert_eq!(
+            &wasm[8..8 + expected_type_section.len()],
+            &expected_type_section
+        );
+    }
+
+    #[test]
+    fn labels_resolve_to_relative_depth() {
+        let wasm = assemble(
+            "(module (func
+                (block $outer
+                    (loop $inner
+                        br $outer
+                        br $inner
+                        (br_if $outer (i32.const 1))))))",
+        )
+        .unwrap();
+        let expected_body = [
+            OP_BLOCK,
+            BLOCK_TYPE_EMPTY,
+            OP_LOOP,
+            BLOCK_TYPE_EMPTY,
+            0x0C,
+            1,
+            0x0C,
+            0,
+            0x41,
+            1,
+            0x0D,
+            1,
+            OP_END,
+            OP_END,
+            OP_END,
+        ];
+        assert!(
+            wasm.windows(expected_body.len())
+                .any(|w| w == expected_body)
+        );
+    }
+
+    #[test]
+    fn unknown_instruction_is_error() {
+        let err = assemble("(module (func\n i32.frobnicate))").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+    #[test]
+    fn test_programs_assemble_with_all_features() {
+        let features = [
+            FeatureOptions::new(true, false),
+            FeatureOptions::new(false, false),
+            FeatureOptions::new(true, true),
+            FeatureOptions::new(false, true),
+        ];
+        for program in lisp_files("test/lisp") {
+            assemble_test_program(&program, &features);
+        }
+    }
+}
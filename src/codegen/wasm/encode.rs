@@ -0,0 +1,106 @@
+//! Primitives of the binary format.
+
+pub const MAGIC: &[u8] = b"\0asm";
+pub const VERSION: &[u8] = &[1, 0, 0, 0];
+
+pub fn write_u32(buf: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+pub fn write_i32(buf: &mut Vec<u8>, value: i32) {
+    write_i64(buf, value as i64)
+}
+
+pub fn write_i64(buf: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+pub fn write_len(buf: &mut Vec<u8>, len: usize) {
+    write_u32(buf, u32::try_from(len).expect("length exceeds 32 bits"))
+}
+
+/// Writes a vector of bytes, e.g. a name or the payload of a data segment.
+pub fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+/// Writes a section with the given ID, omitting it if there is no content.
+pub fn write_section(buf: &mut Vec<u8>, id: u8, content: &[u8]) {
+    if content.is_empty() {
+        return;
+    }
+    buf.push(id);
+    write_bytes(buf, content);
+}
+
+/// Writes a section that is a vector of entries, omitting it if there are
+/// no entries.
+pub fn write_vec_section(
+    buf: &mut Vec<u8>,
+    id: u8,
+    count: usize,
+    entries: &[u8],
+) {
+    if count == 0 {
+        return;
+    }
+    let mut content = vec![];
+    write_len(&mut content, count);
+    content.extend_from_slice(entries);
+    write_section(buf, id, &content);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unsigned(value: u32) -> Vec<u8> {
+        let mut buf = vec![];
+        write_u32(&mut buf, value);
+        buf
+    }
+
+    fn signed(value: i64) -> Vec<u8> {
+        let mut buf = vec![];
+        write_i64(&mut buf, value);
+        buf
+    }
+
+    #[test]
+    fn unsigned_leb128() {
+        assert_eq!(unsigned(0), vec![0]);
+        assert_eq!(unsigned(127), vec![0x7F]);
+        assert_eq!(unsigned(128), vec![0x80, 0x01]);
+        assert_eq!(unsigned(624485), vec![0xE5, 0x8E, 0x26]);
+        assert_eq!(unsigned(u32::MAX), vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    }
+
+    #[test]
+    fn signed_leb128() {
+        assert_eq!(signed(0), vec![0]);
+        assert_eq!(signed(63), vec![0x3F]);
+        assert_eq!(signed(64), vec![0xC0, 0x00]);
+        assert_eq!(signed(-1), vec![0x7F]);
+        assert_eq!(signed(-64), vec![0x40]);
+        assert_eq!(signed(-65), vec![0xBF, 0x7F]);
+        assert_eq!(signed(-123456), vec![0xC0, 0xBB, 0x78]);
+    }
+}
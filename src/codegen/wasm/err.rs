@@ -0,0 +1,40 @@
+use std::{error::Error, fmt, io};
+
+pub type AssembleResult<T> = Result<T, AssembleError>;
+
+/// Web assembly text could not be assembled into a binary module.
+///
+/// Since we only ever assemble text that we generated ourselves, this
+/// indicates a bug in the code generator or in rt.wat.
+#[derive(Debug)]
+pub struct AssembleError {
+    line: usize,
+    msg: String,
+}
+
+impl AssembleError {
+    pub fn new(line: usize, msg: impl Into<String>) -> Self {
+        Self {
+            line,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to assemble web assembly text at line {}: {}",
+            self.line, self.msg
+        )
+    }
+}
+
+impl Error for AssembleError {}
+
+impl From<AssembleError> for io::Error {
+    fn from(value: AssembleError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
@@ -0,0 +1,248 @@
+//! Opcodes of plain instructions, i.e. all instructions except for the
+//! structured control instructions like `block`, `loop` and `if`.
+
+/// Kind of the immediate arguments following the opcode of an instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Immediate {
+    None,
+    I32,
+    I64,
+    F32,
+    F64,
+    Label,
+    LabelTable,
+    Local,
+    Global,
+    Func,
+    Tag,
+    /// Type use like `(type $t)` followed by the implicit table zero.
+    CallIndirect,
+    /// Optional offset and alignment, the alignment defaulting to the given
+    /// natural alignment as a power of two.
+    Memarg(u32),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Opcode {
+    pub bytes: &'static [u8],
+    pub immediate: Immediate,
+}
+
+const fn op(bytes: &'static [u8], immediate: Immediate) -> Option<Opcode> {
+    Some(Opcode { bytes, immediate })
+}
+
+const fn plain(bytes: &'static [u8]) -> Option<Opcode> {
+    op(bytes, Immediate::None)
+}
+
+pub fn lookup(name: &str) -> Option<Opcode> {
+    use Immediate::{
+        CallIndirect, F32, F64, Func, Global, I32, I64, Label, LabelTable,
+        Local, Memarg, Tag,
+    };
+    match name {
+        "unreachable" => plain(&[0x00]),
+        "nop" => plain(&[0x01]),
+        "br" => op(&[0x0C], Label),
+        "br_if" => op(&[0x0D], Label),
+        "br_table" => op(&[0x0E], LabelTable),
+        "return" => plain(&[0x0F]),
+        "call" => op(&[0x10], Func),
+        "call_indirect" => op(&[0x11], CallIndirect),
+        "return_call" => op(&[0x12], Func),
+        "return_call_indirect" => op(&[0x13], CallIndirect),
+        "throw" => op(&[0x08], Tag),
+        "rethrow" => op(&[0x09], Label),
+        "drop" => plain(&[0x1A]),
+        "select" => plain(&[0x1B]),
+
+        "local.get" => op(&[0x20], Local),
+        "local.set" => op(&[0x21], Local),
+        "local.tee" => op(&[0x22], Local),
+        "global.get" => op(&[0x23], Global),
+        "global.set" => op(&[0x24], Global),
+
+        "i32.load" => op(&[0x28], Memarg(2)),
+        "i64.load" => op(&[0x29], Memarg(3)),
+        "f32.load" => op(&[0x2A], Memarg(2)),
+        "f64.load" => op(&[0x2B], Memarg(3)),
+        "i32.load8_s" => op(&[0x2C], Memarg(0)),
+        "i32.load8_u" => op(&[0x2D], Memarg(0)),
+        "i32.load16_s" => op(&[0x2E], Memarg(1)),
+        "i32.load16_u" => op(&[0x2F], Memarg(1)),
+        "i64.load8_s" => op(&[0x30], Memarg(0)),
+        "i64.load8_u" => op(&[0x31], Memarg(0)),
+        "i64.load16_s" => op(&[0x32], Memarg(1)),
+        "i64.load16_u" => op(&[0x33], Memarg(1)),
+        "i64.load32_s" => op(&[0x34], Memarg(2)),
+        "i64.load32_u" => op(&[0x35], Memarg(2)),
+        "i32.store" => op(&[0x36], Memarg(2)),
+        "i64.store" => op(&[0x37], Memarg(3)),
+        "f32.store" => op(&[0x38], Memarg(2)),
+        "f64.store" => op(&[0x39], Memarg(3)),
+        "i32.store8" => op(&[0x3A], Memarg(0)),
+        "i32.store16" => op(&[0x3B], Memarg(1)),
+        "i64.store8" => op(&[0x3C], Memarg(0)),
+        "i64.store16" => op(&[0x3D], Memarg(1)),
+        "i64.store32" => op(&[0x3E], Memarg(2)),
+        "memory.size" => plain(&[0x3F, 0x00]),
+        "memory.grow" => plain(&[0x40, 0x00]),
+        "memory.copy" => plain(&[0xFC, 0x0A, 0x00, 0x00]),
+        "memory.fill" => plain(&[0xFC, 0x0B, 0x00]),
+
+        "i32.const" => op(&[0x41], I32),
+        "i64.const" => op(&[0x42], I64),
+        "f32.const" => op(&[0x43], F32),
+        "f64.const" => op(&[0x44], F64),
+
+        "i32.eqz" => plain(&[0x45]),
+        "i32.eq" => plain(&[0x46]),
+        "i32.ne" => plain(&[0x47]),
+        "i32.lt_s" => plain(&[0x48]),
+        "i32.lt_u" => plain(&[0x49]),
+        "i32.gt_s" => plain(&[0x4A]),
+        "i32.gt_u" => plain(&[0x4B]),
+        "i32.le_s" => plain(&[0x4C]),
+        "i32.le_u" => plain(&[0x4D]),
+        "i32.ge_s" => plain(&[0x4E]),
+        "i32.ge_u" => plain(&[0x4F]),
+
+        "i64.eqz" => plain(&[0x50]),
+        "i64.eq" => plain(&[0x51]),
+        "i64.ne" => plain(&[0x52]),
+        "i64.lt_s" => plain(&[0x53]),
+        "i64.lt_u" => plain(&[0x54]),
+        "i64.gt_s" => plain(&[0x55]),
+        "i64.gt_u" => plain(&[0x56]),
+        "i64.le_s" => plain(&[0x57]),
+        "i64.le_u" => plain(&[0x58]),
+        "i64.ge_s" => plain(&[0x59]),
+        "i64.ge_u" => plain(&[0x5A]),
+
+        "f32.eq" => plain(&[0x5B]),
+        "f32.ne" => plain(&[0x5C]),
+        "f32.lt" => plain(&[0x5D]),
+        "f32.gt" => plain(&[0x5E]),
+        "f32.le" => plain(&[0x5F]),
+        "f32.ge" => plain(&[0x60]),
+
+        "f64.eq" => plain(&[0x61]),
+        "f64.ne" => plain(&[0x62]),
+        "f64.lt" => plain(&[0x63]),
+        "f64.gt" => plain(&[0x64]),
+        "f64.le" => plain(&[0x65]),
+        "f64.ge" => plain(&[0x66]),
+
+        "i32.clz" => plain(&[0x67]),
+        "i32.ctz" => plain(&[0x68]),
+        "i32.popcnt" => plain(&[0x69]),
+        "i32.add" => plain(&[0x6A]),
+        "i32.sub" => plain(&[0x6B]),
+        "i32.mul" => plain(&[0x6C]),
+        "i32.div_s" => plain(&[0x6D]),
+        "i32.div_u" => plain(&[0x6E]),
+        "i32.rem_s" => plain(&[0x6F]),
+        "i32.rem_u" => plain(&[0x70]),
+        "i32.and" => plain(&[0x71]),
+        "i32.or" => plain(&[0x72]),
+        "i32.xor" => plain(&[0x73]),
+        "i32.shl" => plain(&[0x74]),
+        "i32.shr_s" => plain(&[0x75]),
+        "i32.shr_u" => plain(&[0x76]),
+        "i32.rotl" => plain(&[0x77]),
+        "i32.rotr" => plain(&[0x78]),
+
+        "i64.clz" => plain(&[0x79]),
+        "i64.ctz" => plain(&[0x7A]),
+        "i64.popcnt" => plain(&[0x7B]),
+        "i64.add" => plain(&[0x7C]),
+        "i64.sub" => plain(&[0x7D]),
+        "i64.mul" => plain(&[0x7E]),
+        "i64.div_s" => plain(&[0x7F]),
+        "i64.div_u" => plain(&[0x80]),
+        "i64.rem_s" => plain(&[0x81]),
+        "i64.rem_u" => plain(&[0x82]),
+        "i64.and" => plain(&[0x83]),
+        "i64.or" => plain(&[0x84]),
+        "i64.xor" => plain(&[0x85]),
+        "i64.shl" => plain(&[0x86]),
+        "i64.shr_s" => plain(&[0x87]),
+        "i64.shr_u" => plain(&[0x88]),
+        "i64.rotl" => plain(&[0x89]),
+        "i64.rotr" => plain(&[0x8A]),
+
+        "f32.abs" => plain(&[0x8B]),
+        "f32.neg" => plain(&[0x8C]),
+        "f32.ceil" => plain(&[0x8D]),
+        "f32.floor" => plain(&[0x8E]),
+        "f32.trunc" => plain(&[0x8F]),
+        "f32.nearest" => plain(&[0x90]),
+        "f32.sqrt" => plain(&[0x91]),
+        "f32.add" => plain(&[0x92]),
+        "f32.sub" => plain(&[0x93]),
+        "f32.mul" => plain(&[0x94]),
+        "f32.div" => plain(&[0x95]),
+        "f32.min" => plain(&[0x96]),
+        "f32.max" => plain(&[0x97]),
+        "f32.copysign" => plain(&[0x98]),
+
+        "f64.abs" => plain(&[0x99]),
+        "f64.neg" => plain(&[0x9A]),
+        "f64.ceil" => plain(&[0x9B]),
+        "f64.floor" => plain(&[0x9C]),
+        "f64.trunc" => plain(&[0x9D]),
+        "f64.nearest" => plain(&[0x9E]),
+        "f64.sqrt" => plain(&[0x9F]),
+        "f64.add" => plain(&[0xA0]),
+        "f64.sub" => plain(&[0xA1]),
+        "f64.mul" => plain(&[0xA2]),
+        "f64.div" => plain(&[0xA3]),
+        "f64.min" => plain(&[0xA4]),
+        "f64.max" => plain(&[0xA5]),
+        "f64.copysign" => plain(&[0xA6]),
+
+        "i32.wrap_i64" => plain(&[0xA7]),
+        "i32.trunc_f32_s" => plain(&[0xA8]),
+        "i32.trunc_f32_u" => plain(&[0xA9]),
+        "i32.trunc_f64_s" => plain(&[0xAA]),
+        "i32.trunc_f64_u" => plain(&[0xAB]),
+        "i64.extend_i32_s" => plain(&[0xAC]),
+        "i64.extend_i32_u" => plain(&[0xAD]),
+        "i64.trunc_f32_s" => plain(&[0xAE]),
+        "i64.trunc_f32_u" => plain(&[0xAF]),
+        "i64.trunc_f64_s" => plain(&[0xB0]),
+        "i64.trunc_f64_u" => plain(&[0xB1]),
+        "f32.convert_i32_s" => plain(&[0xB2]),
+        "f32.convert_i32_u" => plain(&[0xB3]),
+        "f32.convert_i64_s" => plain(&[0xB4]),
+        "f32.convert_i64_u" => plain(&[0xB5]),
+        "f32.demote_f64" => plain(&[0xB6]),
+        "f64.convert_i32_s" => plain(&[0xB7]),
+        "f64.convert_i32_u" => plain(&[0xB8]),
+        "f64.convert_i64_s" => plain(&[0xB9]),
+        "f64.convert_i64_u" => plain(&[0xBA]),
+        "f64.promote_f32" => plain(&[0xBB]),
+        "i32.reinterpret_f32" => plain(&[0xBC]),
+        "i64.reinterpret_f64" => plain(&[0xBD]),
+        "f32.reinterpret_i32" => plain(&[0xBE]),
+        "f64.reinterpret_i64" => plain(&[0xBF]),
+
+        "i32.extend8_s" => plain(&[0xC0]),
+        "i32.extend16_s" => plain(&[0xC1]),
+        "i64.extend8_s" => plain(&[0xC2]),
+        "i64.extend16_s" => plain(&[0xC3]),
+        "i64.extend32_s" => plain(&[0xC4]),
+
+        "i32.trunc_sat_f32_s" => plain(&[0xFC, 0x00]),
+        "i32.trunc_sat_f32_u" => plain(&[0xFC, 0x01]),
+        "i32.trunc_sat_f64_s" => plain(&[0xFC, 0x02]),
+        "i32.trunc_sat_f64_u" => plain(&[0xFC, 0x03]),
+        "i64.trunc_sat_f32_s" => plain(&[0xFC, 0x04]),
+        "i64.trunc_sat_f32_u" => plain(&[0xFC, 0x05]),
+        "i64.trunc_sat_f64_s" => plain(&[0xFC, 0x06]),
+        "i64.trunc_sat_f64_u" => plain(&[0xFC, 0x07]),
+
+        _ => None,
+    }
+}
@@ -0,0 +1,294 @@
+use super::err::{AssembleError, AssembleResult};
+
+/// A node in web assembly text, after stripping comments and whitespace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexpr<'t> {
+    /// Keywords, identifiers starting with `$`, numbers and things like
+    /// `offset=4`.
+    Atom {
+        text: &'t str,
+        line: usize,
+    },
+    /// A string literal with escapes already resolved.
+    Str {
+        bytes: Vec<u8>,
+        line: usize,
+    },
+    List {
+        items: Vec<Sexpr<'t>>,
+        line: usize,
+    },
+}
+
+impl<'t> Sexpr<'t> {
+    pub fn line(&self) -> usize {
+        match self {
+            Sexpr::Atom { line, .. } => *line,
+            Sexpr::Str { line, .. } => *line,
+            Sexpr::List { line, .. } => *line,
+        }
+    }
+
+    pub fn atom(&self) -> Option<&'t str> {
+        match self {
+            Sexpr::Atom { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self) -> Option<&[Sexpr<'t>]> {
+        match self {
+            Sexpr::List { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Gets the items of a list if the list starts with the given keyword,
+    /// excluding the keyword itself.
+    pub fn list_of(&self, keyword: &str) -> Option<&[Sexpr<'t>]> {
+        match self.list() {
+            Some([head, rest @ ..]) if head.atom() == Some(keyword) => {
+                Some(rest)
+            }
+            _ => None,
+        }
+    }
+
+    /// The first atom in the list, e.g. `func` for `(func $f ...)`.
+    pub fn keyword(&self) -> Option<&'t str> {
+        self.list()
+            .and_then(|items| items.first())
+            .and_then(Sexpr::atom)
+    }
+}
+
+/// Reads all top-level s-expressions from web assembly text.
+pub fn read(text: &str) -> AssembleResult<Vec<Sexpr<'_>>> {
+    let mut reader = Reader {
+        text,
+        pos: 0,
+        line: 1,
+    };
+    let mut toplevel = vec![];
+    while let Some(sexpr) = reader.next()? {
+        toplevel.push(sexpr);
+    }
+    Ok(toplevel)
+}
+
+struct Reader<'t> {
+    text: &'t str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'t> Reader<'t> {
+    fn next(&mut self) -> AssembleResult<Option<Sexpr<'t>>> {
+        self.skip_whitespace_and_comments()?;
+        let line = self.line;
+        match self.peek() {
+            None => Ok(None),
+            Some(b'(') => {
+                self.pos += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_whitespace_and_comments()?;
+                    match self.peek() {
+                        None => {
+                            return Err(AssembleError::new(
+                                line,
+                                "unclosed parenthesis",
+                            ));
+                        }
+                        Some(b')') => {
+                            self.pos += 1;
+                            return Ok(Some(Sexpr::List { items, line }));
+                        }
+                        Some(_) => items.push(self.next()?.unwrap()),
+                    }
+                }
+            }
+            Some(b')') => {
+                Err(AssembleError::new(line, "unexpected closing parenthesis"))
+            }
+            Some(b'"') => self.read_string().map(Some),
+            Some(_) => {
+                let start = self.pos;
+                while let Some(byte) = self.peek() {
+                    if byte.is_ascii_whitespace()
+                        || matches!(byte, b'(' | b')' | b'"' | b';')
+                    {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Ok(Some(Sexpr::Atom {
+                    text: &self.text[start..self.pos],
+                    line,
+                }))
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> AssembleResult<Sexpr<'t>> {
+        let line = self.line;
+        let mut bytes = vec![];
+        self.pos += 1; // opening quote
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(AssembleError::new(
+                        line,
+                        "unterminated string",
+                    ));
+                }
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(Sexpr::Str { bytes, line });
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    bytes.push(self.read_escape()?);
+                }
+                Some(byte) => {
+                    if byte == b'\n' {
+                        self.line += 1;
+                    }
+                    self.pos += 1;
+                    bytes.push(byte);
+                }
+            }
+        }
+    }
+
+    /// Reads the rest of an escape sequence after the backslash.
+    fn read_escape(&mut self) -> AssembleResult<u8> {
+        let line = self.line;
+        let escaped = match self.peek() {
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b'"') => b'"',
+            Some(b'\'') => b'\'',
+            Some(b'\\') => b'\\',
+            Some(hi) if hi.is_ascii_hexdigit() => {
+                let lo = self.text.as_bytes().get(self.pos + 1).copied();
+                let digits = lo
+                    .filter(u8::is_ascii_hexdigit)
+                    .map(|lo| [hi, lo])
+                    .ok_or_else(|| {
+                        AssembleError::new(line, "invalid hex escape")
+                    })?;
+                self.pos += 2;
+                let digits = std::str::from_utf8(&digits).unwrap();
+                return Ok(u8::from_str_radix(digits, 16).unwrap());
+            }
+            _ => return Err(AssembleError::new(line, "invalid escape")),
+        };
+        self.pos += 1;
+        Ok(escaped)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> AssembleResult<()> {
+        loop {
+            match self.peek() {
+                Some(b'\n') => {
+                    self.line += 1;
+                    self.pos += 1;
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.pos += 1,
+                Some(b';') if self.peek_at(1) == Some(b';') => {
+                    while let Some(byte) = self.peek() {
+                        if byte == b'\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                Some(b'(') if self.peek_at(1) == Some(b';') => {
+                    self.skip_block_comment()?
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips a possibly nested block comment `(; ... ;)`.
+    fn skip_block_comment(&mut self) -> AssembleResult<()> {
+        let line = self.line;
+        let mut depth = 0;
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(b'('), Some(b';')) => {
+                    depth += 1;
+                    self.pos += 2;
+                }
+                (Some(b';'), Some(b')')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some(byte), _) => {
+                    if byte == b'\n' {
+                        self.line += 1;
+                    }
+                    self.pos += 1;
+                }
+                (None, _) => {
+                    return Err(AssembleError::new(
+                        line,
+                        "unterminated block comment",
+                    ));
+                }
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<u8> {
+        self.text.as_bytes().get(self.pos + ahead).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_nested_with_comments() {
+        let sexprs = read(
+            "(module ;; comment\n (; block (; nested ;) ;) (func $f i32.const 1))",
+        )
+        .unwrap();
+        assert_eq!(sexprs.len(), 1);
+        let module = sexprs[0].list_of("module").unwrap();
+        assert_eq!(module.len(), 1);
+        let func = module[0].list_of("func").unwrap();
+        let atoms: Vec<_> = func.iter().map(|s| s.atom().unwrap()).collect();
+        assert_eq!(atoms, vec!["$f", "i32.const", "1"]);
+        assert_eq!(func[0].line(), 2);
+    }
+
+    #[test]
+    fn read_string_escapes() {
+        let sexprs = read(r#""a\00\FF\"\n""#).unwrap();
+        assert_eq!(
+            sexprs[0],
+            Sexpr::Str {
+                bytes: vec![b'a', 0, 0xFF, b'"', b'\n'],
+                line: 1
+            }
+        );
+    }
+
+    #[test]
+    fn unclosed_list_is_error() {
+        assert!(read("(module (func)").is_err());
+        assert!(read("(module))").is_err());
+    }
+}
@@ -5,28 +5,78 @@ use std::{
 };
 
 use crate::ir::{
-    AddressingMode, FunctionAttribute, Instruction, IrDataType, PlaceAddress,
-    Program, StaticData,
+    AddressingMode, DataAddress, FunctionAttribute, Instruction, IrDataType,
+    PlaceAddress, Program, StaticData,
 };
 
-use super::locals::{LocalPlacesInfo, LocalStrategy};
+use super::{
+    layout::MemoryLayout,
+    locals::{LocalPlacesInfo, LocalStrategy},
+};
+
+/// Hand-written parts of the runtime, the garbage collector in `rt/gc.wat`
+/// expects `$gc_mark_static_places` to be generated.
+const RUNTIME_PATHS: &[&str] = &["rt/rt.wat", "rt/gc.wat"];
+
+/// Type tag and length before the first place in a block of persistent places.
+const PLACES_HEADER_SIZE: i32 = 2 * mem::size_of::<i32>() as i32;
+
+/// Proposals on top of web assembly 1.0 that the output may use, which can be
+/// turned off for engines that do not support them yet.
+#[derive(Debug, Copy, Clone)]
+pub struct FeatureOptions {
+    /// Use `return_call` for calls in tail position, otherwise they are
+    /// ordinary calls that grow the stack.
+    tail_calls: bool,
+    /// Unwind throws with `throw` and `try`, otherwise every call is followed
+    /// by a check of `$thrown` and unwinding branches out of the functions.
+    exceptions: bool,
+}
 
-const RUNTIME_PATH: &str = "rt/rt.wat";
+impl FeatureOptions {
+    pub fn new(tail_calls: bool, exceptions: bool) -> Self {
+        Self {
+            tail_calls,
+            exceptions,
+        }
+    }
+}
 
-pub fn write_wat<W: Write>(w: &mut W, program: &Program) -> io::Result<()> {
+impl Default for FeatureOptions {
+    fn default() -> Self {
+        Self::new(true, false)
+    }
+}
+
+pub fn write_wat<W: Write>(
+    w: &mut W,
+    program: &Program,
+    layout: &MemoryLayout,
+    features: &FeatureOptions,
+) -> io::Result<()> {
     write!(w, "(module\n")?;
-    write!(w, "\t(import \"js\" \"mem\" (memory 10))\n")?; // reserves 640KiB
+    write!(
+        w,
+        "\t(import \"js\" \"mem\" (memory {}))\n",
+        layout.initial_pages()
+    )?;
     // we assume this is present to log at a specific memory offset with a specific len, assuming UTF-8
     write!(
         w,
         "\t(import \"console\" \"log\" (func $log (param i32 i32)))\n"
     )?;
     write_static_data(w, program.static_data())?;
+    write_runtime_data(w, layout)?;
     write_tables(w, program.static_data())?;
-    write_runtime_variables(w, program.static_data())?;
+    write_runtime_variables(w, layout)?;
+    if features.exceptions {
+        // carries nothing, the thrown list is in $thrown
+        write!(w, "\t(tag $unwind)\n")?;
+    }
     write_runtime_functions(w)?;
+    write_mark_static_places(w, program.static_data())?;
     for (idx, _) in program.functions().iter().enumerate() {
-        write_function(w, program, idx)?;
+        write_function(w, program, layout, features, idx)?;
     }
     write!(w, ")\n")?; // closing module
     Ok(())
@@ -52,30 +102,87 @@ fn write_tables<W: Write>(
 
 fn write_runtime_variables<W: Write>(
     w: &mut W,
-    static_data: &StaticData,
+    layout: &MemoryLayout,
 ) -> io::Result<()> {
-    let stack_start = static_data.data().len();
-    // reserve 10KiB of stack space right after the constant data, disregarding alignment
-    // the stack will grow there from the bottom by increasing stack_bottom
-    let stack_end = stack_start + 10 * 1024_usize;
-    let heap_start = stack_end;
+    write!(
+        w,
+        "\t(global $stack_start i32 (i32.const {}))\n",
+        layout.stack_start()
+    )?;
+    // the stack will grow from the bottom by increasing stack_bottom
     write!(
         w,
         "\t(global $stack_bottom (mut i32) (i32.const {}))\n",
-        stack_start
+        layout.stack_start()
     )?;
-    write!(w, "\t(global $stack_top i32 (i32.const {}))\n", stack_end)?;
+    write!(
+        w,
+        "\t(global $stack_top i32 (i32.const {}))\n",
+        layout.stack_end()
+    )?;
+    write!(
+        w,
+        "\t(global $heap_base i32 (i32.const {}))\n",
+        layout.heap_start()
+    )?;
+    // the heap grows by increasing heap_start when nothing fits in a free block
     write!(
         w,
         "\t(global $heap_start (mut i32) (i32.const {}))\n",
-        heap_start
+        layout.heap_start()
+    )?;
+    write!(w, "\t(global $free_list (mut i32) (i32.const 0))\n")?;
+    write!(w, "\t(global $heap_live (mut i32) (i32.const 0))\n")?;
+    // values of the last return, see `Instruction::LoadValues`
+    write!(w, "\t(global $values (mut i32) (i32.const 0))\n")?;
+    write!(
+        w,
+        "\t(global $max_pages i32 (i32.const {}))\n",
+        layout.max_pages()
+    )?;
+    write!(
+        w,
+        "\t(global $out_of_memory_message i32 (i32.const {}))\n",
+        layout.out_of_memory_message()
+    )?;
+    // list of tag and value while unwinding, see `Instruction::Throw`
+    write!(w, "\t(global $thrown (mut i32) (i32.const 0))\n")?;
+    write!(w, "\t(global $catchers (mut i32) (i32.const 0))\n")?;
+    write!(
+        w,
+        "\t(global $uncaught_throw_message i32 (i32.const {}))\n",
+        layout.uncaught_throw_message()
     )?;
     Ok(())
 }
 
 fn write_runtime_functions<W: Write>(w: &mut W) -> io::Result<()> {
-    let runtime = fs::read_to_string(RUNTIME_PATH)?;
-    write!(w, "\n{}\n", runtime)?;
+    for path in RUNTIME_PATHS {
+        let runtime = fs::read_to_string(path)?;
+        write!(w, "\n{}\n", runtime)?;
+    }
+    Ok(())
+}
+
+/// A region of code that catches throws, see `Instruction::EnterCatch`.
+struct OpenCatch {
+    num: i32,
+    tag: Option<PlaceAddress>,
+    to: PlaceAddress,
+}
+
+/// Global variables are roots for the garbage collector.
+fn write_mark_static_places<W: Write>(
+    w: &mut W,
+    static_data: &StaticData,
+) -> io::Result<()> {
+    write!(w, "\t(func $gc_mark_static_places\n")?;
+    for place in static_data.places() {
+        write!(w, "\t\ti32.const {}\n", place.offset())?;
+        write!(w, "\t\ti32.load\n")?;
+        write!(w, "\t\tcall $gc_mark\n")?;
+    }
+    write!(w, "\t)\n")?;
     Ok(())
 }
 
@@ -93,16 +200,33 @@ fn write_static_data<W: Write>(
     Ok(())
 }
 
+/// Messages of the runtime that are not part of the program.
+fn write_runtime_data<W: Write>(
+    w: &mut W,
+    layout: &MemoryLayout,
+) -> io::Result<()> {
+    write!(
+        w,
+        "\t(data (i32.const {}) {})\n",
+        layout.runtime_data_start(),
+        WebassemblyString(layout.runtime_data())
+    )?;
+    Ok(())
+}
+
 fn write_function<W: Write>(
     w: &mut W,
     program: &Program,
+    layout: &MemoryLayout,
+    features: &FeatureOptions,
     idx: usize,
 ) -> io::Result<()> {
     let static_data = program.static_data();
     let function = &program.functions()[idx];
-    let locals = LocalPlacesInfo::extract(function, program);
+    let locals = LocalPlacesInfo::extract(function);
     let mut next_block_num = 1;
     let mut block_stack: Vec<i32> = vec![];
+    let mut catch_stack: Vec<OpenCatch> = vec![];
 
     write!(w, ";; {}\n", function.name())?;
     write!(w, "\t(func $fun{} ", idx)?;
@@ -113,25 +237,76 @@ fn write_function<W: Write>(
         w,
         "(param $param_head i32) (param $persistent_bottom i32) (result i32) (local $tmp i32) (local $retval i32)\n"
     )?;
+    let catches = function
+        .instructions()
+        .iter()
+        .any(|inst| matches!(inst, Instruction::EnterCatch { .. }));
+    if features.exceptions && catches {
+        // stack bottom of the function, to release the frames of the
+        // functions that an exception unwinds
+        write!(w, "\t\t(local $frame_bottom i32)\n")?;
+    }
 
     // function prologue
     if let Some(ref locals) = locals {
         write!(w, "\t\t;; start of function prologue\n")?;
+        write!(w, "\t\ti32.const {}\n", locals.stack_len())?;
+        write!(w, "\t\ti32.const {}\n", layout.stack_overflow_message(idx))?;
+        write!(w, "\t\tcall $check_stack\n")?;
+        // keep the argument list for the garbage collector, the caller may
+        // have released its frame for a tail call
+        write!(w, "\t\tglobal.get $stack_bottom\n")?;
+        write!(w, "\t\tlocal.get $param_head\n")?;
+        write!(w, "\t\ti32.store\n")?;
+        write!(w, "\t\ti32.const {}\n", mem::size_of::<i32>())?;
+        write!(w, "\t\tcall $inc_stack_bottom\n")?;
         match locals.strategy() {
             LocalStrategy::Stack => {
+                // the garbage collector scans the stack, so clear out any
+                // stale addresses from earlier calls
+                write!(w, "\t\tglobal.get $stack_bottom\n")?;
+                write!(w, "\t\ti32.const 0\n")?;
+                write!(w, "\t\ti32.const {}\n", locals.len())?;
+                write!(w, "\t\tmemory.fill\n")?;
                 write!(w, "\t\ti32.const {}\n", locals.len())?;
                 write!(w, "\t\tcall $inc_stack_bottom\n")?;
             }
             LocalStrategy::Heap => {
-                if function
+                let is_lambda = function
                     .attributes()
-                    .contains(&FunctionAttribute::CreatesPersistentPlaces)
-                {
-                    // if persistent, reserve space for all the lambdas on the top-level function
-                    write!(w, "\t\ti32.const {}\n", locals.len())?;
-                    write!(w, "\t\tcall $alloc_heap\n")?;
-                    write!(w, "\t\tlocal.set $persistent_bottom\n")?;
-                } // lambdas themselves don't need to reserve anything in the prologue
+                    .contains(&FunctionAttribute::AcceptsPersistentPlaces);
+                // the places of the parent may not be reachable from
+                // anything else after a tail call, so keep them in the stack
+                // slot of the block while allocating
+                write!(w, "\t\tglobal.get $stack_bottom\n")?;
+                if is_lambda {
+                    write!(w, "\t\tlocal.get $persistent_bottom\n")?;
+                    write!(w, "\t\ti32.const {}\n", PLACES_HEADER_SIZE)?;
+                    write!(w, "\t\ti32.sub\n")?;
+                } else {
+                    write!(w, "\t\ti32.const 0\n")?;
+                }
+                write!(w, "\t\ti32.store\n")?;
+                write!(w, "\t\ti32.const {}\n", mem::size_of::<i32>())?;
+                write!(w, "\t\tcall $inc_stack_bottom\n")?;
+                // every call gets places of its own
+                write!(w, "\t\ti32.const {}\n", locals.len())?;
+                write!(w, "\t\tcall $alloc_places\n")?;
+                write!(w, "\t\tlocal.set $persistent_bottom\n")?;
+                if is_lambda {
+                    // the first place links to the block of the parent
+                    write!(w, "\t\tlocal.get $persistent_bottom\n")?;
+                    write_load_stack_slot(w)?;
+                    write!(w, "\t\ti32.load\n")?;
+                    write!(w, "\t\ti32.store\n")?;
+                }
+                // replace it with the block of places, so the garbage
+                // collector keeps that while the function runs
+                write_load_stack_slot(w)?;
+                write!(w, "\t\tlocal.get $persistent_bottom\n")?;
+                write!(w, "\t\ti32.const {}\n", PLACES_HEADER_SIZE)?;
+                write!(w, "\t\ti32.sub\n")?;
+                write!(w, "\t\ti32.store\n")?;
             }
         }
         write!(w, "\t\t;; end of function prologue\n")?;
@@ -147,10 +322,75 @@ fn write_function<W: Write>(
                 write!(w, "\t\t\ti32.const {}\n", data.offset())?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
+            Instruction::CharCode { character, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_place_referee(w, &locals, character)?;
+                // skip the type tag to load the code
+                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+                write!(w, "\t\t\ti32.add\n")?;
+                write!(w, "\t\t\ti32.load\n")?;
+                write!(w, "\t\t\tcall $make_num\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::CodeChar { code, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_number(w, &locals, code)?;
+                write!(w, "\t\t\tcall $make_char\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::CharAt { string, index, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_place_referee(w, &locals, string)?;
+                write_load_number(w, &locals, index)?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tcall $char_at\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::CharString { character, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_place_referee(w, &locals, character)?;
+                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+                write!(w, "\t\t\ti32.add\n")?;
+                write!(w, "\t\t\ti32.load\n")?;
+                write!(w, "\t\t\tcall $char_string\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::MakeVector { length, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_number(w, &locals, length)?;
+                write!(w, "\t\t\tcall $make_vector\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::LoadElement { vector, index, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_element_address(w, &locals, vector, index)?;
+                write!(w, "\t\t\ti32.load\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::StoreElement {
+                vector,
+                index,
+                value,
+            } => {
+                write_element_address(w, &locals, vector, index)?;
+                write_load_place_referee(w, &locals, value)?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::LoadLength { of, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_place_referee(w, &locals, of)?;
+                write!(w, "\t\t\tcall $length_of\n")?;
+                write!(w, "\t\t\tcall $make_num\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
             Instruction::LoadTypeTag { of, to } => {
                 write_load_place_self_address(w, &locals, to)?;
                 write_load_place_referee(w, &locals, of)?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write!(w, "\t\t\tcall $type_tag_of\n")?;
                 write!(w, "\t\t\tcall $make_num\n")?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
@@ -172,36 +412,26 @@ fn write_function<W: Write>(
                 write!(w, "\t\t\ti32.load\n")?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
-            Instruction::Cons { car, cdr, to } => {
-                // type tag, car address, cdr address
-                write_heap_alloc(w, mem::size_of::<i32>() * 3)?;
-                write!(w, "\t\t\tlocal.set $tmp\n")?; // remember the allocation
-
-                // write tag at offset 0
-                write!(w, "\t\t\tlocal.get $tmp\n")?;
-                let tag = IrDataType::ListNode.to_u32();
-                write!(w, "\t\t\ti32.const {}\n", tag)?;
-                write!(w, "\t\t\ti32.store\n")?;
-
-                // load car address and write at offset 1
-                write!(w, "\t\t\tlocal.get $tmp\n")?;
+            Instruction::StoreCar { list, value } => {
+                write_load_place_referee(w, &locals, list)?;
+                // skip the type to go to car
                 write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
                 write!(w, "\t\t\ti32.add\n")?;
-                write_load_place_referee(w, &locals, car)?;
+                write_load_place_referee(w, &locals, value)?;
                 write!(w, "\t\t\ti32.store\n")?;
-
-                // load cdr address and write at offset 2
-                write!(w, "\t\t\tlocal.get $tmp\n")?;
+            }
+            Instruction::StoreCdr { list, value } => {
+                write_load_place_referee(w, &locals, list)?;
+                // skip two to go to cdr after type and car
                 write!(w, "\t\t\ti32.const {}\n", 2 * mem::size_of::<i32>())?;
                 write!(w, "\t\t\ti32.add\n")?;
-                write_load_place_referee(w, &locals, cdr)?;
-                write!(w, "\t\t\ti32.store\n")?;
-
-                // finally, remember the list in the target place
-                write_load_place_self_address(w, &locals, to)?;
-                write!(w, "\t\t\tlocal.get $tmp\n")?;
+                write_load_place_referee(w, &locals, value)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
+            Instruction::Cons { car, cdr, to } => {
+                let tag = IrDataType::ListNode;
+                write_make_node(w, &locals, tag, car, cdr, to)?;
+            }
             Instruction::WritePlace { from, to } => {
                 write_load_place_self_address(w, &locals, to)?;
                 write_load_place_referee(w, &locals, from)?;
@@ -221,6 +451,7 @@ fn write_function<W: Write>(
                 write_load_place_referee(w, &locals, params)?;
                 write!(w, "\t\t\ti32.const 0\n")?; // target of direct call never uses persistent storage, so 0
                 write!(w, "\t\t\tcall $fun{}\n", function.to_i32())?;
+                write_check_thrown(w, features, &catch_stack)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
             Instruction::CallIndirect {
@@ -233,15 +464,75 @@ fn write_function<W: Write>(
                 write_load_place_referee(w, &locals, params)?;
                 // persistent parameter comes from the storage of the function
                 write!(w, "\t\t\tcall $call_function\n")?;
+                write_check_thrown(w, features, &catch_stack)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
-            Instruction::Return { value } => {
+            Instruction::TailCall { function, params } => {
+                write!(
+                    w,
+                    "\t\t\t;; tail calling {}\n",
+                    program.resolve_function_addr(function).name()
+                )?;
+                write_load_place_referee(w, &locals, params)?;
+                // no persistent storage for direct calls, like above
+                write!(w, "\t\t\ti32.const 0\n")?;
+                if features.tail_calls {
+                    write_release_stack_frame(w, &locals)?;
+                    write!(w, "\t\t\treturn_call $fun{}\n", function.to_i32())?;
+                } else {
+                    write!(w, "\t\t\tcall $fun{}\n", function.to_i32())?;
+                    write!(w, "\t\t\tlocal.set $retval\n")?;
+                    write!(w, "\t\t\tbr $body\n")?;
+                }
+            }
+            Instruction::TailCallIndirect { function, params } => {
+                if features.tail_calls {
+                    // like $call_function, but without a frame of its own
+                    write_load_place_referee(w, &locals, function)?;
+                    write!(w, "\t\t\tlocal.set $tmp\n")?;
+                    write_load_place_referee(w, &locals, params)?;
+                    // persistent places and table index of the function
+                    write!(w, "\t\t\tlocal.get $tmp\n")?;
+                    write!(
+                        w,
+                        "\t\t\ti32.const {}\n",
+                        2 * mem::size_of::<i32>()
+                    )?;
+                    write!(w, "\t\t\ti32.add\n")?;
+                    write!(w, "\t\t\ti32.load\n")?;
+                    write!(w, "\t\t\tlocal.get $tmp\n")?;
+                    write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+                    write!(w, "\t\t\ti32.add\n")?;
+                    write!(w, "\t\t\ti32.load\n")?;
+                    write_release_stack_frame(w, &locals)?;
+                    write!(w, "\t\t\treturn_call_indirect (type $user_fun)\n")?;
+                } else {
+                    write_load_place_referee(w, &locals, function)?;
+                    write_load_place_referee(w, &locals, params)?;
+                    write!(w, "\t\t\tcall $call_function\n")?;
+                    write!(w, "\t\t\tlocal.set $retval\n")?;
+                    write!(w, "\t\t\tbr $body\n")?;
+                }
+            }
+            Instruction::Return { value, values } => {
+                match values {
+                    Some(values) => {
+                        write_load_place_referee(w, &locals, values)?
+                    }
+                    None => write!(w, "\t\t\ti32.const 0\n")?,
+                }
+                write!(w, "\t\t\tglobal.set $values\n")?;
                 // keep the return value on the stack when branching out of body
                 write_load_place_referee(w, &locals, value)?;
                 write!(w, "\t\t\tlocal.set $retval\n")?;
                 // branch out of body and deallocate stack frame, leaving return value in place
                 write!(w, "\t\t\tbr $body\n")?;
             }
+            Instruction::LoadValues { to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write!(w, "\t\t\tglobal.get $values\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
             Instruction::CreateFunction { to, function } => {
                 write!(
                     w,
@@ -292,6 +583,96 @@ fn write_function<W: Write>(
                 // and directly store a reference to the list
                 write!(w, "\t\t\ti32.store\n")?;
             }
+            Instruction::ParamPresent { to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                // parameters are present if the argument list is not nil
+                write!(w, "\t\t\tlocal.get $param_head\n")?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\ti32.ne\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FindKeyParam { keyword, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write!(w, "\t\t\tlocal.get $param_head\n")?;
+                write!(w, "\t\t\ti32.const {}\n", keyword.offset())?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tcall $find_key_param\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::KeyParamsKnown { keywords, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tlocal.get $param_head\n")?;
+                write!(w, "\t\t\ti32.const {}\n", keywords.offset())?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tcall $key_params_known\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::ArityMatches { min, max, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tlocal.get $param_head\n")?;
+                write!(w, "\t\t\ti32.const {}\n", min)?;
+                // -1 stands for no upper limit
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    max.map_or(-1, |max| max as i32)
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write!(w, "\t\t\tcall $arity_matches\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
             Instruction::ConcatStringLike { left, right, to } => {
                 write_load_place_self_address(w, &locals, to)?;
                 write_load_place_referee(w, &locals, left)?;
@@ -299,72 +680,42 @@ fn write_function<W: Write>(
                 write!(w, "\t\t\tcall $concat_strings\n")?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
+            Instruction::MakeBignum { sign, digits, to } => {
+                let tag = IrDataType::BigInt;
+                write_make_node(w, &locals, tag, sign, digits, to)?;
+            }
+            Instruction::MakeRatio {
+                numerator,
+                denominator,
+                to,
+            } => {
+                let tag = IrDataType::Ratio;
+                write_make_node(w, &locals, tag, numerator, denominator, to)?;
+            }
             Instruction::Add { left, right, to } => {
+                let nil = static_data.nil_data();
                 write_load_place_self_address(w, &locals, to)?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                // add together
-                write!(w, "\t\t\ti32.add")?;
-                // create new number and save address into to
-                write!(w, "\t\t\tcall $make_num")?;
-                write!(w, "\t\t\ti32.store")?;
+                write_checked_op(w, &locals, "i64.add", left, right, nil)?;
+                write!(w, "\t\t\ti32.store\n")?;
             }
             Instruction::Sub { left, right, to } => {
+                let nil = static_data.nil_data();
                 write_load_place_self_address(w, &locals, to)?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                // subtract
-                write!(w, "\t\t\ti32.sub\n")?;
-                // create new number and save address into to
-                write!(w, "\t\t\tcall $make_num\n")?;
+                write_checked_op(w, &locals, "i64.sub", left, right, nil)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
             Instruction::Mul { left, right, to } => {
+                let nil = static_data.nil_data();
                 write_load_place_self_address(w, &locals, to)?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                // multiply
-                write!(w, "\t\t\ti32.mul\n")?;
-                // create new number and save address into to
-                write!(w, "\t\t\tcall $make_num\n")?;
+                write_checked_op(w, &locals, "i64.mul", left, right, nil)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
             Instruction::Div { left, right, to } => {
+                // signed division, which only overflows for the lowest number
+                // divided by -1
+                let nil = static_data.nil_data();
                 write_load_place_self_address(w, &locals, to)?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                // signed division
-                write!(w, "\t\t\ti32.div_s\n")?;
-                // create new number and save address into to
-                write!(w, "\t\t\tcall $make_num\n")?;
+                write_checked_op(w, &locals, "i64.div_s", left, right, nil)?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
             Instruction::Eq { left, right, to } => {
@@ -380,16 +731,28 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
+                // perform check and leave address of T or nil on stack after target address, then store
+                write!(w, "\t\t\ti32.eq\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::Same { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                // the addresses of the objects themselves
                 write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
                 write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.eq\n")?;
                 write!(w, "\t\t\tselect\n")?;
                 write!(w, "\t\t\ti32.store\n")?;
@@ -407,15 +770,8 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
                 // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.ne\n")?;
                 write!(w, "\t\t\tselect\n")?;
@@ -434,15 +790,8 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
                 // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.lt_s\n")?;
                 write!(w, "\t\t\tselect\n")?;
@@ -461,15 +810,8 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
                 // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.gt_s\n")?;
                 write!(w, "\t\t\tselect\n")?;
@@ -488,15 +830,8 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
                 // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.le_s\n")?;
                 write!(w, "\t\t\tselect\n")?;
@@ -515,20 +850,120 @@ fn write_function<W: Write>(
                     "\t\t\ti32.const {}\n",
                     static_data.nil_data().offset()
                 )?;
-                // number on the left after type tag, number on the right after type tag
-                write_load_place_referee(w, &locals, left)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
-                write_load_place_referee(w, &locals, right)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, left)?;
+                write_load_number(w, &locals, right)?;
                 // perform check and leave address of T or nil on stack after target address, then store
                 write!(w, "\t\t\ti32.ge_s\n")?;
                 write!(w, "\t\t\tselect\n")?;
                 write!(w, "\t\t\ti32.store\n")?;
             }
+            Instruction::FloatAdd { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.add\n")?;
+                // create new float and save address into to
+                write!(w, "\t\t\tcall $make_float\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatSub { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.sub\n")?;
+                // create new float and save address into to
+                write!(w, "\t\t\tcall $make_float\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatMul { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.mul\n")?;
+                // create new float and save address into to
+                write!(w, "\t\t\tcall $make_float\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatDiv { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.div\n")?;
+                // create new float and save address into to
+                write!(w, "\t\t\tcall $make_float\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatEq { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.eq\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatLt { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.lt\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatLte { left, right, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                // true value is address of T, false value is address of nil
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.t_data().offset()
+                )?;
+                write!(
+                    w,
+                    "\t\t\ti32.const {}\n",
+                    static_data.nil_data().offset()
+                )?;
+                write_load_float(w, &locals, left)?;
+                write_load_float(w, &locals, right)?;
+                write!(w, "\t\t\tf32.le\n")?;
+                write!(w, "\t\t\tselect\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::IntToFloat { number, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_number(w, &locals, number)?;
+                write!(w, "\t\t\tf32.convert_i32_s\n")?;
+                write!(w, "\t\t\tcall $make_float\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
+            Instruction::FloatTruncate { float, to } => {
+                write_load_place_self_address(w, &locals, to)?;
+                write_load_float(w, &locals, float)?;
+                write!(w, "\t\t\ti32.trunc_f32_s\n")?;
+                write!(w, "\t\t\tcall $make_num\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+            }
             Instruction::Break { block_up } => {
                 let target_block =
                     block_stack[block_stack.len() - (block_up as usize)];
@@ -582,6 +1017,75 @@ fn write_function<W: Write>(
                 write!(w, "\t\t\t));; end block\n")?;
                 block_stack.pop();
             }
+            Instruction::EnterCatch { tag, to } => {
+                let num = next_block_num;
+                next_block_num += 1;
+                if features.exceptions {
+                    write!(w, "\t\t\tglobal.get $stack_bottom\n")?;
+                    write!(w, "\t\t\tlocal.set $frame_bottom\n")?;
+                }
+                if let Some(tag) = tag {
+                    write_load_place_referee(w, &locals, tag)?;
+                    write!(w, "\t\t\tcall $push_catcher\n")?;
+                }
+                if features.exceptions {
+                    write!(
+                        w,
+                        "\t\t\t(block $catch_{}_end (try $catch_{} (do\n",
+                        num, num
+                    )?;
+                } else {
+                    write!(
+                        w,
+                        "\t\t\t(block $catch_{}_end (block $catch_{}_handler\n",
+                        num, num
+                    )?;
+                }
+                catch_stack.push(OpenCatch { num, tag, to });
+            }
+            Instruction::ExitCatch => {
+                let catch = catch_stack.pop().expect("unbalanced ExitCatch");
+                if catch.tag.is_some() {
+                    write!(w, "\t\t\tcall $pop_catcher\n")?;
+                }
+                write!(w, "\t\t\tbr $catch_{}_end\n", catch.num)?;
+                if features.exceptions {
+                    write!(w, "\t\t\t) (catch $unwind\n")?;
+                    write!(w, "\t\t\tlocal.get $frame_bottom\n")?;
+                    write!(w, "\t\t\tglobal.set $stack_bottom\n")?;
+                } else {
+                    write!(w, "\t\t\t)\n")?;
+                }
+                // unwinding arrives here, tagged catchers are done either way
+                // and only take what is thrown to their tag
+                if let Some(tag) = catch.tag {
+                    write!(w, "\t\t\tcall $pop_catcher\n")?;
+                    write!(w, "\t\t\tglobal.get $thrown\n")?;
+                    write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+                    write!(w, "\t\t\ti32.add\n")?;
+                    write!(w, "\t\t\ti32.load\n")?;
+                    write_load_place_referee(w, &locals, tag)?;
+                    write!(w, "\t\t\ti32.ne\n")?;
+                    write!(w, "\t\t\tif\n")?;
+                    write_unwind(w, features, &catch_stack)?;
+                    write!(w, "\t\t\tend\n")?;
+                }
+                write_load_place_self_address(w, &locals, catch.to)?;
+                write!(w, "\t\t\tglobal.get $thrown\n")?;
+                write!(w, "\t\t\ti32.store\n")?;
+                write!(w, "\t\t\ti32.const 0\n")?;
+                write!(w, "\t\t\tglobal.set $thrown\n")?;
+                if features.exceptions {
+                    write!(w, "\t\t\t)));; end catch\n")?;
+                } else {
+                    write!(w, "\t\t\t);; end catch\n")?;
+                }
+            }
+            Instruction::Throw { thrown } => {
+                write_load_place_referee(w, &locals, thrown)?;
+                write!(w, "\t\t\tcall $throw\n")?;
+                write_unwind(w, features, &catch_stack)?;
+            }
             Instruction::NilIfZero { check, to } => {
                 write_load_place_self_address(w, &locals, to)?;
 
@@ -596,10 +1100,7 @@ fn write_function<W: Write>(
                 )?;
 
                 // load the number value as the test
-                write_load_place_referee(w, &locals, check)?;
-                write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
-                write!(w, "\t\t\ti32.add\n")?;
-                write!(w, "\t\t\ti32.load\n")?;
+                write_load_number(w, &locals, check)?;
 
                 // and select nil address or the original number address based on the value being zero or not
                 write!(w, "\t\t\tselect\n")?;
@@ -616,19 +1117,48 @@ fn write_function<W: Write>(
 
     // function epilogue
     write!(w, "\t\t;; start of function epilogue\n")?;
-    if let Some(ref locals) = locals {
-        if let LocalStrategy::Stack = locals.strategy() {
-            write!(w, "\t\ti32.const {}\n", -locals.len())?;
-            write!(w, "\t\tcall $inc_stack_bottom\n")?;
-        }
-        write!(w, "\t\tlocal.get $retval\n")?;
-    }
+    write_release_stack_frame(w, &locals)?;
+    write!(w, "\t\tlocal.get $retval\n")?;
     write!(w, "\t\t;; end of function epilogue\n")?;
 
     write!(w, "\t)\n")?;
     Ok(())
 }
 
+/// Continues unwinding from the current function, which has the given regions
+/// that catch throws open.
+fn write_unwind<W: Write>(
+    w: &mut W,
+    features: &FeatureOptions,
+    catch_stack: &[OpenCatch],
+) -> io::Result<()> {
+    if features.exceptions {
+        write!(w, "\t\t\tthrow $unwind\n")
+    } else if let Some(catch) = catch_stack.last() {
+        write!(w, "\t\t\tbr $catch_{}_handler\n", catch.num)
+    } else {
+        // the caller continues unwinding after the epilogue
+        write!(w, "\t\t\tbr $body\n")
+    }
+}
+
+/// Unwinds after a call if it started unwinding, before the result of the
+/// call is stored anywhere.
+fn write_check_thrown<W: Write>(
+    w: &mut W,
+    features: &FeatureOptions,
+    catch_stack: &[OpenCatch],
+) -> io::Result<()> {
+    if features.exceptions {
+        return Ok(());
+    }
+    write!(w, "\t\t\tglobal.get $thrown\n")?;
+    match catch_stack.last() {
+        Some(catch) => write!(w, "\t\t\tbr_if $catch_{}_handler\n", catch.num),
+        None => write!(w, "\t\t\tbr_if $body\n"),
+    }
+}
+
 /// Loads the address of a place itself, so that it can be overwritten.
 fn write_load_place_self_address<W: Write>(
     w: &mut W,
@@ -637,9 +1167,10 @@ fn write_load_place_self_address<W: Write>(
 ) -> io::Result<()> {
     let offset = from.offset() as usize;
 
-    match (from.mode(), local_info.as_ref().unwrap().strategy()) {
+    let strategy = local_info.as_ref().map(LocalPlacesInfo::strategy);
+    match (from.mode(), strategy) {
         // local variables are below the stack bottom that gets bumped on entry
-        (AddressingMode::Local, LocalStrategy::Stack) => {
+        (AddressingMode::Local, Some(LocalStrategy::Stack)) => {
             write!(w, "\t\t\tglobal.get $stack_bottom\n")?;
             write!(
                 w,
@@ -649,17 +1180,59 @@ fn write_load_place_self_address<W: Write>(
             write!(w, "\t\t\ti32.sub\n")
         }
         // persistent are addressed from the beginning, so no extra needed
-        (AddressingMode::Local, LocalStrategy::Heap) => {
+        (AddressingMode::Local, Some(LocalStrategy::Heap)) => {
             write!(w, "\t\t\tlocal.get $persistent_bottom\n")?;
             write!(w, "\t\t\ti32.const {}\n", offset)?;
             write!(w, "\t\t\ti32.add\n")
         }
+        (AddressingMode::Local, None) => {
+            unreachable!("local place in function without locals")
+        }
+        // follow the links in the first place of each lambda up to the block
+        // of places that the captured place is in
+        (AddressingMode::Captured { depth }, Some(LocalStrategy::Heap)) => {
+            write!(w, "\t\t\tlocal.get $persistent_bottom\n")?;
+            write!(w, "\t\t\ti32.load\n")?;
+            for _ in 1..depth {
+                write!(w, "\t\t\ti32.const {}\n", PLACES_HEADER_SIZE)?;
+                write!(w, "\t\t\ti32.add\n")?;
+                write!(w, "\t\t\ti32.load\n")?;
+            }
+            write!(
+                w,
+                "\t\t\ti32.const {}\n",
+                PLACES_HEADER_SIZE + offset as i32
+            )?;
+            write!(w, "\t\t\ti32.add\n")
+        }
+        (AddressingMode::Captured { .. }, _) => {
+            unreachable!("captured place in function that is not a lambda")
+        }
         (AddressingMode::Global, _) => {
             write!(w, "\t\t\ti32.const {}\n", offset)
         }
     }
 }
 
+/// Pops everything that the function prologue pushed onto the stack.
+fn write_release_stack_frame<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+) -> io::Result<()> {
+    if let Some(local_info) = local_info {
+        write!(w, "\t\ti32.const {}\n", -local_info.stack_len())?;
+        write!(w, "\t\tcall $inc_stack_bottom\n")?;
+    }
+    Ok(())
+}
+
+/// Loads the address of the last word pushed onto the stack.
+fn write_load_stack_slot<W: Write>(w: &mut W) -> io::Result<()> {
+    write!(w, "\t\tglobal.get $stack_bottom\n")?;
+    write!(w, "\t\ti32.const {}\n", mem::size_of::<i32>())?;
+    write!(w, "\t\ti32.sub\n")
+}
+
 /// Loads the address that a place points to
 fn write_load_place_referee<W: Write>(
     w: &mut W,
@@ -670,14 +1243,108 @@ fn write_load_place_referee<W: Write>(
     write!(w, "\t\t\ti32.load\n")
 }
 
+/// Writes a load of the value of the number that a place points to, which is
+/// either an immediate or after the type tag of a number on the heap
+fn write_load_number<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+    from: PlaceAddress,
+) -> io::Result<()> {
+    write_load_place_referee(w, local_info, from)?;
+    write!(w, "\t\t\tcall $num_value\n")
+}
+
+/// Writes the address of the element at an index of a vector, after the type
+/// tag and length
+fn write_element_address<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+    vector: PlaceAddress,
+    index: PlaceAddress,
+) -> io::Result<()> {
+    write_load_place_referee(w, local_info, vector)?;
+    write!(w, "\t\t\ti32.const {}\n", PLACES_HEADER_SIZE)?;
+    write!(w, "\t\t\ti32.add\n")?;
+    write_load_number(w, local_info, index)?;
+    write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+    write!(w, "\t\t\ti32.mul\n")?;
+    write!(w, "\t\t\ti32.add\n")
+}
+
+/// Writes an operation on two numbers that is computed with 64 bits, leaving
+/// the resulting number, or nil if it does not fit into 32 bits
+fn write_checked_op<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+    op: &str,
+    left: PlaceAddress,
+    right: PlaceAddress,
+    nil: DataAddress,
+) -> io::Result<()> {
+    write_load_number(w, local_info, left)?;
+    write!(w, "\t\t\ti64.extend_i32_s\n")?;
+    write_load_number(w, local_info, right)?;
+    write!(w, "\t\t\ti64.extend_i32_s\n")?;
+    write!(w, "\t\t\t{}\n", op)?;
+    write!(w, "\t\t\ti32.const {}\n", nil.offset())?;
+    write!(w, "\t\t\tcall $make_num_checked\n")
+}
+
+/// Writes an allocation of an object laid out like a list node, with the
+/// given type tag and the referees of two places, and stores it to a place
+fn write_make_node<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+    tag: IrDataType,
+    car: PlaceAddress,
+    cdr: PlaceAddress,
+    to: PlaceAddress,
+) -> io::Result<()> {
+    // type tag, car address, cdr address
+    write_heap_alloc(w, mem::size_of::<i32>() * 3)?;
+    write!(w, "\t\t\tlocal.set $tmp\n")?; // remember the allocation
+
+    // write tag at offset 0
+    write!(w, "\t\t\tlocal.get $tmp\n")?;
+    write!(w, "\t\t\ti32.const {}\n", tag.to_u32())?;
+    write!(w, "\t\t\ti32.store\n")?;
+
+    // load car address and write at offset 1
+    write!(w, "\t\t\tlocal.get $tmp\n")?;
+    write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+    write!(w, "\t\t\ti32.add\n")?;
+    write_load_place_referee(w, local_info, car)?;
+    write!(w, "\t\t\ti32.store\n")?;
+
+    // load cdr address and write at offset 2
+    write!(w, "\t\t\tlocal.get $tmp\n")?;
+    write!(w, "\t\t\ti32.const {}\n", 2 * mem::size_of::<i32>())?;
+    write!(w, "\t\t\ti32.add\n")?;
+    write_load_place_referee(w, local_info, cdr)?;
+    write!(w, "\t\t\ti32.store\n")?;
+
+    // finally, remember the node in the target place
+    write_load_place_self_address(w, local_info, to)?;
+    write!(w, "\t\t\tlocal.get $tmp\n")?;
+    write!(w, "\t\t\ti32.store\n")
+}
+
+/// Writes a load of the float after the type tag of the referee of a place
+fn write_load_float<W: Write>(
+    w: &mut W,
+    local_info: &Option<LocalPlacesInfo>,
+    from: PlaceAddress,
+) -> io::Result<()> {
+    write_load_place_referee(w, local_info, from)?;
+    write!(w, "\t\t\ti32.const {}\n", mem::size_of::<i32>())?;
+    write!(w, "\t\t\ti32.add\n")?;
+    write!(w, "\t\t\tf32.load\n")
+}
+
 /// Writes a heap allocation, the result being the start address of the allocation
 fn write_heap_alloc<W: Write>(w: &mut W, size: usize) -> io::Result<()> {
-    // just append to the back for now
-    write!(w, "\t\t\tglobal.get $heap_start\n")?;
-    write!(w, "\t\t\tglobal.get $heap_start\n")?;
     write!(w, "\t\t\ti32.const {}\n", size)?;
-    write!(w, "\t\t\ti32.add\n")?;
-    write!(w, "\t\t\tglobal.set $heap_start\n")?;
+    write!(w, "\t\t\tcall $alloc_heap\n")?;
     Ok(())
 }
 
@@ -688,7 +1355,8 @@ impl<'s> fmt::Display for WebassemblyString<'s> {
         write!(f, "\"")?;
         for &byte in self.0 {
             if ((byte.is_ascii_graphic() || byte.is_ascii_punctuation())
-                && byte != b'\"')
+                && byte != b'\"'
+                && byte != b'\\')
                 || byte == b' '
             {
                 write!(f, "{}", char::from(byte))?;
@@ -0,0 +1,10 @@
+//! Runs programs without a web assembly engine by interpreting the IR.
+
+mod err;
+mod heap;
+mod machine;
+mod memory;
+mod print;
+
+pub use err::RuntimeError;
+pub use machine::Interpreter;
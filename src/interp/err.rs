@@ -0,0 +1,64 @@
+use std::{fmt, io};
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+/// Execution of a program stopped abnormally.
+///
+/// Most of these correspond to traps in web assembly.
+#[derive(Debug)]
+pub enum RuntimeError {
+    /// A panic instruction was executed, usually after printing a message.
+    Unreachable,
+    OutOfBounds {
+        address: i64,
+        len: u32,
+    },
+    DivideByZero,
+    IntegerOverflow,
+    InvalidConversionToInteger,
+    CallStackExhausted,
+    UndefinedTableElement {
+        idx: i32,
+    },
+    /// Printing failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Unreachable => {
+                writeln!(f, "program panicked, unreachable executed")
+            }
+            RuntimeError::OutOfBounds { address, len } => writeln!(
+                f,
+                "out of bounds memory access of {} bytes at {}",
+                len, address
+            ),
+            RuntimeError::DivideByZero => {
+                writeln!(f, "integer divide by zero")
+            }
+            RuntimeError::IntegerOverflow => {
+                writeln!(f, "integer overflow")
+            }
+            RuntimeError::InvalidConversionToInteger => {
+                writeln!(f, "invalid conversion to integer")
+            }
+            RuntimeError::CallStackExhausted => {
+                writeln!(f, "call stack exhausted")
+            }
+            RuntimeError::UndefinedTableElement { idx } => {
+                writeln!(f, "undefined function table element {}", idx)
+            }
+            RuntimeError::Io(err) => {
+                writeln!(f, "failed to print: {}", err)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for RuntimeError {
+    fn from(value: io::Error) -> Self {
+        RuntimeError::Io(value)
+    }
+}
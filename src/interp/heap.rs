@@ -0,0 +1,360 @@
+use crate::{
+    codegen::{FREE_BIT, MARK_BIT, PAGE_SIZE, PLACES_TAG, WORD_SIZE},
+    ir::{IrDataType, fixnum_value},
+};
+
+use super::{
+    err::{RuntimeError, RuntimeResult},
+    memory::Memory,
+};
+
+const WORD: i32 = WORD_SIZE as i32;
+
+/// Mark-sweep garbage collected heap, working exactly like the collector in
+/// `rt/gc.wat` so that objects end up at the same addresses.
+///
+/// The heap starts at a fixed base and ends at its top, where fresh memory is
+/// taken from when no free block fits. Every block on the heap is either an
+/// object starting with its type tag, or a free block starting with its size
+/// and [`FREE_BIT`]. Free blocks of at least two words are linked through
+/// their second word.
+pub struct Heap {
+    base: i32,
+    /// Corresponds to the `$heap_start` global in web assembly.
+    top: i32,
+    /// First free block or zero.
+    free_list: i32,
+    /// Bytes of objects that survived the last collection.
+    live: u32,
+}
+
+impl Heap {
+    pub fn new(base: i32) -> Self {
+        Self {
+            base,
+            top: base,
+            free_list: 0,
+            live: 0,
+        }
+    }
+
+    /// Tries to allocate a word-aligned number of bytes from the free list or
+    /// the rest of memory, without collecting garbage.
+    pub fn try_alloc(
+        &mut self,
+        memory: &mut Memory,
+        bytes: i32,
+    ) -> RuntimeResult<Option<i32>> {
+        // first fit from the free list
+        let mut prev = 0;
+        let mut block = self.free_list;
+        while block != 0 {
+            let size = (memory.load_i32(block)? as u32 & !FREE_BIT) as i32;
+            let rest = size.checked_sub(bytes).unwrap_or(-1);
+            if rest >= 0 {
+                let mut next = memory.load_i32(block + WORD)?;
+                if rest >= 2 * WORD {
+                    // split and let the remaining block take the place in
+                    // the list
+                    let remaining = block + bytes;
+                    memory.store_i32(
+                        remaining,
+                        (rest as u32 | FREE_BIT) as i32,
+                    )?;
+                    memory.store_i32(remaining + WORD, next)?;
+                    next = remaining;
+                } else if rest > 0 {
+                    // a single free word that is not in the list
+                    memory.store_i32(
+                        block + bytes,
+                        (WORD_SIZE | FREE_BIT) as i32,
+                    )?;
+                }
+                if prev != 0 {
+                    memory.store_i32(prev + WORD, next)?;
+                } else {
+                    self.free_list = next;
+                }
+                return Ok(Some(block));
+            }
+            prev = block;
+            block = memory.load_i32(block + WORD)?;
+        }
+
+        // otherwise take fresh memory after the last block if there is enough
+        let end = self.top as u32 as u64 + bytes as u32 as u64;
+        if end > memory.size() as u64 {
+            return Ok(None);
+        }
+        let start = self.top;
+        self.top += bytes;
+        Ok(Some(start))
+    }
+
+    /// Grows memory so that the given number of bytes fit after the last
+    /// block, returning whether that worked without exceeding the maximum.
+    ///
+    /// Memory grows at least by its current size, so that collections do not
+    /// get more frequent the more memory is in use.
+    pub fn grow(
+        &self,
+        memory: &mut Memory,
+        bytes: i32,
+        max_pages: u32,
+    ) -> bool {
+        let end = self.top as u32 as u64 + bytes as u32 as u64;
+        // nothing may be missing if growing early
+        let missing = end.saturating_sub(memory.size() as u64);
+        let needed = missing.div_ceil(PAGE_SIZE as u64) as u32;
+        let pages = memory.size() / PAGE_SIZE;
+        if pages + needed > max_pages {
+            return false;
+        }
+        let grow_by = needed.max(pages).min(max_pages - pages);
+        memory.grow(grow_by * PAGE_SIZE);
+        true
+    }
+
+    /// Whether more than half of the memory available to the heap was still
+    /// in use after the last collection, so that memory should grow before
+    /// collections get more frequent.
+    pub fn mostly_live(&self, memory: &Memory) -> bool {
+        let capacity = memory.size() as u64 - self.base as u32 as u64;
+        2 * self.live as u64 > capacity
+    }
+
+    /// Marks everything reachable from the given roots and frees everything
+    /// else.
+    pub fn collect(
+        &mut self,
+        memory: &mut Memory,
+        roots: &[i32],
+    ) -> RuntimeResult<()> {
+        for &root in roots {
+            self.mark(memory, root)?;
+        }
+        self.sweep(memory)
+    }
+
+    fn contains(&self, address: i32) -> bool {
+        (address as u32) >= (self.base as u32)
+            && (address as u32) < (self.top as u32)
+    }
+
+    /// Marks an object and everything reachable from it, ignoring immediates
+    /// and addresses outside of the heap like static data.
+    fn mark(&self, memory: &mut Memory, root: i32) -> RuntimeResult<()> {
+        let mut pending = vec![root];
+        while let Some(address) = pending.pop() {
+            if fixnum_value(address).is_some() || !self.contains(address) {
+                continue;
+            }
+            let tag = memory.load_i32(address)? as u32;
+            if tag & MARK_BIT != 0 {
+                continue;
+            }
+            memory.store_i32(address, (tag | MARK_BIT) as i32)?;
+
+            if tag == IrDataType::ListNode.to_u32()
+                || tag == IrDataType::BigInt.to_u32()
+                || tag == IrDataType::Ratio.to_u32()
+            {
+                pending.push(memory.load_i32(address + 2 * WORD)?);
+                pending.push(memory.load_i32(address + WORD)?);
+            } else if tag == IrDataType::Function.to_u32() {
+                // the block starts with type tag and length before the first
+                // place
+                let places = memory.load_i32(address + 2 * WORD)?;
+                pending.push(places.wrapping_sub(2 * WORD));
+            } else if tag == PLACES_TAG || tag == IrDataType::Vector.to_u32() {
+                let len = memory.load_i32(address + WORD)?;
+                let places = address + 2 * WORD;
+                for place in (places..places + len).step_by(WORD_SIZE as usize)
+                {
+                    pending.push(memory.load_i32(place)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Frees all unmarked objects and unmarks the rest. Adjacent free blocks
+    /// are merged and the last ones are given back to the rest of memory.
+    fn sweep(&mut self, memory: &mut Memory) -> RuntimeResult<()> {
+        self.free_list = 0;
+        self.live = 0;
+        let mut tail = 0;
+        let mut run = None;
+        let mut address = self.base;
+        while address < self.top {
+            let size = block_size(memory, address)?;
+            let tag = memory.load_i32(address)? as u32;
+            if tag & MARK_BIT != 0 {
+                memory.store_i32(address, (tag & !MARK_BIT) as i32)?;
+                self.live += size as u32;
+                if let Some(start) = run.take() {
+                    tail =
+                        self.free_run(memory, start, address - start, tail)?;
+                }
+            } else if run.is_none() {
+                run = Some(address);
+            }
+            address += size;
+        }
+        if let Some(start) = run {
+            self.top = start;
+        }
+        Ok(())
+    }
+
+    /// Turns a run of garbage into a single free block and appends it to the
+    /// free list after the given tail, returning the new tail.
+    fn free_run(
+        &mut self,
+        memory: &mut Memory,
+        start: i32,
+        size: i32,
+        tail: i32,
+    ) -> RuntimeResult<i32> {
+        memory.store_i32(start, (size as u32 | FREE_BIT) as i32)?;
+        // single words are not linked
+        if size < 2 * WORD {
+            return Ok(tail);
+        }
+        memory.store_i32(start + WORD, 0)?;
+        if tail != 0 {
+            memory.store_i32(tail + WORD, start)?;
+        } else {
+            self.free_list = start;
+        }
+        Ok(start)
+    }
+}
+
+/// Size of the object or free block at the given address in bytes.
+fn block_size(memory: &Memory, address: i32) -> RuntimeResult<i32> {
+    let tag = memory.load_i32(address)? as u32 & !MARK_BIT;
+    if tag & FREE_BIT != 0 {
+        return Ok((tag & !FREE_BIT) as i32);
+    }
+    let is = |data_type: IrDataType| tag == data_type.to_u32();
+    if is(IrDataType::ListNode)
+        || is(IrDataType::Function)
+        || is(IrDataType::BigInt)
+        || is(IrDataType::Ratio)
+    {
+        Ok(3 * WORD)
+    } else if is(IrDataType::SInt32)
+        || is(IrDataType::Float32)
+        || is(IrDataType::Character)
+    {
+        Ok(2 * WORD)
+    } else if is(IrDataType::CharacterData)
+        || is(IrDataType::Identifier)
+        || is(IrDataType::Vector)
+        || tag == PLACES_TAG
+    {
+        // the length after the type tag is rounded up to whole words
+        let len = memory.load_i32(address + WORD)? as u32;
+        Ok((2 * WORD_SIZE + len.next_multiple_of(WORD_SIZE)) as i32)
+    } else {
+        // heap is corrupted
+        Err(RuntimeError::Unreachable)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BASE: i32 = 64;
+
+    fn make_num(heap: &mut Heap, memory: &mut Memory, value: i32) -> i32 {
+        let num = heap.try_alloc(memory, 2 * WORD).unwrap().unwrap();
+        memory
+            .store_i32(num, IrDataType::SInt32.to_u32() as i32)
+            .unwrap();
+        memory.store_i32(num + WORD, value).unwrap();
+        num
+    }
+
+    fn cons(heap: &mut Heap, memory: &mut Memory, car: i32, cdr: i32) -> i32 {
+        let node = heap.try_alloc(memory, 3 * WORD).unwrap().unwrap();
+        memory
+            .store_i32(node, IrDataType::ListNode.to_u32() as i32)
+            .unwrap();
+        memory.store_i32(node + WORD, car).unwrap();
+        memory.store_i32(node + 2 * WORD, cdr).unwrap();
+        node
+    }
+
+    #[test]
+    fn reachable_objects_survive() {
+        let mut memory = Memory::new(256);
+        let mut heap = Heap::new(BASE);
+        let one = make_num(&mut heap, &mut memory, 1);
+        let garbage = make_num(&mut heap, &mut memory, 2);
+        let list = cons(&mut heap, &mut memory, one, 0);
+        heap.collect(&mut memory, &[list]).unwrap();
+
+        assert_eq!(memory.load_i32(one + WORD).unwrap(), 1);
+        assert_eq!(memory.load_i32(list + WORD).unwrap(), one);
+        // the garbage in between is reused for the next number
+        assert_eq!(make_num(&mut heap, &mut memory, 3), garbage);
+    }
+
+    #[test]
+    fn adjacent_garbage_is_merged() {
+        let mut memory = Memory::new(256);
+        let mut heap = Heap::new(BASE);
+        let first = make_num(&mut heap, &mut memory, 1);
+        make_num(&mut heap, &mut memory, 2);
+        let kept = make_num(&mut heap, &mut memory, 3);
+        heap.collect(&mut memory, &[kept]).unwrap();
+
+        // two numbers fit a list node with a word to spare
+        let node = cons(&mut heap, &mut memory, kept, 0);
+        assert_eq!(node, first);
+        assert_eq!(
+            memory.load_i32(first + 3 * WORD).unwrap() as u32,
+            WORD_SIZE | FREE_BIT
+        );
+        heap.collect(&mut memory, &[node]).unwrap();
+        assert_eq!(memory.load_i32(kept + WORD).unwrap(), 3);
+    }
+
+    #[test]
+    fn garbage_at_the_end_is_given_back() {
+        let mut memory = Memory::new(256);
+        let mut heap = Heap::new(BASE);
+        make_num(&mut heap, &mut memory, 1);
+        make_num(&mut heap, &mut memory, 2);
+        heap.collect(&mut memory, &[]).unwrap();
+        assert_eq!(heap.top, BASE);
+        assert_eq!(heap.free_list, 0);
+    }
+
+    #[test]
+    fn growing_makes_room_up_to_the_maximum() {
+        let mut memory = Memory::new(PAGE_SIZE);
+        let mut heap = Heap::new(BASE);
+        let bytes = PAGE_SIZE as i32;
+        assert!(heap.try_alloc(&mut memory, bytes).unwrap().is_none());
+        assert!(heap.grow(&mut memory, bytes, 3));
+        assert_eq!(memory.size(), 2 * PAGE_SIZE);
+        assert_eq!(heap.try_alloc(&mut memory, bytes).unwrap(), Some(BASE));
+        // doubling is capped at the maximum
+        assert!(heap.grow(&mut memory, bytes, 3));
+        assert_eq!(memory.size(), 3 * PAGE_SIZE);
+        assert!(heap.try_alloc(&mut memory, bytes).unwrap().is_some());
+        assert!(!heap.grow(&mut memory, bytes, 3));
+    }
+
+    #[test]
+    fn full_heap_fails_to_allocate() {
+        let mut memory = Memory::new(BASE as u32 + 16);
+        let mut heap = Heap::new(BASE);
+        assert!(heap.try_alloc(&mut memory, 12).unwrap().is_some());
+        assert!(heap.try_alloc(&mut memory, 8).unwrap().is_none());
+    }
+}
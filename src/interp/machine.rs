@@ -0,0 +1,1125 @@
+use std::io::Write;
+
+use crate::{
+    codegen::{
+        LocalPlacesInfo, LocalStrategy, MemoryLayout, PAGE_SIZE, PLACES_TAG,
+        WORD_SIZE,
+    },
+    ir::{
+        AddressingMode, FunctionAttribute, Instruction, IrDataType,
+        PlaceAddress, Program, fixnum, fixnum_value,
+    },
+};
+
+use super::{
+    err::{RuntimeError, RuntimeResult},
+    heap::Heap,
+    memory::Memory,
+    print::DisplayValue,
+};
+
+/// Maximum number of nested calls before giving up, roughly matching the
+/// limits of web assembly engines.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+const WORD: i32 = WORD_SIZE as i32;
+
+/// Executes IR directly, with the same memory layout that the code generator
+/// uses for web assembly.
+///
+/// Calls are not implemented with recursion in Rust, so deeply recursive
+/// LISP code does not overflow the stack of the interpreter.
+pub struct Interpreter<'p, W> {
+    program: &'p Program,
+    functions: Vec<FunctionInfo>,
+    memory: Memory,
+    stack_start: i32,
+    /// Corresponds to the `$stack_bottom` global in web assembly.
+    stack_bottom: i32,
+    /// First address after the stack space.
+    stack_top: i32,
+    heap: Heap,
+    max_pages: u32,
+    /// String printed before panicking when memory cannot grow anymore.
+    out_of_memory_message: i32,
+    /// String printed before panicking when nothing catches a throw.
+    uncaught_throw_message: i32,
+    frames: Vec<Frame>,
+    /// Regions that catch throws, innermost last.
+    catchers: Vec<Catcher>,
+    /// Corresponds to the `$values` global in web assembly.
+    values: i32,
+    out: W,
+}
+
+/// Information about a function that is computed once before running.
+struct FunctionInfo {
+    locals: Option<LocalPlacesInfo>,
+    /// Lambdas link their places to the places of their creator.
+    is_lambda: bool,
+    /// String printed before panicking when the function does not fit on the
+    /// stack.
+    stack_overflow_message: i32,
+    /// Maps the index of each `EnterBlock` to its matching `ExitBlock`, and
+    /// of each `EnterCatch` to its matching `ExitCatch`.
+    block_ends: Vec<usize>,
+}
+
+/// A region of code that catches throws, see `Instruction::EnterCatch`.
+struct Catcher {
+    /// Catches any throw if there is no tag.
+    tag: Option<i32>,
+    /// Index of the frame that the region is in.
+    frame: usize,
+    /// Number of blocks that the frame had entered at the start of the
+    /// region.
+    blocks: usize,
+    /// Index of the `ExitCatch` instruction.
+    end: usize,
+    to: PlaceAddress,
+}
+
+/// State of a function invocation.
+struct Frame {
+    function: usize,
+    /// Index of the next instruction to execute.
+    pc: usize,
+    /// Indexes of the `EnterBlock` instructions of entered blocks, innermost
+    /// last.
+    blocks: Vec<usize>,
+    param_head: i32,
+    persistent_bottom: i32,
+    retval: i32,
+    /// Where to put the return value of the call in progress.
+    call_target: Option<PlaceAddress>,
+}
+
+impl<'p, W: Write> Interpreter<'p, W> {
+    /// Prepares memory for running the program in the given layout, printing
+    /// to the given writer.
+    pub fn new(
+        program: &'p Program,
+        layout: &MemoryLayout,
+        out: W,
+    ) -> RuntimeResult<Self> {
+        let mut memory = Memory::new(layout.initial_pages() * PAGE_SIZE);
+        let data = program.static_data().data();
+        memory
+            .slice_mut(0, data.len() as u32)?
+            .copy_from_slice(data);
+        let runtime_data = layout.runtime_data();
+        memory
+            .slice_mut(
+                layout.runtime_data_start() as i32,
+                runtime_data.len() as u32,
+            )?
+            .copy_from_slice(runtime_data);
+        let functions = program
+            .functions()
+            .iter()
+            .enumerate()
+            .map(|(idx, function)| FunctionInfo {
+                locals: LocalPlacesInfo::extract(function),
+                is_lambda: function
+                    .attributes()
+                    .contains(&FunctionAttribute::AcceptsPersistentPlaces),
+                stack_overflow_message: layout.stack_overflow_message(idx)
+                    as i32,
+                block_ends: block_ends(function.instructions()),
+            })
+            .collect();
+        Ok(Self {
+            program,
+            functions,
+            memory,
+            stack_start: layout.stack_start() as i32,
+            stack_bottom: layout.stack_start() as i32,
+            stack_top: layout.stack_end() as i32,
+            heap: Heap::new(layout.heap_start() as i32),
+            max_pages: layout.max_pages(),
+            out_of_memory_message: layout.out_of_memory_message() as i32,
+            uncaught_throw_message: layout.uncaught_throw_message() as i32,
+            frames: vec![],
+            catchers: vec![],
+            values: 0,
+            out,
+        })
+    }
+
+    /// Runs the root code and returns the address of the value it returned.
+    pub fn run_main(&mut self) -> RuntimeResult<i32> {
+        let main = self
+            .program
+            .functions()
+            .iter()
+            .position(|f| f.export_name() == Some("main"))
+            .expect("every program has a main function");
+        // like the host calling main without arguments
+        self.call(main, 0, 0)
+    }
+
+    /// Displays a value in memory as LISP data.
+    pub fn display_value(&self, address: i32) -> DisplayValue<'_> {
+        DisplayValue::new(&self.memory, address, self.nil())
+    }
+
+    fn call(
+        &mut self,
+        function: usize,
+        param_head: i32,
+        persistent_bottom: i32,
+    ) -> RuntimeResult<i32> {
+        let depth = self.frames.len();
+        self.enter(function, param_head, persistent_bottom)?;
+        loop {
+            if let Some(retval) = self.step()? {
+                if self.frames.len() == depth {
+                    return Ok(retval);
+                }
+                let target = self.frame_mut().call_target.take().unwrap();
+                self.store_place(target, retval)?;
+                self.frame_mut().pc += 1;
+            }
+        }
+    }
+
+    /// Pushes a new frame and runs the function prologue.
+    fn enter(
+        &mut self,
+        function: usize,
+        param_head: i32,
+        mut persistent_bottom: i32,
+    ) -> RuntimeResult<()> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::CallStackExhausted);
+        }
+        let info = &self.functions[function];
+        if let Some(ref locals) = info.locals {
+            self.check_stack(locals.stack_len(), info.stack_overflow_message)?;
+        }
+        let info = &self.functions[function];
+        if let Some(ref locals) = info.locals {
+            // keep the argument list for the garbage collector, the caller
+            // may have released its frame for a tail call
+            self.memory.store_i32(self.stack_bottom, param_head)?;
+            self.stack_bottom = self.stack_bottom.wrapping_add(WORD);
+            let len = locals.len();
+            match locals.strategy() {
+                LocalStrategy::Stack => {
+                    // the garbage collector scans the stack, so clear out any
+                    // stale addresses from earlier calls
+                    self.memory.fill(self.stack_bottom, len as u32, 0)?;
+                    self.stack_bottom = self.stack_bottom.wrapping_add(len);
+                }
+                LocalStrategy::Heap => {
+                    // the places of the parent may not be reachable from
+                    // anything else after a tail call, so keep them in the
+                    // stack slot of the block while allocating
+                    let is_lambda = info.is_lambda;
+                    let parent = if is_lambda {
+                        persistent_bottom.wrapping_sub(2 * WORD)
+                    } else {
+                        0
+                    };
+                    self.memory.store_i32(self.stack_bottom, parent)?;
+                    self.stack_bottom = self.stack_bottom.wrapping_add(WORD);
+                    // every call gets places of its own
+                    persistent_bottom = self.alloc_places(len)?;
+                    let slot = self.stack_bottom.wrapping_sub(WORD);
+                    if is_lambda {
+                        // the first place links to the block of the parent
+                        self.memory.store_i32(persistent_bottom, parent)?;
+                    }
+                    // replace it with the block of places, so the garbage
+                    // collector keeps that while the function runs
+                    let block = persistent_bottom.wrapping_sub(2 * WORD);
+                    self.memory.store_i32(slot, block)?;
+                }
+            }
+        }
+        self.frames.push(Frame {
+            function,
+            pc: 0,
+            blocks: vec![],
+            param_head,
+            persistent_bottom,
+            retval: 0,
+            call_target: None,
+        });
+        Ok(())
+    }
+
+    /// Finds the function to call for a function object, along with the
+    /// persistent places it was created with, like `$call_function` in
+    /// `rt/rt.wat`.
+    fn resolve_function_object(
+        &self,
+        function: i32,
+    ) -> RuntimeResult<(usize, i32)> {
+        let table_idx = self.memory.load_i32(function.wrapping_add(WORD))?;
+        let persistent_bottom = self.memory.load_i32(function.wrapping_add(2 * WORD))?;
+        let function = self
+            .program
+            .static_data()
+            .table_entries()
+            .get(table_idx as u32 as usize)
+            .ok_or(RuntimeError::UndefinedTableElement { idx: table_idx })?;
+        Ok((function.to_i32() as usize, persistent_bottom))
+    }
+
+    /// Panics after printing the given message if the stack cannot grow by
+    /// the given number of bytes.
+    fn check_stack(&mut self, bytes: i32, message: i32) -> RuntimeResult<()> {
+        let end = self.stack_bottom as u32 as u64 + bytes as u32 as u64;
+        if end > self.stack_top as u32 as u64 {
+            self.print(message)?;
+            return Err(RuntimeError::Unreachable);
+        }
+        Ok(())
+    }
+
+    /// Runs the function epilogue and pops the frame.
+    fn exit(&mut self) -> i32 {
+        let frame = self.frames.pop().unwrap();
+        if let Some(ref locals) = self.functions[frame.function].locals {
+            self.stack_bottom =
+                self.stack_bottom.wrapping_sub(locals.stack_len());
+        }
+        frame.retval
+    }
+
+    /// Executes the next instruction in the current frame, returning the
+    /// return value if that finished the function.
+    fn step(&mut self) -> RuntimeResult<Option<i32>> {
+        let frame = self.frame();
+        let function = &self.program.functions()[frame.function];
+        let Some(&instruction) = function.instructions().get(frame.pc) else {
+            return Ok(Some(self.exit()));
+        };
+
+        match instruction {
+            Instruction::Call {
+                function,
+                params,
+                to,
+            } => {
+                let params = self.load_place(params)?;
+                self.frame_mut().call_target = Some(to);
+                // target of direct call never uses persistent storage
+                self.enter(function.to_i32() as usize, params, 0)?;
+                return Ok(None);
+            }
+            Instruction::CallIndirect {
+                function,
+                params,
+                to,
+            } => {
+                let function = self.load_place(function)?;
+                let params = self.load_place(params)?;
+                let (function, persistent_bottom) =
+                    self.resolve_function_object(function)?;
+                self.frame_mut().call_target = Some(to);
+                self.enter(function, params, persistent_bottom)?;
+                return Ok(None);
+            }
+            // the new frame replaces the current one, so the return value
+            // goes to the call target of the caller
+            Instruction::TailCall { function, params } => {
+                let params = self.load_place(params)?;
+                self.exit();
+                self.enter(function.to_i32() as usize, params, 0)?;
+                return Ok(None);
+            }
+            Instruction::TailCallIndirect { function, params } => {
+                let function = self.load_place(function)?;
+                let params = self.load_place(params)?;
+                let (function, persistent_bottom) =
+                    self.resolve_function_object(function)?;
+                self.exit();
+                self.enter(function, params, persistent_bottom)?;
+                return Ok(None);
+            }
+            Instruction::CallPrint { string } => {
+                let string = self.load_place(string)?;
+                self.print(string)?;
+            }
+            Instruction::Return { value, values } => {
+                self.frame_mut().retval = self.load_place(value)?;
+                self.values = match values {
+                    Some(values) => self.load_place(values)?,
+                    None => 0,
+                };
+                return Ok(Some(self.exit()));
+            }
+            Instruction::LoadValues { to } => {
+                self.store_place(to, self.values)?;
+            }
+            Instruction::EnterBlock => {
+                let frame = self.frame_mut();
+                frame.blocks.push(frame.pc);
+            }
+            Instruction::ExitBlock => {
+                self.frame_mut().blocks.pop();
+            }
+            Instruction::EnterCatch { tag, to } => {
+                let tag = tag.map(|tag| self.load_place(tag)).transpose()?;
+                let frame = self.frame();
+                let catcher = Catcher {
+                    tag,
+                    frame: self.frames.len() - 1,
+                    blocks: frame.blocks.len(),
+                    end: self.functions[frame.function].block_ends[frame.pc],
+                    to,
+                };
+                self.catchers.push(catcher);
+            }
+            Instruction::ExitCatch => {
+                self.catchers.pop();
+            }
+            Instruction::Throw { thrown } => {
+                let thrown = self.load_place(thrown)?;
+                self.throw(thrown)?;
+                return Ok(None);
+            }
+            Instruction::Break { block_up } => {
+                self.break_block(block_up);
+                return Ok(None);
+            }
+            Instruction::Continue { block_up } => {
+                self.continue_block(block_up);
+                return Ok(None);
+            }
+            Instruction::BreakIfNotNil {
+                if_not_nil,
+                block_up,
+            } => {
+                if self.load_place(if_not_nil)? != self.nil() {
+                    self.break_block(block_up);
+                    return Ok(None);
+                }
+            }
+            Instruction::BreakIfNil { if_nil, block_up } => {
+                if self.load_place(if_nil)? == self.nil() {
+                    self.break_block(block_up);
+                    return Ok(None);
+                }
+            }
+            Instruction::ContinueIfNotNil {
+                if_not_nil,
+                block_up,
+            } => {
+                if self.load_place(if_not_nil)? != self.nil() {
+                    self.continue_block(block_up);
+                    return Ok(None);
+                }
+            }
+            Instruction::NilIfZero { check, to } => {
+                let number = self.load_place(check)?;
+                let value = self.load_number(check)?;
+                let result = if value != 0 { number } else { self.nil() };
+                self.store_place(to, result)?;
+            }
+            Instruction::ConsumeParam { to } => {
+                let param_head = self.frame().param_head;
+                let car = self.memory.load_i32(param_head.wrapping_add(WORD))?;
+                self.store_place(to, car)?;
+                let cdr = self.memory.load_i32(param_head.wrapping_add(2 * WORD))?;
+                self.frame_mut().param_head = cdr;
+            }
+            Instruction::ConsumeRest { to } => {
+                let param_head = self.frame().param_head;
+                self.store_place(to, param_head)?;
+            }
+            Instruction::ParamPresent { to } => {
+                let present = self.frame().param_head != self.nil();
+                let result = if present { self.t() } else { self.nil() };
+                self.store_place(to, result)?;
+            }
+            Instruction::FindKeyParam { keyword, to } => {
+                let found = self.find_key_param(keyword.offset())?;
+                self.store_place(to, found)?;
+            }
+            Instruction::KeyParamsKnown { keywords, to } => {
+                let known = self.key_params_known(keywords.offset())?;
+                let result = if known { self.t() } else { self.nil() };
+                self.store_place(to, result)?;
+            }
+            Instruction::ArityMatches { min, max, to } => {
+                let matches = self.arity_matches(min, max)?;
+                let result = if matches { self.t() } else { self.nil() };
+                self.store_place(to, result)?;
+            }
+            Instruction::LoadData { data, to } => {
+                self.store_place(to, data.offset())?;
+            }
+            Instruction::WritePlace { from, to } => {
+                let value = self.load_place(from)?;
+                self.store_place(to, value)?;
+            }
+            Instruction::CreateFunction { function, to } => {
+                let persistent_bottom = self.frame().persistent_bottom;
+                let function =
+                    self.make_function(function.to_u32(), persistent_bottom)?;
+                self.store_place(to, function)?;
+            }
+            Instruction::Cons { car, cdr, to } => {
+                let node = self.make_node(IrDataType::ListNode, car, cdr)?;
+                self.store_place(to, node)?;
+            }
+            Instruction::LoadCar { list, to } => {
+                let list = self.load_place(list)?;
+                let car = self.memory.load_i32(list.wrapping_add(WORD))?;
+                self.store_place(to, car)?;
+            }
+            Instruction::LoadCdr { list, to } => {
+                let list = self.load_place(list)?;
+                let cdr = self.memory.load_i32(list.wrapping_add(2 * WORD))?;
+                self.store_place(to, cdr)?;
+            }
+            Instruction::StoreCar { list, value } => {
+                let list = self.load_place(list)?;
+                let value = self.load_place(value)?;
+                self.memory.store_i32(list.wrapping_add(WORD), value)?;
+            }
+            Instruction::StoreCdr { list, value } => {
+                let list = self.load_place(list)?;
+                let value = self.load_place(value)?;
+                self.memory.store_i32(list.wrapping_add(2 * WORD), value)?;
+            }
+            Instruction::MakeBignum { sign, digits, to } => {
+                let bignum =
+                    self.make_node(IrDataType::BigInt, sign, digits)?;
+                self.store_place(to, bignum)?;
+            }
+            Instruction::MakeRatio {
+                numerator,
+                denominator,
+                to,
+            } => {
+                let ratio =
+                    self.make_node(IrDataType::Ratio, numerator, denominator)?;
+                self.store_place(to, ratio)?;
+            }
+            Instruction::Add { left, right, to } => {
+                self.arithmetic(left, right, to, |l, r| Ok(l.checked_add(r)))?
+            }
+            Instruction::Sub { left, right, to } => {
+                self.arithmetic(left, right, to, |l, r| Ok(l.checked_sub(r)))?
+            }
+            Instruction::Mul { left, right, to } => {
+                self.arithmetic(left, right, to, |l, r| Ok(l.checked_mul(r)))?
+            }
+            Instruction::Div { left, right, to } => {
+                self.arithmetic(left, right, to, |l, r| match r {
+                    0 => Err(RuntimeError::DivideByZero),
+                    r => Ok(l.checked_div(r)),
+                })?
+            }
+            Instruction::Eq { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l == r)?
+            }
+            Instruction::Same { left, right, to } => {
+                let same = self.load_place(left)? == self.load_place(right)?;
+                let result = if same { self.t() } else { self.nil() };
+                self.store_place(to, result)?
+            }
+            Instruction::Ne { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l != r)?
+            }
+            Instruction::Lt { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l < r)?
+            }
+            Instruction::Gt { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l > r)?
+            }
+            Instruction::Lte { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l <= r)?
+            }
+            Instruction::Gte { left, right, to } => {
+                self.comparison(left, right, to, |l, r| l >= r)?
+            }
+            Instruction::FloatAdd { left, right, to } => {
+                self.float_arithmetic(left, right, to, |l, r| l + r)?
+            }
+            Instruction::FloatSub { left, right, to } => {
+                self.float_arithmetic(left, right, to, |l, r| l - r)?
+            }
+            Instruction::FloatMul { left, right, to } => {
+                self.float_arithmetic(left, right, to, |l, r| l * r)?
+            }
+            Instruction::FloatDiv { left, right, to } => {
+                self.float_arithmetic(left, right, to, |l, r| l / r)?
+            }
+            Instruction::FloatEq { left, right, to } => {
+                self.float_comparison(left, right, to, |l, r| l == r)?
+            }
+            Instruction::FloatLt { left, right, to } => {
+                self.float_comparison(left, right, to, |l, r| l < r)?
+            }
+            Instruction::FloatLte { left, right, to } => {
+                self.float_comparison(left, right, to, |l, r| l <= r)?
+            }
+            Instruction::IntToFloat { number, to } => {
+                let float = self.make_float(self.load_number(number)? as f32)?;
+                self.store_place(to, float)?;
+            }
+            Instruction::FloatTruncate { float, to } => {
+                let float = self.load_float(float)?.trunc();
+                if float.is_nan() {
+                    return Err(RuntimeError::InvalidConversionToInteger);
+                }
+                // the bounds are exact as floats, unlike i32::MAX
+                if float < i32::MIN as f32 || float >= -(i32::MIN as f32) {
+                    return Err(RuntimeError::IntegerOverflow);
+                }
+                let number = self.make_num(float as i32)?;
+                self.store_place(to, number)?;
+            }
+            Instruction::ConcatStringLike { left, right, to } => {
+                let left = self.load_place(left)?;
+                let right = self.load_place(right)?;
+                let concatenated = self.concat_strings(left, right)?;
+                self.store_place(to, concatenated)?;
+            }
+            Instruction::CharCode { character, to } => {
+                let character = self.load_place(character)?;
+                let code = self.memory.load_i32(character.wrapping_add(WORD))?;
+                let code = self.make_num(code)?;
+                self.store_place(to, code)?;
+            }
+            Instruction::CodeChar { code, to } => {
+                let character = self.make_character(self.load_number(code)?)?;
+                self.store_place(to, character)?;
+            }
+            Instruction::CharAt { string, index, to } => {
+                let string = self.load_place(string)?;
+                let index = self.load_number(index)?;
+                let character = match self.char_at(string, index)? {
+                    Some(character) => self.make_character(character as i32)?,
+                    None => self.nil(),
+                };
+                self.store_place(to, character)?;
+            }
+            Instruction::CharString { character, to } => {
+                let character = self.load_place(character)?;
+                let code = self.memory.load_i32(character.wrapping_add(WORD))?;
+                let string = self.char_string(code)?;
+                self.store_place(to, string)?;
+            }
+            Instruction::MakeVector { length, to } => {
+                let len = self.load_number(length)?.wrapping_mul(WORD);
+                let vector = self.alloc_sized(len, IrDataType::Vector)?;
+                self.memory.fill(vector.wrapping_add(2 * WORD), len as u32, 0)?;
+                self.store_place(to, vector)?;
+            }
+            Instruction::LoadElement { vector, index, to } => {
+                let element = self.element_address(vector, index)?;
+                let element = self.memory.load_i32(element)?;
+                self.store_place(to, element)?;
+            }
+            Instruction::StoreElement {
+                vector,
+                index,
+                value,
+            } => {
+                let element = self.element_address(vector, index)?;
+                let value = self.load_place(value)?;
+                self.memory.store_i32(element, value)?;
+            }
+            Instruction::LoadLength { of, to } => {
+                let of = self.load_place(of)?;
+                let len = self.length_of(of)?;
+                let len = self.make_num(len)?;
+                self.store_place(to, len)?;
+            }
+            Instruction::LoadTypeTag { of, to } => {
+                let of = self.load_place(of)?;
+                let tag = match fixnum_value(of) {
+                    Some(_) => IrDataType::SInt32.to_u32() as i32,
+                    None => self.memory.load_i32(of)?,
+                };
+                let tag = self.make_num(tag)?;
+                self.store_place(to, tag)?;
+            }
+            Instruction::Panic => return Err(RuntimeError::Unreachable),
+        }
+
+        self.frame_mut().pc += 1;
+        Ok(None)
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn nil(&self) -> i32 {
+        self.program.static_data().nil_data().offset()
+    }
+
+    fn t(&self) -> i32 {
+        self.program.static_data().t_data().offset()
+    }
+
+    /// Continues after the end of the block `block_up` levels up.
+    fn break_block(&mut self, block_up: u32) {
+        let frame = self.frames.last_mut().unwrap();
+        let target_level = frame.blocks.len() - block_up as usize;
+        let target = frame.blocks[target_level];
+        frame.blocks.truncate(target_level);
+        frame.pc = self.functions[frame.function].block_ends[target] + 1;
+    }
+
+    /// Unwinds to the innermost catcher that catches the thrown list and
+    /// continues after its region, or panics if no catcher has its tag.
+    fn throw(&mut self, thrown: i32) -> RuntimeResult<()> {
+        let tag = self.memory.load_i32(thrown.wrapping_add(WORD))?;
+        if !self.catchers.iter().any(|c| c.tag == Some(tag)) {
+            self.print(self.uncaught_throw_message)?;
+            return Err(RuntimeError::Unreachable);
+        }
+        let catcher = loop {
+            let catcher = self.catchers.pop().unwrap();
+            if catcher.tag.is_none_or(|catcher_tag| catcher_tag == tag) {
+                break catcher;
+            }
+        };
+        while self.frames.len() > catcher.frame + 1 {
+            self.exit();
+        }
+        let frame = self.frame_mut();
+        frame.blocks.truncate(catcher.blocks);
+        frame.pc = catcher.end + 1;
+        // the call that was in progress is not going to return
+        frame.call_target = None;
+        self.store_place(catcher.to, thrown)
+    }
+
+    /// Continues at the start of the block `block_up` levels up.
+    fn continue_block(&mut self, block_up: u32) {
+        let frame = self.frame_mut();
+        let target_level = frame.blocks.len() - block_up as usize;
+        let target = frame.blocks[target_level];
+        frame.blocks.truncate(target_level + 1);
+        frame.pc = target + 1;
+    }
+
+    /// Gets the address of a place itself, so that it can be overwritten.
+    fn place_address(&self, place: PlaceAddress) -> RuntimeResult<i32> {
+        let frame = self.frame();
+        let strategy = self.functions[frame.function]
+            .locals
+            .as_ref()
+            .map(LocalPlacesInfo::strategy);
+        let address = match (place.mode(), strategy) {
+            // local variables are below the stack bottom that gets bumped on entry
+            (AddressingMode::Local, Some(LocalStrategy::Stack)) => {
+                self.stack_bottom.wrapping_sub(place.offset() + WORD)
+            }
+            (AddressingMode::Local, Some(LocalStrategy::Heap)) => {
+                frame.persistent_bottom.wrapping_add(place.offset())
+            }
+            (AddressingMode::Local, None) => {
+                unreachable!("local place in function without locals")
+            }
+            // follow the links in the first place of each lambda up to the
+            // block of places that the captured place is in
+            (AddressingMode::Captured { depth }, Some(LocalStrategy::Heap)) => {
+                let mut block =
+                    self.memory.load_i32(frame.persistent_bottom)?;
+                for _ in 1..depth {
+                    block = self.memory.load_i32(block.wrapping_add(2 * WORD))?;
+                }
+                block.wrapping_add(2 * WORD + place.offset())
+            }
+            (AddressingMode::Captured { .. }, _) => {
+                unreachable!("captured place in function that is not a lambda")
+            }
+            (AddressingMode::Global, _) => place.offset(),
+        };
+        Ok(address)
+    }
+
+    /// Gets the address that a place points to.
+    fn load_place(&self, place: PlaceAddress) -> RuntimeResult<i32> {
+        self.memory.load_i32(self.place_address(place)?)
+    }
+
+    fn store_place(
+        &mut self,
+        place: PlaceAddress,
+        value: i32,
+    ) -> RuntimeResult<()> {
+        self.memory.store_i32(self.place_address(place)?, value)
+    }
+
+    /// Gets the value of the number that a place points to.
+    fn load_number(&self, place: PlaceAddress) -> RuntimeResult<i32> {
+        let word = self.load_place(place)?;
+        match fixnum_value(word) {
+            Some(value) => Ok(value),
+            None => self.memory.load_i32(word.wrapping_add(WORD)),
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+        op: impl Fn(i32, i32) -> RuntimeResult<Option<i32>>,
+    ) -> RuntimeResult<()> {
+        let result = op(self.load_number(left)?, self.load_number(right)?)?;
+        let result = match result {
+            Some(result) => self.make_num(result)?,
+            None => self.nil(),
+        };
+        self.store_place(to, result)
+    }
+
+    fn comparison(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+        op: impl Fn(i32, i32) -> bool,
+    ) -> RuntimeResult<()> {
+        let result = op(self.load_number(left)?, self.load_number(right)?);
+        let result = if result { self.t() } else { self.nil() };
+        self.store_place(to, result)
+    }
+
+    fn load_float(&self, place: PlaceAddress) -> RuntimeResult<f32> {
+        let bits = self.memory.load_i32(self.load_place(place)?.wrapping_add(WORD))?;
+        Ok(f32::from_bits(bits as u32))
+    }
+
+    fn float_arithmetic(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+        op: impl Fn(f32, f32) -> f32,
+    ) -> RuntimeResult<()> {
+        let result = op(self.load_float(left)?, self.load_float(right)?);
+        let result = self.make_float(result)?;
+        self.store_place(to, result)
+    }
+
+    fn float_comparison(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+        op: impl Fn(f32, f32) -> bool,
+    ) -> RuntimeResult<()> {
+        let result = op(self.load_float(left)?, self.load_float(right)?);
+        let result = if result { self.t() } else { self.nil() };
+        self.store_place(to, result)
+    }
+
+    /// Finds a keyword in the parameters left to consume like
+    /// `$find_key_param` in `rt/rt.wat`, returning the rest of the parameters
+    /// starting at its value or nil.
+    fn find_key_param(&self, keyword: i32) -> RuntimeResult<i32> {
+        let nil = self.nil();
+        let mut params = self.frame().param_head;
+        while params != nil {
+            let value = self.memory.load_i32(params.wrapping_add(2 * WORD))?;
+            // a keyword without a value is not found
+            if value == nil {
+                break;
+            }
+            if self.memory.load_i32(params.wrapping_add(WORD))? == keyword {
+                return Ok(value);
+            }
+            params = self.memory.load_i32(value.wrapping_add(2 * WORD))?;
+        }
+        Ok(nil)
+    }
+
+    /// Checks that the parameters left to consume are pairs of keywords from
+    /// the given list and values, like `$key_params_known` in `rt/rt.wat`.
+    fn key_params_known(&self, keywords: i32) -> RuntimeResult<bool> {
+        let nil = self.nil();
+        let mut params = self.frame().param_head;
+        while params != nil {
+            let value = self.memory.load_i32(params.wrapping_add(2 * WORD))?;
+            if value == nil {
+                return Ok(false);
+            }
+            let keyword = self.memory.load_i32(params.wrapping_add(WORD))?;
+            let mut known = keywords;
+            while known != nil {
+                if self.memory.load_i32(known.wrapping_add(WORD))? == keyword {
+                    break;
+                }
+                known = self.memory.load_i32(known.wrapping_add(2 * WORD))?;
+            }
+            if known == nil {
+                return Ok(false);
+            }
+            params = self.memory.load_i32(value.wrapping_add(2 * WORD))?;
+        }
+        Ok(true)
+    }
+
+    /// Counts the parameters left to consume like `$arity_matches` in
+    /// `rt/rt.wat`, but not more than one past the maximum.
+    fn arity_matches(&self, min: u32, max: Option<u32>) -> RuntimeResult<bool> {
+        let nil = self.nil();
+        let limit = max.map_or(min, |max| max + 1);
+        let mut params = self.frame().param_head;
+        let mut count = 0;
+        while params != nil && count < limit {
+            count += 1;
+            params = self.memory.load_i32(params.wrapping_add(2 * WORD))?;
+        }
+        Ok(count >= min && max.is_none_or(|max| count <= max))
+    }
+
+    /// Prints a string like the host does, with one line per call.
+    fn print(&mut self, string: i32) -> RuntimeResult<()> {
+        let len = self.memory.load_i32(string.wrapping_add(WORD))?;
+        let bytes = self.memory.slice(string.wrapping_add(2 * WORD), len as u32)?;
+        self.out.write_all(bytes)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Allocates space on the heap and returns the start, collecting garbage
+    /// if the heap is full and then growing memory if that did not help or if
+    /// most of the heap is still in use. The size is rounded up to whole words.
+    ///
+    /// Panics after printing a message if memory cannot grow anymore.
+    fn alloc_heap(&mut self, bytes: i32) -> RuntimeResult<i32> {
+        // sizes are unsigned like in web assembly, and blocks too large for
+        // an i32 could never fit into memory
+        let Some(bytes) = (bytes as u32)
+            .checked_next_multiple_of(WORD_SIZE)
+            .and_then(|bytes| i32::try_from(bytes).ok())
+        else {
+            return self.out_of_memory();
+        };
+        if let Some(start) = self.heap.try_alloc(&mut self.memory, bytes)? {
+            return Ok(start);
+        }
+        self.collect_garbage()?;
+        if self.heap.mostly_live(&self.memory) {
+            self.heap.grow(&mut self.memory, bytes, self.max_pages);
+        }
+        if let Some(start) = self.heap.try_alloc(&mut self.memory, bytes)? {
+            return Ok(start);
+        }
+        if !self.heap.grow(&mut self.memory, bytes, self.max_pages) {
+            return self.out_of_memory();
+        }
+        self.heap
+            .try_alloc(&mut self.memory, bytes)?
+            .ok_or(RuntimeError::Unreachable)
+    }
+
+    /// Panics with the same message as `rt/gc.wat` when an allocation does
+    /// not fit.
+    fn out_of_memory(&mut self) -> RuntimeResult<i32> {
+        self.print(self.out_of_memory_message)?;
+        Err(RuntimeError::Unreachable)
+    }
+
+    /// Collects garbage, with the places of global variables and all words
+    /// on the stack as roots.
+    fn collect_garbage(&mut self) -> RuntimeResult<()> {
+        let mut roots = vec![];
+        for place in self.program.static_data().places() {
+            roots.push(self.memory.load_i32(place.offset())?);
+        }
+        roots.push(self.values);
+        roots.extend(self.catchers.iter().filter_map(|c| c.tag));
+        for word in
+            (self.stack_start..self.stack_bottom).step_by(WORD_SIZE as usize)
+        {
+            roots.push(self.memory.load_i32(word)?);
+        }
+        self.heap.collect(&mut self.memory, &roots)
+    }
+
+    /// Allocates a sized thing like a string with the given length and type,
+    /// where only the type and length are initialized.
+    fn alloc_sized(
+        &mut self,
+        len: i32,
+        data_type: IrDataType,
+    ) -> RuntimeResult<i32> {
+        self.alloc_tagged(len, data_type.to_u32())
+    }
+
+    /// Allocates a block of persistent places that initially point to nil and
+    /// returns the address of the first place.
+    fn alloc_places(&mut self, len: i32) -> RuntimeResult<i32> {
+        let places = self.alloc_tagged(len, PLACES_TAG)? + 2 * WORD;
+        self.memory.fill(places, len as u32, 0)?;
+        Ok(places)
+    }
+
+    /// Allocates space with the given tag and length in bytes after it.
+    fn alloc_tagged(&mut self, len: i32, tag: u32) -> RuntimeResult<i32> {
+        let Some(bytes) = len.checked_add(2 * WORD) else {
+            return self.out_of_memory();
+        };
+        let start = self.alloc_heap(bytes)?;
+        self.memory.store_i32(start, tag as i32)?;
+        self.memory.store_i32(start.wrapping_add(WORD), len)?;
+        Ok(start)
+    }
+
+    /// Allocates an object laid out like a list node, with the given type
+    /// tag and what two places point to.
+    fn make_node(
+        &mut self,
+        data_type: IrDataType,
+        car: PlaceAddress,
+        cdr: PlaceAddress,
+    ) -> RuntimeResult<i32> {
+        let node = self.alloc_heap(3 * WORD)?;
+        self.memory.store_i32(node, data_type.to_u32() as i32)?;
+        let car = self.load_place(car)?;
+        self.memory.store_i32(node.wrapping_add(WORD), car)?;
+        let cdr = self.load_place(cdr)?;
+        self.memory.store_i32(node.wrapping_add(2 * WORD), cdr)?;
+        Ok(node)
+    }
+
+    fn make_num(&mut self, value: i32) -> RuntimeResult<i32> {
+        if let Some(word) = fixnum(value) {
+            return Ok(word);
+        }
+        let start = self.alloc_heap(2 * WORD)?;
+        self.memory
+            .store_i32(start, IrDataType::SInt32.to_u32() as i32)?;
+        self.memory.store_i32(start.wrapping_add(WORD), value)?;
+        Ok(start)
+    }
+
+    fn make_float(&mut self, value: f32) -> RuntimeResult<i32> {
+        let start = self.alloc_heap(2 * WORD)?;
+        self.memory
+            .store_i32(start, IrDataType::Float32.to_u32() as i32)?;
+        self.memory
+            .store_i32(start.wrapping_add(WORD), value.to_bits() as i32)?;
+        Ok(start)
+    }
+
+    fn make_character(&mut self, code: i32) -> RuntimeResult<i32> {
+        let start = self.alloc_heap(2 * WORD)?;
+        self.memory
+            .store_i32(start, IrDataType::Character.to_u32() as i32)?;
+        self.memory.store_i32(start.wrapping_add(WORD), code)?;
+        Ok(start)
+    }
+
+    fn make_function(
+        &mut self,
+        table_idx: u32,
+        persistent_bottom: i32,
+    ) -> RuntimeResult<i32> {
+        let start = self.alloc_heap(3 * WORD)?;
+        self.memory
+            .store_i32(start, IrDataType::Function.to_u32() as i32)?;
+        self.memory.store_i32(start.wrapping_add(WORD), table_idx as i32)?;
+        self.memory.store_i32(start.wrapping_add(2 * WORD), persistent_bottom)?;
+        Ok(start)
+    }
+
+    fn concat_strings(&mut self, left: i32, right: i32) -> RuntimeResult<i32> {
+        let left_len = self.memory.load_i32(left.wrapping_add(WORD))?;
+        let right_len = self.memory.load_i32(right.wrapping_add(WORD))?;
+        let result = self.alloc_sized(
+            left_len.wrapping_add(right_len),
+            IrDataType::CharacterData,
+        )?;
+        let chars = result.wrapping_add(2 * WORD);
+        self.memory.copy(chars, left.wrapping_add(2 * WORD), left_len as u32)?;
+        self.memory.copy(
+            chars + left_len,
+            right.wrapping_add(2 * WORD),
+            right_len as u32,
+        )?;
+        Ok(result)
+    }
+
+    /// Decodes the character at an index of the UTF-8 data of a string or
+    /// identifier, if the index is in range.
+    fn char_at(&self, string: i32, index: i32) -> RuntimeResult<Option<char>> {
+        let len = self.memory.load_i32(string.wrapping_add(WORD))?;
+        let bytes = self.memory.slice(string.wrapping_add(2 * WORD), len as u32)?;
+        let characters =
+            str::from_utf8(bytes).map_err(|_| RuntimeError::Unreachable)?;
+        Ok(usize::try_from(index)
+            .ok()
+            .and_then(|index| characters.chars().nth(index)))
+    }
+
+    /// Gets the address of the element at an index of a vector.
+    fn element_address(
+        &self,
+        vector: PlaceAddress,
+        index: PlaceAddress,
+    ) -> RuntimeResult<i32> {
+        let vector = self.load_place(vector)?;
+        let index = self.load_number(index)?;
+        Ok(vector
+            .wrapping_add(2 * WORD)
+            .wrapping_add(index.wrapping_mul(WORD)))
+    }
+
+    /// Counts the elements of a vector, or the characters of the UTF-8 data
+    /// of a string or identifier.
+    fn length_of(&self, of: i32) -> RuntimeResult<i32> {
+        let tag = self.memory.load_i32(of)? as u32;
+        let len = self.memory.load_i32(of.wrapping_add(WORD))?;
+        if tag == IrDataType::Vector.to_u32() {
+            return Ok(len / WORD);
+        }
+        let bytes = self.memory.slice(of.wrapping_add(2 * WORD), len as u32)?;
+        let characters =
+            str::from_utf8(bytes).map_err(|_| RuntimeError::Unreachable)?;
+        Ok(characters.chars().count() as i32)
+    }
+
+    /// Creates a string with the UTF-8 encoding of a character.
+    fn char_string(&mut self, code: i32) -> RuntimeResult<i32> {
+        let character =
+            char::from_u32(code as u32).ok_or(RuntimeError::Unreachable)?;
+        let mut encoded = [0; 4];
+        let encoded = character.encode_utf8(&mut encoded).as_bytes();
+        let len = encoded.len() as u32;
+        let string = self.alloc_sized(len as i32, IrDataType::CharacterData)?;
+        self.memory
+            .slice_mut(string.wrapping_add(2 * WORD), len)?
+            .copy_from_slice(encoded);
+        Ok(string)
+    }
+}
+
+/// Finds the matching `ExitBlock` for every `EnterBlock`, and the matching
+/// `ExitCatch` for every `EnterCatch`.
+fn block_ends(instructions: &[Instruction]) -> Vec<usize> {
+    let mut ends = vec![usize::MAX; instructions.len()];
+    let mut open = vec![];
+    for (idx, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::EnterBlock | Instruction::EnterCatch { .. } => {
+                open.push(idx)
+            }
+            Instruction::ExitBlock | Instruction::ExitCatch => {
+                let start = open.pop().expect("unbalanced ExitBlock");
+                ends[start] = idx;
+            }
+            _ => {}
+        }
+    }
+    ends
+}
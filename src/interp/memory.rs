@@ -0,0 +1,85 @@
+use super::err::{RuntimeError, RuntimeResult};
+
+/// Linear memory, addressed like web assembly memory.
+pub struct Memory {
+    bytes: Vec<u8>,
+}
+
+impl Memory {
+    pub fn new(size: u32) -> Self {
+        Self {
+            bytes: vec![0; size as usize],
+        }
+    }
+
+    /// Size in bytes.
+    pub fn size(&self) -> u32 {
+        self.bytes.len() as u32
+    }
+
+    /// Adds zeroed bytes at the end, like `memory.grow` in web assembly.
+    pub fn grow(&mut self, bytes: u32) {
+        self.bytes.resize(self.bytes.len() + bytes as usize, 0);
+    }
+
+    pub fn load_i32(&self, address: i32) -> RuntimeResult<i32> {
+        let bytes = self.slice(address, 4)?;
+        Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn store_i32(&mut self, address: i32, value: i32) -> RuntimeResult<()> {
+        self.slice_mut(address, 4)?
+            .copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /// Gets bytes at an address, which is interpreted as unsigned like in
+    /// web assembly.
+    pub fn slice(&self, address: i32, len: u32) -> RuntimeResult<&[u8]> {
+        let range = self.range(address, len)?;
+        Ok(&self.bytes[range])
+    }
+
+    pub fn slice_mut(
+        &mut self,
+        address: i32,
+        len: u32,
+    ) -> RuntimeResult<&mut [u8]> {
+        let range = self.range(address, len)?;
+        Ok(&mut self.bytes[range])
+    }
+
+    pub fn fill(
+        &mut self,
+        address: i32,
+        len: u32,
+        value: u8,
+    ) -> RuntimeResult<()> {
+        self.slice_mut(address, len)?.fill(value);
+        Ok(())
+    }
+
+    /// Copies bytes, allowing for overlap.
+    pub fn copy(&mut self, dst: i32, src: i32, len: u32) -> RuntimeResult<()> {
+        let src = self.range(src, len)?;
+        let dst = self.range(dst, len)?;
+        self.bytes.copy_within(src, dst.start);
+        Ok(())
+    }
+
+    fn range(
+        &self,
+        address: i32,
+        len: u32,
+    ) -> RuntimeResult<std::ops::Range<usize>> {
+        let start = address as u32 as usize;
+        let end = start + len as usize;
+        if end > self.bytes.len() {
+            return Err(RuntimeError::OutOfBounds {
+                address: start as i64,
+                len,
+            });
+        }
+        Ok(start..end)
+    }
+}
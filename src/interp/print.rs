@@ -0,0 +1,417 @@
+use std::fmt;
+
+use crate::ir::{BIGNUM_RADIX, IrDataType, IrDataTypeTag, fixnum_value};
+
+use super::memory::Memory;
+
+/// Nodes to print at most, so that circular lists terminate.
+const MAX_PRINTED_NODES: usize = 10_000;
+
+/// Displays a value in memory as LISP data, e.g. `(1 "two" THREE)`.
+pub struct DisplayValue<'m> {
+    memory: &'m Memory,
+    address: i32,
+    nil: i32,
+}
+
+impl<'m> DisplayValue<'m> {
+    pub fn new(memory: &'m Memory, address: i32, nil: i32) -> Self {
+        Self {
+            memory,
+            address,
+            nil,
+        }
+    }
+}
+
+impl fmt::Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut budget = MAX_PRINTED_NODES;
+        self.write_value(f, self.address, &mut budget)
+    }
+}
+
+impl DisplayValue<'_> {
+    fn write_value(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        address: i32,
+        budget: &mut usize,
+    ) -> fmt::Result {
+        if *budget == 0 {
+            return write!(f, "...");
+        }
+        *budget -= 1;
+
+        if address == self.nil {
+            return write!(f, "NIL");
+        }
+        if let Some(number) = fixnum_value(address) {
+            return write!(f, "{}", number);
+        }
+        let Some(data_type) = self.data_type(address) else {
+            return write!(f, "#<invalid value at {}>", address);
+        };
+        match data_type {
+            IrDataType::Nil => write!(f, "NIL"),
+            IrDataType::SInt32 => match self.word(address, 1) {
+                Some(number) => write!(f, "{}", number),
+                None => write!(f, "#<invalid number at {}>", address),
+            },
+            IrDataType::Float32 => match self.word(address, 1) {
+                Some(bits) => write_float(f, f32::from_bits(bits as u32)),
+                None => write!(f, "#<invalid float at {}>", address),
+            },
+            IrDataType::Character => {
+                match self.word(address, 1).map(|c| char::from_u32(c as u32)) {
+                    Some(Some(character)) => write_character(f, character),
+                    _ => write!(f, "#<invalid character at {}>", address),
+                }
+            }
+            IrDataType::CharacterData => match self.characters(address) {
+                Some(string) => {
+                    write!(f, "\"")?;
+                    for c in string.chars() {
+                        if c == '"' || c == '\\' {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                    write!(f, "\"")
+                }
+                None => write!(f, "#<invalid string at {}>", address),
+            },
+            IrDataType::Identifier => match self.characters(address) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "#<invalid symbol at {}>", address),
+            },
+            IrDataType::Function => write!(f, "#<FUNCTION>"),
+            IrDataType::ListNode => self.write_list(f, address, budget),
+            IrDataType::Vector => self.write_vector(f, address, budget),
+            IrDataType::Ratio => {
+                match (self.word(address, 1), self.word(address, 2)) {
+                    (Some(numerator), Some(denominator)) => {
+                        self.write_value(f, numerator, budget)?;
+                        write!(f, "/")?;
+                        self.write_value(f, denominator, budget)
+                    }
+                    _ => write!(f, "#<invalid ratio at {}>", address),
+                }
+            }
+            IrDataType::BigInt => match self.bignum(address) {
+                Some((sign, digits)) => write_bignum(f, sign, digits),
+                None => write!(f, "#<invalid bignum at {}>", address),
+            },
+        }
+    }
+
+    fn write_list(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        mut address: i32,
+        budget: &mut usize,
+    ) -> fmt::Result {
+        write!(f, "(")?;
+        loop {
+            let (Some(car), Some(cdr)) =
+                (self.word(address, 1), self.word(address, 2))
+            else {
+                return write!(f, "#<invalid list node at {}>)", address);
+            };
+            self.write_value(f, car, budget)?;
+            if cdr == self.nil {
+                return write!(f, ")");
+            }
+            if *budget == 0 {
+                return write!(f, " ...)");
+            }
+            if !matches!(self.data_type(cdr), Some(IrDataType::ListNode)) {
+                write!(f, " . ")?;
+                self.write_value(f, cdr, budget)?;
+                return write!(f, ")");
+            }
+            write!(f, " ")?;
+            address = cdr;
+        }
+    }
+
+    fn write_vector(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        address: i32,
+        budget: &mut usize,
+    ) -> fmt::Result {
+        let Some(len) = self.word(address, 1) else {
+            return write!(f, "#<invalid vector at {}>", address);
+        };
+        write!(f, "#(")?;
+        for idx in 0..len / 4 {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            if *budget == 0 {
+                return write!(f, "...)");
+            }
+            match self.word(address, 2 + idx) {
+                Some(element) => self.write_value(f, element, budget)?,
+                None => return write!(f, "#<invalid element>)"),
+            }
+        }
+        write!(f, ")")
+    }
+
+    fn data_type(&self, address: i32) -> Option<IrDataType> {
+        if fixnum_value(address).is_some() {
+            return Some(IrDataType::SInt32);
+        }
+        let tag = self.word(address, 0)?;
+        IrDataTypeTag::try_from(tag as u32)
+            .ok()
+            .map(IrDataTypeTag::to_type)
+    }
+
+    /// Gets the sign and the digits of a bignum, least significant first.
+    fn bignum(&self, address: i32) -> Option<(i32, Vec<i32>)> {
+        let sign = fixnum_value(self.word(address, 1)?)?;
+        let mut digits = vec![];
+        let mut node = self.word(address, 2)?;
+        while node != self.nil && digits.len() < MAX_PRINTED_NODES {
+            digits.push(fixnum_value(self.word(node, 1)?)?);
+            node = self.word(node, 2)?;
+        }
+        Some((sign, digits))
+    }
+
+    /// Gets the word at the given index in a variant, where zero is the tag.
+    fn word(&self, address: i32, idx: i32) -> Option<i32> {
+        self.memory.load_i32(address.wrapping_add(idx * 4)).ok()
+    }
+
+    fn characters(&self, address: i32) -> Option<String> {
+        let len = self.word(address, 1)?;
+        let bytes = self
+            .memory
+            .slice(address.wrapping_add(8), len as u32)
+            .ok()?;
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Writes the decimal digits of a bignum, dividing its digits by ten until
+/// nothing is left.
+fn write_bignum(
+    f: &mut fmt::Formatter<'_>,
+    sign: i32,
+    mut digits: Vec<i32>,
+) -> fmt::Result {
+    let mut decimal = vec![];
+    while !digits.is_empty() {
+        let mut remainder = 0;
+        for digit in digits.iter_mut().rev() {
+            let value = remainder * BIGNUM_RADIX + *digit;
+            *digit = value / 10;
+            remainder = value % 10;
+        }
+        decimal.push(char::from(b'0' + remainder as u8));
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+    }
+    if sign < 0 {
+        write!(f, "-")?;
+    }
+    if decimal.is_empty() {
+        decimal.push('0');
+    }
+    decimal.iter().rev().try_for_each(|c| write!(f, "{}", c))
+}
+
+/// Writes a character like it is read, with names for the ones that would be
+/// white-space.
+fn write_character(f: &mut fmt::Formatter<'_>, character: char) -> fmt::Result {
+    match character {
+        ' ' => write!(f, "#\\Space"),
+        '\n' => write!(f, "#\\Newline"),
+        _ => write!(f, "#\\{}", character),
+    }
+}
+
+/// Writes up to seven significant digits of a float, in scientific notation
+/// for very large or small floats.
+///
+/// Calculates exactly like `to-string-float` in `rt/debug.lisp` so that floats
+/// look the same when dumped. The digits are rounded in `f64`, which holds
+/// every `f32` and the products with powers of ten that matter for rounding
+/// exactly, unlike `f32` itself.
+fn write_float(f: &mut fmt::Formatter<'_>, float: f32) -> fmt::Result {
+    if float.is_nan() {
+        write!(f, "NaN")
+    } else if float < 0.0 {
+        write!(f, "-")?;
+        write_float(f, -float)
+    } else if float == 0.0 {
+        write!(f, "0.0")
+    } else if float.is_infinite() {
+        write!(f, "Infinity")
+    } else if !(0.001..10_000_000.0).contains(&float) {
+        write_scientific(f, float.into())
+    } else {
+        write_fixed(f, float.into())
+    }
+}
+
+/// Writes a positive float with one digit before the point and up to six
+/// after it, followed by the exponent.
+fn write_scientific(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    let mut exponent = decimal_exponent(value);
+    let mut digits = round_half_up(times_power_of_ten(value, 6 - exponent));
+    // rounding up can carry over to the next power of ten
+    if digits == 10_000_000 {
+        digits = 1_000_000;
+        exponent += 1;
+    }
+    write_parts(f, digits / 1_000_000, digits % 1_000_000, 6)?;
+    write!(f, "e{}", exponent)
+}
+
+/// Writes a positive float below ten million without exponent, with seven
+/// significant digits but at least one after the point. Significant digits
+/// start at the first digit that is not zero.
+fn write_fixed(f: &mut fmt::Formatter<'_>, value: f64) -> fmt::Result {
+    let digits = (6 - decimal_exponent(value)).max(1) as u32;
+    let scale = 10_u64.pow(digits);
+    // rounding up can carry over to the whole part
+    let scaled = round_half_up(value * scale as f64);
+    write_parts(f, scaled / scale, scaled % scale, digits)
+}
+
+/// Gets the exponent of the largest power of ten that is not larger than the
+/// positive value.
+fn decimal_exponent(value: f64) -> i32 {
+    // the logarithm can be off by one next to powers of ten
+    let exponent = value.log10().floor() as i32;
+    if times_power_of_ten(1.0, exponent) > value {
+        exponent - 1
+    } else if times_power_of_ten(1.0, exponent + 1) <= value {
+        exponent + 1
+    } else {
+        exponent
+    }
+}
+
+/// Writes the whole part and the given number of fraction digits, leaving out
+/// trailing zeros of the fraction but keeping at least one digit.
+fn write_parts(
+    f: &mut fmt::Formatter<'_>,
+    whole: u64,
+    fraction: u64,
+    digits: u32,
+) -> fmt::Result {
+    let fraction = format!("{:0width$}", fraction, width = digits as usize);
+    let trimmed = fraction.trim_end_matches('0');
+    if trimmed.is_empty() {
+        write!(f, "{}.0", whole)
+    } else {
+        write!(f, "{}.{}", whole, trimmed)
+    }
+}
+
+/// Rounds a positive value with halfway values going up, like `round-half-up`
+/// in `rt/debug.lisp`.
+fn round_half_up(value: f64) -> u64 {
+    value.round() as u64
+}
+
+/// Multiplies or divides by a power of ten. Dividing keeps the result exact
+/// where the quotient is representable, which multiplying with an inexact
+/// fraction like 0.1 would not.
+fn times_power_of_ten(value: f64, exponent: i32) -> f64 {
+    if exponent < 0 {
+        value / 10_f64.powi(-exponent)
+    } else {
+        value * 10_f64.powi(exponent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct DisplayFloat(f32);
+
+    impl fmt::Display for DisplayFloat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_float(f, self.0)
+        }
+    }
+
+    struct DisplayBignum(i32, Vec<i32>);
+
+    struct DisplayCharacter(char);
+
+    impl fmt::Display for DisplayCharacter {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_character(f, self.0)
+        }
+    }
+
+    impl fmt::Display for DisplayBignum {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_bignum(f, self.0, self.1.clone())
+        }
+    }
+
+    #[test]
+    fn bignums() {
+        let cases = [
+            (1, vec![0, 0, 2], "2147483648"),
+            (-1, vec![1, 0, 2], "-2147483649"),
+            (1, vec![0, 0, 0, 1], "35184372088832"),
+            (1, vec![32767, 32767, 32767, 32767], "1152921504606846975"),
+        ];
+        for (sign, digits, expected) in cases {
+            assert_eq!(DisplayBignum(sign, digits).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn characters() {
+        let cases = [
+            ('a', "#\\a"),
+            (' ', "#\\Space"),
+            ('\n', "#\\Newline"),
+            ('\\', "#\\\\"),
+            ('ä', "#\\ä"),
+        ];
+        for (character, expected) in cases {
+            assert_eq!(DisplayCharacter(character).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn floats() {
+        let cases = [
+            (0.5, "0.5"),
+            (1.0, "1.0"),
+            (-2.25, "-2.25"),
+            (0.1, "0.1"),
+            (1.0 / 3.0, "0.3333333"),
+            (0.001_234_51, "0.00123451"),
+            (0.001_234_49, "0.00123449"),
+            (0.003_406_377, "0.003406377"),
+            (2.0 / 3.0, "0.6666667"),
+            (0.0001, "1.0e-4"),
+            (0.128_906_25, "0.1289063"),
+            (12_345_675.0, "1.234568e7"),
+            (123.456, "123.456"),
+            (0.999_999_9, "0.9999999"),
+            (1.999_999_9, "2.0"),
+            (1.5e10, "1.5e10"),
+            (2.5e-5, "2.5e-5"),
+            (f32::INFINITY, "Infinity"),
+        ];
+        for (float, expected) in cases {
+            assert_eq!(DisplayFloat(float).to_string(), expected);
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub use datatype::{IrDataType, IrDataTypeTag};
 pub use func::{Function, FunctionAttribute, StaticFunctionAddress};
 pub use funcbuilder::FunctionsBuilder;
 pub use functable::FunctionTableIndex;
+pub use inmem::{BIGNUM_RADIX, fixnum, fixnum_value};
 pub use inst::{Instruction, InstructionBuilder};
 pub use place::{AddressingMode, PlaceAddress};
 pub use program::Program;
@@ -1,3 +1,4 @@
+/// Address of data, or an immediate small integer with the lowest bit set.
 #[derive(Debug, Clone, Copy)]
 pub struct DataAddress(i32);
 
@@ -5,8 +5,9 @@ use crate::ir::{data::DataAddress, inmem::append_string};
 use super::{
     FunctionTableIndex, StaticFunctionAddress,
     inmem::{
-        append_function, append_identifier, append_list_node, append_nil,
-        append_place, append_sint32,
+        append_bignum, append_character, append_float32, append_function,
+        append_identifier, append_list_node, append_nil, append_place,
+        append_sint32, append_vector, fixnum,
     },
     place::PlaceAddress,
 };
@@ -14,6 +15,7 @@ use super::{
 pub struct StaticData {
     static_data: Vec<u8>,
     table_entries: Vec<StaticFunctionAddress>,
+    places: Vec<PlaceAddress>,
     nil_data: DataAddress,
     t_data: DataAddress,
 }
@@ -21,6 +23,7 @@ pub struct StaticData {
 pub struct StaticDataBuilder {
     static_data: Vec<u8>,
     table_entries: Vec<StaticFunctionAddress>,
+    places: Vec<PlaceAddress>,
     nil_data: DataAddress,
     t_data: DataAddress,
 }
@@ -34,6 +37,11 @@ impl StaticData {
         &self.table_entries
     }
 
+    /// Places of global variables, which are roots for garbage collection.
+    pub fn places(&self) -> &[PlaceAddress] {
+        &self.places
+    }
+
     pub fn nil_data(&self) -> DataAddress {
         self.nil_data
     }
@@ -56,6 +64,7 @@ impl StaticDataBuilder {
         StaticDataBuilder {
             static_data,
             table_entries: vec![],
+            places: vec![],
             nil_data,
             t_data,
         }
@@ -85,12 +94,40 @@ impl StaticDataBuilder {
         FunctionTableIndex::new_unsafe(self.table_entries.len() as u32)
     }
 
+    /// Gets an immediate for small numbers, and only appends larger ones.
     pub fn static_number(&mut self, number: i32) -> DataAddress {
+        if let Some(word) = fixnum(number) {
+            return DataAddress::new_unsafe(word);
+        }
         let address = self.top_static_data_address();
         append_sint32(&mut self.static_data, number).unwrap();
         address
     }
 
+    /// Append a new bignum with a sign and a list of digits, which are not
+    /// checked.
+    pub fn static_bignum(
+        &mut self,
+        sign: DataAddress,
+        digits: DataAddress,
+    ) -> DataAddress {
+        let address = self.top_static_data_address();
+        append_bignum(&mut self.static_data, sign, digits).unwrap();
+        address
+    }
+
+    pub fn static_float(&mut self, number: f32) -> DataAddress {
+        let address = self.top_static_data_address();
+        append_float32(&mut self.static_data, number).unwrap();
+        address
+    }
+
+    pub fn static_character(&mut self, character: char) -> DataAddress {
+        let address = self.top_static_data_address();
+        append_character(&mut self.static_data, character).unwrap();
+        address
+    }
+
     /// Append a new static string as data without checking for duplicates.
     pub fn static_string(&mut self, data: &str) -> DataAddress {
         let address = self.top_static_data_address();
@@ -115,9 +152,17 @@ impl StaticDataBuilder {
         address
     }
 
+    /// Append a new vector of the given elements.
+    pub fn static_vector(&mut self, elements: &[DataAddress]) -> DataAddress {
+        let address = self.top_static_data_address();
+        append_vector(&mut self.static_data, elements).unwrap();
+        address
+    }
+
     pub fn static_place(&mut self, data: DataAddress) -> PlaceAddress {
         let address = self.top_static_place_address();
         append_place(&mut self.static_data, data).unwrap();
+        self.places.push(address);
         address
     }
 
@@ -147,6 +192,7 @@ impl StaticDataBuilder {
         StaticData {
             static_data: mem::take(&mut self.static_data),
             table_entries: mem::take(&mut self.table_entries),
+            places: mem::take(&mut self.places),
             nil_data: self.nil_data,
             t_data: self.t_data,
         }
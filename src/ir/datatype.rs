@@ -32,6 +32,23 @@ pub enum IrDataType {
     /// pass an alternate stack offset for accessing closure parameters. It's
     /// zero (nil) if no closure parameters are to be used.
     Function,
+    /// IEEE 754 single precision floating point number
+    Float32,
+    /// An integer that does not fit into 32 bits, laid out like a list node:
+    /// The first 32 bits are the sign as an immediate 1 or -1, the last point
+    /// to a list of immediate digits in base `BIGNUM_RADIX`, least significant
+    /// digit first and without leading zeros.
+    BigInt,
+    /// A ratio of two integers in lowest terms, laid out like a list node:
+    /// The first 32 bits point to the numerator, the last to the denominator,
+    /// which is greater than one.
+    Ratio,
+    /// A character, with the 32 bits after the type tag holding its Unicode
+    /// scalar value.
+    Character,
+    /// A simple vector, laid out like a string: A 32-bit length of the
+    /// elements in bytes, followed by one 32-bit place for each element.
+    Vector,
 }
 
 #[derive(Copy, Clone)]
@@ -39,7 +56,7 @@ pub struct IrDataTypeTag {
     value: u32,
 }
 
-const TYPE_COUNT: u32 = 5;
+const TYPE_COUNT: u32 = 10;
 const HIGHEST_T_BIT: u32 = 1 << TYPE_COUNT;
 const LOWEST_T_BIT: u32 = 0b1;
 const ALL_T_BITS: u32 = HIGHEST_T_BIT + (HIGHEST_T_BIT - 1);
@@ -54,6 +71,11 @@ impl IrDataType {
                 IrDataType::CharacterData => 0b1000,
                 IrDataType::Identifier => 0b1_0000,
                 IrDataType::Function => 0b10_0000,
+                IrDataType::Float32 => 0b100_0000,
+                IrDataType::BigInt => 0b1000_0000,
+                IrDataType::Ratio => 0b1_0000_0000,
+                IrDataType::Character => 0b10_0000_0000,
+                IrDataType::Vector => 0b100_0000_0000,
             },
         }
     }
@@ -72,6 +94,11 @@ impl IrDataTypeTag {
             0b1000 => IrDataType::CharacterData,
             0b1_0000 => IrDataType::Identifier,
             0b10_0000 => IrDataType::Function,
+            0b100_0000 => IrDataType::Float32,
+            0b1000_0000 => IrDataType::BigInt,
+            0b1_0000_0000 => IrDataType::Ratio,
+            0b10_0000_0000 => IrDataType::Character,
+            0b100_0000_0000 => IrDataType::Vector,
             _ => unreachable!(), // valid tags don't end up here, and IrDataTypeTag contains a valid tag
         }
     }
@@ -23,13 +23,15 @@ pub struct Function {
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum FunctionAttribute {
-    /// Creates space for persistent places used in the function and also for
-    /// functions it creates that inherit the places, all in one go.
+    /// Creates space for persistent places used in the function on every
+    /// call, so that lambdas created in the function can keep using them.
     CreatesPersistentPlaces,
-    /// Persistent scope is taken over from the parameter.
+    /// The function is a lambda that gets the persistent places of the
+    /// function that created it as a parameter.
     ///
-    /// No new space is reserved for additional places, it is assumed that
-    /// this was considered in the function that creates the places.
+    /// It creates space for its own places on every call too, and the first
+    /// of them links to the places from the parameter, so that captured
+    /// places can be reached through the chain of links.
     AcceptsPersistentPlaces,
     /// The function is a public interface that can be called from the outside
     /// via JavaScript.
@@ -1,7 +1,10 @@
 //! Creates in-memory representations of data
 
 use super::{data::DataAddress, datatype::IrDataType};
-use std::io::{self, IoSlice, Write};
+use std::{
+    io::{self, IoSlice, Write},
+    mem,
+};
 
 pub fn append_function<W: Write>(
     buf: &mut W,
@@ -30,6 +33,7 @@ pub fn append_string<W: Write>(buf: &mut W, data: &str) -> io::Result<()> {
         IoSlice::new(&type_to_tag_bytes(IrDataType::CharacterData)),
         IoSlice::new(&(u32::try_from(data.len()).unwrap()).to_le_bytes()),
         IoSlice::new(data.as_bytes()),
+        IoSlice::new(padding(data.len())),
     ])?;
     Ok(())
 }
@@ -39,10 +43,17 @@ pub fn append_identifier<W: Write>(buf: &mut W, data: &str) -> io::Result<()> {
         IoSlice::new(&type_to_tag_bytes(IrDataType::Identifier)),
         IoSlice::new(&(u32::try_from(data.len()).unwrap()).to_le_bytes()),
         IoSlice::new(data.as_bytes()),
+        IoSlice::new(padding(data.len())),
     ])?;
     Ok(())
 }
 
+/// Zeroes to pad characters to whole words, so that all data stays
+/// word-aligned and addresses never look like immediates.
+fn padding(len: usize) -> &'static [u8] {
+    &[0; 3][..len.next_multiple_of(4) - len]
+}
+
 pub fn append_list_node<W: Write>(
     buf: &mut W,
     car: DataAddress,
@@ -56,6 +67,35 @@ pub fn append_list_node<W: Write>(
     Ok(())
 }
 
+/// Gets the immediate for an integer that fits into 31 bits, which is stored
+/// in places instead of an address, shifted left by one with the lowest bit
+/// set. Addresses of data never have that bit set since all data is
+/// word-aligned.
+pub fn fixnum(number: i32) -> Option<i32> {
+    let word = (number << 1) | 1;
+    (word >> 1 == number).then_some(word)
+}
+
+/// Gets the integer of an immediate, or none if the word is an address.
+pub fn fixnum_value(word: i32) -> Option<i32> {
+    (word & 1 != 0).then_some(word >> 1)
+}
+
+/// Base of the digits of bignums, small enough that the product of two digits
+/// plus a carry is still an immediate.
+pub const BIGNUM_RADIX: i32 = 1 << 15;
+
+pub fn append_bignum<W: Write>(
+    buf: &mut W,
+    sign: DataAddress,
+    digits: DataAddress,
+) -> io::Result<()> {
+    buf.write_all(&type_to_tag_bytes(IrDataType::BigInt))?;
+    buf.write_all(&sign.to_le_bytes())?;
+    buf.write_all(&digits.to_le_bytes())?;
+    Ok(())
+}
+
 pub fn append_sint32<W: Write>(buf: &mut W, number: i32) -> io::Result<()> {
     buf.write_vectored(&[
         IoSlice::new(&type_to_tag_bytes(IrDataType::SInt32)),
@@ -64,6 +104,34 @@ pub fn append_sint32<W: Write>(buf: &mut W, number: i32) -> io::Result<()> {
     Ok(())
 }
 
+pub fn append_float32<W: Write>(buf: &mut W, number: f32) -> io::Result<()> {
+    buf.write_all(&type_to_tag_bytes(IrDataType::Float32))?;
+    buf.write_all(&number.to_le_bytes())?;
+    Ok(())
+}
+
+pub fn append_character<W: Write>(
+    buf: &mut W,
+    character: char,
+) -> io::Result<()> {
+    buf.write_all(&type_to_tag_bytes(IrDataType::Character))?;
+    buf.write_all(&(character as u32).to_le_bytes())?;
+    Ok(())
+}
+
+pub fn append_vector<W: Write>(
+    buf: &mut W,
+    elements: &[DataAddress],
+) -> io::Result<()> {
+    let len = elements.len() * mem::size_of::<i32>();
+    buf.write_all(&type_to_tag_bytes(IrDataType::Vector))?;
+    buf.write_all(&(u32::try_from(len).unwrap()).to_le_bytes())?;
+    for element in elements {
+        buf.write_all(&element.to_le_bytes())?;
+    }
+    Ok(())
+}
+
 pub fn append_place<W: Write>(
     buf: &mut W,
     data_address: DataAddress,
@@ -17,6 +17,17 @@ pub enum Instruction {
         params: PlaceAddress,
         to: PlaceAddress,
     },
+    /// Calls a function in tail position, so that the callee returns
+    /// directly to the caller of this function and the frame of this
+    /// function is released before the call.
+    TailCall {
+        function: StaticFunctionAddress,
+        params: PlaceAddress,
+    },
+    TailCallIndirect {
+        function: PlaceAddress,
+        params: PlaceAddress,
+    },
     /// Builtin to print a string, with no typechecking.
     CallPrint {
         string: PlaceAddress,
@@ -25,6 +36,17 @@ pub enum Instruction {
     // the function
     Return {
         value: PlaceAddress,
+        /// The values register is set to the values in this place, or to nil
+        /// if there is only the one value.
+        values: Option<PlaceAddress>,
+    },
+    /// Copies the values register to a place, right after a call returned.
+    ///
+    /// The register is nil if the call returned a single value, otherwise a
+    /// list with the list of all values as its only element, so that zero
+    /// values can be told apart from a single value.
+    LoadValues {
+        to: PlaceAddress,
     },
     // mark the beginning of a block in code
     EnterBlock,
@@ -56,6 +78,29 @@ pub enum Instruction {
     },
     // mark the end of a block in code
     ExitBlock,
+    /// Marks the beginning of a region that catches throws until the
+    /// matching `ExitCatch`, for as long as the code in it runs, including
+    /// the functions it calls.
+    ///
+    /// A throw to a tag that is eq to the given one, or any throw if there is
+    /// no tag, continues after the `ExitCatch` with the thrown list written to
+    /// a place. Code never breaks out of a region, and regions do not count
+    /// as blocks for breaking.
+    EnterCatch {
+        tag: Option<PlaceAddress>,
+        to: PlaceAddress,
+    },
+    // mark the end of a catching region
+    ExitCatch,
+    /// Unwinds to the innermost region that catches the thrown list, which
+    /// has the tag as its car and the thrown value as its cdr. Regions
+    /// without a tag catch it on the way, so they have to throw it again.
+    ///
+    /// Panics with a message, before unwinding anything, if no region catches
+    /// the tag.
+    Throw {
+        thrown: PlaceAddress,
+    },
     /// read a param from the current function and write a reference to it to
     /// a place.
     /// Modify the params to include only the rest after the consumed
@@ -66,6 +111,36 @@ pub enum Instruction {
     ConsumeRest {
         to: PlaceAddress,
     },
+    /// Writes T to a place if there are parameters left to consume, otherwise
+    /// NIL. Used for optional parameters.
+    ParamPresent {
+        to: PlaceAddress,
+    },
+    /// Finds a keyword in the parameters left to consume, which are read as
+    /// alternating keywords and values. Writes the rest of the parameters
+    /// starting at the value after the keyword to a place, or NIL if the
+    /// keyword was not passed.
+    ///
+    /// The parameters are left as they are.
+    FindKeyParam {
+        keyword: DataAddress,
+        to: PlaceAddress,
+    },
+    /// Writes T to a place if the parameters left to consume are pairs of
+    /// keywords and values with every keyword in the given static list,
+    /// otherwise NIL.
+    KeyParamsKnown {
+        keywords: DataAddress,
+        to: PlaceAddress,
+    },
+    /// Writes T to a place if the number of parameters left to consume is
+    /// at least min and at most max, otherwise NIL.
+    ArityMatches {
+        min: u32,
+        /// None if there is no upper limit.
+        max: Option<u32>,
+        to: PlaceAddress,
+    },
     /// Write a reference to a constant value to a place.
     ///
     /// This is also used to write nil.
@@ -107,25 +182,59 @@ pub enum Instruction {
         list: PlaceAddress,
         to: PlaceAddress,
     },
-    /// Creates a new number from adding two numbers.
+    /// Overwrites the car (first element) part of a list node, without any
+    /// typechecking.
+    StoreCar {
+        list: PlaceAddress,
+        value: PlaceAddress,
+    },
+    /// Overwrites the cdr (tail list) part of a list node, without any
+    /// typechecking.
+    StoreCdr {
+        list: PlaceAddress,
+        value: PlaceAddress,
+    },
+    /// Creates a new bignum from a sign and a list of digits and writes a
+    /// reference to it to a place.
+    ///
+    /// Neither is checked, see `IrDataType::BigInt` for what they should be.
+    MakeBignum {
+        sign: PlaceAddress,
+        digits: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new ratio from a numerator and a denominator and writes a
+    /// reference to it to a place.
+    ///
+    /// Neither is checked, see `IrDataType::Ratio` for what they should be.
+    MakeRatio {
+        numerator: PlaceAddress,
+        denominator: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new number from adding two numbers, or writes NIL if the
+    /// sum does not fit into 32 bits.
     Add {
         left: PlaceAddress,
         right: PlaceAddress,
         to: PlaceAddress,
     },
-    /// Creates a new number from subtracting two numbers.
+    /// Creates a new number from subtracting two numbers, or writes NIL if the
+    /// difference does not fit into 32 bits.
     Sub {
         left: PlaceAddress,
         right: PlaceAddress,
         to: PlaceAddress,
     },
-    /// Creates a new number from multiplying two numbers.
+    /// Creates a new number from multiplying two numbers, or writes NIL if
+    /// the product does not fit into 32 bits.
     Mul {
         left: PlaceAddress,
         right: PlaceAddress,
         to: PlaceAddress,
     },
-    /// Creates a new number from doing a truncating division of left by right.
+    /// Creates a new number from doing a truncating division of left by right,
+    /// or writes NIL if the quotient does not fit into 32 bits.
     Div {
         left: PlaceAddress,
         right: PlaceAddress,
@@ -137,6 +246,13 @@ pub enum Instruction {
         right: PlaceAddress,
         to: PlaceAddress,
     },
+    /// If left and right refer to the same object, write T to the target
+    /// place, otherwise NIL.
+    Same {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
     /// If left != right, write T to the target place, otherwise NIL.
     Ne {
         left: PlaceAddress,
@@ -167,6 +283,61 @@ pub enum Instruction {
         right: PlaceAddress,
         to: PlaceAddress,
     },
+    /// Creates a new float from adding two floats.
+    FloatAdd {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new float from subtracting two floats.
+    FloatSub {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new float from multiplying two floats.
+    FloatMul {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new float from dividing left by right.
+    FloatDiv {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// If the floats left == right, write T to the target place, otherwise NIL.
+    FloatEq {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// If the floats left < right, write T to the target place, otherwise NIL.
+    FloatLt {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// If the floats left <= right, write T to the target place, otherwise
+    /// NIL.
+    FloatLte {
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new float with the value of a number, rounded to the nearest
+    /// float if it cannot be represented exactly.
+    IntToFloat {
+        number: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new number from a float by truncating towards zero. Traps if
+    /// the float is not a number or out of range for a number.
+    FloatTruncate {
+        float: PlaceAddress,
+        to: PlaceAddress,
+    },
     /// Concatenate two strings or identifiers (or a mix) to form a new string.
     /// No typechecking.
     ConcatStringLike {
@@ -174,6 +345,56 @@ pub enum Instruction {
         right: PlaceAddress,
         to: PlaceAddress,
     },
+    /// Writes the code of a character as a new number. No typechecking.
+    CharCode {
+        character: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new character from a number, which must be a Unicode scalar
+    /// value. No typechecking.
+    CodeChar {
+        code: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new character from the one at an index of a string or
+    /// identifier, counting characters and not bytes, or writes NIL if the
+    /// index is out of range. No typechecking.
+    CharAt {
+        string: PlaceAddress,
+        index: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new string that holds a single character. No typechecking.
+    CharString {
+        character: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Creates a new vector with a number of elements that all point to NIL.
+    /// No typechecking.
+    MakeVector {
+        length: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Writes the element at an index of a vector to the target place. No
+    /// typechecking or bounds checking.
+    LoadElement {
+        vector: PlaceAddress,
+        index: PlaceAddress,
+        to: PlaceAddress,
+    },
+    /// Overwrites the element at an index of a vector. No typechecking or
+    /// bounds checking.
+    StoreElement {
+        vector: PlaceAddress,
+        index: PlaceAddress,
+        value: PlaceAddress,
+    },
+    /// Creates a new number with the number of elements of a vector, or the
+    /// number of characters of a string or identifier. No typechecking.
+    LoadLength {
+        of: PlaceAddress,
+        to: PlaceAddress,
+    },
     /// Gets the type tag of the thing referred to by of, and writes an integer
     /// with the type tag to `to`.
     LoadTypeTag {
@@ -187,15 +408,24 @@ pub enum Instruction {
 
 pub struct InstructionBuilder {
     instructions: Vec<Instruction>,
+    /// Number of blocks entered and not exited yet.
+    block_depth: u32,
 }
 
 impl InstructionBuilder {
     pub fn new() -> Self {
         Self {
             instructions: vec![],
+            block_depth: 0,
         }
     }
 
+    /// The number of blocks that the next instruction is in, so that breaks
+    /// can be computed for blocks entered further up in the code.
+    pub fn block_depth(&self) -> u32 {
+        self.block_depth
+    }
+
     pub fn call(
         &mut self,
         function: StaticFunctionAddress,
@@ -224,6 +454,26 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn tail_call(
+        &mut self,
+        function: StaticFunctionAddress,
+        params: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::TailCall { function, params });
+        self
+    }
+
+    pub fn tail_call_indirect(
+        &mut self,
+        function: PlaceAddress,
+        params: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::TailCallIndirect { function, params });
+        self
+    }
+
     pub fn create_function(
         &mut self,
         function: FunctionTableIndex,
@@ -241,12 +491,34 @@ impl InstructionBuilder {
     }
 
     pub fn add_return(&mut self, value: PlaceAddress) -> &mut Self {
-        self.instructions.push(Instruction::Return { value });
+        self.instructions.push(Instruction::Return {
+            value,
+            values: None,
+        });
+        self
+    }
+
+    /// Returns with multiple values in the format of the values register.
+    pub fn add_return_values(
+        &mut self,
+        value: PlaceAddress,
+        values: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::Return {
+            value,
+            values: Some(values),
+        });
+        self
+    }
+
+    pub fn load_values(&mut self, to: PlaceAddress) -> &mut Self {
+        self.instructions.push(Instruction::LoadValues { to });
         self
     }
 
     pub fn enter_block(&mut self) -> &mut Self {
         self.instructions.push(Instruction::EnterBlock);
+        self.block_depth += 1;
         self
     }
 
@@ -305,6 +577,59 @@ impl InstructionBuilder {
 
     pub fn exit_block(&mut self) -> &mut Self {
         self.instructions.push(Instruction::ExitBlock);
+        self.block_depth -= 1;
+        self
+    }
+
+    pub fn enter_catch(
+        &mut self,
+        tag: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::EnterCatch { tag: Some(tag), to });
+        self
+    }
+
+    /// Enters a region that catches every throw.
+    pub fn enter_catch_all(&mut self, to: PlaceAddress) -> &mut Self {
+        self.instructions
+            .push(Instruction::EnterCatch { tag: None, to });
+        self
+    }
+
+    pub fn exit_catch(&mut self) -> &mut Self {
+        self.instructions.push(Instruction::ExitCatch);
+        self
+    }
+
+    pub fn throw(&mut self, thrown: PlaceAddress) -> &mut Self {
+        self.instructions.push(Instruction::Throw { thrown });
+        self
+    }
+
+    pub fn make_bignum(
+        &mut self,
+        sign: PlaceAddress,
+        digits: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::MakeBignum { sign, digits, to });
+        self
+    }
+
+    pub fn make_ratio(
+        &mut self,
+        numerator: PlaceAddress,
+        denominator: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::MakeRatio {
+            numerator,
+            denominator,
+            to,
+        });
         self
     }
 
@@ -358,6 +683,17 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn same(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::Same { left, right, to });
+        self
+    }
+
     pub fn ne(
         &mut self,
         left: PlaceAddress,
@@ -408,6 +744,103 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn float_add(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatAdd { left, right, to });
+        self
+    }
+
+    pub fn float_sub(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatSub { left, right, to });
+        self
+    }
+
+    pub fn float_mul(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatMul { left, right, to });
+        self
+    }
+
+    pub fn float_div(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatDiv { left, right, to });
+        self
+    }
+
+    pub fn float_eq(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatEq { left, right, to });
+        self
+    }
+
+    pub fn float_lt(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatLt { left, right, to });
+        self
+    }
+
+    pub fn float_lte(
+        &mut self,
+        left: PlaceAddress,
+        right: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatLte { left, right, to });
+        self
+    }
+
+    pub fn int_to_float(
+        &mut self,
+        number: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::IntToFloat { number, to });
+        self
+    }
+
+    pub fn float_truncate(
+        &mut self,
+        float: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FloatTruncate { float, to });
+        self
+    }
+
     pub fn consume_param(&mut self, to: PlaceAddress) -> &mut Self {
         self.instructions.push(Instruction::ConsumeParam { to });
         self
@@ -418,6 +851,42 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn param_present(&mut self, to: PlaceAddress) -> &mut Self {
+        self.instructions.push(Instruction::ParamPresent { to });
+        self
+    }
+
+    pub fn find_key_param(
+        &mut self,
+        keyword: DataAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::FindKeyParam { keyword, to });
+        self
+    }
+
+    pub fn key_params_known(
+        &mut self,
+        keywords: DataAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::KeyParamsKnown { keywords, to });
+        self
+    }
+
+    pub fn arity_matches(
+        &mut self,
+        min: u32,
+        max: Option<u32>,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::ArityMatches { min, max, to });
+        self
+    }
+
     pub fn concat_string_like(
         &mut self,
         left: PlaceAddress,
@@ -477,6 +946,108 @@ impl InstructionBuilder {
         self
     }
 
+    pub fn store_car(
+        &mut self,
+        list: PlaceAddress,
+        value: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::StoreCar { list, value });
+        self
+    }
+
+    pub fn store_cdr(
+        &mut self,
+        list: PlaceAddress,
+        value: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::StoreCdr { list, value });
+        self
+    }
+
+    pub fn char_code(
+        &mut self,
+        character: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::CharCode { character, to });
+        self
+    }
+
+    pub fn code_char(
+        &mut self,
+        code: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::CodeChar { code, to });
+        self
+    }
+
+    pub fn char_at(
+        &mut self,
+        string: PlaceAddress,
+        index: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::CharAt { string, index, to });
+        self
+    }
+
+    pub fn char_string(
+        &mut self,
+        character: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::CharString { character, to });
+        self
+    }
+
+    pub fn make_vector(
+        &mut self,
+        length: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::MakeVector { length, to });
+        self
+    }
+
+    pub fn load_element(
+        &mut self,
+        vector: PlaceAddress,
+        index: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions
+            .push(Instruction::LoadElement { vector, index, to });
+        self
+    }
+
+    pub fn store_element(
+        &mut self,
+        vector: PlaceAddress,
+        index: PlaceAddress,
+        value: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::StoreElement {
+            vector,
+            index,
+            value,
+        });
+        self
+    }
+
+    pub fn load_length(
+        &mut self,
+        of: PlaceAddress,
+        to: PlaceAddress,
+    ) -> &mut Self {
+        self.instructions.push(Instruction::LoadLength { of, to });
+        self
+    }
+
     pub fn load_type_tag(
         &mut self,
         of: PlaceAddress,
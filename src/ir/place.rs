@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PlaceAddress {
     mode: AddressingMode,
     /// Offset in bytes
@@ -12,6 +12,10 @@ pub enum AddressingMode {
     /// accessing a place relative to the beginning of memory, used for global
     /// variables
     Global,
+    /// accessing a place of a function that a lambda is nested in, `depth`
+    /// levels up, by following the links from the places of each lambda to
+    /// the places of the function that created it
+    Captured { depth: u32 },
 }
 
 impl PlaceAddress {
@@ -29,6 +33,13 @@ impl PlaceAddress {
         }
     }
 
+    pub fn new_captured(depth: u32, local_place_byte_offset: i32) -> Self {
+        Self {
+            mode: AddressingMode::Captured { depth },
+            offset: local_place_byte_offset,
+        }
+    }
+
     pub fn mode(self) -> AddressingMode {
         self.mode
     }
@@ -5,13 +5,18 @@ mod args;
 mod cmd;
 mod codegen;
 mod diagnostic;
+mod interp;
 mod ir;
 mod parse;
 mod source;
 
 fn main() {
     let args = args::TopLevelArgs::parse();
-    let result = cmd::compile(&args);
+    let result = match args.command() {
+        Some(args::Command::Run(run_args)) => cmd::run(run_args),
+        Some(args::Command::Test(test_args)) => cmd::test(test_args),
+        None => cmd::compile(&args),
+    };
     match result {
         Ok(_) => {}
         Err(e) => {
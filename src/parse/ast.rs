@@ -18,8 +18,18 @@ pub enum AstNode<'s> {
     Atom(Atom<'s>),
     /// A standard list, usually a function invocation, e.g. `(max 4)` or `()`.
     List(List<'s>),
+    /// A vector, which evaluates to itself, e.g. `#(1 2)`.
+    Vector(List<'s>),
     /// A quoted list or identifier, e.g. `'(1 2)`.`, 'string
     Quoted(Quoted<'s>),
+    /// A quasiquoted list or atom, which is quoted except for the unquoted
+    /// parts inside, e.g. `` `(1 ,two) ``.
+    Quasiquoted(Quasiquoted<'s>),
+    /// Something evaluated inside a quasiquote, e.g. `,two`.
+    Unquoted(Unquoted<'s>),
+    /// A list evaluated inside a quasiquote with its elements spliced into
+    /// the surrounding list, e.g. `,@rest`.
+    UnquotedSplicing(Unquoted<'s>),
 }
 
 #[derive(Debug)]
@@ -39,6 +49,18 @@ pub struct Quoted<'s> {
     quoted: Box<AstNode<'s>>,
 }
 
+#[derive(Debug)]
+pub struct Quasiquoted<'s> {
+    source_range: SourceRange<'s>,
+    quasiquoted: Box<AstNode<'s>>,
+}
+
+#[derive(Debug)]
+pub struct Unquoted<'s> {
+    source_range: SourceRange<'s>,
+    unquoted: Box<AstNode<'s>>,
+}
+
 #[derive(Debug)]
 pub struct FunctionName<'s> {
     source_range: SourceRange<'s>,
@@ -85,8 +107,16 @@ impl<'s> AstNode<'s> {
     pub fn source_range<'a>(&'a self) -> SourceRange<'s> {
         match self {
             &AstNode::Atom(Atom { ref token }) => token.source_range(),
-            &AstNode::List(List { source_range, .. }) => source_range,
+            &AstNode::List(List { source_range, .. })
+            | &AstNode::Vector(List { source_range, .. }) => source_range,
             &AstNode::Quoted(Quoted { source_range, .. }) => source_range,
+            &AstNode::Quasiquoted(Quasiquoted { source_range, .. }) => {
+                source_range
+            }
+            &AstNode::Unquoted(Unquoted { source_range, .. })
+            | &AstNode::UnquotedSplicing(Unquoted { source_range, .. }) => {
+                source_range
+            }
         }
     }
 
@@ -113,6 +143,14 @@ impl<'s> AstNode<'s> {
             None
         }
     }
+
+    pub fn quasiquoted<'a>(&'a self) -> Option<&'a Quasiquoted<'s>> {
+        if let &AstNode::Quasiquoted(ref quasiquoted) = self {
+            Some(quasiquoted)
+        } else {
+            None
+        }
+    }
 }
 
 impl<'s> Atom<'s> {
@@ -144,6 +182,16 @@ impl<'s> List<'s> {
         })
     }
 
+    pub fn new_vector(
+        source_range: SourceRange<'s>,
+        elements: Vec<AstNode<'s>>,
+    ) -> AstNode<'s> {
+        AstNode::Vector(List {
+            source_range,
+            elements,
+        })
+    }
+
     pub fn source_range<'b>(&'b self) -> SourceRange<'s> {
         self.source_range
     }
@@ -180,3 +228,45 @@ impl<'s> Quoted<'s> {
         self.source_range().of(source)
     }
 }
+
+impl<'s> Quasiquoted<'s> {
+    pub fn new(
+        source_range: SourceRange<'s>,
+        quasiquoted: AstNode<'s>,
+    ) -> AstNode<'s> {
+        AstNode::Quasiquoted(Quasiquoted {
+            source_range,
+            quasiquoted: Box::new(quasiquoted),
+        })
+    }
+
+    pub fn quasiquoted<'b>(&'b self) -> &'b AstNode<'s> {
+        &self.quasiquoted
+    }
+}
+
+impl<'s> Unquoted<'s> {
+    pub fn new(
+        source_range: SourceRange<'s>,
+        unquoted: AstNode<'s>,
+    ) -> AstNode<'s> {
+        AstNode::Unquoted(Unquoted {
+            source_range,
+            unquoted: Box::new(unquoted),
+        })
+    }
+
+    pub fn new_splicing(
+        source_range: SourceRange<'s>,
+        unquoted: AstNode<'s>,
+    ) -> AstNode<'s> {
+        AstNode::UnquotedSplicing(Unquoted {
+            source_range,
+            unquoted: Box::new(unquoted),
+        })
+    }
+
+    pub fn unquoted<'b>(&'b self) -> &'b AstNode<'s> {
+        &self.unquoted
+    }
+}
@@ -70,6 +70,16 @@ impl<'s> TokenStream<'s> for Lexer<'s> {
             ('\'', _) => {
                 Ok(Token::new(self.take("\'".len()), TokenKind::Quote))
             }
+            ('`', _) => {
+                Ok(Token::new(self.take("`".len()), TokenKind::Quasiquote))
+            }
+            (',', Some('@')) => Ok(Token::new(
+                self.take(",@".len()),
+                TokenKind::UnquoteSplicing,
+            )),
+            (',', _) => {
+                Ok(Token::new(self.take(",".len()), TokenKind::Unquote))
+            }
             ('#', Some('\'')) => {
                 let after = rest.skip(1).find(|&(_, c)| {
                     !is_identifier_start(c) && !is_identifier_continue(c)
@@ -85,6 +95,34 @@ impl<'s> TokenStream<'s> for Lexer<'s> {
                 }
                 Ok(Token::new(range, TokenKind::FuncIdent))
             }
+            ('#', Some('(')) => Ok(Token::new(
+                self.take("#(".len()),
+                TokenKind::VectorLeftParen,
+            )),
+            ('#', Some('\\')) => {
+                // the character after the backslash is taken as it is, but if
+                // an identifier could start with it, the whole identifier is
+                // taken as the name of a character like `Space`
+                let mut after = rest.skip(1);
+                match after.next() {
+                    Some((_, c)) if is_identifier_start(c) => {
+                        let len = after
+                            .find(|&(_, c)| !is_identifier_continue(c))
+                            .map(|(idx, _)| idx)
+                            .unwrap_or_else(|| {
+                                self.source.len() - self.position
+                            });
+                        Ok(Token::new(self.take(len), TokenKind::CharLit))
+                    }
+                    Some((idx, c)) => Ok(Token::new(
+                        self.take(idx + c.len_utf8()),
+                        TokenKind::CharLit,
+                    )),
+                    None => Err(LexerError::EmptyCharName {
+                        fragment: self.take_rest().unwrap().of(self.source),
+                    }),
+                }
+            }
             (';', _) => {
                 let len = rest
                     .find(|&(_, c)| c == '\n')
@@ -213,6 +251,8 @@ pub enum LexerError<'s> {
     UnterminatedStringLit { fragment: Fragment<'s> },
     // can only occur at the very end
     EmptyFuncName { fragment: Fragment<'s> },
+    // can only occur at the very end
+    EmptyCharName { fragment: Fragment<'s> },
     UnrecognizedChar { fragment: Fragment<'s> },
 }
 
@@ -227,6 +267,10 @@ impl<'s> fmt::Display for LexerError<'s> {
                 writeln!(f, "missing function name: {}", fragment)?;
                 writeln!(f, "{}", fragment.source_context())?;
             }
+            LexerError::EmptyCharName { fragment } => {
+                writeln!(f, "missing character name: {}", fragment)?;
+                writeln!(f, "{}", fragment.source_context())?;
+            }
             LexerError::UnterminatedStringLit { fragment } => {
                 writeln!(
                     f,
@@ -852,4 +896,90 @@ mod test {
         assert!(matches!(token.kind(), TokenKind::RightParen));
         assert_eq!(token.fragment(source).source(), ")");
     }
+
+    #[test]
+    fn chars() {
+        let source_set = SourceSet::new_debug("#\\a #\\Space #\\( #\\ä)");
+        let source = source_set.one();
+        let mut lexer = Lexer::new(source);
+        let mut kinds = vec![];
+        while let Some(token) = lexer.next() {
+            let token = token.unwrap();
+            if !matches!(token.kind(), TokenKind::Ws) {
+                kinds.push((token.kind(), token.fragment(source).source()));
+            }
+        }
+        assert_eq!(
+            kinds,
+            [
+                (TokenKind::CharLit, "#\\a"),
+                (TokenKind::CharLit, "#\\Space"),
+                (TokenKind::CharLit, "#\\("),
+                (TokenKind::CharLit, "#\\ä"),
+                (TokenKind::RightParen, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn vector() {
+        let source_set = SourceSet::new_debug("#(1 #\\a)");
+        let source = source_set.one();
+        let mut lexer = Lexer::new(source);
+        let mut kinds = vec![];
+        while let Some(token) = lexer.next() {
+            let token = token.unwrap();
+            if !matches!(token.kind(), TokenKind::Ws) {
+                kinds.push((token.kind(), token.fragment(source).source()));
+            }
+        }
+        assert_eq!(
+            kinds,
+            [
+                (TokenKind::VectorLeftParen, "#("),
+                (TokenKind::IntLit, "1"),
+                (TokenKind::CharLit, "#\\a"),
+                (TokenKind::RightParen, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_char_name() {
+        let source_set = SourceSet::new_debug("#\\");
+        let source = source_set.one();
+        let mut lexer = Lexer::new(source);
+        assert!(matches!(
+            lexer.next().unwrap().unwrap_err(),
+            LexerError::EmptyCharName { .. }
+        ));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn quasiquote() {
+        let source_set = SourceSet::new_debug("`(a ,b ,@c)");
+        let source = source_set.one();
+        let mut lexer = Lexer::new(source);
+        let mut kinds = vec![];
+        while let Some(token) = lexer.next() {
+            let token = token.unwrap();
+            if !matches!(token.kind(), TokenKind::Ws) {
+                kinds.push((token.kind(), token.fragment(source).source()));
+            }
+        }
+        assert_eq!(
+            kinds,
+            [
+                (TokenKind::Quasiquote, "`"),
+                (TokenKind::LeftParen, "("),
+                (TokenKind::Ident, "a"),
+                (TokenKind::Unquote, ","),
+                (TokenKind::Ident, "b"),
+                (TokenKind::UnquoteSplicing, ",@"),
+                (TokenKind::Ident, "c"),
+                (TokenKind::RightParen, ")"),
+            ]
+        );
+    }
 }
@@ -7,7 +7,7 @@ use crate::{
 
 use super::{
     ahead::LookaheadStream,
-    ast::{Ast, AstNode, List, Quoted},
+    ast::{Ast, AstNode, List, Quasiquoted, Quoted, Unquoted},
     ignore::Ignore,
     lexer::{Lexer, LexerError},
     stream::TokenStream as _,
@@ -47,6 +47,23 @@ impl<'s> Parser<'s> {
         Ok(Ast::new(self.lexer.source(), items))
     }
 
+    /// Parses source code that consists of exactly one list or atom, like
+    /// the expansion of a macro.
+    pub fn parse_single<'a>(
+        &'a mut self,
+    ) -> Result<AstNode<'s>, ParserError<'s>> {
+        let node = self.parse_one()?;
+        match self.lexer.next() {
+            None => Ok(node),
+            Some(Ok(extra)) => {
+                Err(ParserError::mismatched_token(self.lexer.source(), extra))
+            }
+            Some(Err(e)) => {
+                Err(ParserError::lexer_error(self.lexer.source(), e.clone()))
+            }
+        }
+    }
+
     /// Parses a single list or atom.
     ///
     /// If the end of string is reached, an error is returned.
@@ -57,11 +74,17 @@ impl<'s> Parser<'s> {
             .ok_or_else(|| ParserError::unexpected_end(source))?
             .map_err(|e| ParserError::lexer_error(source, e.clone()))?;
         match token0.kind() {
-            TokenKind::Quote => self.parse_quoted(),
-            TokenKind::LeftParen => self.parse_list(),
+            TokenKind::Quote
+            | TokenKind::Quasiquote
+            | TokenKind::Unquote
+            | TokenKind::UnquoteSplicing => self.parse_quoted(),
+            TokenKind::LeftParen | TokenKind::VectorLeftParen => {
+                self.parse_list()
+            }
             TokenKind::FloatLit
             | TokenKind::IntLit
             | TokenKind::StringLit
+            | TokenKind::CharLit
             | TokenKind::Ident
             | TokenKind::FuncIdent => {
                 Ok(Atom::new(self.lexer.next().unwrap().unwrap()))
@@ -81,10 +104,11 @@ impl<'s> Parser<'s> {
             .next()
             .ok_or_else(|| ParserError::unexpected_end(source))?
             .map_err(|e| ParserError::lexer_error(source, e.clone()))?;
-        match opening.kind() {
-            TokenKind::LeftParen => {}
+        let new = match opening.kind() {
+            TokenKind::LeftParen => List::new,
+            TokenKind::VectorLeftParen => List::new_vector,
             _ => return Err(ParserError::mismatched_token(source, opening)),
-        }
+        };
 
         let mut closing = None;
         let mut items = vec![];
@@ -105,7 +129,7 @@ impl<'s> Parser<'s> {
             })?
             .unwrap();
 
-        Ok(List::new(
+        Ok(new(
             SourceRange::union_two(
                 opening.source_range(),
                 closing.source_range(),
@@ -114,6 +138,8 @@ impl<'s> Parser<'s> {
         ))
     }
 
+    /// Parses a node prefixed with a quote, quasiquote, unquote or
+    /// unquote-splicing token.
     fn parse_quoted<'a>(&'a mut self) -> Result<AstNode<'s>, ParserError<'s>> {
         let source = self.lexer.source();
         let tick = self
@@ -121,14 +147,17 @@ impl<'s> Parser<'s> {
             .next()
             .ok_or_else(|| ParserError::unexpected_end(source))?
             .map_err(|e| ParserError::lexer_error(source, e.clone()))?;
-        match (tick.kind(), tick.fragment(self.lexer.source()).source()) {
-            (TokenKind::Quote, "'") => {}
+        let new = match (tick.kind(), tick.fragment(source).source()) {
+            (TokenKind::Quote, "'") => Quoted::new,
+            (TokenKind::Quasiquote, "`") => Quasiquoted::new,
+            (TokenKind::Unquote, ",") => Unquoted::new,
+            (TokenKind::UnquoteSplicing, ",@") => Unquoted::new_splicing,
             _ => return Err(ParserError::mismatched_token(source, tick)),
-        }
+        };
 
         let quoted = self.parse_one()?;
 
-        Ok(Quoted::new(
+        Ok(new(
             SourceRange::union_two(tick.source_range(), quoted.source_range()),
             quoted,
         ))
@@ -365,6 +394,29 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_quasiquote() {
+        let source_set = SourceSet::new_debug("`(a ,b ,@c)");
+        let source = source_set.one();
+        let mut parser = Parser::new(source);
+        let node = parser.parse_one().unwrap();
+        let quasiquoted = node.quasiquoted().unwrap();
+        assert_eq!(node.source_range().of(source).source(), "`(a ,b ,@c)");
+
+        let elements = quasiquoted.quasiquoted().list().unwrap().elements();
+        assert!(matches!(elements[0], AstNode::Atom(_)));
+        let AstNode::Unquoted(unquoted) = &elements[1] else {
+            panic!("expected unquote, got {:?}", elements[1]);
+        };
+        assert_eq!(elements[1].fragment(source).source(), ",b");
+        assert_eq!(unquoted.unquoted().fragment(source).source(), "b");
+        let AstNode::UnquotedSplicing(spliced) = &elements[2] else {
+            panic!("expected unquote-splicing, got {:?}", elements[2]);
+        };
+        assert_eq!(elements[2].fragment(source).source(), ",@c");
+        assert_eq!(spliced.unquoted().fragment(source).source(), "c");
+    }
+
     #[test]
     fn parse_two_lists() {
         let source_set = SourceSet::new_debug(
@@ -28,6 +28,8 @@ impl<'s> Token<'s> {
 pub enum TokenKind {
     /// Opening parenthesis.
     LeftParen,
+    /// Opening parenthesis of a vector, e.g. the `#(` in `#(1 2)`.
+    VectorLeftParen,
     /// Closing parenthesis.
     RightParen,
     /// General identifier like a function name e.g. `map`, `+`
@@ -38,6 +40,8 @@ pub enum TokenKind {
     FloatLit,
     /// String literal like `""` or `"\"asdf\""`
     StringLit,
+    /// Character literal like `#\a`, `#\(` or a named one like `#\Space`.
+    CharLit,
     /// Comment excluding the newline that ends it e.g. `; this is a comment`.
     Comment,
     /// White-space, including newlines and the newlines directly after
@@ -46,6 +50,16 @@ pub enum TokenKind {
     /// A single quote, used for escaping the next thing, e.g. the quote
     /// in `'string`.
     Quote,
+    /// A backquote, escaping the next thing except for unquoted parts, e.g.
+    /// in `` `(1 ,two) ``.
+    Quasiquote,
+    /// A comma, evaluating the next thing inside a quasiquote, e.g. in
+    /// `,two`.
+    Unquote,
+    /// A comma followed by an at sign, evaluating the next thing inside a
+    /// quasiquote and splicing the resulting list into the surrounding list,
+    /// e.g. in `,@rest`.
+    UnquoteSplicing,
     /// E.g. `#'+` or `#' +` (white-space allowed). Used for calling a function by name as a value, e.g. for map/reduce.
     FuncIdent,
 }
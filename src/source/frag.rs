@@ -177,11 +177,7 @@ impl<'s> fmt::Display for SourceContext<'s> {
         writeln!(
             f,
             "{} at line {}:{}",
-            self.0
-                .source
-                .path()
-                .map(|p| p.to_str().unwrap())
-                .unwrap_or("<builtin>"),
+            self.0.source.name(),
             from.line_no(),
             from.col_no()
         )?;
@@ -198,6 +194,9 @@ impl<'s> fmt::Display for SourceContext<'s> {
         for _ in highlight_from..highlight_to {
             write!(f, "^")?;
         }
+        if let Some(origin) = self.0.source.origin() {
+            write!(f, "\n{}", origin)?;
+        }
         Ok(())
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     fmt,
     fs::File,
     io::{self, Read},
@@ -27,6 +28,11 @@ pub struct SourceSetIter<'set> {
 struct SourceInfoEntry {
     /// File, if this source was loaded from a file
     file: Option<PathBuf>,
+    /// Shown instead of a file for sources generated by the compiler
+    description: Option<String>,
+    /// Shown after code of generated sources, e.g. the macro call and
+    /// definition that an expansion came from
+    origin: Option<String>,
     /// The byte range in the combined source
     range: Range<usize>,
 }
@@ -88,6 +94,8 @@ impl SourceSet {
 
         self.entries.push(SourceInfoEntry {
             file: Some(path.into()),
+            description: None,
+            origin: None,
             range: (self.combined_source.len() - appended_bytes_after_padding)
                 ..self.combined_source.len(),
         });
@@ -102,9 +110,34 @@ impl SourceSet {
     ///
     /// No attempt is made to check for duplicates for sources added this way.
     pub fn load_without_path(&mut self, source: &str) -> Source {
+        self.push_without_path(None, source)
+    }
+
+    /// Loads source code that the compiler generated itself, like the
+    /// expansion of a macro, with a description to show instead of a file
+    /// and the origin of the code to show after it in diagnostics.
+    pub fn load_generated(
+        &mut self,
+        description: String,
+        origin: String,
+        source: &str,
+    ) -> Source<'_> {
+        let source = self.push_without_path(Some(description), source);
+        let idx = source.idx;
+        self.entries[idx].origin = Some(origin);
+        Source { set: self, idx }
+    }
+
+    fn push_without_path(
+        &mut self,
+        description: Option<String>,
+        source: &str,
+    ) -> Source<'_> {
         self.combined_source.push_str(source);
         self.entries.push(SourceInfoEntry {
             file: None,
+            description,
+            origin: None,
             range: (self.combined_source.len() - source.len())
                 ..self.combined_source.len(),
         });
@@ -163,6 +196,26 @@ impl<'set> Source<'set> {
             .map(|f| f.as_ref())
     }
 
+    /// Description of generated sources, which have no path.
+    pub fn description(self) -> Option<&'set str> {
+        self.set.entries[self.idx].description.as_deref()
+    }
+
+    /// The path, or the description of generated sources, as shown to
+    /// users.
+    pub fn name(self) -> Cow<'set, str> {
+        match (self.path(), self.description()) {
+            (Some(path), _) => path.to_string_lossy(),
+            (None, Some(description)) => Cow::Borrowed(description),
+            (None, None) => Cow::Borrowed("<builtin>"),
+        }
+    }
+
+    /// Where a generated source came from, if it was given.
+    pub fn origin(self) -> Option<&'set str> {
+        self.set.entries[self.idx].origin.as_deref()
+    }
+
     pub fn as_str(self) -> &'set str {
         &self.set.combined_source[self.set.entries[self.idx].range.clone()]
     }
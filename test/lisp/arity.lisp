@@ -0,0 +1,14 @@
+(defun pair (a b)
+    (list a b))
+
+(defun at-least-one (a &rest more)
+    (cons a more))
+
+(dump (pair 1 2))
+(dump (funcall #'pair 3 4))
+(dump (apply #'at-least-one '(1 2 3)))
+(dump (length '(1 2 3)))
+
+(let ((f (lambda (x &optional y) (list x y))))
+    (dump (funcall f 1))
+    (funcall f 1 2 3))
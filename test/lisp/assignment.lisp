@@ -0,0 +1,33 @@
+(defparameter *counter* 0)
+
+(defun bump ()
+    (setq *counter* (+ *counter* 1)))
+
+(defun make-counter ()
+    (let ((count 0))
+        (lambda () (setq count (+ count 1)))))
+
+(defun changed-by-lambda ()
+    (let ((x 1))
+        (funcall (lambda () (setf x 10)))
+        x))
+
+(defun swap-args (a b)
+    (setq a b b a)
+    (list a b))
+
+(bump)
+(bump)
+(let ((counter (make-counter))
+      (pair (list 1 2))
+      (x 1))
+    (funcall counter)
+    (dump
+        *counter*
+        (funcall counter)
+        (setf (car pair) 'one (cdr pair) '(two three))
+        pair
+        (changed-by-lambda)
+        (swap-args 1 2)
+        (list x (setq x 2) x)
+        (setq)))
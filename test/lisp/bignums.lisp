@@ -0,0 +1,37 @@
+;; integers promote to bignums when they overflow 32 bits, and back when they
+;; fit again
+(dump
+    (+ 2147483647 1)
+    (- -2147483648 1)
+    (* 65536 65536)
+    (- (+ 2147483647 1) 1)
+    (integerp (* 65536 65536))
+    -2147483648)
+
+;; large literals, and arithmetic mixing them with small integers
+(defparameter *big* 123456789012345678901234567890)
+(dump
+    *big*
+    (- *big*)
+    (+ *big* 1)
+    (- *big* *big*)
+    (* *big* -987654321098765432109876543210)
+    (* *big* 0))
+
+;; division and comparisons
+(dump
+    (floor *big* 1000000007)
+    (floor (- *big*) 7)
+    (floor *big* 98765432109876543210)
+    (/ (* *big* 3) 3)
+    (< *big* (+ *big* 1))
+    (> (- *big*) 1)
+    (= (* 4294967296 4294967296) 18446744073709551616)
+    (float 4294967296)
+    (+ 4294967296 0.5))
+
+;; factorials overflow quickly
+(defun factorial (n)
+    (if (= n 0) 1 (* n (factorial (- n 1)))))
+(dump (factorial 13) (factorial 30))
+(* 99999999999 99999999999)
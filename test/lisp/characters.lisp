@@ -0,0 +1,30 @@
+;; character literals, with names for white-space
+(dump #\a #\Space #\Newline #\( '(#\x #\y))
+
+;; codes are unicode scalar values, and surrogates are no characters
+(dump
+    (char-code #\a)
+    (code-char 955)
+    (code-char 55296)
+    (code-char -1)
+    (characterp #\a)
+    (characterp "a"))
+
+;; strings are indexed by character and not by byte of their utf-8 encoding
+(dump
+    (char "héllo" 1)
+    (char "héllo" 2)
+    (schar "a€𝄞b" 2)
+    (char "a€𝄞b" 3)
+    (char-code (char "a€𝄞b" 2))
+    (handler-case (char "abc" 3)
+        (error (c) (condition-message c))))
+
+(dump
+    (char= #\a #\a #\a)
+    (char= #\a #\b)
+    (char< #\a #\b #\c)
+    (char< #\b #\a)
+    (handler-case (char-code "a")
+        (type-error (c) (type-error-expected-type c))))
+(char "ä" 0)
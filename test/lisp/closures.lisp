@@ -0,0 +1,43 @@
+;; every call of a lambda has its own variables, so closures created by
+;; different calls of the same lambda don't share them
+(defun add-3-curried (a)
+    (lambda (b)
+        (lambda (c)
+            (+ a b c))))
+
+(defun make-account (balance)
+    (lambda (amount)
+        (let ((before balance))
+            (setq balance (+ balance amount))
+            (lambda () (list before balance)))))
+
+;; closures created in a recursive lambda each keep their own n
+(defun make-adders (n)
+    (let ((build nil))
+        (setq build
+            (lambda (n acc)
+                (if (= n 0)
+                    acc
+                    (funcall build
+                             (- n 1)
+                             (cons (lambda (x) (+ x n)) acc)))))
+        (funcall build n nil)))
+
+(defun call-all (fs x)
+    (if (null fs)
+        nil
+        (cons (funcall (car fs) x) (call-all (cdr fs) x))))
+
+(let ((add-10 (add-3-curried 10))
+      (account (make-account 100)))
+    (let ((add-30 (funcall add-10 20))
+          (add-35 (funcall add-10 25))
+          (first (funcall account 50))
+          (second (funcall account -30)))
+        (dump
+            (funcall add-30 0)
+            (funcall add-35 0)
+            (funcall (funcall add-10 1) 2)
+            (funcall first)
+            (funcall second)
+            (call-all (make-adders 4) 100))))
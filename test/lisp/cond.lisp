@@ -0,0 +1,26 @@
+(defun classify (n)
+    (cond
+        ((< n 0) 'negative)
+        ((= n 0) 'zero)
+        ((< n 10) (dump "small") 'small)
+        (t 'large)))
+
+(defun first-truthy (a b)
+    (cond (a) (b) (t "neither")))
+
+(dump
+    (classify -5)
+    (classify 0)
+    (classify 3)
+    (classify 42)
+    (cond)
+    (cond ((= 1 2) 'unreachable))
+    (first-truthy nil 7)
+    (first-truthy nil nil)
+    (when (< 1 2) (dump "when ran") 'when-result)
+    (when (> 1 2) (dump "should not print"))
+    (unless (> 1 2) 'unless-result)
+    (unless (< 1 2) 'should-not-be-returned)
+    (progn)
+    (progn (dump "progn") 1 2 3))
+(progn 'last)
@@ -0,0 +1,80 @@
+;; type errors from the runtime can be caught
+(dump (handler-case (car (floor 7 "2"))
+    (type-error (c) (list (type-error-datum c) (type-error-expected-type c)))))
+(dump (handler-case (+ 1 2)
+    (error () 'not-reached)))
+(dump (multiple-value-list (handler-case (floor 7 2)
+    (error () 'not-reached))))
+
+;; arithmetic and comparisons check that both arguments are numbers
+(defun type-error-of (thunk)
+    (handler-case (funcall thunk)
+        (type-error (c) (list (type-error-datum c) (type-error-expected-type c)))))
+(dump (type-error-of (lambda () (+ 1 "a"))))
+(dump (type-error-of (lambda () (+ 1 '(5 6)))))
+(dump (type-error-of (lambda () (+ 1.5 "a"))))
+(dump (type-error-of (lambda () (- 10000000000 "a"))))
+(dump (type-error-of (lambda () (* (/ 1 2) "a"))))
+(dump (type-error-of (lambda () (= 1 "a"))))
+(dump (type-error-of (lambda () (< 1 '(1)))))
+(dump (type-error-of (lambda () (<= (/ 1 3) 'one))))
+
+;; the first clause for a supertype handles it
+(dump (handler-case (floor 1 0)
+    (type-error () 'type)
+    (arithmetic-error (c) (condition-message c))
+    (error () 'error)))
+(dump (handler-case (error "custom")
+    (type-error () 'type)
+    (error (c) (list (condition-type c) (condition-message c)))))
+(dump (handler-case (error 'type-error :datum 3 :expected-type 'string)
+    (condition (c) (condition-message c))))
+
+;; the inner handler takes precedence, and rethrowing reaches the outer one
+(dump (handler-case
+    (handler-case (error "inner")
+        (error (c) (error (concatenate 'string "rethrown " (condition-message c)))))
+    (error (c) (condition-message c))))
+
+;; handler-bind runs the handler without leaving, declining when it returns
+(defparameter *seen* '())
+
+(dump (handler-case
+    (handler-bind ((error (lambda (c) (setq *seen* (cons (condition-message c) *seen*)))))
+        (error "first")
+        'not-reached)
+    (error () 'handled-outside)))
+(dump *seen*)
+
+;; signal returns nil if no handler takes over
+(dump (handler-bind ((warning (lambda (c) (setq *seen* (cons 'warned *seen*)))))
+    (signal 'warning)
+    (signal "not a warning")
+    'done))
+(dump *seen*)
+
+;; a handler can leave through a non-local exit of its own
+(dump (block b
+    (handler-bind ((error (lambda (c) (return-from b 'escaped))))
+        (assert nil "failed assertion")
+        'not-reached)))
+
+;; ignore-errors returns nil and the condition
+(dump (multiple-value-list (ignore-errors (rplaca 5 1))))
+(dump (ignore-errors 'fine))
+(dump (multiple-value-bind (result condition)
+        (ignore-errors (let ((f #'identity)) (funcall f 1 2)))
+    (list result (condition-type condition))))
+
+(defun identity (x) x)
+
+;; handlers are removed again once the form is left
+(dump (handler-case (catch 'out
+    (handler-case (throw 'out 'thrown)
+        (error () 'inner)))
+    (error () 'outer)))
+(dump (handler-case (assert-integer "no") (error () *handlers*)))
+
+;; unhandled errors still stop the program
+(ignore-errors (floor 1 0))
+(dump (floor 3 "1"))
@@ -0,0 +1,16 @@
+(dump
+    1073741823
+    1073741824
+    -1073741824
+    -1073741825
+    (+ 1073741823 1)
+    (- -1073741824 1)
+    (- 1073741824 1)
+    (* 2147483647 2)
+    (= 1073741824 (+ 1073741823 1))
+    (< 1073741823 1073741824)
+    (list 0 -1 2147483647 -2147483647)
+    (integerp 1073741824)
+    (integerp 5)
+    (floatp 5))
+(cons 1073741823 (+ 1073741823 1073741823))
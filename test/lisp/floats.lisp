@@ -0,0 +1,27 @@
+(dump
+    .5
+    6.
+    -0.25
+    (+ 1 0.5)
+    (- 2.5 1)
+    (* 3 0.1)
+    (/ 1 4.0)
+    (/ 12 4)
+    (< 1 1.5)
+    (> 1 1.5)
+    (= 2 2.0)
+    (<= 0.5 0.5 1)
+    (truncate -7.9)
+    (float 3)
+    (* 100000 100000 1.5)
+    (/ 1.0 40000)
+    (/ 1.0 3)
+    (/ 1.0 10000)
+    0.00123451
+    0.00123449
+    0.003406377
+    0.05
+    (/ 33.0 256)
+    (* 2469135.0 5)
+    (/ 1.0 0))
+(+ 0.5 0.25)
@@ -0,0 +1,20 @@
+;; allocates many short-lived numbers and argument lists in a call tree that
+;; stays shallow, so the heap fills up many times over
+(defun churn (depth)
+    (if (= depth 0)
+        1
+        (+ (churn (- depth 1)) (churn (- depth 1)))))
+
+(defun make-adder (a)
+    (lambda (b)
+        (+ a b)))
+
+(defparameter *kept* '(1 2 3))
+
+;; things that are still referenced after churning must survive collection
+(let ((list (list 4 5 (concatenate 'string "six" "!")))
+      (add-100 (make-adder 100)))
+    (dump (churn 12))
+    (dump list)
+    (dump *kept*)
+    (dump (funcall add-100 23)))
@@ -0,0 +1,52 @@
+;; dotimes counts from zero, with the variable bound to the count in the result
+(defparameter *squares* '())
+(dotimes (i 5)
+    (setq *squares* (cons (* i i) *squares*)))
+(dump *squares*)
+(dump (dotimes (i 3 (list 'done i))))
+(dump (dotimes (i 0) 'never))
+
+;; dolist goes through the list, and return leaves the block named nil
+(defparameter *reversed* '())
+(dump (dolist (x '(1 2 3) *reversed*)
+    (setq *reversed* (cons x *reversed*))))
+(dump (dolist (x '(1 2 3 4 5))
+    (when (> x 2) (return (list 'found x)))))
+
+;; do steps in parallel and do* in sequence
+(dump (do ((i 0 (+ i 1))
+           (previous nil i))
+          ((= i 3) (list i previous))))
+(dump (do* ((i 0 (+ i 1))
+            (twice 0 (* i 2)))
+           ((= i 3) (list i twice))))
+(dump (do ((n 5 (- n 1)) (acc 1)) ((= n 0) acc)
+    (setq acc (* acc n))))
+
+;; loop accumulates, with conditions and a finally clause
+(dump (loop for x in '(1 2 3 4) collect (* x 10)))
+(dump (loop for i from 1 to 10 sum i))
+(dump (loop for i from 0 below 10 by 3 collect i))
+(dump (loop for x in '(1 2 3 4 5 6) when (> x 3) collect x))
+(dump (loop for x in '(a b c) for i from 0 collect (list i x)))
+(dump (loop with limit = 3 for i from 0 while (< i limit) collect i))
+(dump (loop for x in '(1 2 3 4) unless (= x 2) sum x))
+(dump (loop for i from 0 when (> i 4) return (* i 100)))
+(dump (loop for x in '(1 2 3) do (dump x) finally (dump 'finally)))
+
+;; the simple loop runs until return
+(defparameter *n* 0)
+(dump (loop (setq *n* (+ *n* 1)) (when (> *n* 3) (return *n*))))
+
+;; closures share the loop variable, which keeps its last value
+(dump (funcall (car (loop for x in '(1 2) collect (lambda () x)))))
+
+;; returning through a lambda from a loop
+(dump (dolist (x '(1 2 3))
+    (funcall (lambda () (when (= x 2) (return 'through-lambda))))))
+
+;; iteration runs in constant stack
+(dump (loop for i from 1 to 100000 sum 1))
+(defparameter *count* 0)
+(dotimes (i 100000) (setq *count* (+ *count* 1)))
+(dump *count*)
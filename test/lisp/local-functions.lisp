@@ -0,0 +1,46 @@
+;; flet functions only see the functions outside of the flet
+(defun twice (x)
+    (* 2 x))
+
+(defun quadruple (x)
+    (flet ((twice (y) (twice (twice y))))
+        (twice x)))
+
+;; labels functions can call themselves and each other
+(defun count-parity (list)
+    (labels ((count-even (list n)
+                 (if (null list)
+                     n
+                     (count-odd (cdr list) (+ n 1))))
+             (count-odd (list n)
+                 (if (null list)
+                     (- n)
+                     (count-even (cdr list) (+ n 1)))))
+        (count-even list 0)))
+
+(defun scale-all (factor list)
+    (flet ((scale (x) (* x factor)))
+        (remove-if-not #'numberp (list (scale 1) (funcall #'scale 2)
+                                       (apply #'scale (list 3))))))
+
+(defun make-multiplier (n)
+    (labels ((multiply (x &optional (times n))
+                 (if (= times 0) 0 (+ x (multiply x (- times 1))))))
+        #'multiply))
+
+(dump
+    (quadruple 3)
+    (count-parity '(a b c))
+    (count-parity '(a b c d))
+    (scale-all 10 nil)
+    (funcall (make-multiplier 3) 7)
+    (flet ((one () 1)) (one))
+    (flet () 'empty)
+    (labels ((count-down (n) (if (= n 0) 'done (count-down (- n 1)))))
+        (count-down 100)))
+
+;; direct calls with the wrong number of arguments do not compile, calls
+;; through the function object are checked when called
+(labels ((strict (a) a))
+    (let ((function #'strict))
+        (funcall function 1 2)))
@@ -0,0 +1,62 @@
+(defmacro swap-vars (a b)
+    "Swaps the values of two variables."
+    (let ((tmp (gensym "tmp")))
+        (list 'let (list (list tmp a))
+            (list 'setq a b)
+            (list 'setq b tmp))))
+
+(defmacro my-unless (test &body body)
+    (list 'if test nil (cons 'progn body)))
+
+(defmacro defconstant-fn (name value)
+    (list 'defun name '() value))
+
+(defmacro twice (form)
+    (list 'progn form form))
+
+(defmacro first-or-default (args)
+    (if (null args) ''default (car args)))
+
+;; macro bodies can call functions defined before the call, and use let,
+;; cond, eq and optional parameters
+(defun setq-each (names value)
+    (if (null names)
+        nil
+        (cons (list 'setq (car names) value) (setq-each (cdr names) value))))
+
+(defmacro reset-all (names &optional (value 0))
+    (let ((assignments (setq-each names value)))
+        (cond ((null assignments) nil)
+              ((eq (car (cdr names)) nil) (car assignments))
+              (t (cons 'progn assignments)))))
+
+;; macros can expand into definitions at root level and into other macros
+(defconstant-fn forty-two 42)
+
+(defun count-up (n)
+    (let ((count 0))
+        (twice (setq count (+ count n)))
+        count))
+
+(let ((x 1) (y 2))
+    (swap-vars x y)
+    (dump x y))
+
+;; the temporary variable does not capture variables with the same name
+(let ((tmp 3) (other 4))
+    (swap-vars tmp other)
+    (dump tmp other))
+
+(dump
+    (forty-two)
+    (count-up 5)
+    (my-unless (> 1 2) (dump "unless ran") 'ran)
+    (my-unless (< 1 2) 'not-returned)
+    (first-or-default ())
+    (first-or-default ("first" "second")))
+
+(let ((a 1) (b 2) (c 3))
+    (reset-all (a b))
+    (reset-all (c) 'reset)
+    (reset-all ())
+    (dump a b c))
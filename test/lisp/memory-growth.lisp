@@ -0,0 +1,22 @@
+;; allocates more than fits into the initial memory while a tree allocated
+;; before is still reachable, so memory has to grow without losing anything
+(defun make-tree (depth)
+    (if (= depth 0)
+        1
+        (cons (make-tree (- depth 1)) (make-tree (- depth 1)))))
+
+(defun count-leaves (tree)
+    (if (consp tree)
+        (+ (count-leaves (car tree)) (count-leaves (cdr tree)))
+        tree))
+
+;; a string with 2^times times the characters, larger than a page of memory
+;; after 16 times
+(defun double-string (string times)
+    (if (= times 0)
+        string
+        (double-string (concatenate 'string string string) (- times 1))))
+
+(let ((tree (make-tree 10))
+      (large (double-string "proboscis" 17)))
+    (dump (count-leaves tree)))
@@ -0,0 +1,42 @@
+;; let* sees the names bound before
+(let ((x 1))
+    (let* ((x 10) (y (+ x 1)))
+        (dump (list x y))))
+
+(destructuring-bind (a (b c) &optional (d 4) &rest e) '(1 (2 3))
+    (dump (list a b c d e)))
+(destructuring-bind (a &optional (b (* a 2) b-p) &body rest) '(5 6 7 8)
+    (dump (list a b b-p rest)))
+(destructuring-bind (a &optional (b (* a 2) b-p)) '(5)
+    (dump (list a b b-p)))
+
+(defun divide (top bottom)
+    (floor top bottom))
+
+(defun quotient-and-remainder (top bottom)
+    (multiple-value-list (divide top bottom)))
+
+(dump (quotient-and-remainder 7 2))
+(dump (quotient-and-remainder -7 2))
+(dump (quotient-and-remainder 7 -2))
+(dump (quotient-and-remainder -7 -2))
+(dump (quotient-and-remainder 6 -3))
+
+;; only the primary value is used outside of multiple value forms
+(dump (+ (floor 7 2) 1))
+(dump (multiple-value-list (values (floor 7 2))))
+(dump (multiple-value-list (values)))
+(dump (multiple-value-list 5))
+
+(defun pick (which)
+    (if which
+        (values 1 2 3)
+        (let ((x 4))
+            (values x))))
+
+(dump (multiple-value-list (pick t)))
+(dump (multiple-value-list (pick nil)))
+(dump (multiple-value-list (funcall (lambda () (values 'a 'b)))))
+
+(multiple-value-bind (q r extra) (floor 17 5)
+    (dump (list q r extra)))
@@ -0,0 +1,91 @@
+;; return-from leaves the block right away
+(dump (block b
+    (return-from b 1)
+    2))
+(dump (block nil
+    (when t (return 'early))
+    'late))
+(dump (block b 'fall-through))
+(dump (multiple-value-list (block b (return-from b (values 1 2)))))
+
+;; from a lambda, the block has to be left by unwinding
+(defun find-first (predicate list)
+    (block search
+        (remove-if-not (lambda (x) (when (funcall predicate x) (return-from search x)))
+            list)
+        nil))
+
+(dump (find-first (lambda (x) (> x 2)) '(1 2 3 4)))
+(dump (find-first (lambda (x) (> x 5)) '(1 2 3 4)))
+
+;; a deep search stops without threading a flag through every call
+(defun walk (tree)
+    (cond ((consp tree) (walk (car tree)) (walk (cdr tree)))
+          ((and (numberp tree) (< tree 0)) (throw 'found tree))
+          (tree (dump tree))))
+
+(dump (catch 'found
+    (walk '(1 (2 (-3 4)) 5))
+    'not-found))
+(dump (catch 'found (walk '(1 2)) 'not-found))
+
+;; the innermost catch of a tag gets the throw
+(dump (catch 'x
+    (list 'outer (catch 'x (throw 'x 'inner)))))
+(dump (catch 'x
+    (catch 'y (throw 'x 'past-y))
+    'not-here))
+
+;; cleanup runs in order from the inside out, also on normal exit
+(dump (unwind-protect 'protected (dump 'cleanup)))
+(dump (catch 'done
+    (unwind-protect
+        (unwind-protect
+            (throw 'done 'thrown)
+            (dump 'inner-cleanup))
+        (dump 'outer-cleanup))))
+(dump (block b
+    (unwind-protect
+        (return-from b 'returned)
+        (dump 'cleanup-on-return))))
+(dump (multiple-value-list (unwind-protect (values 1 2) 3)))
+
+;; tail positions inside of regions
+(defun catch-in-tail (tag)
+    (catch tag (values 'a 'b)))
+
+(dump (multiple-value-list (catch-in-tail 'any)))
+
+(defun countdown (n)
+    (block loop
+        (labels ((step (n)
+                     (when (= n 0) (return-from loop 'done))
+                     (step (- n 1))))
+            (step n))))
+
+(dump (countdown 100))
+
+;; functions are blocks named after them, also when left from a lambda
+(defun first-negative (list)
+    (dolist (x list)
+        (when (< x 0) (return-from first-negative x)))
+    'none)
+
+(defun first-even (list)
+    (remove-if-not (lambda (x) (when (evenp x) (return-from first-even x))) list)
+    'none)
+
+(dump (first-negative '(1 -2 3)) (first-negative '(1 2)) (first-even '(1 3 4 5)))
+(dump (labels ((count-down (n)
+                   (if (= n 0) (return-from count-down 'done))
+                   (count-down (- n 1))))
+          (count-down 3000))
+      (flet ((twice (x) (return-from twice (values x x)) 'never))
+          (multiple-value-list (twice 7))))
+
+;; a block that was already left cannot be returned from
+(defun escape ()
+    (block b (lambda () (return-from b 1))))
+
+(dump 'before)
+(funcall (escape))
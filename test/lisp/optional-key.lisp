@@ -0,0 +1,48 @@
+(defun greet (name &optional (greeting "hello") (punctuation "!" punctuation-p))
+    (list greeting name punctuation punctuation-p))
+
+(defun offset (x &optional by)
+    (if by (+ x by) x))
+
+;; defaults can refer to parameters before them
+(defun range (start &optional (end (+ start 3)))
+    (list start end))
+
+(defun make-point (&key x (y 0) (z (+ y 1) z-p))
+    (list x y z z-p))
+
+(defun rest-and-keys (first &rest rest &key verbose)
+    (list first rest verbose))
+
+(defun anything (&key a &allow-other-keys)
+    a)
+
+(dump
+    (greet "world")
+    (greet "world" "hi")
+    (greet "world" "hi" "?")
+    (offset 1)
+    (offset 1 2)
+    (range 1)
+    (range 1 2)
+    (make-point)
+    (make-point :x 1)
+    (make-point :z 5 :x 1 :y 2)
+    (make-point :y 2 :y 3)
+    (make-point :x 1 :other 2 :allow-other-keys t)
+    (rest-and-keys 1)
+    (rest-and-keys 1 :verbose t)
+    (anything :b 1 :a 2)
+    (funcall (lambda (a &optional (b 10) &key (c 100)) (+ a b c)) 1)
+    (funcall (lambda (a &optional (b 10) &key (c 100)) (+ a b c)) 1 2 :c 3)
+    (floor 7)
+    :keyword)
+
+;; wrong keys are program errors that name the function and the keyword
+(dump
+    (handler-case (make-point :x 1 :y)
+        (program-error (c) (condition-message c)))
+    (handler-case (funcall (lambda (&key a) a) :b 2)
+        (error (c) (list (condition-type c) (condition-message c)))))
+
+(make-point :w 1)
@@ -0,0 +1,4 @@
+;; the largest vector that make-array accepts takes almost 2GiB, more than
+;; memory can grow to
+(dump (length (make-array 3)))
+(make-array 536870909)
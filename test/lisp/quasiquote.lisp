@@ -0,0 +1,31 @@
+(defmacro my-when (test &body body)
+    `(if ,test (progn ,@body) nil))
+
+(defmacro swap-vars (a b)
+    `(let ((tmp ,a))
+        (setq ,a ,b)
+        (setq ,b tmp)))
+
+(defun around (items)
+    `(first ,@items last))
+
+(defun nested (x)
+    `(,x (,x ,@(list x x)) (,(+ x 1))))
+
+;; without unquotes a template is static data, just like a quoted list
+(defparameter *static* `(1 (2 3) "four"))
+
+(let ((x 1) (y 2))
+    (swap-vars x y)
+    (dump x y))
+
+(dump
+    *static*
+    `(a ,(+ 1 2) ,@(list 4 5) ,@nil end)
+    (around '(1 2 3))
+    (around ())
+    (nested 7)
+    `(,@(list 1 2) ,@(list 3))
+    `,(* 6 7)
+    (my-when (< 1 2) (dump "when ran") 'ran)
+    (my-when (> 1 2) 'not-returned))
@@ -0,0 +1,63 @@
+;; integers that do not divide evenly make ratios in lowest terms, with the
+;; sign in the numerator
+(dump
+    (/ 1 3)
+    (/ 6 4)
+    (/ 6 -4)
+    (/ 4 2)
+    (/ 3)
+    (/ 1 2 3)
+    (/ (* 100000000000 3) 200000000000)
+    (/ 100000000000 3)
+    (/ -2147483648 -1))
+
+;; arithmetic on ratios makes integers again when the denominator is one
+(dump
+    (+ (/ 1 2) (/ 1 3))
+    (* (/ 2 3) (/ 3 2))
+    (- (/ 1 2) (/ 1 2))
+    (< (/ 1 3) (/ 1 2))
+    (= (/ 2 4) (/ 1 2))
+    (float (/ 1 4))
+    (+ (/ 1 2) 0.25)
+    (numerator (/ -6 4))
+    (denominator (/ -6 4))
+    (denominator 5)
+    (gcd 12 18 8))
+
+;; division to integers, with the remainder as a second value
+(dump
+    (multiple-value-list (floor -7 2))
+    (multiple-value-list (ceiling 7 2))
+    (multiple-value-list (truncate -7 2))
+    (multiple-value-list (round 5 2))
+    (multiple-value-list (round 7 2))
+    (multiple-value-list (round 7 -3))
+    (multiple-value-list (floor (/ 7 2)))
+    (multiple-value-list (round (/ -5 2)))
+    (multiple-value-list (floor 2.5))
+    (mod -7 3)
+    (rem -7 3)
+    (mod (/ 7 2) (/ 2 3))
+    (oddp 3))
+
+;; floats outside of the 32-bit range become bignums, and those that are not
+;; finite signal an error
+(dump
+    (floor 3000000000.0)
+    (multiple-value-list (truncate 10000000000.0))
+    (ceiling -10000000000.0)
+    (round (* 150000000000.0 1000000000.0))
+    (handler-case (floor (/ 1.0 0))
+        (arithmetic-error (c) (condition-message c)))
+    (handler-case (truncate (- (/ 1.0 0) (/ 1.0 0)))
+        (arithmetic-error () 'caught)))
+
+;; dividing by zero signals an error, except for floats
+(dump
+    (handler-case (/ 1 0)
+        (division-by-zero (c) (condition-message c)))
+    (handler-case (mod (/ 1 2) 0)
+        (arithmetic-error () 'caught))
+    (/ 1.0 0))
+(/ 22 7)
@@ -0,0 +1,7 @@
+;; recursion without end runs out of stack space, which panics instead of
+;; overwriting the heap
+(defun nest (n)
+    (list (nest n)))
+
+(dump "nesting")
+(nest 0)
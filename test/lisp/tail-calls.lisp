@@ -0,0 +1,31 @@
+;; calls in tail position do not grow the stack, so loops can be written as
+;; recursion without running out of stack space
+(defun count-down (n)
+    (if (= n 0)
+        "done"
+        (count-down (- n 1))))
+
+(defun make-numbers (n acc)
+    (cond
+        ((= n 0) acc)
+        (t (make-numbers (- n 1) (cons n acc)))))
+
+(defun even-p (n)
+    (when t
+        (if (= n 0) t (odd-p (- n 1)))))
+
+(defun odd-p (n)
+    (let ((next (- n 1)))
+        (and (/= n 0) (even-p next))))
+
+(defun sum-with (f list acc)
+    (if (null list)
+        acc
+        (funcall f f (cdr list) (+ acc (car list)))))
+
+(dump (count-down 10000))
+(dump (even-p 10001))
+(dump (apply #'- (cons 0 (make-numbers 5000 nil))))
+(dump (sum-with (lambda (self list acc) (sum-with self list acc))
+                (make-numbers 5000 nil)
+                0))
@@ -0,0 +1,62 @@
+;; sorts a vector in place by inserting every element into the sorted part
+;; before it, which only works well because aref takes constant time
+(defun insertion-sort (vector)
+    (dotimes (sorted (length vector) vector)
+        (let ((element (aref vector sorted))
+              (index sorted))
+            (loop
+                (if (or (= index 0) (<= (aref vector (- index 1)) element))
+                    (return))
+                (setf (aref vector index) (aref vector (- index 1)))
+                (setq index (- index 1)))
+            (setf (aref vector index) element))))
+
+(defun churn (depth)
+    (if (= depth 0)
+        1
+        (+ (churn (- depth 1)) (churn (- depth 1)))))
+
+;; vector literals evaluate to themselves and can be nested
+(dump #(1 "two" #\3 (4 5) #(6)) #() '#(a b))
+
+(dump
+    (make-array 3)
+    (make-array '(2) :initial-element 0)
+    (vector 1 (+ 1 1) 'three)
+    (aref #(a b c) 2)
+    (aref "héllo" 1))
+
+;; length counts elements of vectors and characters of strings
+(dump (length #(1 2 3)) (length "héllo") (length '(1 2)) (length nil))
+
+(dump (vectorp #(1)) (vectorp "a") (vectorp '(1)) (simple-vector-p "a"))
+
+(let ((numbers (make-array 8)))
+    (dotimes (index 8)
+        (setf (aref numbers index) (mod (* index 5) 8)))
+    (dump (insertion-sort numbers)))
+
+;; elements that only the vector refers to survive collection
+(let ((strings (make-array 3)))
+    (dotimes (index 3)
+        (setf (aref strings index)
+            (concatenate 'string "string " (to-string-number index))))
+    (dump (churn 12))
+    (dump strings))
+
+(dump
+    (handler-case (aref #(1 2) 2)
+        (error (c) (condition-message c)))
+    (handler-case (setf (aref (vector 1) -1) 0)
+        (error (c) (condition-message c)))
+    (handler-case (make-array '(2 2))
+        (error (c) (condition-message c)))
+    (handler-case (make-array 10000000000)
+        (error (c) (condition-message c)))
+    (handler-case (make-array 1073741823)
+        (error (c) (condition-message c)))
+    (handler-case (aref '(1 2) 0)
+        (type-error (c) (type-error-expected-type c)))
+    (handler-case (length 3)
+        (type-error (c) (type-error-expected-type c))))
+(aref (vector 1 2) 1)